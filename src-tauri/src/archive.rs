@@ -0,0 +1,413 @@
+use crate::error::{AppError, ErrorKind};
+use crate::{formats, handles};
+use chrono::{Local, NaiveDate, NaiveDateTime, TimeZone};
+use sha2::{Digest, Sha256};
+use std::ffi::OsString;
+use std::fs::{self, File};
+use std::io::Read;
+use std::path::{Component, Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::OnceLock;
+use zip::ZipArchive;
+
+// -----------------------------
+// Blends inside archives
+// -----------------------------
+// Entries are addressed as `<archive>!/<inner path>`, so the archive shows
+// up as a folder named `shot.zip!` in the tree. Headers and blocks are read
+// by streaming the entry; nothing is extracted until the file is opened.
+// Zip is read in-process; 7z and RAR go through a 7-Zip binary, either one
+// shipped next to the app or the one installed on the system.
+
+pub const ARCHIVE_EXTENSIONS: &[&str] = &["zip", "7z", "rar"];
+
+// Extracted entries go below the per-user app cache dir (never the shared
+// temp dir, where another user could plant a file), one folder per archive.
+// Each copy has a `.source` stamp of the archive's mtime and the entry's
+// CRC-32; it is reused only while both still match.
+const EXTRACT_DIR: &str = "extracted";
+
+static CACHE_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+pub fn init(cache_dir: Option<PathBuf>) {
+    if let Some(dir) = cache_dir {
+        let _ = CACHE_DIR.set(dir);
+    }
+}
+
+pub fn is_archive(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| ARCHIVE_EXTENSIONS.contains(&e.to_ascii_lowercase().as_str()))
+}
+
+fn is_zip(archive: &Path) -> bool {
+    archive
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("zip"))
+}
+
+// Whether entries of `archive` can be listed here; 7z and RAR need 7-Zip
+pub fn can_list(archive: &Path) -> bool {
+    is_zip(archive) || seven_zip().is_some()
+}
+
+// Path of an entry; `inner` is `/`-separated as stored in the archive
+pub fn entry_path(archive: &Path, inner: &str) -> PathBuf {
+    let mut name = archive.as_os_str().to_owned();
+    name.push("!");
+    PathBuf::from(name).join(inner)
+}
+
+// Splits an entry path into archive and inner path; None for plain files
+pub fn split(path: &Path) -> Option<(PathBuf, String)> {
+    let mut archive = PathBuf::new();
+    let mut parts = path.components();
+    for c in parts.by_ref() {
+        let name = c.as_os_str().to_string_lossy();
+        if let Some(stem) = name.strip_suffix('!').filter(|s| is_archive(Path::new(s))) {
+            archive.push(stem);
+            let inner: Vec<String> = parts
+                .map(|c| c.as_os_str().to_string_lossy().to_string())
+                .collect();
+            return (!inner.is_empty()).then(|| (archive, inner.join("/")));
+        }
+        archive.push(c);
+    }
+    None
+}
+
+fn zip_error(e: zip::result::ZipError, path: &Path) -> AppError {
+    let kind = match e {
+        zip::result::ZipError::FileNotFound => ErrorKind::NotFound,
+        zip::result::ZipError::Io(ref io) => return AppError::io(io, path),
+        _ => ErrorKind::CorruptFile,
+    };
+    AppError::new(kind, e.to_string()).with_path(path)
+}
+
+// A blend stored in an archive, as the walk lists it
+pub struct Entry {
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    pub modified: Option<String>,
+}
+
+// Blends (and numbered backups with `backups`) stored in `archive`; only
+// the central directory is read
+pub fn list(archive: &Path, backups: bool) -> Result<Vec<Entry>, AppError> {
+    let is_blend = |inner: &Path| {
+        formats::of_path(inner, false).is_some()
+            || (backups && formats::backup_number(inner).is_some())
+    };
+    if !is_zip(archive) {
+        return Ok(external_list(archive, None)?
+            .into_iter()
+            .filter(|e| is_blend(Path::new(&e.inner)) && enclosed(&e.inner).is_some())
+            .map(|e| Entry {
+                path: entry_path(archive, &e.inner),
+                size_bytes: e.size_bytes,
+                modified: e.modified,
+            })
+            .collect());
+    }
+    let file = handles::open(archive)?;
+    let mut zip = ZipArchive::new(&*file).map_err(|e| zip_error(e, archive))?;
+    let mut entries = Vec::new();
+    for i in 0..zip.len() {
+        let entry = zip.by_index_raw(i).map_err(|e| zip_error(e, archive))?;
+        if !entry.is_file() || !is_blend(Path::new(entry.name())) || entry.enclosed_name().is_none()
+        {
+            continue;
+        }
+        // Zip times carry no zone; they are the packing machine's local time
+        let modified = entry.last_modified().and_then(|t| {
+            let naive = NaiveDate::from_ymd_opt(t.year().into(), t.month().into(), t.day().into())?
+                .and_hms_opt(t.hour().into(), t.minute().into(), t.second().into())?;
+            Local.from_local_datetime(&naive).earliest()
+        });
+        entries.push(Entry {
+            path: entry_path(archive, entry.name()),
+            size_bytes: entry.size(),
+            modified: modified.map(|t| t.to_rfc3339()),
+        });
+    }
+    Ok(entries)
+}
+
+// Runs `f` on the decompressed stream of one entry
+pub fn with_entry<T>(path: &Path, f: impl FnOnce(&mut dyn Read) -> T) -> Result<T, AppError> {
+    let (archive, inner) = split(path).ok_or_else(|| {
+        AppError::new(ErrorKind::InvalidInput, "Not a path inside an archive").with_path(path)
+    })?;
+    if !is_zip(&archive) {
+        let mut child = external_cat(&archive, &inner)?;
+        let out = child.stdout.as_mut().map(|stdout| f(stdout));
+        // The caller may stop early (a header is 12 bytes)
+        let _ = child.kill();
+        let _ = child.wait();
+        return out
+            .ok_or_else(|| AppError::new(ErrorKind::Io, "7-Zip gave no output").with_path(path));
+    }
+    let file = handles::open(&archive)?;
+    let mut zip = ZipArchive::new(&*file).map_err(|e| zip_error(e, &archive))?;
+    let mut entry = zip.by_name(&inner).map_err(|e| zip_error(e, path))?;
+    Ok(f(&mut entry))
+}
+
+// Extracts one entry next to the others from the same archive and returns
+// where it landed; an earlier copy of the same entry is reused
+pub fn extract(path: &Path) -> Result<PathBuf, AppError> {
+    let (archive, inner) = split(path).ok_or_else(|| {
+        AppError::new(ErrorKind::InvalidInput, "Not a path inside an archive").with_path(path)
+    })?;
+    let rel = enclosed(&inner).ok_or_else(|| {
+        AppError::new(ErrorKind::InvalidInput, "Unsafe path in archive").with_path(path)
+    })?;
+    let dest = extract_dest(&archive, &rel)?;
+    let modified = fs::metadata(&archive)
+        .and_then(|m| m.modified())
+        .map_err(|e| AppError::io(&e, &archive))?;
+
+    if !is_zip(&archive) {
+        let crc = external_list(&archive, Some(&inner))?
+            .first()
+            .ok_or_else(|| AppError::new(ErrorKind::NotFound, "Not in archive").with_path(path))?
+            .crc;
+        let stamp = crc.map(|crc| stamp(modified, crc));
+        if stamp.as_ref().is_some_and(|s| is_current(&dest, s)) {
+            return Ok(dest);
+        }
+        let mut child = external_cat(&archive, &inner)?;
+        let mut stdout = child.stdout.take().expect("stdout is piped");
+        let written = write_out(&dest, &mut stdout, path, stamp.as_deref());
+        let status = child.wait().map_err(|e| AppError::io(&e, &archive))?;
+        if written.is_ok() && !status.success() {
+            let _ = fs::remove_file(&dest);
+            return Err(
+                AppError::new(ErrorKind::CorruptFile, "7-Zip could not extract the file")
+                    .with_path(path),
+            );
+        }
+        return written.map(|_| dest);
+    }
+
+    let file = handles::open(&archive)?;
+    let mut zip = ZipArchive::new(&*file).map_err(|e| zip_error(e, &archive))?;
+    let mut entry = zip.by_name(&inner).map_err(|e| zip_error(e, path))?;
+    let stamp = stamp(modified, entry.crc32());
+    if is_current(&dest, &stamp) {
+        return Ok(dest);
+    }
+    write_out(&dest, &mut entry, path, Some(&stamp))?;
+    Ok(dest)
+}
+
+fn stamp(archive_modified: std::time::SystemTime, crc: u32) -> String {
+    let ms = archive_modified
+        .duration_since(std::time::UNIX_EPOCH)
+        .map_or(0, |d| d.as_millis());
+    format!("{} {:08x}", ms, crc)
+}
+
+fn stamp_path(dest: &Path) -> PathBuf {
+    let mut name = dest.as_os_str().to_owned();
+    name.push(".source");
+    PathBuf::from(name)
+}
+
+fn is_current(dest: &Path, stamp: &str) -> bool {
+    dest.is_file() && fs::read_to_string(stamp_path(dest)).is_ok_and(|s| s == stamp)
+}
+
+// `inner` as a relative path, or None if it would land outside the folder
+fn enclosed(inner: &str) -> Option<PathBuf> {
+    let rel: PathBuf = inner.split(['/', '\\']).filter(|p| !p.is_empty()).collect();
+    let safe = rel.components().all(|c| matches!(c, Component::Normal(_)));
+    (safe && rel.file_name().is_some()).then_some(rel)
+}
+
+fn extract_dest(archive: &Path, rel: &Path) -> Result<PathBuf, AppError> {
+    let cache = CACHE_DIR.get().ok_or_else(|| {
+        AppError::new(ErrorKind::NotFound, "No cache folder to extract into").with_path(archive)
+    })?;
+    let digest = Sha256::digest(archive.as_os_str().as_encoded_bytes());
+    let folder: String = digest[..6].iter().map(|b| format!("{:02x}", b)).collect();
+    let mut stem = archive
+        .file_stem()
+        .map(|s| s.to_owned())
+        .unwrap_or_default();
+    stem.push(OsString::from(format!("-{}", folder)));
+    Ok(cache.join(EXTRACT_DIR).join(stem).join(rel))
+}
+
+// Without a stamp the copy is never reused
+fn write_out(
+    dest: &Path,
+    entry: &mut impl Read,
+    path: &Path,
+    stamp: Option<&str>,
+) -> Result<(), AppError> {
+    if let Some(dir) = dest.parent() {
+        fs::create_dir_all(dir).map_err(|e| AppError::io(&e, dir))?;
+    }
+    let stamp_file = stamp_path(dest);
+    let _ = fs::remove_file(&stamp_file);
+    // Written under a temp name so a failed extraction never looks complete
+    let tmp = dest.with_extension("partial");
+    let mut out = File::create(&tmp).map_err(|e| AppError::io(&e, &tmp))?;
+    if let Err(e) = std::io::copy(entry, &mut out) {
+        drop(out);
+        let _ = fs::remove_file(&tmp);
+        return Err(AppError::io(&e, path));
+    }
+    drop(out);
+    fs::rename(&tmp, dest).map_err(|e| AppError::io(&e, dest))?;
+    if let Some(stamp) = stamp {
+        fs::write(&stamp_file, stamp).map_err(|e| AppError::io(&e, &stamp_file))?;
+    }
+    tracing::info!(path = %dest.display(), "extracted from archive");
+    Ok(())
+}
+
+// -----------------------------
+// 7z and RAR via 7-Zip
+// -----------------------------
+
+#[cfg(windows)]
+const SEVEN_ZIP_NAMES: &[&str] = &["7zz.exe", "7z.exe"];
+#[cfg(not(windows))]
+const SEVEN_ZIP_NAMES: &[&str] = &["7zz", "7z"];
+
+// Install locations GUI launches may not have on PATH
+#[cfg(windows)]
+const SEVEN_ZIP_DIRS: &[&str] = &[r"C:\Program Files\7-Zip", r"C:\Program Files (x86)\7-Zip"];
+#[cfg(target_os = "macos")]
+const SEVEN_ZIP_DIRS: &[&str] = &["/opt/homebrew/bin", "/usr/local/bin"];
+#[cfg(not(any(windows, target_os = "macos")))]
+const SEVEN_ZIP_DIRS: &[&str] = &["/usr/bin", "/usr/local/bin"];
+
+// A copy shipped next to the executable wins over the system one
+fn seven_zip() -> Option<&'static Path> {
+    static TOOL: OnceLock<Option<PathBuf>> = OnceLock::new();
+    TOOL.get_or_init(|| {
+        let bundled = std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(Path::to_path_buf));
+        let on_path = std::env::var_os("PATH")
+            .map(|p| std::env::split_paths(&p).collect::<Vec<_>>())
+            .unwrap_or_default();
+        let tool = bundled
+            .into_iter()
+            .chain(on_path)
+            .chain(SEVEN_ZIP_DIRS.iter().map(PathBuf::from))
+            .flat_map(|dir| SEVEN_ZIP_NAMES.iter().map(move |n| dir.join(n)))
+            .find(|p| p.is_file());
+        match &tool {
+            Some(p) => tracing::info!(path = %p.display(), "7-Zip found"),
+            None => tracing::info!("7-Zip not found; .7z and .rar archives are skipped"),
+        }
+        tool
+    })
+    .as_deref()
+}
+
+fn seven_zip_command(archive: &Path) -> Result<Command, AppError> {
+    let tool = seven_zip().ok_or_else(|| {
+        AppError::new(
+            ErrorKind::NotFound,
+            "7-Zip is needed to read .7z and .rar archives",
+        )
+        .with_code("seven_zip_missing")
+        .with_path(archive)
+    })?;
+    let mut cmd = Command::new(tool);
+    // An encrypted archive fails instead of prompting; -spd takes names literally
+    cmd.stdin(Stdio::null()).arg("-p").arg("-spd");
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+    Ok(cmd)
+}
+
+// Entry name as 7-Zip expects it on this OS
+fn native_name(inner: &str) -> String {
+    inner.replace('/', std::path::MAIN_SEPARATOR_STR)
+}
+
+struct Listed {
+    inner: String, // `/`-separated
+    size_bytes: u64,
+    modified: Option<String>,
+    crc: Option<u32>, // not every format stores one
+}
+
+// Files in `archive` (only `only` when given) from `7z l -slt`, which prints
+// one `Key = value` block per entry after a `----------` line
+fn external_list(archive: &Path, only: Option<&str>) -> Result<Vec<Listed>, AppError> {
+    let mut cmd = seven_zip_command(archive)?;
+    cmd.arg("l").arg("-slt").arg("--").arg(archive);
+    if let Some(inner) = only {
+        cmd.arg(native_name(inner));
+    }
+    let out = cmd.output().map_err(|e| AppError::io(&e, archive))?;
+    if !out.status.success() {
+        let stderr = String::from_utf8_lossy(&out.stderr);
+        let reason = stderr
+            .lines()
+            .find(|l| l.starts_with("ERROR:"))
+            .unwrap_or("7-Zip could not read the archive");
+        return Err(AppError::new(ErrorKind::CorruptFile, reason.trim()).with_path(archive));
+    }
+
+    let stdout = String::from_utf8_lossy(&out.stdout);
+    let Some((_, body)) = stdout.split_once("\n----------") else {
+        return Ok(Vec::new());
+    };
+    let mut entries = Vec::new();
+    for block in body.replace("\r\n", "\n").split("\n\n") {
+        let field = |key: &str| {
+            block.lines().find_map(|l| {
+                l.strip_prefix(key)
+                    .and_then(|rest| rest.strip_prefix(" = "))
+                    .map(str::trim)
+            })
+        };
+        let (Some(inner), Some(size)) = (field("Path"), field("Size")) else {
+            continue;
+        };
+        let is_dir =
+            field("Folder") == Some("+") || field("Attributes").is_some_and(|a| a.starts_with('D'));
+        if is_dir {
+            continue;
+        }
+        // Local time, sometimes with fractional seconds
+        let modified = field("Modified")
+            .and_then(|m| m.get(..19))
+            .and_then(|m| NaiveDateTime::parse_from_str(m, "%Y-%m-%d %H:%M:%S").ok())
+            .and_then(|t| Local.from_local_datetime(&t).earliest());
+        entries.push(Listed {
+            inner: inner.replace('\\', "/"),
+            size_bytes: size.parse().unwrap_or(0),
+            modified: modified.map(|t| t.to_rfc3339()),
+            crc: field("CRC").and_then(|c| u32::from_str_radix(c, 16).ok()),
+        });
+    }
+    Ok(entries)
+}
+
+// Streams one entry to stdout (`7z e -so`)
+fn external_cat(archive: &Path, inner: &str) -> Result<std::process::Child, AppError> {
+    let mut cmd = seven_zip_command(archive)?;
+    cmd.arg("e")
+        .arg("-so")
+        .arg("--")
+        .arg(archive)
+        .arg(native_name(inner))
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null());
+    cmd.spawn().map_err(|e| AppError::io(&e, archive))
+}
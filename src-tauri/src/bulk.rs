@@ -0,0 +1,82 @@
+use crate::error::{AppError, ErrorKind};
+use crate::{labels, ratings, tags};
+use serde::{Deserialize, Serialize};
+
+// -----------------------------
+// Bulk metadata edits
+// -----------------------------
+// One call applies the same edit to a whole selection. Problems with the
+// edit itself fail the call up front; after that each path reports its own
+// outcome so one bad entry doesn't sink the batch.
+
+#[derive(Deserialize, Default)]
+#[serde(default)]
+pub struct BulkEdit {
+    pub add_tags: Vec<String>, // created on first use
+    pub remove_tags: Vec<String>,
+    pub rating: Option<u8>,    // 0 clears; absent leaves ratings alone
+    pub label: Option<String>, // "" clears; absent leaves labels alone
+}
+
+#[derive(Serialize)]
+pub struct BulkItem {
+    pub path: String,
+    pub ok: bool,
+    pub error: Option<AppError>,
+}
+
+pub fn apply(paths: &[String], edit: &BulkEdit) -> Result<Vec<BulkItem>, AppError> {
+    if edit.rating.is_some_and(|r| r > ratings::MAX_RATING) {
+        return Err(AppError::new(
+            ErrorKind::InvalidInput,
+            format!("Ratings go from 1 to {}", ratings::MAX_RATING),
+        )
+        .with_code("rating_range")
+        .with_param("max", ratings::MAX_RATING));
+    }
+    let label = edit.label.as_deref().map(str::trim);
+    if let Some(name) = label.filter(|l| !l.is_empty()) {
+        labels::check(name)?;
+    }
+    let add = edit
+        .add_tags
+        .iter()
+        .filter(|n| !n.trim().is_empty())
+        .map(|n| tags::assign(&[], n).map(|t| t.id))
+        .collect::<Result<Vec<_>, _>>()?;
+    // Unknown tag names have nothing to remove
+    let known = tags::list()?;
+    let remove: Vec<i64> = edit
+        .remove_tags
+        .iter()
+        .filter_map(|n| known.iter().find(|t| t.name.eq_ignore_ascii_case(n.trim())))
+        .map(|t| t.id)
+        .collect();
+
+    Ok(paths
+        .iter()
+        .map(|p| {
+            let ids = std::slice::from_ref(p);
+            let res = (|| {
+                for id in &add {
+                    tags::attach(ids, *id)?;
+                }
+                for id in &remove {
+                    tags::unassign(ids, *id)?;
+                }
+                if edit.rating.is_some() {
+                    ratings::set(p, edit.rating)?;
+                }
+                if let Some(l) = label {
+                    labels::set(ids, Some(l).filter(|l| !l.is_empty()))?;
+                }
+                Ok::<_, AppError>(())
+            })();
+            BulkItem {
+                path: p.clone(),
+                ok: res.is_ok(),
+                error: res.err(),
+            }
+        })
+        .collect())
+}
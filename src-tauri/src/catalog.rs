@@ -0,0 +1,259 @@
+use crate::error::{AppError, ErrorKind};
+use crate::http_api::{self, error_response, json_response, HttpResponse};
+use crate::jobs::JobCtx;
+use crate::{annotate_user_data, for_each_latest_result, paths, FlatFile};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::time::Duration;
+use tiny_http::Header;
+
+// -----------------------------
+// Shared catalogs
+// -----------------------------
+// One machine publishes the latest results of its scans over the HTTP API
+// (see http_api.rs, then bound to the network) and other instances attach
+// to it instead of rescanning the same asset server. Read-only:
+//
+//   GET /catalog/info                    host, roots, file count
+//   GET /catalog/files?q=…&offset=…      files (no thumbnails), paged
+//   GET /catalog/thumbnail?path=…        embedded thumbnail as PNG
+//
+// Publishing takes a key; requests from other machines need `?key=…` or
+// an `X-Catalog-Key` header. Paths are the publisher's; they open elsewhere
+// when the share is mounted the same way.
+
+pub const PAGE_LIMIT: usize = 2000;
+const TIMEOUT: Duration = Duration::from_secs(10);
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+#[serde(default)]
+pub struct RemoteCatalog {
+    pub name: String,
+    pub url: String, // http://host:port, as shown by the publisher
+    pub key: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct CatalogInfo {
+    pub host: String,
+    pub roots: Vec<String>,
+    pub file_count: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct CatalogPage<F> {
+    pub total: usize,
+    pub files: Vec<F>,
+}
+
+// Job result of `fetch`; files stay JSON, as the publisher sent them
+#[derive(Serialize)]
+pub struct RemoteSnapshot {
+    pub name: String,
+    pub info: CatalogInfo,
+    pub files: Vec<serde_json::Value>,
+}
+
+// -----------------------------
+// Publishing
+// -----------------------------
+fn host_name() -> String {
+    ["COMPUTERNAME", "HOSTNAME"]
+        .iter()
+        .find_map(|v| std::env::var(v).ok().filter(|h| !h.is_empty()))
+        .unwrap_or_else(|| "unknown".to_string())
+}
+
+fn info() -> CatalogInfo {
+    let (mut roots, mut file_count) = (Vec::new(), 0);
+    for_each_latest_result(|result| {
+        roots.extend(
+            result
+                .roots
+                .iter()
+                .map(|r| paths::from_id(r).to_string_lossy().to_string()),
+        );
+        file_count += result.files.len();
+    });
+    roots.sort();
+    CatalogInfo {
+        host: host_name(),
+        roots,
+        file_count,
+    }
+}
+
+fn files(q: &str, offset: usize, limit: usize) -> CatalogPage<FlatFile> {
+    let q = q.to_lowercase();
+    let limit = limit.min(PAGE_LIMIT);
+    let (mut total, mut files) = (0, Vec::new());
+    for_each_latest_result(|result| {
+        for f in &result.files {
+            if !q.is_empty()
+                && !f.name.to_lowercase().contains(&q)
+                && !f.path.to_lowercase().contains(&q)
+            {
+                continue;
+            }
+            if total >= offset && files.len() < limit {
+                files.push(FlatFile {
+                    thumbnail: None,
+                    ..f.clone()
+                });
+            }
+            total += 1;
+        }
+    });
+    annotate_user_data(&mut files);
+    CatalogPage { total, files }
+}
+
+pub fn serve(path: &str, params: &HashMap<String, String>) -> HttpResponse {
+    let num = |k: &str, default: usize| {
+        params
+            .get(k)
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(default)
+    };
+    match path {
+        "/catalog/info" => json_response(200, &info()),
+        "/catalog/files" => {
+            let q = params.get("q").map(String::as_str).unwrap_or("");
+            json_response(200, &files(q, num("offset", 0), num("limit", PAGE_LIMIT)))
+        }
+        "/catalog/thumbnail" => {
+            match params
+                .get("path")
+                .and_then(|p| http_api::find_file(p))
+                .and_then(|f| http_api::thumbnail_png(&f))
+            {
+                Some(png) => HttpResponse::from_data(png)
+                    .with_header(Header::from_bytes("Content-Type", "image/png").unwrap()),
+                None => error_response(404, ErrorKind::NotFound, "No thumbnail"),
+            }
+        }
+        _ => error_response(404, ErrorKind::NotFound, "Unknown endpoint"),
+    }
+}
+
+// -----------------------------
+// Attaching
+// -----------------------------
+pub fn check(catalogs: &[RemoteCatalog]) -> Result<(), AppError> {
+    let mut names = Vec::new();
+    for c in catalogs {
+        if c.name.trim().is_empty() || c.url.trim().is_empty() {
+            return Err(AppError::new(
+                ErrorKind::InvalidInput,
+                "A catalog needs a name and a URL",
+            ));
+        }
+        // https goes through rustls (reqwest's `rustls-tls`)
+        if !c.url.starts_with("http://") && !c.url.starts_with("https://") {
+            return Err(AppError::new(
+                ErrorKind::InvalidInput,
+                format!("\"{}\" is not an http:// or https:// URL", c.url),
+            ));
+        }
+        if names.contains(&c.name) {
+            return Err(AppError::new(
+                ErrorKind::InvalidInput,
+                format!("Two catalogs are named \"{}\"", c.name),
+            ));
+        }
+        names.push(c.name.clone());
+    }
+    Ok(())
+}
+
+fn server_error(remote: &RemoteCatalog, msg: impl std::fmt::Display) -> AppError {
+    AppError::new(ErrorKind::Server, format!("{}: {}", remote.name, msg))
+        .with_code("catalog_unreachable")
+        .with_param("name", &remote.name)
+}
+
+fn send(
+    client: &reqwest::blocking::Client,
+    remote: &RemoteCatalog,
+    endpoint: &str,
+    query: &[(&str, String)],
+) -> Result<reqwest::blocking::Response, AppError> {
+    let url = format!("{}/catalog/{}", remote.url.trim_end_matches('/'), endpoint);
+    let mut req = client.get(url).query(query);
+    if let Some(key) = &remote.key {
+        req = req.header("X-Catalog-Key", key);
+    }
+    req.send().map_err(|e| server_error(remote, e))
+}
+
+fn ok(
+    remote: &RemoteCatalog,
+    resp: reqwest::blocking::Response,
+) -> Result<reqwest::blocking::Response, AppError> {
+    match resp.status().as_u16() {
+        200 => Ok(resp),
+        401 => Err(server_error(remote, "the key was refused")),
+        status => Err(server_error(remote, format!("HTTP {}", status))),
+    }
+}
+
+fn client() -> Result<reqwest::blocking::Client, AppError> {
+    reqwest::blocking::Client::builder()
+        .timeout(TIMEOUT)
+        .build()
+        .map_err(|e| AppError::new(ErrorKind::Server, e.to_string()))
+}
+
+// Downloads the whole file list, page by page
+pub fn fetch(ctx: &JobCtx, remote: &RemoteCatalog) -> Result<RemoteSnapshot, AppError> {
+    let client = client()?;
+    let info: CatalogInfo = ok(remote, send(&client, remote, "info", &[])?)?
+        .json()
+        .map_err(|e| server_error(remote, e))?;
+    ctx.set_totals(info.file_count as u64, 0);
+    let mut files = Vec::new();
+    loop {
+        ctx.check_cancelled()?;
+        let query = [
+            ("offset", files.len().to_string()),
+            ("limit", PAGE_LIMIT.to_string()),
+        ];
+        let page: CatalogPage<serde_json::Value> =
+            ok(remote, send(&client, remote, "files", &query)?)?
+                .json()
+                .map_err(|e| server_error(remote, e))?;
+        let got = page.files.len();
+        files.extend(page.files);
+        for _ in 0..got {
+            ctx.item_done();
+        }
+        if got == 0 || files.len() >= page.total {
+            break;
+        }
+    }
+    tracing::info!(catalog = %remote.name, files = files.len(), "remote catalog loaded");
+    Ok(RemoteSnapshot {
+        name: remote.name.clone(),
+        info,
+        files,
+    })
+}
+
+// Base64 PNG, or None when the file has no thumbnail
+pub fn thumbnail(remote: &RemoteCatalog, path_id: &str) -> Result<Option<String>, AppError> {
+    use base64::prelude::*;
+    let client = client()?;
+    let resp = send(
+        &client,
+        remote,
+        "thumbnail",
+        &[("path", path_id.to_string())],
+    )?;
+    if resp.status().as_u16() == 404 {
+        return Ok(None);
+    }
+    let png = ok(remote, resp)?
+        .bytes()
+        .map_err(|e| server_error(remote, e))?;
+    Ok(Some(BASE64_STANDARD.encode(png)))
+}
@@ -0,0 +1,222 @@
+use crate::db;
+use crate::error::AppError;
+use crate::scan::{FoundBlend, ParseDepth};
+use crate::{paths, BlendInfo};
+use rusqlite::params;
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+// -----------------------------
+// Checkpoints of running scans
+// -----------------------------
+// Parsed files are written to the index in batches while a scan runs. A
+// scan of the same root after a crash or forced quit reuses what was parsed
+// for files whose size and mtime still match, instead of reading them all
+// again.
+//
+// Once the scan finishes, its checkpoint moves into `file_index`, which
+// keeps the parsed info of every file of the root between launches. Scans
+// with `use_index` (see `rescan_indexed`) reuse it the same way, so a
+// rescan only reads the files that changed.
+
+const BATCH_SIZE: usize = 256;
+const BATCH_INTERVAL: Duration = Duration::from_secs(5);
+
+struct Row {
+    path_id: String,
+    size_bytes: u64,
+    modified: Option<String>,
+    blend: String, // BlendInfo as JSON
+}
+
+pub(crate) struct Checkpointer {
+    root_id: String,
+    depth: ParseDepth,
+    pending: Mutex<(Vec<Row>, Instant)>,
+}
+
+impl Checkpointer {
+    pub fn new(root: &Path, depth: ParseDepth) -> Self {
+        Self {
+            root_id: paths::to_id(root),
+            depth,
+            pending: Mutex::new((Vec::new(), Instant::now())),
+        }
+    }
+
+    // Queues a parsed file; the caller that fills a batch writes it
+    pub fn add(&self, fb: &FoundBlend) {
+        // Failed files are read again, so their errors are reported again
+        if fb.blend.error.is_some() {
+            return;
+        }
+        let Ok(blend) = serde_json::to_string(&fb.blend) else {
+            return;
+        };
+        let batch = {
+            let mut pending = self.pending.lock().unwrap();
+            pending.0.push(Row {
+                path_id: paths::to_id(&fb.path),
+                size_bytes: fb.size_bytes,
+                modified: fb.modified.clone(),
+                blend,
+            });
+            if pending.0.len() < BATCH_SIZE && pending.1.elapsed() < BATCH_INTERVAL {
+                return;
+            }
+            pending.1 = Instant::now();
+            std::mem::take(&mut pending.0)
+        };
+        self.write(&batch);
+    }
+
+    pub fn flush(&self) {
+        let batch = std::mem::take(&mut self.pending.lock().unwrap().0);
+        self.write(&batch);
+    }
+
+    fn write(&self, batch: &[Row]) {
+        if batch.is_empty() {
+            return;
+        }
+        let res = db::with(|conn| {
+            let tx = conn.transaction()?;
+            {
+                let mut stmt = tx.prepare(
+                    "INSERT OR REPLACE INTO scan_checkpoints
+                     (root_id, path_id, size_bytes, modified, depth, blend)
+                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                )?;
+                for r in batch {
+                    stmt.execute(params![
+                        self.root_id,
+                        r.path_id,
+                        r.size_bytes,
+                        r.modified,
+                        self.depth.level(),
+                        r.blend
+                    ])?;
+                }
+            }
+            tx.commit()
+        });
+        if let Err(e) = res {
+            tracing::warn!("writing scan checkpoint failed: {}", e);
+        }
+    }
+}
+
+struct Saved {
+    size_bytes: u64,
+    modified: Option<String>,
+    depth: u8, // ParseDepth::level
+    blend: BlendInfo,
+}
+
+type SavedRow = (String, u64, Option<String>, u8, String);
+
+fn saved_rows(table: &str, root_id: &str) -> Vec<SavedRow> {
+    db::with(|conn| {
+        let mut stmt = conn.prepare(&format!(
+            "SELECT path_id, size_bytes, modified, depth, blend
+             FROM {} WHERE root_id = ?1",
+            table
+        ))?;
+        let rows = stmt.query_map([root_id], |r| {
+            Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?))
+        })?;
+        rows.collect::<rusqlite::Result<Vec<_>>>()
+    })
+    .unwrap_or_else(|e| {
+        tracing::warn!("reading {} failed: {}", table, e);
+        Vec::new()
+    })
+}
+
+// What an interrupted scan of `root` had parsed, by path id, on top of the
+// index of its last finished scan when `use_index` is set
+pub(crate) struct Resumed(HashMap<String, Saved>);
+
+impl Resumed {
+    pub fn load(root: &Path, use_index: bool) -> Self {
+        let root_id = paths::to_id(root);
+        let mut rows = if use_index {
+            saved_rows("file_index", &root_id)
+        } else {
+            Vec::new()
+        };
+        let indexed = rows.len();
+        rows.extend(saved_rows("scan_checkpoints", &root_id));
+        let saved: HashMap<String, Saved> = rows
+            .into_iter()
+            .filter_map(|(path_id, size_bytes, modified, depth, blend)| {
+                let blend = serde_json::from_str(&blend).ok()?;
+                Some((
+                    path_id,
+                    Saved {
+                        size_bytes,
+                        modified,
+                        depth,
+                        blend,
+                    },
+                ))
+            })
+            .collect();
+        if saved.len() > indexed {
+            tracing::info!(root = %root_id, files = saved.len() - indexed, "resuming interrupted scan");
+        }
+        if indexed > 0 {
+            tracing::info!(root = %root_id, files = indexed, "reusing indexed files");
+        }
+        Self(saved)
+    }
+
+    // Parsed info for `fb` if the file is unchanged since it was saved and
+    // was read to at least `depth`
+    pub fn get(&self, fb: &FoundBlend, depth: ParseDepth) -> Option<&BlendInfo> {
+        if self.0.is_empty() {
+            return None;
+        }
+        self.0
+            .get(&paths::to_id(&fb.path))
+            .filter(|s| s.size_bytes == fb.size_bytes && s.modified == fb.modified)
+            .filter(|s| s.depth >= depth.level())
+            .map(|s| &s.blend)
+    }
+}
+
+// The scan of `root` finished with `found` (path ids): what it parsed joins
+// the index, files it no longer found leave it, and the checkpoint goes
+pub(crate) fn commit(root: &Path, found: &HashSet<String>) -> Result<(), AppError> {
+    let root_id = paths::to_id(root);
+    db::with(|conn| {
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO file_index
+             (root_id, path_id, size_bytes, modified, depth, blend)
+             SELECT root_id, path_id, size_bytes, modified, depth, blend
+             FROM scan_checkpoints WHERE root_id = ?1",
+            [&root_id],
+        )?;
+        let gone: Vec<String> = {
+            let mut stmt = tx.prepare("SELECT path_id FROM file_index WHERE root_id = ?1")?;
+            let ids = stmt.query_map([&root_id], |r| r.get::<_, String>(0))?;
+            ids.filter(|id| id.as_ref().is_ok_and(|id| !found.contains(id)))
+                .collect::<rusqlite::Result<_>>()?
+        };
+        {
+            let mut stmt =
+                tx.prepare("DELETE FROM file_index WHERE root_id = ?1 AND path_id = ?2")?;
+            for id in &gone {
+                stmt.execute(params![root_id, id])?;
+            }
+        }
+        tx.execute(
+            "DELETE FROM scan_checkpoints WHERE root_id = ?1",
+            [&root_id],
+        )?;
+        tx.commit()
+    })
+}
@@ -0,0 +1,34 @@
+use std::fs::Metadata;
+
+// -----------------------------
+// Cloud placeholders (OneDrive, Dropbox, Google Drive, iCloud)
+// -----------------------------
+// Online-only files report their full size but reading them triggers a
+// download. Detection only looks at metadata, which never hydrates.
+
+#[cfg(windows)]
+pub fn is_placeholder(meta: &Metadata) -> bool {
+    use std::os::windows::fs::MetadataExt;
+    const FILE_ATTRIBUTE_OFFLINE: u32 = 0x1000;
+    const FILE_ATTRIBUTE_RECALL_ON_OPEN: u32 = 0x40000;
+    const FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS: u32 = 0x400000;
+    meta.file_attributes()
+        & (FILE_ATTRIBUTE_OFFLINE
+            | FILE_ATTRIBUTE_RECALL_ON_OPEN
+            | FILE_ATTRIBUTE_RECALL_ON_DATA_ACCESS)
+        != 0
+}
+
+// File Provider (iCloud Drive, Dropbox, Google Drive for desktop)
+#[cfg(target_os = "macos")]
+pub fn is_placeholder(meta: &Metadata) -> bool {
+    use std::os::macos::fs::MetadataExt;
+    const SF_DATALESS: u32 = 0x4000_0000;
+    meta.st_flags() & SF_DATALESS != 0
+}
+
+// No standard placeholder flag; FUSE sync clients download transparently
+#[cfg(not(any(windows, target_os = "macos")))]
+pub fn is_placeholder(_meta: &Metadata) -> bool {
+    false
+}
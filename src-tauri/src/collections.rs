@@ -0,0 +1,301 @@
+use crate::db;
+use crate::error::{AppError, ErrorKind};
+use crate::paths;
+use crate::query::Query;
+use crate::{annotate_user_data, for_each_latest_result, FlatFile};
+use chrono::Local;
+use rusqlite::params;
+use serde::Serialize;
+use std::collections::HashMap;
+
+// -----------------------------
+// Virtual collections
+// -----------------------------
+// Named sets of files independent of folder structure ("all props for
+// Episode 3"). Members are path ids; a member whose file is gone stays
+// listed until removed. Smart collections instead hold a saved query
+// (see query.rs) that is re-run against the latest scans when listed.
+
+#[derive(Serialize, Clone)]
+pub struct Collection {
+    pub id: i64,
+    pub name: String,
+    pub created_at: String,
+    pub file_count: u64, // members; 0 for smart collections
+    pub query: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct CollectionEntry {
+    pub path: String,
+    pub path_id: String,
+    pub name: String,
+    pub added_at: Option<String>, // None for smart collection matches
+    pub exists: bool,
+    pub file: Option<FlatFile>, // from the newest scan that indexed it
+}
+
+fn clean_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(
+            AppError::new(ErrorKind::InvalidInput, "Collection name can't be empty")
+                .with_code("collection_name_empty"),
+        );
+    }
+    Ok(name.to_string())
+}
+
+fn duplicate(name: &str) -> AppError {
+    AppError::new(
+        ErrorKind::InvalidInput,
+        format!("A collection named \"{}\" already exists", name),
+    )
+    .with_code("collection_exists")
+    .with_param("name", name)
+}
+
+fn is_unique_violation(e: &rusqlite::Error) -> bool {
+    matches!(
+        e.sqlite_error_code(),
+        Some(rusqlite::ErrorCode::ConstraintViolation)
+    )
+}
+
+pub fn list() -> Result<Vec<Collection>, AppError> {
+    db::with(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT c.id, c.name, c.created_at, COUNT(cf.path_id), c.query
+             FROM collections c LEFT JOIN collection_files cf ON cf.collection_id = c.id
+             GROUP BY c.id ORDER BY c.name COLLATE NOCASE",
+        )?;
+        let rows = stmt.query_map([], |r| {
+            Ok(Collection {
+                id: r.get(0)?,
+                name: r.get(1)?,
+                created_at: r.get(2)?,
+                file_count: r.get(3)?,
+                query: r.get(4)?,
+            })
+        })?;
+        rows.collect()
+    })
+}
+
+fn get(id: i64) -> Result<Collection, AppError> {
+    list()?.into_iter().find(|c| c.id == id).ok_or_else(|| {
+        AppError::new(ErrorKind::NotFound, format!("Collection {} not found", id))
+            .with_code("collection_not_found")
+            .with_param("id", id)
+    })
+}
+
+fn clean_query(query: &str) -> Result<String, AppError> {
+    Query::parse(query)?;
+    Ok(query.trim().to_string())
+}
+
+// `query` makes it a smart collection
+pub fn create(name: &str, query: Option<&str>) -> Result<Collection, AppError> {
+    let name = clean_name(name)?;
+    let query = query.map(clean_query).transpose()?;
+    let id = db::with(|conn| {
+        match conn.execute(
+            "INSERT INTO collections (name, created_at, query) VALUES (?1, ?2, ?3)",
+            params![name, Local::now().to_rfc3339(), query],
+        ) {
+            Err(e) if is_unique_violation(&e) => Ok(None),
+            other => other.map(|_| Some(conn.last_insert_rowid())),
+        }
+    })?
+    .ok_or_else(|| duplicate(&name))?;
+    get(id)
+}
+
+pub fn rename(id: i64, name: &str) -> Result<Collection, AppError> {
+    let name = clean_name(name)?;
+    db::with(|conn| {
+        match conn.execute(
+            "UPDATE collections SET name = ?1 WHERE id = ?2",
+            params![name, id],
+        ) {
+            Err(e) if is_unique_violation(&e) => Ok(None),
+            other => other.map(Some),
+        }
+    })?
+    .ok_or_else(|| duplicate(&name))?;
+    get(id)
+}
+
+pub fn set_query(id: i64, query: &str) -> Result<Collection, AppError> {
+    if get(id)?.query.is_none() {
+        return Err(AppError::new(
+            ErrorKind::InvalidInput,
+            "Only smart collections have a query",
+        ));
+    }
+    let query = clean_query(query)?;
+    db::with(|conn| {
+        conn.execute(
+            "UPDATE collections SET query = ?1 WHERE id = ?2",
+            params![query, id],
+        )
+    })?;
+    get(id)
+}
+
+// Smart collections can't take members by hand
+fn manual(id: i64) -> Result<(), AppError> {
+    match get(id)?.query {
+        Some(_) => Err(AppError::new(
+            ErrorKind::InvalidInput,
+            "Smart collections are defined by their query",
+        )),
+        None => Ok(()),
+    }
+}
+
+// Members go with the collection; the files themselves are untouched
+pub fn delete(id: i64) -> Result<(), AppError> {
+    db::with(|conn| conn.execute("DELETE FROM collections WHERE id = ?1", [id])).map(|_| ())
+}
+
+pub fn add(id: i64, path_ids: &[String]) -> Result<Collection, AppError> {
+    manual(id)?;
+    let now = Local::now().to_rfc3339();
+    db::with(|conn| {
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR IGNORE INTO collection_files (collection_id, path_id, added_at)
+                 VALUES (?1, ?2, ?3)",
+            )?;
+            for p in path_ids {
+                stmt.execute(params![id, p, now])?;
+            }
+        }
+        tx.commit()
+    })?;
+    get(id)
+}
+
+pub fn remove(id: i64, path_ids: &[String]) -> Result<Collection, AppError> {
+    manual(id)?;
+    db::with(|conn| {
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "DELETE FROM collection_files WHERE collection_id = ?1 AND path_id = ?2",
+            )?;
+            for p in path_ids {
+                stmt.execute(params![id, p])?;
+            }
+        }
+        tx.commit()
+    })?;
+    get(id)
+}
+
+// Newest first; smart collections in scan order
+pub fn files(id: i64) -> Result<Vec<CollectionEntry>, AppError> {
+    if let Some(query) = get(id)?.query {
+        return run_query(&Query::parse(&query)?);
+    }
+    let members: Vec<(String, String)> = db::with(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT path_id, added_at FROM collection_files
+             WHERE collection_id = ?1 ORDER BY added_at DESC",
+        )?;
+        let rows = stmt.query_map([id], |r| Ok((r.get(0)?, r.get(1)?)))?;
+        rows.collect()
+    })?;
+
+    let mut indexed: HashMap<String, FlatFile> = HashMap::new();
+    for_each_latest_result(|result| {
+        for f in &result.files {
+            if members.iter().any(|(p, _)| *p == f.path_id) {
+                indexed
+                    .entry(f.path_id.clone())
+                    .or_insert_with(|| f.clone());
+            }
+        }
+    });
+
+    let mut entries: Vec<CollectionEntry> = members
+        .into_iter()
+        .map(|(path_id, added_at)| {
+            let path = paths::from_id(&path_id);
+            CollectionEntry {
+                name: path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                path: path.to_string_lossy().to_string(),
+                exists: path.is_file(),
+                file: indexed.remove(&path_id),
+                path_id,
+                added_at: Some(added_at),
+            }
+        })
+        .collect();
+    for e in &mut entries {
+        if let Some(f) = e.file.as_mut() {
+            annotate_user_data(std::slice::from_mut(f));
+        }
+    }
+    Ok(entries)
+}
+
+// Files of the latest scans matching `query`, each listed once
+fn run_query(query: &Query) -> Result<Vec<CollectionEntry>, AppError> {
+    let mut files: Vec<FlatFile> = Vec::new();
+    let mut seen = std::collections::HashSet::new();
+    for_each_latest_result(|result| {
+        for f in &result.files {
+            if seen.insert(f.path_id.clone()) {
+                files.push(f.clone());
+            }
+        }
+    });
+    annotate_user_data(&mut files);
+    Ok(files
+        .into_iter()
+        .filter(|f| query.matches(f))
+        .map(|f| CollectionEntry {
+            path: f.path.clone(),
+            path_id: f.path_id.clone(),
+            name: f.name.clone(),
+            added_at: None,
+            exists: true,
+            file: Some(f),
+        })
+        .collect())
+}
+
+// Fills `collections` on result files with the names of those holding them
+pub fn annotate(files: &mut [FlatFile]) {
+    let members = db::with(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT cf.path_id, c.name FROM collection_files cf
+             JOIN collections c ON c.id = cf.collection_id
+             ORDER BY c.name COLLATE NOCASE",
+        )?;
+        let mut map: HashMap<String, Vec<String>> = HashMap::new();
+        let rows = stmt.query_map([], |r| Ok((r.get::<_, String>(0)?, r.get(1)?)))?;
+        for row in rows {
+            let (path_id, name) = row?;
+            map.entry(path_id).or_default().push(name);
+        }
+        Ok(map)
+    });
+    let map = match members {
+        Ok(map) => map,
+        Err(e) => {
+            tracing::warn!("loading collections failed: {}", e);
+            return;
+        }
+    };
+    for f in files {
+        f.collections = map.get(&f.path_id).cloned().unwrap_or_default();
+    }
+}
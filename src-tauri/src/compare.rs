@@ -0,0 +1,216 @@
+use crate::error::{AppError, ErrorKind};
+use crate::magic::{self, Compression};
+use crate::{archive, cloud, contents, handles, parse_blocks, paths, read_blend_header};
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs;
+use std::io::BufReader;
+use std::path::Path;
+
+// -----------------------------
+// Comparing two blends
+// -----------------------------
+// For "final_v7" against "final_v7_FIXED": both files are read fresh, not
+// from the scan, and only what differs is listed. Counts are datablocks
+// per type (scenes, objects, materials…); dependencies are linked
+// libraries and world images, as saved. Compressed blends and online-only
+// files are compared by their file data only.
+
+#[derive(Serialize, Clone, Default)]
+pub struct Side {
+    pub path: String,
+    pub path_id: String,
+    pub name: String,
+    pub size_bytes: u64,
+    pub modified: Option<String>,
+    pub version: Option<String>,
+    pub compression: &'static str,
+    pub render_engine: Option<String>,
+    pub counts: BTreeMap<String, usize>,
+    pub dependencies: Vec<String>,
+    pub thumbnail: Option<String>, // Base64 RGBA
+    pub thumb_width: Option<i32>,
+    pub thumb_height: Option<i32>,
+    pub note: Option<String>, // why the contents were not read
+}
+
+#[derive(Serialize, Clone)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub a: Option<String>,
+    pub b: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct CountChange {
+    pub id_type: String,
+    pub a: usize,
+    pub b: usize,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Comparison {
+    pub a: Side,
+    pub b: Side,
+    pub fields: Vec<FieldChange>,
+    pub counts: Vec<CountChange>,
+    pub only_a: Vec<String>, // dependencies
+    pub only_b: Vec<String>,
+    pub same_thumbnail: bool,
+}
+
+fn compression_name(c: Compression) -> &'static str {
+    match c {
+        Compression::None => "none",
+        Compression::Gzip => "gzip",
+        Compression::Zstd => "zstd",
+    }
+}
+
+// Header, thumbnail and the block contents of an uncompressed blend
+fn read_contents(path: &Path, side: &mut Side) -> Result<(), AppError> {
+    let mut info = read_blend_header(path)?;
+    let ptr_size = info.pointer_size;
+    let file = handles::open(path)?;
+    let mut reader = BufReader::new(&*file);
+    let index = parse_blocks(&mut info, &mut reader, ptr_size, true)
+        .map_err(|e| AppError::new(ErrorKind::CorruptFile, e.to_string()).with_path(path))?;
+    side.version = info.version.take();
+    side.thumbnail = info.thumbnail.take();
+    side.thumb_width = info.thumb_width;
+    side.thumb_height = info.thumb_height;
+    side.render_engine = info.render_engine.take();
+    match index {
+        Some(index) => {
+            if let Some(id) = contents::render_engine(&index, &mut reader) {
+                side.render_engine = Some(contents::engine_name(&id));
+            }
+            side.counts = contents::id_counts(&index);
+            side.dependencies = contents::libraries(&index, &mut reader);
+            side.dependencies.extend(
+                contents::environments(&index, &mut reader)
+                    .into_iter()
+                    .filter(|e| !e.packed)
+                    .map(|e| e.path),
+            );
+        }
+        None => side.note = Some("No struct layouts (DNA1); contents not read".to_string()),
+    }
+    Ok(())
+}
+
+fn read_side(path: &Path) -> Result<Side, AppError> {
+    if archive::split(path).is_some() {
+        return Err(AppError::new(
+            ErrorKind::InvalidInput,
+            "Extract the file from its archive to compare it",
+        )
+        .with_code("compare_archive")
+        .with_path(path));
+    }
+    let meta = fs::metadata(path).map_err(|e| AppError::io(&e, path))?;
+    let mut side = Side {
+        path: path.to_string_lossy().to_string(),
+        path_id: paths::to_id(path),
+        name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        size_bytes: meta.len(),
+        modified: meta
+            .modified()
+            .ok()
+            .map(|t| DateTime::<Local>::from(t).to_rfc3339()),
+        compression: "unknown",
+        ..Default::default()
+    };
+    // Reading a placeholder would download it
+    if cloud::is_placeholder(&meta) {
+        side.note = Some("Online-only; download it to compare the contents".to_string());
+        return Ok(side);
+    }
+    let compression = magic::compression(path)?;
+    side.compression = compression_name(compression);
+    if compression != Compression::None {
+        side.note = Some("Compressed; contents not read".to_string());
+        return Ok(side);
+    }
+    if let Err(e) = read_contents(path, &mut side) {
+        side.note = Some(e.message);
+    }
+    Ok(side)
+}
+
+fn field(
+    changes: &mut Vec<FieldChange>,
+    field: &'static str,
+    a: Option<String>,
+    b: Option<String>,
+) {
+    if a != b {
+        changes.push(FieldChange { field, a, b });
+    }
+}
+
+pub fn compare(a: &Path, b: &Path) -> Result<Comparison, AppError> {
+    let (a, b) = (read_side(a)?, read_side(b)?);
+
+    let mut fields = Vec::new();
+    field(&mut fields, "version", a.version.clone(), b.version.clone());
+    field(
+        &mut fields,
+        "size",
+        Some(a.size_bytes.to_string()),
+        Some(b.size_bytes.to_string()),
+    );
+    field(
+        &mut fields,
+        "modified",
+        a.modified.clone(),
+        b.modified.clone(),
+    );
+    field(
+        &mut fields,
+        "compression",
+        Some(a.compression.to_string()),
+        Some(b.compression.to_string()),
+    );
+    field(
+        &mut fields,
+        "render_engine",
+        a.render_engine.clone(),
+        b.render_engine.clone(),
+    );
+
+    let types: BTreeSet<&String> = a.counts.keys().chain(b.counts.keys()).collect();
+    let counts = types
+        .into_iter()
+        .map(|t| CountChange {
+            id_type: t.clone(),
+            a: a.counts.get(t).copied().unwrap_or(0),
+            b: b.counts.get(t).copied().unwrap_or(0),
+        })
+        .filter(|c| c.a != c.b)
+        .collect();
+
+    let only = |x: &Side, y: &Side| -> Vec<String> {
+        x.dependencies
+            .iter()
+            .filter(|d| !y.dependencies.contains(d))
+            .cloned()
+            .collect()
+    };
+    let (only_a, only_b) = (only(&a, &b), only(&b, &a));
+    let same_thumbnail = a.thumbnail == b.thumbnail;
+    tracing::debug!(a = %a.path, b = %b.path, fields = fields.len(), "compared files");
+    Ok(Comparison {
+        fields,
+        counts,
+        only_a,
+        only_b,
+        same_thumbnail,
+        a,
+        b,
+    })
+}
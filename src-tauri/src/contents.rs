@@ -0,0 +1,764 @@
+use crate::sdna::{Field, Sdna, View};
+use crate::BlockReader;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+// -----------------------------
+// What a blend contains
+// -----------------------------
+// The block scan records where each block sits; once the struct layouts
+// (DNA1, at the end of the file) are known, the blocks of interest are
+// read back. That needs a seekable file, so archive entries, which are
+// streamed, skip this part.
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct AssetInfo {
+    pub name: String,
+    pub code: String,            // ID code, e.g. `MA`
+    pub id_type: String,         // readable type, e.g. `Material`
+    pub catalog: Option<String>, // the catalog's simple name
+    pub has_preview: bool,
+}
+
+#[derive(Serialize, Clone)]
+pub struct AssetPreview {
+    pub data: String, // Base64 RGBA
+    pub width: i32,
+    pub height: i32,
+}
+
+pub(crate) struct BlockHead {
+    pub code: [u8; 4],
+    pub size: u64,
+    pub old: u64, // address when saved; pointers in other blocks refer to it
+    pub sdna: u32,
+    pub offset: u64, // of the body
+}
+
+impl BlockHead {
+    // Datablocks (`OB\0\0`, `MA\0\0`…) as opposed to `DATA`, `REND`…
+    fn is_id(&self) -> bool {
+        self.code[2] == 0 && self.code[3] == 0 && self.code[0] != 0
+    }
+
+    fn id_code(&self) -> String {
+        String::from_utf8_lossy(&self.code[..2]).to_string()
+    }
+}
+
+pub(crate) struct Index {
+    heads: Vec<BlockHead>,
+    by_old: HashMap<u64, usize>,
+    sdna: Sdna,
+    little: bool,
+}
+
+// Larger bodies are never read back whole
+const MAX_READ: u64 = 16 * 1024 * 1024;
+// Guards walks over linked lists against cycles in damaged files
+const MAX_LIST_ITEMS: usize = 100_000;
+
+impl Index {
+    pub fn new(heads: Vec<BlockHead>, sdna: Sdna, little: bool) -> Self {
+        let by_old = heads
+            .iter()
+            .enumerate()
+            .filter(|(_, h)| h.old != 0)
+            .map(|(i, h)| (h.old, i))
+            .collect();
+        Self {
+            heads,
+            by_old,
+            sdna,
+            little,
+        }
+    }
+
+    fn read(&self, file: &mut impl BlockReader, head: &BlockHead, len: u64) -> Option<Vec<u8>> {
+        let len = len.min(head.size);
+        if len > MAX_READ {
+            return None;
+        }
+        let mut buf = vec![0u8; len as usize];
+        file.read_at(head.offset, &mut buf).ok()?;
+        Some(buf)
+    }
+
+    // Block a saved pointer refers to
+    fn deref(&self, ptr: u64) -> Option<&BlockHead> {
+        self.heads.get(*self.by_old.get(&ptr)?)
+    }
+
+    fn view<'a>(&self, data: &'a [u8]) -> View<'a> {
+        View {
+            data,
+            little: self.little,
+        }
+    }
+
+    fn ids(&self) -> impl Iterator<Item = &BlockHead> {
+        self.heads.iter().filter(|h| h.is_id())
+    }
+}
+
+// Readable names for ID codes
+fn id_type_name(code: &str) -> &str {
+    match code {
+        "AC" => "Action",
+        "AR" => "Armature",
+        "BR" => "Brush",
+        "CA" => "Camera",
+        "CF" => "Cache File",
+        "CU" => "Curve",
+        "CV" => "Curves",
+        "GD" | "GP" => "Grease Pencil",
+        "GR" => "Collection",
+        "IM" => "Image",
+        "KE" => "Shape Key",
+        "LA" => "Light",
+        "LI" => "Library",
+        "LP" => "Light Probe",
+        "LS" => "Line Style",
+        "LT" => "Lattice",
+        "MA" => "Material",
+        "MB" => "Metaball",
+        "MC" => "Movie Clip",
+        "ME" => "Mesh",
+        "MS" => "Mask",
+        "NT" => "Node Group",
+        "OB" => "Object",
+        "PA" => "Particles",
+        "PC" => "Paint Curve",
+        "PL" => "Palette",
+        "PT" => "Point Cloud",
+        "SC" => "Scene",
+        "SO" => "Sound",
+        "SK" => "Speaker",
+        "TE" => "Texture",
+        "TX" => "Text",
+        "VO" => "Volume",
+        "WO" => "World",
+        "WS" => "Workspace",
+        _ => code,
+    }
+}
+
+// -----------------------------
+// Assets
+// -----------------------------
+
+// Datablocks marked as assets, in file order
+pub(crate) fn assets(index: &Index, file: &mut impl BlockReader) -> Vec<AssetInfo> {
+    let sdna = &index.sdna;
+    let (asset_data, name) = (sdna.field("ID", "asset_data"), sdna.field("ID", "name"));
+    if asset_data.is_none() {
+        return Vec::new(); // saved before asset marking existed
+    }
+    let catalog = sdna.field("AssetMetaData", "catalog_simple_name");
+
+    let mut out = Vec::new();
+    for head in index.ids() {
+        // Datablock structs are small; their arrays live in `DATA` blocks
+        let Some(data) = index.read(file, head, head.size) else {
+            continue;
+        };
+        let id = index.view(&data);
+        let Some(meta_ptr) = id.uint(asset_data).filter(|&p| p != 0) else {
+            continue;
+        };
+        // `name` starts with the two-letter code
+        let Some(full) = id.str(name) else { continue };
+        let catalog = index
+            .deref(meta_ptr)
+            .and_then(|h| index.read(file, h, h.size))
+            .and_then(|meta| index.view(&meta).str(catalog));
+        let has_preview = id.uint(preview_field(index, head)).is_some_and(|p| p != 0);
+        let code = head.id_code();
+        out.push(AssetInfo {
+            name: full.get(2..).unwrap_or_default().to_string(),
+            id_type: id_type_name(&code).to_string(),
+            code,
+            catalog,
+            has_preview,
+        });
+    }
+    out
+}
+
+// The datablock's `PreviewImage` pointer, for types that have one
+fn preview_field(index: &Index, head: &BlockHead) -> Option<Field> {
+    let owner = index.sdna.struct_name(head.sdna)?;
+    index.sdna.field(owner, "preview").filter(|f| f.pointer)
+}
+
+// Large preview of the asset `code` + `name`
+pub(crate) fn asset_preview(
+    index: &Index,
+    file: &mut impl BlockReader,
+    code: &str,
+    name: &str,
+) -> Option<AssetPreview> {
+    use base64::prelude::*;
+
+    let sdna = &index.sdna;
+    let id_name = sdna.field("ID", "name");
+    let wanted = format!("{}{}", code, name);
+    let (head, data) = index
+        .ids()
+        .filter(|h| h.id_code() == code)
+        .filter_map(|h| Some((h, index.read(file, h, h.size)?)))
+        .find(|(_, d)| index.view(d).str(id_name).is_some_and(|n| n == wanted))?;
+    let prv_ptr = index
+        .view(&data)
+        .uint(preview_field(index, head))
+        .filter(|&p| p != 0)?;
+    let prv_head = index.deref(prv_ptr)?;
+    let prv = index.read(file, prv_head, prv_head.size)?;
+    let prv = index.view(&prv);
+
+    // Index 1 is the large preview; `w`/`h` are uint, `rect` pointers
+    let (w, h) = (
+        prv.item(sdna.field("PreviewImage", "w"), 1, 4)?,
+        prv.item(sdna.field("PreviewImage", "h"), 1, 4)?,
+    );
+    let rect_ptr = prv.item(sdna.field("PreviewImage", "rect"), 1, sdna.ptr_size())?;
+    let len = w * h * 4;
+    if len == 0 || len > MAX_READ {
+        return None;
+    }
+    let rect_head = index.deref(rect_ptr).filter(|r| r.size >= len)?;
+    let rgba = index.read(file, rect_head, len)?;
+    Some(AssetPreview {
+        data: BASE64_STANDARD.encode(&rgba),
+        width: w as i32,
+        height: h as i32,
+    })
+}
+
+// Blocks of a linked list (`ListBase`) from its first item, in order
+fn list_items<'a>(
+    index: &'a Index,
+    file: &mut impl BlockReader,
+    first: u64,
+    next: Option<Field>,
+) -> Vec<(&'a BlockHead, Vec<u8>)> {
+    let mut out: Vec<(&BlockHead, Vec<u8>)> = Vec::new();
+    let mut ptr = first;
+    while ptr != 0 && out.len() < MAX_LIST_ITEMS {
+        let Some(head) = index.deref(ptr) else { break };
+        let Some(data) = index.read(file, head, head.size) else {
+            break;
+        };
+        ptr = index.view(&data).uint(next).unwrap_or(0);
+        out.push((head, data));
+    }
+    out
+}
+
+// -----------------------------
+// Compositing
+// -----------------------------
+
+// What a new scene's tree holds; only other nodes do work on the farm
+const DEFAULT_COMP_NODES: &[&str] = &[
+    "CompositorNodeRLayers",
+    "CompositorNodeComposite",
+    "CompositorNodeViewer",
+];
+
+// RenderData.scemode: Post Processing > Compositing
+const R_DOCOMP: u64 = 1 << 10;
+
+// Whether a scene renders through a compositor tree beyond the default one
+pub(crate) fn compositing(index: &Index, file: &mut impl BlockReader) -> bool {
+    let sdna = &index.sdna;
+    let (use_nodes, nodetree, group, scemode) = (
+        sdna.field("Scene", "use_nodes"),
+        sdna.field("Scene", "nodetree"),
+        sdna.field("Scene", "compositing_node_group"),
+        sdna.field("Scene", "r.scemode"),
+    );
+    let (first, next, idname) = (
+        sdna.field("bNodeTree", "nodes.first"),
+        sdna.field("bNode", "next"),
+        sdna.field("bNode", "idname"),
+    );
+    let scenes: Vec<&BlockHead> = index.ids().filter(|h| h.id_code() == "SC").collect();
+    scenes.into_iter().any(|head| {
+        let Some(data) = index.read(file, head, head.size) else {
+            return false;
+        };
+        let scene = index.view(&data);
+        if scene.uint(scemode).is_some_and(|m| m & R_DOCOMP == 0) {
+            return false;
+        }
+        // Blender 5 links a node group instead of embedding a tree behind
+        // `use_nodes`
+        let tree = match scene.uint(group).filter(|&p| p != 0) {
+            Some(p) => p,
+            None if scene.uint(use_nodes) == Some(0) => return false,
+            None => scene.uint(nodetree).unwrap_or(0),
+        };
+        let Some(tree) = index.deref(tree).and_then(|h| index.read(file, h, h.size)) else {
+            return false;
+        };
+        let Some(first) = index.view(&tree).uint(first) else {
+            return false;
+        };
+        list_items(index, file, first, next)
+            .iter()
+            .any(|(_, node)| {
+                index
+                    .view(node)
+                    .str(idname)
+                    .is_some_and(|n| !DEFAULT_COMP_NODES.contains(&n.as_str()))
+            })
+    })
+}
+
+// -----------------------------
+// Auto-pack
+// -----------------------------
+
+// FileGlobal.fileflags: File > External Data > Automatically Pack Resources
+const G_FILE_AUTOPACK: u64 = 1 << 0;
+
+// Whether every save packs external files into the blend
+pub(crate) fn autopack(index: &Index, file: &mut impl BlockReader) -> bool {
+    let Some(head) = index.heads.iter().find(|h| &h.code == b"GLOB") else {
+        return false;
+    };
+    let flags = index.sdna.field("FileGlobal", "fileflags");
+    index
+        .read(file, head, head.size)
+        .and_then(|data| index.view(&data).uint(flags))
+        .is_some_and(|f| f & G_FILE_AUTOPACK != 0)
+}
+
+// -----------------------------
+// Simulation caches
+// -----------------------------
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct SimCache {
+    pub kind: String, // Cloth, Fluid, Rigid Body…
+    pub name: Option<String>,
+    pub baked: bool,
+    pub external: bool,       // frames live on disk; otherwise packed in the blend
+    pub path: Option<String>, // cache folder, when set explicitly
+}
+
+// PointCache.flag
+const PTCACHE_BAKED: u64 = 1 << 0;
+const PTCACHE_DISK_CACHE: u64 = 1 << 6;
+const PTCACHE_EXTERNAL: u64 = 1 << 9;
+
+// FluidDomainSettings.cache_flag: data, noise, mesh, particles, guides baked
+const FLUID_DOMAIN_BAKED_ANY: u64 = 1 << 1 | 1 << 3 | 1 << 5 | 1 << 7 | 1 << 9;
+
+// Structs pointing at a point cache, by simulation; the plain `SoftBody`
+// and `RigidBodyWorld` entries cover files from before 2.80
+const CACHE_OWNERS: &[(&str, &str, &str)] = &[
+    ("ClothModifierData", "point_cache", "Cloth"),
+    ("SoftBody_Shared", "pointcache", "Soft Body"),
+    ("SoftBody", "pointcache", "Soft Body"),
+    ("RigidBodyWorld_Shared", "pointcache", "Rigid Body"),
+    ("RigidBodyWorld", "pointcache", "Rigid Body"),
+    ("ParticleSystem", "pointcache", "Particles"),
+    ("DynamicPaintSurface", "pointcache", "Dynamic Paint"),
+];
+
+// Point caches and Mantaflow domains, in file order
+pub(crate) fn simulations(index: &Index, file: &mut impl BlockReader) -> Vec<SimCache> {
+    let sdna = &index.sdna;
+    let (flag, name, path) = (
+        sdna.field("PointCache", "flag"),
+        sdna.field("PointCache", "name"),
+        sdna.field("PointCache", "path"),
+    );
+    let (fluid_flag, fluid_dir) = (
+        sdna.field("FluidDomainSettings", "cache_flag"),
+        sdna.field("FluidDomainSettings", "cache_directory"),
+    );
+
+    let mut seen = HashSet::new();
+    let mut out = Vec::new();
+    for head in &index.heads {
+        let Some(owner) = sdna.struct_name(head.sdna) else {
+            continue;
+        };
+        // Mantaflow always bakes to its cache folder
+        if owner == "FluidDomainSettings" {
+            let Some(data) = index.read(file, head, head.size) else {
+                continue;
+            };
+            let domain = index.view(&data);
+            out.push(SimCache {
+                kind: "Fluid".to_string(),
+                name: None,
+                baked: domain
+                    .uint(fluid_flag)
+                    .is_some_and(|f| f & FLUID_DOMAIN_BAKED_ANY != 0),
+                external: true,
+                path: domain.str(fluid_dir),
+            });
+            continue;
+        }
+        let Some(&(_, field, kind)) = CACHE_OWNERS.iter().find(|(s, _, _)| *s == owner) else {
+            continue;
+        };
+        let Some(field) = sdna.field(owner, field) else {
+            continue;
+        };
+        let Some(ptr) = index
+            .read(file, head, (field.offset + field.size) as u64)
+            .and_then(|d| index.view(&d).uint(Some(field)))
+            .filter(|&p| p != 0 && seen.insert(p))
+        else {
+            continue;
+        };
+        let Some(cache) = index.deref(ptr).and_then(|h| index.read(file, h, h.size)) else {
+            continue;
+        };
+        let cache = index.view(&cache);
+        let f = cache.uint(flag).unwrap_or(0);
+        // Every particle system has a cache; hair and unbaked emitters
+        // leave nothing to carry along
+        if kind == "Particles" && f & (PTCACHE_BAKED | PTCACHE_DISK_CACHE | PTCACHE_EXTERNAL) == 0 {
+            continue;
+        }
+        out.push(SimCache {
+            kind: kind.to_string(),
+            name: cache.str(name),
+            baked: f & PTCACHE_BAKED != 0,
+            external: f & (PTCACHE_DISK_CACHE | PTCACHE_EXTERNAL) != 0,
+            path: (f & PTCACHE_EXTERNAL != 0)
+                .then(|| cache.str(path))
+                .flatten(),
+        });
+    }
+    out
+}
+
+// -----------------------------
+// Video Sequencer
+// -----------------------------
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct Sequencer {
+    pub strips: u32, // all strips, inside meta strips too
+    pub movies: u32,
+    pub sounds: u32,
+    pub images: u32,
+    pub media: u32, // distinct files (or image folders) the strips use
+}
+
+// Strip types (SEQ_TYPE_*)
+const SEQ_IMAGE: u64 = 0;
+const SEQ_META: u64 = 1;
+const SEQ_MOVIE: u64 = 3;
+const SEQ_SOUND_RAM: u64 = 4;
+const SEQ_SOUND_HD: u64 = 5;
+
+// Strips of every scene's sequencer; None when no scene has any
+pub(crate) fn sequencer(index: &Index, file: &mut impl BlockReader) -> Option<Sequencer> {
+    let sdna = &index.sdna;
+    // Blender 4.4 renamed Sequence/Strip to Strip/StripData
+    let (seq, data) = if sdna.field("Sequence", "type").is_some() {
+        ("Sequence", "Strip")
+    } else {
+        ("Strip", "StripData")
+    };
+    let ed = sdna.field("Scene", "ed");
+    let top = sdna.field("Editing", "seqbase.first");
+    let walk = StripFields {
+        next: sdna.field(seq, "next"),
+        kind: sdna.field(seq, "type"),
+        nested: sdna.field(seq, "seqbase.first"),
+        strip: sdna.field_any(seq, &["strip", "data"]),
+        sound: sdna.field(seq, "sound"),
+        dir: sdna.field_any(data, &["dirpath", "dir"]),
+        elems: sdna.field(data, "stripdata"),
+        elem_name: sdna.field_any("StripElem", &["filename", "name"]),
+        sound_path: sdna.field_any("bSound", &["filepath", "name"]),
+    };
+
+    let mut out = Sequencer::default();
+    let mut media = HashSet::new();
+    let scenes: Vec<&BlockHead> = index.ids().filter(|h| h.id_code() == "SC").collect();
+    for head in scenes {
+        let Some(first) = index
+            .read(file, head, head.size)
+            .and_then(|d| index.view(&d).uint(ed))
+            .and_then(|p| index.deref(p))
+            .and_then(|h| index.read(file, h, h.size))
+            .and_then(|d| index.view(&d).uint(top))
+        else {
+            continue;
+        };
+        walk.count(index, file, first, &mut out, &mut media, 0);
+    }
+    out.media = media.len() as u32;
+    (out.strips > 0).then_some(out)
+}
+
+struct StripFields {
+    next: Option<Field>,
+    kind: Option<Field>,
+    nested: Option<Field>, // meta strips hold their own list
+    strip: Option<Field>,
+    sound: Option<Field>,
+    dir: Option<Field>,
+    elems: Option<Field>,
+    elem_name: Option<Field>,
+    sound_path: Option<Field>,
+}
+
+impl StripFields {
+    fn count(
+        &self,
+        index: &Index,
+        file: &mut impl BlockReader,
+        first: u64,
+        out: &mut Sequencer,
+        media: &mut HashSet<String>,
+        depth: u32,
+    ) {
+        for (_, data) in list_items(index, file, first, self.next) {
+            let s = index.view(&data);
+            out.strips += 1;
+            let kind = s.uint(self.kind).unwrap_or(u64::MAX);
+            match kind {
+                SEQ_META if depth < 32 => {
+                    let nested = s.uint(self.nested).unwrap_or(0);
+                    self.count(index, file, nested, out, media, depth + 1);
+                }
+                SEQ_MOVIE | SEQ_IMAGE => {
+                    if kind == SEQ_MOVIE {
+                        out.movies += 1;
+                    } else {
+                        out.images += 1;
+                    }
+                    let Some(sd) = s
+                        .uint(self.strip)
+                        .and_then(|p| index.deref(p))
+                        .and_then(|h| index.read(file, h, h.size))
+                    else {
+                        continue;
+                    };
+                    let sd = index.view(&sd);
+                    let dir = sd.str(self.dir).unwrap_or_default();
+                    // An image sequence counts once, by its folder
+                    let name = match kind {
+                        SEQ_MOVIE => sd
+                            .uint(self.elems)
+                            .and_then(|p| index.deref(p))
+                            .and_then(|h| index.read(file, h, h.size))
+                            .and_then(|e| index.view(&e).str(self.elem_name))
+                            .unwrap_or_default(),
+                        _ => String::new(),
+                    };
+                    media.insert(format!("{}{}", dir, name));
+                }
+                SEQ_SOUND_RAM | SEQ_SOUND_HD => {
+                    out.sounds += 1;
+                    if let Some(path) = s
+                        .uint(self.sound)
+                        .and_then(|p| index.deref(p))
+                        .and_then(|h| index.read(file, h, h.size))
+                        .and_then(|d| index.view(&d).str(self.sound_path))
+                    {
+                        media.insert(path);
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+// -----------------------------
+// World environment textures
+// -----------------------------
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct EnvTexture {
+    pub world: String,
+    pub path: String, // as saved, often `//`-relative
+    pub packed: bool,
+}
+
+// Images behind Environment Texture nodes of each world's shader
+pub(crate) fn environments(index: &Index, file: &mut impl BlockReader) -> Vec<EnvTexture> {
+    let sdna = &index.sdna;
+    let id_name = sdna.field("ID", "name");
+    let (use_nodes, nodetree) = (
+        sdna.field("World", "use_nodes"),
+        sdna.field("World", "nodetree"),
+    );
+    let (first, next, idname, node_id) = (
+        sdna.field("bNodeTree", "nodes.first"),
+        sdna.field("bNode", "next"),
+        sdna.field("bNode", "idname"),
+        sdna.field("bNode", "id"),
+    );
+    let (image_path, packed, packed_list) = (
+        sdna.field_any("Image", &["filepath", "name"]),
+        sdna.field("Image", "packedfile"),
+        sdna.field("Image", "packedfiles.first"),
+    );
+
+    let mut out = Vec::new();
+    let worlds: Vec<&BlockHead> = index.ids().filter(|h| h.id_code() == "WO").collect();
+    for head in worlds {
+        let Some(data) = index.read(file, head, head.size) else {
+            continue;
+        };
+        let world = index.view(&data);
+        if world.uint(use_nodes) == Some(0) {
+            continue;
+        }
+        let name = world
+            .str(id_name)
+            .and_then(|n| n.get(2..).map(str::to_string))
+            .unwrap_or_default();
+        let Some(first) = world
+            .uint(nodetree)
+            .and_then(|p| index.deref(p))
+            .and_then(|h| index.read(file, h, h.size))
+            .and_then(|t| index.view(&t).uint(first))
+        else {
+            continue;
+        };
+        for (_, node) in list_items(index, file, first, next) {
+            let node = index.view(&node);
+            if node.str(idname).as_deref() != Some("ShaderNodeTexEnvironment") {
+                continue;
+            }
+            let Some(image) = node
+                .uint(node_id)
+                .and_then(|p| index.deref(p))
+                .and_then(|h| index.read(file, h, h.size))
+            else {
+                continue;
+            };
+            let image = index.view(&image);
+            let Some(path) = image.str(image_path) else {
+                continue;
+            };
+            let packed = [packed, packed_list]
+                .into_iter()
+                .any(|f| image.uint(f).is_some_and(|p| p != 0));
+            out.push(EnvTexture {
+                world: name.clone(),
+                path,
+                packed,
+            });
+        }
+    }
+    out
+}
+
+// -----------------------------
+// Linked libraries
+// -----------------------------
+
+// Blends this file links data from, as saved (often `//`-relative)
+pub(crate) fn libraries(index: &Index, file: &mut impl BlockReader) -> Vec<String> {
+    let path = index.sdna.field_any("Library", &["filepath", "name"]);
+    let mut out: Vec<String> = Vec::new();
+    let libs: Vec<&BlockHead> = index.ids().filter(|h| h.id_code() == "LI").collect();
+    for head in libs {
+        if let Some(p) = index
+            .read(file, head, head.size)
+            .and_then(|d| index.view(&d).str(path))
+        {
+            if !out.contains(&p) {
+                out.push(p);
+            }
+        }
+    }
+    out
+}
+
+// -----------------------------
+// Registered text blocks
+// -----------------------------
+
+// Text.flags: Register, run as a module when the file loads
+const TXT_ISSCRIPT: u64 = 1 << 4;
+
+// Names of the texts Blender runs on load when Auto Run Python Scripts is on
+pub(crate) fn registered_texts(index: &Index, file: &mut impl BlockReader) -> Vec<String> {
+    let (id_name, flags) = (
+        index.sdna.field("ID", "name"),
+        index.sdna.field("Text", "flags"),
+    );
+    let texts: Vec<&BlockHead> = index.ids().filter(|h| h.id_code() == "TX").collect();
+    texts
+        .into_iter()
+        .filter_map(|head| {
+            let data = index.read(file, head, head.size)?;
+            let text = index.view(&data);
+            if text.uint(flags)? & TXT_ISSCRIPT == 0 {
+                return None;
+            }
+            text.str(id_name)
+                .and_then(|n| n.get(2..).map(str::to_string))
+        })
+        .collect()
+}
+
+// -----------------------------
+// Datablock counts
+// -----------------------------
+
+// Datablocks per readable type, e.g. `Object` → 12
+pub(crate) fn id_counts(index: &Index) -> BTreeMap<String, usize> {
+    let mut out = BTreeMap::new();
+    for head in index.ids() {
+        *out.entry(id_type_name(&head.id_code()).to_string())
+            .or_default() += 1;
+    }
+    out
+}
+
+// -----------------------------
+// Render engine
+// -----------------------------
+
+// Friendly names for engine identifiers; others are shown as saved
+pub(crate) fn engine_name(id: &str) -> String {
+    match id {
+        "CYCLES" => "Cycles",
+        "BLENDER_EEVEE" | "BLENDER_EEVEE_NEXT" => "Eevee",
+        "BLENDER_WORKBENCH" | "BLENDER_WORKBENCH_NEXT" => "Workbench",
+        "BLENDER_RENDER" => "Blender Internal",
+        "BLENDER_GAME" => "Blender Game",
+        "PRMAN_RENDER" => "RenderMan",
+        "LUXCORE" => "LuxCore",
+        "octane" => "Octane",
+        "REDSHIFT" => "Redshift",
+        "RPR" => "Radeon ProRender",
+        "VRAY_RENDER_RT" => "V-Ray",
+        "APPLESEED_RENDER" => "appleseed",
+        other => other,
+    }
+    .to_string()
+}
+
+// Engine identifier (`RenderData.engine`) of the scene the file opens with
+pub(crate) fn render_engine(index: &Index, file: &mut impl BlockReader) -> Option<String> {
+    let sdna = &index.sdna;
+    let engine = sdna.field("Scene", "r.engine");
+    let current = index
+        .heads
+        .iter()
+        .find(|h| &h.code == b"GLOB")
+        .and_then(|h| index.read(file, h, h.size))
+        .and_then(|d| index.view(&d).uint(sdna.field("FileGlobal", "curscene")))
+        .and_then(|p| index.deref(p));
+    let scene = current.or_else(|| index.ids().find(|h| h.id_code() == "SC"))?;
+    let data = index.read(file, scene, scene.size)?;
+    index.view(&data).str(engine)
+}
@@ -0,0 +1,172 @@
+use crate::error::{AppError, ErrorKind};
+use rusqlite::Connection;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+// -----------------------------
+// Index database (index.db in the app data dir)
+// -----------------------------
+// User data that must survive rescans lives here, keyed by path id.
+// Migrations run in order; `PRAGMA user_version` records how many applied.
+
+pub const DB_FILE_NAME: &str = "index.db";
+
+const MIGRATIONS: &[&str] = &[
+    // 1: tags
+    "CREATE TABLE tags (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+        color TEXT
+    );
+    CREATE TABLE file_tags (
+        path_id TEXT NOT NULL,
+        tag_id INTEGER NOT NULL REFERENCES tags(id) ON DELETE CASCADE,
+        PRIMARY KEY (path_id, tag_id)
+    );
+    CREATE INDEX file_tags_tag ON file_tags(tag_id);",
+    // 2: favorites
+    "CREATE TABLE favorites (
+        path_id TEXT PRIMARY KEY,
+        added_at TEXT NOT NULL
+    );",
+    // 3: ratings
+    "CREATE TABLE ratings (
+        path_id TEXT PRIMARY KEY,
+        rating INTEGER NOT NULL CHECK (rating BETWEEN 1 AND 5)
+    );",
+    // 4: color labels (files and folders)
+    "CREATE TABLE labels (
+        path_id TEXT PRIMARY KEY,
+        label TEXT NOT NULL
+    );",
+    // 5: virtual collections
+    "CREATE TABLE collections (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE COLLATE NOCASE,
+        created_at TEXT NOT NULL
+    );
+    CREATE TABLE collection_files (
+        collection_id INTEGER NOT NULL REFERENCES collections(id) ON DELETE CASCADE,
+        path_id TEXT NOT NULL,
+        added_at TEXT NOT NULL,
+        PRIMARY KEY (collection_id, path_id)
+    );",
+    // 6: notes
+    "CREATE TABLE notes (
+        path_id TEXT PRIMARY KEY,
+        note TEXT NOT NULL,
+        updated_at TEXT NOT NULL
+    );",
+    // 7: smart collections (members come from a saved query)
+    "ALTER TABLE collections ADD COLUMN query TEXT;",
+    // 8: files seen by the last scan of each root, to flag what's new
+    "CREATE TABLE scanned_roots (
+        root_id TEXT PRIMARY KEY,
+        last_scan_at TEXT NOT NULL
+    );
+    CREATE TABLE scan_files (
+        root_id TEXT NOT NULL,
+        path_id TEXT NOT NULL,
+        size_bytes INTEGER NOT NULL,
+        modified TEXT,
+        PRIMARY KEY (root_id, path_id)
+    );",
+    // 9: stable file ids
+    "CREATE TABLE file_ids (
+        id TEXT PRIMARY KEY,
+        path_id TEXT NOT NULL UNIQUE
+    );",
+    // 10: files parsed by a scan still running (or interrupted)
+    "CREATE TABLE scan_checkpoints (
+        root_id TEXT NOT NULL,
+        path_id TEXT NOT NULL,
+        size_bytes INTEGER NOT NULL,
+        modified TEXT,
+        deep INTEGER NOT NULL,
+        blend TEXT NOT NULL,
+        PRIMARY KEY (root_id, path_id)
+    );",
+    // 11: content checksums, for bitrot checks
+    "CREATE TABLE checksums (
+        path_id TEXT PRIMARY KEY,
+        sha256 TEXT NOT NULL,
+        size_bytes INTEGER NOT NULL,
+        modified TEXT,
+        hashed_at TEXT NOT NULL
+    );",
+    // 12: checkpoints keep the parse depth (scan::ParseDepth::level), not a
+    // deep flag
+    "ALTER TABLE scan_checkpoints RENAME COLUMN deep TO depth;
+     UPDATE scan_checkpoints SET depth = CASE depth WHEN 0 THEN 1 ELSE 3 END;",
+    // 13: local usage statistics (see usage.rs)
+    "CREATE TABLE usage (
+        path_id TEXT NOT NULL,
+        event TEXT NOT NULL,
+        count INTEGER NOT NULL,
+        first_at TEXT NOT NULL,
+        last_at TEXT NOT NULL,
+        PRIMARY KEY (path_id, event)
+    );",
+    // 14: parsed files of each root's last finished scan (see checkpoint.rs)
+    "CREATE TABLE file_index (
+        root_id TEXT NOT NULL,
+        path_id TEXT NOT NULL,
+        size_bytes INTEGER NOT NULL,
+        modified TEXT,
+        depth INTEGER NOT NULL,
+        blend TEXT NOT NULL,
+        PRIMARY KEY (root_id, path_id)
+    );",
+];
+
+static DB: OnceLock<Mutex<Connection>> = OnceLock::new();
+
+pub fn db_error(e: rusqlite::Error) -> AppError {
+    AppError::new(ErrorKind::Database, e.to_string())
+}
+
+fn migrate(conn: &mut Connection) -> rusqlite::Result<()> {
+    let applied: usize = conn.query_row("PRAGMA user_version", [], |r| r.get(0))?;
+    for (i, sql) in MIGRATIONS.iter().enumerate().skip(applied) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(sql)?;
+        tx.pragma_update(None, "user_version", i + 1)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+fn open(path: Option<&PathBuf>) -> rusqlite::Result<Connection> {
+    let mut conn = match path {
+        Some(p) => Connection::open(p)?,
+        None => Connection::open_in_memory()?,
+    };
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    conn.pragma_update(None, "foreign_keys", true)?;
+    migrate(&mut conn)?;
+    Ok(conn)
+}
+
+// Opens (or creates) the database; falls back to memory so the app still
+// works, without persistence, if the file can't be used
+pub fn init(data_dir: Option<PathBuf>) {
+    let path = data_dir.and_then(|dir| {
+        fs::create_dir_all(&dir).ok()?;
+        Some(dir.join(DB_FILE_NAME))
+    });
+    let conn = open(path.as_ref())
+        .or_else(|e| {
+            tracing::error!("index database unavailable, using memory: {}", e);
+            open(None)
+        })
+        .expect("in-memory SQLite database");
+    let _ = DB.set(Mutex::new(conn));
+}
+
+// Runs `f` on the shared connection (opened in memory if `init` never ran)
+pub fn with<T>(f: impl FnOnce(&mut Connection) -> rusqlite::Result<T>) -> Result<T, AppError> {
+    let db = DB.get_or_init(|| Mutex::new(open(None).expect("in-memory SQLite database")));
+    let mut conn = db.lock().unwrap();
+    f(&mut conn).map_err(db_error)
+}
@@ -0,0 +1,150 @@
+use crate::error::{AppError, ErrorKind};
+use crate::paths;
+use crate::MAIN_WINDOW;
+use serde::Serialize;
+use std::path::Path;
+use std::sync::{
+    atomic::{AtomicBool, Ordering},
+    Mutex, OnceLock,
+};
+use tauri::{Emitter, Manager};
+
+// -----------------------------
+// blendfinder:// links
+// -----------------------------
+//   blendfinder://open?path=/projects/shot.blend
+//   blendfinder://search?q=hero_rig
+//   blendfinder://scan?root=/projects/show
+pub const SCHEME: &str = "blendfinder";
+const EVENT_APP_REQUEST: &str = "app://request";
+
+#[derive(Serialize, Clone, Debug)]
+#[serde(tag = "action", rename_all = "snake_case")]
+pub enum AppRequest {
+    Open { path: String },
+    Search { q: String },
+    Scan { root: String },
+    ScanAll { roots: Vec<String> }, // one multi-root scan
+    ShowScan { scan_id: u64 },
+    QuickFind,
+}
+
+// Requests that arrived before the frontend was listening (e.g. the link
+// that launched the app); drained by `take_pending_requests`
+static PENDING: OnceLock<Mutex<Vec<AppRequest>>> = OnceLock::new();
+static FRONTEND_READY: AtomicBool = AtomicBool::new(false);
+
+fn pending() -> &'static Mutex<Vec<AppRequest>> {
+    PENDING.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+pub fn parse_url(url: &tauri::Url) -> Result<AppRequest, AppError> {
+    let invalid = |msg: String| AppError::new(ErrorKind::InvalidInput, msg);
+    if url.scheme() != SCHEME {
+        return Err(invalid(format!(
+            "Unsupported link scheme: {}",
+            url.scheme()
+        )));
+    }
+
+    let param = |key: &str| {
+        url.query_pairs()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.to_string())
+            .filter(|v| !v.is_empty())
+            .ok_or_else(|| invalid(format!("Link is missing the '{}' parameter", key)))
+    };
+
+    // `blendfinder://open?…` puts the action in the host; tolerate the
+    // `blendfinder:open?…` form too
+    let action = url
+        .host_str()
+        .unwrap_or_else(|| url.path().trim_matches('/'));
+    // Paths come back normalized, in the form the scan results use
+    let path_param = |key: &str| param(key).map(|p| paths::to_id(&paths::from_id(&p)));
+    match action {
+        "open" => Ok(AppRequest::Open {
+            path: path_param("path")?,
+        }),
+        "search" => Ok(AppRequest::Search { q: param("q")? }),
+        "scan" => Ok(AppRequest::Scan {
+            root: path_param("root")?,
+        }),
+        other => Err(invalid(format!("Unknown link action: {}", other))),
+    }
+}
+
+// Forwards the request to the main window (or queues it until the frontend
+// is ready), bringing the window forward
+pub fn dispatch(app: &tauri::AppHandle, request: AppRequest) {
+    tracing::debug!(?request, "app request");
+    focus_main(app);
+    // Checked under the queue lock so nothing slips between take and emit
+    let mut queue = pending().lock().unwrap();
+    if FRONTEND_READY.load(Ordering::Relaxed) {
+        drop(queue);
+        let _ = app.emit_to(MAIN_WINDOW, EVENT_APP_REQUEST, request);
+    } else {
+        queue.push(request);
+    }
+}
+
+// Delivers a request to one window without focusing it
+pub fn send_to(app: &tauri::AppHandle, label: &str, request: AppRequest) {
+    let _ = app.emit_to(label, EVENT_APP_REQUEST, request);
+}
+
+pub fn handle_urls(app: &tauri::AppHandle, urls: Vec<tauri::Url>) {
+    for url in urls {
+        // "Open with" on macOS delivers files as file:// URLs
+        if url.scheme() == "file" {
+            if let Ok(path) = url.to_file_path() {
+                let path = path.to_string_lossy().to_string();
+                for request in requests_from_args(&[path], Path::new("")) {
+                    dispatch(app, request);
+                }
+            }
+            continue;
+        }
+        match parse_url(&url) {
+            Ok(request) => dispatch(app, request),
+            Err(e) => tracing::warn!(url = %url, "ignoring deep link: {}", e),
+        }
+    }
+}
+
+// Turns command-line arguments (our own, or forwarded by a second instance)
+// into requests: folders are scanned, files are opened. Links are skipped here
+// because the deep-link plugin already delivers them.
+pub fn requests_from_args(args: &[String], cwd: &Path) -> Vec<AppRequest> {
+    args.iter()
+        .filter(|a| !a.starts_with('-') && !a.starts_with(&format!("{}:", SCHEME)))
+        .filter_map(|a| {
+            let p = cwd.join(a); // no-op for absolute paths
+            let path = paths::to_id(&p);
+            if p.is_dir() {
+                Some(AppRequest::Scan { root: path })
+            } else if p.is_file() {
+                Some(AppRequest::Open { path })
+            } else {
+                None
+            }
+        })
+        .collect()
+}
+
+// Brings the main window forward without queuing anything
+pub fn focus_main(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+// Marks the frontend as listening and hands over anything queued so far
+pub fn take_pending() -> Vec<AppRequest> {
+    let mut queue = pending().lock().unwrap();
+    FRONTEND_READY.store(true, Ordering::Relaxed);
+    std::mem::take(&mut *queue)
+}
@@ -0,0 +1,226 @@
+use crate::error::{AppError, ErrorKind};
+use crate::export::export_stamp;
+use crate::settings;
+use crate::{FlatFile, ScanResult};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+// -----------------------------
+// Scan diff models
+// -----------------------------
+#[derive(Serialize, Clone)]
+pub struct DiffFile {
+    pub name: String,
+    pub path: String,
+    pub size_bytes: u64,
+    pub modified: Option<String>,
+    pub modified_ms: Option<i64>,
+    pub blender_version: Option<String>,
+    pub render_engine: Option<String>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct DiffChange {
+    pub path: String,
+    pub changed_fields: Vec<String>,
+    pub before: DiffFile,
+    pub after: DiffFile,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ScanDiff {
+    pub old_scan_id: u64,
+    pub new_scan_id: u64,
+    pub added: Vec<DiffFile>,
+    pub removed: Vec<DiffFile>,
+    pub changed: Vec<DiffChange>,
+}
+
+impl From<&FlatFile> for DiffFile {
+    fn from(f: &FlatFile) -> Self {
+        Self {
+            name: f.name.clone(),
+            path: f.path.clone(),
+            size_bytes: f.size_bytes,
+            modified: f.modified.clone(),
+            modified_ms: f.modified_ms,
+            blender_version: f.blender_version.clone(),
+            render_engine: f.render_engine.clone(),
+        }
+    }
+}
+
+// -----------------------------
+// Diffing
+// -----------------------------
+pub fn diff_results(old_id: u64, old: &ScanResult, new_id: u64, new: &ScanResult) -> ScanDiff {
+    let before: BTreeMap<&str, &FlatFile> =
+        old.files.iter().map(|f| (f.path_id.as_str(), f)).collect();
+    let after: BTreeMap<&str, &FlatFile> =
+        new.files.iter().map(|f| (f.path_id.as_str(), f)).collect();
+
+    let mut diff = ScanDiff {
+        old_scan_id: old_id,
+        new_scan_id: new_id,
+        added: Vec::new(),
+        removed: Vec::new(),
+        changed: Vec::new(),
+    };
+
+    for (path, a) in after.iter() {
+        match before.get(path) {
+            None => diff.added.push(DiffFile::from(*a)),
+            Some(b) => {
+                let fields = changed_fields(b, a);
+                if !fields.is_empty() {
+                    diff.changed.push(DiffChange {
+                        path: path.to_string(),
+                        changed_fields: fields,
+                        before: DiffFile::from(*b),
+                        after: DiffFile::from(*a),
+                    });
+                }
+            }
+        }
+    }
+
+    for (path, b) in before.iter() {
+        if !after.contains_key(path) {
+            diff.removed.push(DiffFile::from(*b));
+        }
+    }
+
+    diff
+}
+
+fn changed_fields(b: &FlatFile, a: &FlatFile) -> Vec<String> {
+    let mut fields = Vec::new();
+    if b.size_bytes != a.size_bytes {
+        fields.push("size_bytes".to_string());
+    }
+    if b.modified != a.modified {
+        fields.push("modified".to_string());
+    }
+    if b.blender_version != a.blender_version {
+        fields.push("blender_version".to_string());
+    }
+    if b.render_engine != a.render_engine {
+        fields.push("render_engine".to_string());
+    }
+    fields
+}
+
+// -----------------------------
+// Export
+// -----------------------------
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn csv_opt(s: &Option<String>) -> String {
+    csv_field(s.as_deref().unwrap_or(""))
+}
+
+fn csv_row(
+    change: &str,
+    path: &str,
+    before: Option<&DiffFile>,
+    after: Option<&DiffFile>,
+    fields: &str,
+) -> String {
+    let side = |f: Option<&DiffFile>| -> [String; 4] {
+        match f {
+            Some(f) => [
+                f.size_bytes.to_string(),
+                csv_opt(&f.modified),
+                csv_opt(&f.blender_version),
+                csv_opt(&f.render_engine),
+            ],
+            None => Default::default(),
+        }
+    };
+    let [bs, bm, bv, be] = side(before);
+    let [as_, am, av, ae] = side(after);
+    format!(
+        "{},{},{},{},{},{},{},{},{},{},{}\n",
+        change,
+        csv_field(path),
+        csv_field(fields),
+        bs,
+        bm,
+        bv,
+        be,
+        as_,
+        am,
+        av,
+        ae
+    )
+}
+
+pub fn diff_to_csv(diff: &ScanDiff) -> String {
+    let mut out = String::from(
+        "change,path,changed_fields,before_size,before_modified,before_version,before_engine,after_size,after_modified,after_version,after_engine\n",
+    );
+    for f in &diff.added {
+        out.push_str(&csv_row("added", &f.path, None, Some(f), ""));
+    }
+    for f in &diff.removed {
+        out.push_str(&csv_row("removed", &f.path, Some(f), None, ""));
+    }
+    for c in &diff.changed {
+        out.push_str(&csv_row(
+            "changed",
+            &c.path,
+            Some(&c.before),
+            Some(&c.after),
+            &c.changed_fields.join(";"),
+        ));
+    }
+    out
+}
+
+// Copy with timestamps in the zone exports use (see export.rs)
+fn for_export(diff: &ScanDiff) -> ScanDiff {
+    let utc = settings::get().export_utc;
+    let zoned = |f: &DiffFile| DiffFile {
+        modified: export_stamp(f.modified.as_deref(), utc),
+        ..f.clone()
+    };
+    ScanDiff {
+        old_scan_id: diff.old_scan_id,
+        new_scan_id: diff.new_scan_id,
+        added: diff.added.iter().map(zoned).collect(),
+        removed: diff.removed.iter().map(zoned).collect(),
+        changed: diff
+            .changed
+            .iter()
+            .map(|c| DiffChange {
+                before: zoned(&c.before),
+                after: zoned(&c.after),
+                ..c.clone()
+            })
+            .collect(),
+    }
+}
+
+pub fn export_diff(diff: &ScanDiff, format: &str, output_path: &Path) -> Result<(), AppError> {
+    let diff = &for_export(diff);
+    let body = match format.to_lowercase().as_str() {
+        "csv" => diff_to_csv(diff),
+        "json" => serde_json::to_string_pretty(diff)
+            .map_err(|e| AppError::new(ErrorKind::Export, e.to_string()))?,
+        other => {
+            return Err(AppError::new(
+                ErrorKind::InvalidInput,
+                format!("Unsupported export format: {}", other),
+            ))
+        }
+    };
+    fs::write(output_path, body).map_err(|e| AppError::io(&e, output_path))
+}
@@ -0,0 +1,365 @@
+use crate::error::{AppError, ErrorKind};
+use crate::exclude::{Exclusions, GlobFilter};
+use crate::scan::FoundBlend;
+use crate::{cloud, extract_block_info, formats, paths, read_blend_header, BlendInfo};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::RandomState;
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, OpenOptions};
+use std::hash::{BuildHasher, Hasher};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use walkdir::WalkDir;
+
+// -----------------------------
+// Elevated access for protected folders
+// -----------------------------
+// The app re-launches itself with admin rights (UAC, polkit, or the macOS
+// admin prompt) in a headless helper mode that indexes one folder and
+// writes the parsed files to a JSON file the unprivileged app reads back.
+// That file sits in a private folder of random name, created by the app
+// beforehand; the helper only writes to it, never through a link. It
+// lists what the scan would have (see `Filter`), so excluded files don't
+// come back through it.
+
+pub const HELPER_FLAG: &str = "--elevated-scan";
+
+static NEXT_OUTPUT: AtomicU64 = AtomicU64::new(1);
+
+// The scan's listing options, passed to the helper as JSON
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+pub struct Filter {
+    pub root: String, // path id of the scan root the globs are relative to
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    pub exclusions: Option<Vec<String>>, // folder exclusions, when the scan used them
+    pub other_formats: bool,
+    pub backups: bool,
+    pub max_files: Option<u64>, // what is left of the scan's limit
+}
+
+#[derive(Serialize, Deserialize)]
+struct ElevatedFile {
+    path_id: String,
+    format: String,
+    size_bytes: u64,
+    created: Option<String>,
+    modified: Option<String>,
+    cloud_only: bool,
+    #[serde(default)]
+    read_only: bool,
+    blend: BlendInfo,
+}
+
+// `<exe> --elevated-scan <folder> <output file> <filter json>`; Some(exit
+// code) in helper mode
+pub fn helper_main() -> Option<i32> {
+    let args: Vec<OsString> = std::env::args_os().collect();
+    if args.get(1).map(|a| a == HELPER_FLAG) != Some(true) {
+        return None;
+    }
+    let (Some(dir), Some(out), Some(filter)) = (args.get(2), args.get(3), args.get(4)) else {
+        return Some(2);
+    };
+    let Some(filter) = filter
+        .to_str()
+        .and_then(|f| serde_json::from_str::<Filter>(f).ok())
+    else {
+        return Some(2);
+    };
+    let Ok(files) = collect(Path::new(dir), &filter) else {
+        return Some(2);
+    };
+    let written = serde_json::to_vec(&files)
+        .ok()
+        .and_then(|bytes| open_output(Path::new(out))?.write_all(&bytes).ok());
+    Some(if written.is_some() { 0 } else { 1 })
+}
+
+// The existing file only; a link planted in its place is not followed
+fn open_output(out: &Path) -> Option<fs::File> {
+    let mut options = OpenOptions::new();
+    options.write(true).truncate(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.custom_flags(libc::O_NOFOLLOW);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::fs::OpenOptionsExt;
+        const FILE_FLAG_OPEN_REPARSE_POINT: u32 = 0x0020_0000;
+        options.custom_flags(FILE_FLAG_OPEN_REPARSE_POINT);
+    }
+    if fs::symlink_metadata(out).ok()?.file_type().is_symlink() {
+        return None;
+    }
+    options.open(out).ok()
+}
+
+// A new folder only the user can enter, named unguessably
+fn private_dir() -> Result<PathBuf, AppError> {
+    let mut builder = fs::DirBuilder::new();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::DirBuilderExt;
+        builder.mode(0o700);
+    }
+    loop {
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_u64(NEXT_OUTPUT.fetch_add(1, Ordering::Relaxed));
+        let dir = std::env::temp_dir().join(format!(
+            "blendfinder-elevated-{}-{:016x}",
+            std::process::id(),
+            hasher.finish()
+        ));
+        match builder.create(&dir) {
+            Ok(()) => return Ok(dir),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(AppError::io(&e, &dir)),
+        }
+    }
+}
+
+fn collect(dir: &Path, filter: &Filter) -> Result<Vec<ElevatedFile>, AppError> {
+    let root = match filter.root.as_str() {
+        "" => dir.to_path_buf(),
+        id => paths::from_id(id),
+    };
+    let globs = GlobFilter::new(&filter.include, &filter.exclude)?;
+    let exclusions = filter.exclusions.as_deref().map(Exclusions::new);
+    let rel = |p: &Path| p.strip_prefix(&root).ok().map(Path::to_path_buf);
+    let skipped_dir = |p: &Path| {
+        exclusions.as_ref().is_some_and(|x| x.matches(&root, p))
+            || rel(p).is_some_and(|r| globs.skips_dir(&r))
+    };
+
+    let mut files = Vec::new();
+    let entries = WalkDir::new(dir)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !e.file_type().is_dir() || !skipped_dir(e.path()));
+    for entry in entries.flatten() {
+        let p = entry.path();
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let backup = filter.backups && formats::backup_number(p).is_some();
+        let Some(format) =
+            formats::of_path(p, filter.other_formats).or(backup.then_some(formats::BLEND))
+        else {
+            continue;
+        };
+        if rel(p).is_some_and(|r| globs.skips_file(&r)) {
+            continue;
+        }
+        if filter
+            .max_files
+            .is_some_and(|max| files.len() as u64 >= max)
+        {
+            break;
+        }
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        // Other formats are listed, not parsed, as in a scan
+        let mut blend = BlendInfo::default();
+        if format == formats::BLEND {
+            blend = read_blend_header(p).unwrap_or_else(|e| BlendInfo {
+                error: Some(e.message),
+                ..Default::default()
+            });
+            if blend.error.is_none() {
+                let _ = extract_block_info(p, &mut blend, true);
+            }
+        }
+        files.push(ElevatedFile {
+            path_id: paths::to_id(p),
+            format: format.to_string(),
+            size_bytes: meta.len(),
+            created: meta
+                .created()
+                .ok()
+                .map(|t| DateTime::<Local>::from(t).to_rfc3339()),
+            modified: meta
+                .modified()
+                .ok()
+                .map(|t| DateTime::<Local>::from(t).to_rfc3339()),
+            cloud_only: cloud::is_placeholder(&meta),
+            read_only: meta.permissions().readonly(),
+            blend,
+        });
+    }
+    Ok(files)
+}
+
+// Runs the helper elevated and waits for it; blocks while the OS prompt is up
+pub fn scan_elevated(dir: &Path, filter: &Filter) -> Result<Vec<FoundBlend>, AppError> {
+    let exe =
+        std::env::current_exe().map_err(|e| AppError::new(ErrorKind::Elevation, e.to_string()))?;
+    // Created here so it stays owned by the user and can be removed afterwards
+    let private = private_dir()?;
+    let out = private.join("result.json");
+    let created = OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&out)
+        .map_err(|e| AppError::io(&e, &out));
+    if let Err(e) = created {
+        let _ = fs::remove_dir(&private);
+        return Err(e);
+    }
+
+    let filter = serde_json::to_string(filter).unwrap_or_default();
+    let args = [
+        OsStr::new(HELPER_FLAG),
+        dir.as_os_str(),
+        out.as_os_str(),
+        OsStr::new(&filter),
+    ];
+    let status = launch(&exe, &args);
+    let data = fs::read(&out);
+    let _ = fs::remove_file(&out);
+    let _ = fs::remove_dir(&private);
+
+    match status? {
+        0 => {}
+        code => {
+            return Err(AppError::new(
+                ErrorKind::Elevation,
+                format!("Elevated scan failed or was cancelled (exit code {})", code),
+            )
+            .with_path(dir))
+        }
+    }
+    let files: Vec<ElevatedFile> = data
+        .ok()
+        .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+        .ok_or_else(|| {
+            AppError::new(ErrorKind::Elevation, "Elevated scan produced no results").with_path(dir)
+        })?;
+
+    Ok(files
+        .into_iter()
+        .map(|f| FoundBlend {
+            path: paths::from_id(&f.path_id),
+            size_bytes: f.size_bytes,
+            created: f.created,
+            modified: f.modified,
+            cloud_only: f.cloud_only,
+            read_only: f.read_only,
+            trash: None,
+            format: f.format,
+            blend: f.blend,
+            aliases: Vec::new(),
+        })
+        .collect())
+}
+
+#[cfg(target_os = "linux")]
+fn launch(exe: &Path, args: &[&OsStr]) -> Result<i32, AppError> {
+    // pkexec exits with 126 when the prompt is dismissed
+    let status = std::process::Command::new("pkexec")
+        .arg(exe)
+        .args(args)
+        .status()
+        .map_err(|e| AppError::new(ErrorKind::Elevation, format!("pkexec: {}", e)))?;
+    Ok(status.code().unwrap_or(-1))
+}
+
+#[cfg(target_os = "macos")]
+fn launch(exe: &Path, args: &[&OsStr]) -> Result<i32, AppError> {
+    let shell_quote = |s: &OsStr| format!("'{}'", s.to_string_lossy().replace('\'', r"'\''"));
+    let command = std::iter::once(exe.as_os_str())
+        .chain(args.iter().copied())
+        .map(shell_quote)
+        .collect::<Vec<_>>()
+        .join(" ");
+    let script = format!(
+        "do shell script \"{}\" with administrator privileges",
+        command.replace('\\', "\\\\").replace('"', "\\\"")
+    );
+    let status = std::process::Command::new("/usr/bin/osascript")
+        .arg("-e")
+        .arg(script)
+        .status()
+        .map_err(|e| AppError::new(ErrorKind::Elevation, format!("osascript: {}", e)))?;
+    Ok(status.code().unwrap_or(-1))
+}
+
+#[cfg(windows)]
+fn launch(exe: &Path, args: &[&OsStr]) -> Result<i32, AppError> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::System::Threading::{
+        GetExitCodeProcess, WaitForSingleObject, INFINITE,
+    };
+    use windows_sys::Win32::UI::Shell::{
+        ShellExecuteExW, SEE_MASK_NOCLOSEPROCESS, SHELLEXECUTEINFOW,
+    };
+
+    let wide = |s: &OsStr| s.encode_wide().chain(Some(0)).collect::<Vec<u16>>();
+    let params = args
+        .iter()
+        .map(|a| quote_windows_arg(a))
+        .collect::<Vec<_>>()
+        .join(" ");
+    let (verb, file) = (wide(OsStr::new("runas")), wide(exe.as_os_str()));
+    let params = wide(OsStr::new(&params));
+
+    // SAFETY: the wide strings outlive the call; the process handle is closed below
+    unsafe {
+        let mut info: SHELLEXECUTEINFOW = std::mem::zeroed();
+        info.cbSize = std::mem::size_of::<SHELLEXECUTEINFOW>() as u32;
+        info.fMask = SEE_MASK_NOCLOSEPROCESS;
+        info.lpVerb = verb.as_ptr();
+        info.lpFile = file.as_ptr();
+        info.lpParameters = params.as_ptr();
+        info.nShow = 0; // SW_HIDE: the helper has no window
+        if ShellExecuteExW(&mut info) == 0 {
+            // Includes the user declining the UAC prompt
+            return Err(
+                AppError::new(ErrorKind::Elevation, "Elevation was cancelled")
+                    .with_code("elevation_cancelled"),
+            );
+        }
+        WaitForSingleObject(info.hProcess, INFINITE);
+        let mut code = 0u32;
+        GetExitCodeProcess(info.hProcess, &mut code);
+        CloseHandle(info.hProcess);
+        Ok(code as i32)
+    }
+}
+
+// CommandLineToArgvW rules: backslashes only need doubling before a quote
+#[cfg(windows)]
+fn quote_windows_arg(arg: &OsStr) -> String {
+    let mut out = String::from("\"");
+    let mut backslashes = 0;
+    for c in arg.to_string_lossy().chars() {
+        if c == '\\' {
+            backslashes += 1;
+            continue;
+        }
+        if c == '"' {
+            out.push_str(&"\\".repeat(backslashes * 2 + 1));
+        } else {
+            out.push_str(&"\\".repeat(backslashes));
+        }
+        out.push(c);
+        backslashes = 0;
+    }
+    out.push_str(&"\\".repeat(backslashes * 2));
+    out.push('"');
+    out
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn launch(_exe: &Path, _args: &[&OsStr]) -> Result<i32, AppError> {
+    Err(AppError::new(
+        ErrorKind::Elevation,
+        "Elevated access is not supported on this platform",
+    ))
+}
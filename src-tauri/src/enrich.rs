@@ -0,0 +1,152 @@
+use crate::{
+    annotate_user_data, emit_to_owner, extract_block_info, formats, paths, read_blend_header, scan,
+    scans_map, ScanFileFound, EVENT_FILE_ENRICHED,
+};
+use std::collections::{HashSet, VecDeque};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+
+// -----------------------------
+// On-demand parsing
+// -----------------------------
+// Scans below the deep parse depth leave thumbnails, engines and contents
+// unread. The frontend names the files it is showing and a small worker
+// pool reads those first; the rest of a result is filled in behind them.
+// Each file read is patched into its result and sent to the scan's window.
+
+const WORKERS: usize = 2;
+
+type Item = (u64, String); // scan id, path id
+
+#[derive(Default)]
+struct Queue {
+    visible: VecDeque<Item>,
+    background: VecDeque<Item>,
+    seen: HashSet<Item>, // queued, being read or done
+    workers: usize,
+}
+
+static QUEUE: OnceLock<Mutex<Queue>> = OnceLock::new();
+
+fn queue() -> &'static Mutex<Queue> {
+    QUEUE.get_or_init(|| Mutex::new(Queue::default()))
+}
+
+// Queues files of a finished result. `visible` replaces the files shown
+// before; those not read yet keep their place at the front of the
+// background lane.
+pub fn request(app: &tauri::AppHandle, scan_id: u64, path_ids: Vec<String>, visible: bool) {
+    let live: HashSet<u64> = scans_map().lock().unwrap().keys().copied().collect();
+    let mut q = queue().lock().unwrap();
+    q.seen.retain(|(id, _)| live.contains(id));
+    q.background.retain(|(id, _)| live.contains(id));
+
+    if visible {
+        let shown: HashSet<Item> = path_ids.iter().map(|p| (scan_id, p.clone())).collect();
+        let Queue {
+            visible: lane,
+            background,
+            seen,
+            ..
+        } = &mut *q;
+        // Files shown before and not read yet wait behind the new ones
+        while let Some(item) = lane.pop_back() {
+            background.push_front(item);
+        }
+        // Waiting files that are shown now move up
+        background.retain(|item| {
+            let moving = shown.contains(item);
+            if moving {
+                seen.remove(item);
+            }
+            !moving
+        });
+    }
+    for path_id in path_ids {
+        let item = (scan_id, path_id);
+        if q.seen.insert(item.clone()) {
+            if visible {
+                q.visible.push_back(item);
+            } else {
+                q.background.push_back(item);
+            }
+        }
+    }
+
+    let pending = q.visible.len() + q.background.len();
+    while q.workers < WORKERS.min(pending) {
+        q.workers += 1;
+        let app = app.clone();
+        thread::spawn(move || work(app));
+    }
+}
+
+// Workers exit once both lanes are empty; the next request starts them again
+fn work(app: tauri::AppHandle) {
+    loop {
+        let item = {
+            let mut q = queue().lock().unwrap();
+            match q.visible.pop_front().or_else(|| q.background.pop_front()) {
+                Some(item) => item,
+                None => {
+                    q.workers -= 1;
+                    return;
+                }
+            }
+        };
+        enrich(&app, item.0, &item.1);
+    }
+}
+
+fn enrich(app: &tauri::AppHandle, scan_id: u64, path_id: &str) {
+    let Some(state) = scans_map().lock().unwrap().get(&scan_id).cloned() else {
+        return;
+    };
+    // Placeholders would be downloaded; other formats have nothing to read
+    let wanted = state.result.lock().unwrap().as_ref().is_some_and(|r| {
+        r.files
+            .iter()
+            .any(|f| f.path_id == path_id && f.format == formats::BLEND && !f.cloud_only)
+    });
+    if !wanted {
+        return;
+    }
+
+    let path = paths::from_id(path_id);
+    let mut info = match read_blend_header(&path) {
+        Ok(info) => info,
+        Err(e) => {
+            tracing::debug!(path = %path.display(), "on-demand parse failed: {}", e);
+            return;
+        }
+    };
+    if let Err(e) = extract_block_info(&path, &mut info, true) {
+        tracing::debug!(path = %path.display(), "on-demand block scan failed: {}", e);
+    }
+
+    let mut file = {
+        let mut guard = state.result.lock().unwrap();
+        let Some(result) = guard.as_mut() else {
+            return;
+        };
+        if let Some(meta) = result
+            .tree
+            .find_file_mut(path_id)
+            .and_then(|n| n.meta.as_mut())
+        {
+            meta.blender = info.clone();
+        }
+        let Some(f) = result.files.iter_mut().find(|f| f.path_id == path_id) else {
+            return;
+        };
+        scan::apply_blend(f, &info);
+        f.clone()
+    };
+    annotate_user_data(std::slice::from_mut(&mut file));
+    let _ = emit_to_owner(
+        app,
+        state.owner.as_deref(),
+        EVENT_FILE_ENRICHED,
+        ScanFileFound { scan_id, file },
+    );
+}
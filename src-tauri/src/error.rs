@@ -0,0 +1,173 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+// -----------------------------
+// Structured errors (shared by all commands and scan results)
+// -----------------------------
+// `kind` and `code` are stable, for the UI to branch on; `code` is finer
+// where one kind covers several failures ("tag_exists" under
+// invalid_input) and is the kind's own name otherwise. A localized message
+// is the code's text filled with `params` (and `path`); `message` is the
+// English one, for logs and codes the UI has no text for.
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorKind {
+    NotFound,
+    PermissionDenied,
+    Io,
+    CorruptFile,
+    NotABlend,
+    InvalidInput,
+    ScanNotFound,
+    ScanNotFinished,
+    JobNotFound,
+    Cancelled,
+    Export,
+    Template,
+    Dialog,
+    Opener,
+    Window,
+    Server,
+    Timeout,
+    VolumeOffline,
+    TooManyOpenFiles,
+    Elevation,
+    InsufficientSpace,
+    Database,
+    Shortcut,
+    Update,
+}
+
+impl ErrorKind {
+    // The serialized name
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorKind::NotFound => "not_found",
+            ErrorKind::PermissionDenied => "permission_denied",
+            ErrorKind::Io => "io",
+            ErrorKind::CorruptFile => "corrupt_file",
+            ErrorKind::NotABlend => "not_a_blend",
+            ErrorKind::InvalidInput => "invalid_input",
+            ErrorKind::ScanNotFound => "scan_not_found",
+            ErrorKind::ScanNotFinished => "scan_not_finished",
+            ErrorKind::JobNotFound => "job_not_found",
+            ErrorKind::Cancelled => "cancelled",
+            ErrorKind::Export => "export",
+            ErrorKind::Template => "template",
+            ErrorKind::Dialog => "dialog",
+            ErrorKind::Opener => "opener",
+            ErrorKind::Window => "window",
+            ErrorKind::Server => "server",
+            ErrorKind::Timeout => "timeout",
+            ErrorKind::VolumeOffline => "volume_offline",
+            ErrorKind::TooManyOpenFiles => "too_many_open_files",
+            ErrorKind::Elevation => "elevation",
+            ErrorKind::InsufficientSpace => "insufficient_space",
+            ErrorKind::Database => "database",
+            ErrorKind::Shortcut => "shortcut",
+            ErrorKind::Update => "update",
+        }
+    }
+}
+
+// EMFILE/ENFILE, ERROR_TOO_MANY_OPEN_FILES
+#[cfg(unix)]
+const TOO_MANY_OPEN_FILES: &[i32] = &[24, 23];
+#[cfg(not(unix))]
+const TOO_MANY_OPEN_FILES: &[i32] = &[4];
+
+#[derive(Serialize, Clone, Debug)]
+pub struct AppError {
+    pub kind: ErrorKind,
+    pub code: &'static str,
+    pub params: BTreeMap<&'static str, String>,
+    pub path: Option<String>,
+    pub os_code: Option<i32>,
+    pub message: String,
+}
+
+impl AppError {
+    pub fn new(kind: ErrorKind, message: impl Into<String>) -> Self {
+        Self {
+            kind,
+            code: kind.as_str(),
+            params: BTreeMap::new(),
+            path: None,
+            os_code: None,
+            message: message.into(),
+        }
+    }
+
+    pub fn with_code(mut self, code: &'static str) -> Self {
+        self.code = code;
+        self
+    }
+
+    pub fn with_param(mut self, key: &'static str, value: impl ToString) -> Self {
+        self.params.insert(key, value.to_string());
+        self
+    }
+
+    pub fn with_path(mut self, path: &Path) -> Self {
+        self.path = Some(path.to_string_lossy().to_string());
+        self
+    }
+
+    // Classifies an OS error, keeping the raw code for the UI
+    pub fn io(err: &io::Error, path: &Path) -> Self {
+        if err
+            .raw_os_error()
+            .is_some_and(|c| TOO_MANY_OPEN_FILES.contains(&c))
+        {
+            return Self {
+                os_code: err.raw_os_error(),
+                ..Self::new(
+                    ErrorKind::TooManyOpenFiles,
+                    "Too many open files: lower the scan's max_threads or raise the system limit",
+                )
+                .with_path(path)
+            };
+        }
+        let kind = match err.kind() {
+            io::ErrorKind::NotFound => ErrorKind::NotFound,
+            io::ErrorKind::PermissionDenied => ErrorKind::PermissionDenied,
+            io::ErrorKind::TimedOut => ErrorKind::Timeout,
+            io::ErrorKind::StorageFull => ErrorKind::InsufficientSpace,
+            _ => ErrorKind::Io,
+        };
+        Self {
+            os_code: err.raw_os_error(),
+            ..Self::new(kind, err.to_string()).with_path(path)
+        }
+    }
+
+    pub fn walk(err: &walkdir::Error) -> Self {
+        let path = err.path().map(|p| p.to_string_lossy().to_string());
+        match err.io_error() {
+            Some(io_err) => {
+                let mut e = AppError::io(io_err, Path::new(""));
+                e.path = path;
+                e.message = err.to_string();
+                e
+            }
+            None => Self {
+                path,
+                ..Self::new(ErrorKind::Io, err.to_string())
+            },
+        }
+    }
+}
+
+impl fmt::Display for AppError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.path {
+            Some(p) => write!(f, "{} ({})", self.message, p),
+            None => write!(f, "{}", self.message),
+        }
+    }
+}
+
+impl std::error::Error for AppError {}
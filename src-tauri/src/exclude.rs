@@ -0,0 +1,269 @@
+use crate::error::{AppError, ErrorKind};
+use globset::{GlobBuilder, GlobSet, GlobSetBuilder};
+use std::path::{Component, Path};
+
+// -----------------------------
+// Folder exclusions for whole-drive scans
+// -----------------------------
+// Entries starting with `/` are anchored at the scanned drive root
+// (`/Windows`, `/proc`); anything else matches a folder path suffix
+// anywhere (`node_modules`, `Library/Caches`). Both use `/` as separator.
+
+pub const DEFAULT_EXCLUSIONS: &[&str] = &[
+    // Windows
+    "/Windows",
+    "/Program Files",
+    "/Program Files (x86)",
+    "/ProgramData",
+    "/$Recycle.Bin",
+    "/System Volume Information",
+    "AppData/Local/Temp",
+    // Linux
+    "/proc",
+    "/sys",
+    "/dev",
+    "/run",
+    "/snap",
+    "/var/lib/docker",
+    // macOS
+    "/System",
+    "/private/var",
+    "Library/Caches",
+    // Everywhere
+    "node_modules",
+    ".git",
+];
+
+pub fn defaults() -> Vec<String> {
+    DEFAULT_EXCLUSIONS.iter().map(|s| s.to_string()).collect()
+}
+
+// Windows and macOS file systems are case-insensitive by default
+fn key(s: &str) -> String {
+    if cfg!(any(windows, target_os = "macos")) {
+        s.to_lowercase()
+    } else {
+        s.to_string()
+    }
+}
+
+fn components(path: &Path) -> Vec<String> {
+    path.components()
+        .filter_map(|c| match c {
+            Component::Normal(s) => Some(key(&s.to_string_lossy())),
+            _ => None,
+        })
+        .collect()
+}
+
+pub struct Exclusions {
+    anchored: Vec<Vec<String>>,
+    anywhere: Vec<Vec<String>>,
+}
+
+impl Exclusions {
+    pub fn new(entries: &[String]) -> Self {
+        let mut anchored = Vec::new();
+        let mut anywhere = Vec::new();
+        for entry in entries {
+            let entry = entry.trim();
+            let parts: Vec<String> = entry
+                .split(['/', '\\'])
+                .filter(|p| !p.is_empty())
+                .map(key)
+                .collect();
+            if parts.is_empty() {
+                continue;
+            }
+            if entry.starts_with(['/', '\\']) {
+                anchored.push(parts);
+            } else {
+                anywhere.push(parts);
+            }
+        }
+        Self { anchored, anywhere }
+    }
+
+    // `dir` is a folder found while scanning `root`
+    pub fn matches(&self, root: &Path, dir: &Path) -> bool {
+        let Ok(rel) = dir.strip_prefix(root) else {
+            return false;
+        };
+        let rel = components(rel);
+        self.anchored.contains(&rel) || self.anywhere.iter().any(|a| rel.ends_with(a))
+    }
+}
+
+// -----------------------------
+// Include/exclude globs of one scan
+// -----------------------------
+// Matched against the path below the scan root, `/`-separated. A pattern
+// without `/` matches at any depth (`cache`, `*_old`); one with `/` is
+// anchored at the root (`shots/*/renders`). A trailing `/` limits it to
+// folders. Excluded folders are not descended into; with include patterns
+// only files matching one, or inside a folder matching one, are listed.
+
+struct Globs {
+    any: GlobSet,  // files and folders
+    dirs: GlobSet, // folders only
+}
+
+impl Globs {
+    fn new(patterns: &[String]) -> Result<Option<Self>, AppError> {
+        let (mut any, mut dirs) = (GlobSetBuilder::new(), GlobSetBuilder::new());
+        let mut count = 0;
+        for pattern in patterns {
+            let trimmed = pattern.trim();
+            let body = trimmed.trim_matches('/');
+            if body.is_empty() {
+                continue;
+            }
+            let glob = if body.contains('/') || trimmed.starts_with('/') {
+                body.to_string()
+            } else {
+                format!("**/{}", body)
+            };
+            let glob = GlobBuilder::new(&glob)
+                .literal_separator(true)
+                .case_insensitive(cfg!(any(windows, target_os = "macos")))
+                .build()
+                .map_err(|e| invalid_glob(pattern, e))?;
+            if trimmed.ends_with('/') {
+                dirs.add(glob);
+            } else {
+                any.add(glob);
+            }
+            count += 1;
+        }
+        if count == 0 {
+            return Ok(None);
+        }
+        let build =
+            |b: GlobSetBuilder| b.build().map_err(|e| invalid_glob(&patterns.join(", "), e));
+        Ok(Some(Self {
+            any: build(any)?,
+            dirs: build(dirs)?,
+        }))
+    }
+
+    // `rel` itself, or a folder above it below the root
+    fn matches(&self, rel: &Path, is_dir: bool) -> bool {
+        if self.any.is_match(rel) || (is_dir && self.dirs.is_match(rel)) {
+            return true;
+        }
+        rel.ancestors()
+            .skip(1)
+            .take_while(|a| !a.as_os_str().is_empty())
+            .any(|a| self.any.is_match(a) || self.dirs.is_match(a))
+    }
+}
+
+fn invalid_glob(pattern: &str, e: globset::Error) -> AppError {
+    AppError::new(
+        ErrorKind::InvalidInput,
+        format!("Pattern \"{}\" is not valid: {}", pattern, e.kind()),
+    )
+    .with_code("glob_invalid")
+    .with_param("pattern", pattern)
+}
+
+#[derive(Default)]
+pub struct GlobFilter {
+    include: Option<Globs>,
+    exclude: Option<Globs>,
+}
+
+impl GlobFilter {
+    pub fn new(include: &[String], exclude: &[String]) -> Result<Self, AppError> {
+        Ok(Self {
+            include: Globs::new(include)?,
+            exclude: Globs::new(exclude)?,
+        })
+    }
+
+    // `rel` is a folder below the scan root
+    pub fn skips_dir(&self, rel: &Path) -> bool {
+        self.exclude.as_ref().is_some_and(|x| x.matches(rel, true))
+    }
+
+    // `rel` is a file below the scan root (or inside an archive there)
+    pub fn skips_file(&self, rel: &Path) -> bool {
+        self.exclude.as_ref().is_some_and(|x| x.matches(rel, false))
+            || self
+                .include
+                .as_ref()
+                .is_some_and(|i| !i.matches(rel, false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn filter(include: &[&str], exclude: &[&str]) -> GlobFilter {
+        let owned = |p: &[&str]| p.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        GlobFilter::new(&owned(include), &owned(exclude)).unwrap()
+    }
+
+    #[test]
+    fn pattern_without_slash_matches_at_any_depth() {
+        let f = filter(&[], &["*_old"]);
+        assert!(f.skips_dir(Path::new("scene_old")));
+        assert!(f.skips_dir(Path::new("shots/010/scene_old")));
+        assert!(f.skips_file(Path::new("shots/scene_old/a.blend")));
+        assert!(!f.skips_file(Path::new("shots/scene/a.blend")));
+    }
+
+    #[test]
+    fn pattern_with_slash_is_anchored_at_the_root() {
+        let f = filter(&[], &["shots/*/renders"]);
+        assert!(f.skips_dir(Path::new("shots/010/renders")));
+        assert!(f.skips_file(Path::new("shots/010/renders/a.blend")));
+        assert!(!f.skips_dir(Path::new("old/shots/010/renders")));
+        // `*` stops at a separator
+        assert!(!f.skips_dir(Path::new("shots/010/a/renders")));
+
+        let f = filter(&[], &["/cache"]);
+        assert!(f.skips_dir(Path::new("cache")));
+        assert!(!f.skips_dir(Path::new("assets/cache")));
+    }
+
+    #[test]
+    fn trailing_slash_limits_to_folders() {
+        let f = filter(&[], &["renders/"]);
+        assert!(f.skips_dir(Path::new("renders")));
+        assert!(f.skips_file(Path::new("renders/a.blend")));
+        assert!(!f.skips_file(Path::new("renders")));
+
+        let f = filter(&[], &["renders"]);
+        assert!(f.skips_file(Path::new("renders")));
+    }
+
+    #[test]
+    fn include_keeps_matching_files_and_folder_contents() {
+        let f = filter(&["*.blend", "assets/"], &[]);
+        assert!(!f.skips_file(Path::new("a/b.blend")));
+        assert!(!f.skips_file(Path::new("assets/tex.png")));
+        assert!(f.skips_file(Path::new("a/tex.png")));
+        assert!(!f.skips_dir(Path::new("a")));
+    }
+
+    #[test]
+    fn exclude_wins_over_include() {
+        let f = filter(&["*.blend"], &["*_old"]);
+        assert!(f.skips_file(Path::new("x_old/a.blend")));
+    }
+
+    #[test]
+    fn blank_patterns_filter_nothing() {
+        let f = filter(&[" ", "/"], &[""]);
+        assert!(!f.skips_file(Path::new("a.blend")));
+        assert!(!f.skips_dir(Path::new("a")));
+    }
+
+    #[test]
+    fn invalid_pattern_is_reported() {
+        let err = GlobFilter::new(&[], &["a[".to_string()]).err().unwrap();
+        assert_eq!(err.code, "glob_invalid");
+    }
+}
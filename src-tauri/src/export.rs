@@ -0,0 +1,100 @@
+use crate::error::{AppError, ErrorKind};
+use crate::settings;
+use crate::{FlatFile, ScanResult};
+use chrono::{DateTime, Local, Utc};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use tera::{Context, Tera, Value};
+
+// -----------------------------
+// Template-driven exports
+// -----------------------------
+// Templates are plain Tera files supplied by the user. The context exposes:
+//   files, tree, root, file_count, total_size, generated_at
+// Each file has `path` and a portable `rel_path` (`/`-separated, from root),
+// and custom fields under `fields` (see extractors.rs).
+// Timestamps are RFC3339 in local time, or UTC with the `export_utc` setting;
+// `created_ms`/`modified_ms` are epoch millis either way.
+
+// Re-expresses an RFC3339 stamp in the zone exports use
+pub fn export_stamp(stamp: Option<&str>, utc: bool) -> Option<String> {
+    let s = stamp?;
+    Some(match DateTime::parse_from_rfc3339(s) {
+        Ok(t) if utc => t.with_timezone(&Utc).to_rfc3339(),
+        Ok(t) => t.with_timezone(&Local).to_rfc3339(),
+        Err(_) => s.to_string(),
+    })
+}
+
+fn csv_filter(value: &Value, _args: &HashMap<String, Value>) -> tera::Result<Value> {
+    let s = match value {
+        Value::String(s) => s.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    };
+    let escaped = if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s
+    };
+    Ok(Value::String(escaped))
+}
+
+pub fn render_template(template_path: &Path, result: &ScanResult) -> Result<String, AppError> {
+    let source = fs::read_to_string(template_path).map_err(|e| AppError::io(&e, template_path))?;
+
+    let mut tera = Tera::default();
+    tera.register_filter("csv", csv_filter);
+    tera.add_raw_template("report", &source).map_err(|e| {
+        AppError::new(ErrorKind::Template, format!("Template error: {}", e))
+            .with_path(template_path)
+    })?;
+
+    let utc = settings::get().export_utc;
+    let files: Vec<FlatFile> = result
+        .files
+        .iter()
+        .map(|f| FlatFile {
+            created: export_stamp(f.created.as_deref(), utc),
+            modified: export_stamp(f.modified.as_deref(), utc),
+            ..f.clone()
+        })
+        .collect();
+    let generated_at = if utc {
+        Utc::now().to_rfc3339()
+    } else {
+        Local::now().to_rfc3339()
+    };
+
+    let mut ctx = Context::new();
+    ctx.insert("files", &files);
+    ctx.insert("tree", &result.tree);
+    ctx.insert("root", &result.tree.path);
+    ctx.insert("file_count", &result.files.len());
+    ctx.insert(
+        "total_size",
+        &result.files.iter().map(|f| f.size_bytes).sum::<u64>(),
+    );
+    ctx.insert("generated_at", &generated_at);
+
+    // Tera hides the root cause behind a generic "Failed to render" message
+    tera.render("report", &ctx).map_err(|e| {
+        let mut msg = e.to_string();
+        let mut src = std::error::Error::source(&e);
+        while let Some(inner) = src {
+            msg.push_str(&format!(": {}", inner));
+            src = inner.source();
+        }
+        AppError::new(ErrorKind::Template, msg).with_path(template_path)
+    })
+}
+
+pub fn export_with_template(
+    template_path: &Path,
+    output_path: &Path,
+    result: &ScanResult,
+) -> Result<(), AppError> {
+    let body = render_template(template_path, result)?;
+    fs::write(output_path, body).map_err(|e| AppError::io(&e, output_path))
+}
@@ -0,0 +1,395 @@
+use crate::error::{AppError, ErrorKind};
+use crate::{paths, settings, FlatFile};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// -----------------------------
+// Custom fields
+// -----------------------------
+// Extractors add studio-specific fields to files ("shotgrid_id" from the
+// file name, an asset code from a pipeline database). Fields are plain
+// strings under lowercase names; they are searched as `name:value` and
+// exported as `file.fields.name`.
+//
+// Extractors are built in (behind cargo features) or configured in
+// settings:
+//
+//   {"kind": "pattern", "pattern": "_sg(?P<shotgrid_id>\\d+)"}
+//       each named group of a regex over the file name (or the path, with
+//       "source": "path") becomes a field
+//   {"kind": "script", "name": "pipeline", "program": "python", "args": ["fields.py"]}
+//       a program reading one JSON object per line on stdin,
+//       {"path_id": …, "path": …}, and answering lines of
+//       {"path_id": …, "fields": {"name": "value"}} on stdout
+//
+// Later extractors win when two set the same field.
+
+pub type Fields = BTreeMap<String, String>;
+
+// Scripts get this long for a whole batch of files
+const SCRIPT_TIMEOUT: Duration = Duration::from_secs(120);
+const POLL: Duration = Duration::from_millis(100);
+
+pub trait Extractor: Send + Sync {
+    fn name(&self) -> &str;
+    // Fields by path id; files without any are left out
+    fn extract(&self, files: &[&FlatFile]) -> Result<HashMap<String, Fields>, AppError>;
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum Source {
+    #[default]
+    Name,
+    Path, // `/`-separated
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ExtractorConfig {
+    Pattern {
+        pattern: String,
+        #[serde(default)]
+        source: Source,
+    },
+    Script {
+        name: String,
+        program: String,
+        #[serde(default)]
+        args: Vec<String>,
+    },
+}
+
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+// -----------------------------
+// Pattern extractor
+// -----------------------------
+struct PatternExtractor {
+    pattern: String,
+    re: Regex,
+    source: Source,
+}
+
+impl PatternExtractor {
+    fn new(pattern: &str, source: Source) -> Result<Self, AppError> {
+        let invalid = |msg: String| {
+            AppError::new(
+                ErrorKind::InvalidInput,
+                format!("Field pattern \"{}\" {}", pattern, msg),
+            )
+        };
+        let re = Regex::new(pattern).map_err(|e| invalid(format!("is not valid: {}", e)))?;
+        let names: Vec<&str> = re.capture_names().flatten().collect();
+        if names.is_empty() {
+            return Err(invalid("has no named group to become a field".to_string()));
+        }
+        if let Some(bad) = names.iter().find(|n| !valid_name(n)) {
+            return Err(invalid(format!(
+                "names a field \"{}\"; use lowercase letters, digits and _",
+                bad
+            )));
+        }
+        Ok(Self {
+            pattern: pattern.to_string(),
+            re,
+            source,
+        })
+    }
+}
+
+impl Extractor for PatternExtractor {
+    fn name(&self) -> &str {
+        &self.pattern
+    }
+
+    fn extract(&self, files: &[&FlatFile]) -> Result<HashMap<String, Fields>, AppError> {
+        let mut out = HashMap::new();
+        for f in files {
+            let haystack = match self.source {
+                Source::Name => f.name.clone(),
+                Source::Path => paths::from_id(&f.path_id)
+                    .to_string_lossy()
+                    .replace('\\', "/"),
+            };
+            let Some(caps) = self.re.captures(&haystack) else {
+                continue;
+            };
+            let fields: Fields = self
+                .re
+                .capture_names()
+                .flatten()
+                .filter_map(|n| Some((n.to_string(), caps.name(n)?.as_str().to_string())))
+                .collect();
+            if !fields.is_empty() {
+                out.insert(f.path_id.clone(), fields);
+            }
+        }
+        Ok(out)
+    }
+}
+
+// -----------------------------
+// Script extractor
+// -----------------------------
+struct ScriptExtractor {
+    name: String,
+    program: String,
+    args: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct ScriptRequest<'a> {
+    path_id: &'a str,
+    path: String,
+}
+
+#[derive(Deserialize)]
+struct ScriptAnswer {
+    path_id: String,
+    #[serde(default)]
+    fields: BTreeMap<String, serde_json::Value>,
+}
+
+impl ScriptExtractor {
+    fn failed(&self, msg: impl std::fmt::Display) -> AppError {
+        AppError::new(
+            ErrorKind::Io,
+            format!("Field script \"{}\" failed: {}", self.name, msg),
+        )
+    }
+}
+
+impl Extractor for ScriptExtractor {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn extract(&self, files: &[&FlatFile]) -> Result<HashMap<String, Fields>, AppError> {
+        let mut cmd = Command::new(self.program.trim());
+        cmd.args(&self.args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null());
+        #[cfg(windows)]
+        {
+            use std::os::windows::process::CommandExt;
+            const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+            cmd.creation_flags(CREATE_NO_WINDOW);
+        }
+        let mut child = cmd
+            .spawn()
+            .map_err(|e| AppError::io(&e, Path::new(self.program.trim())))?;
+
+        let requests: Vec<String> = files
+            .iter()
+            .filter_map(|f| {
+                serde_json::to_string(&ScriptRequest {
+                    path_id: &f.path_id,
+                    path: paths::from_id(&f.path_id).to_string_lossy().to_string(),
+                })
+                .ok()
+            })
+            .collect();
+        // Written and read on threads so neither pipe can fill up and stall
+        let mut stdin = child.stdin.take();
+        let writer = thread::spawn(move || {
+            if let Some(stdin) = stdin.as_mut() {
+                for line in requests {
+                    if writeln!(stdin, "{}", line).is_err() {
+                        break;
+                    }
+                }
+            }
+        });
+        let stdout = child.stdout.take();
+        let reader = thread::spawn(move || {
+            stdout
+                .map(|s| BufReader::new(s).lines().map_while(Result::ok).collect())
+                .unwrap_or_else(Vec::<String>::new)
+        });
+
+        let started = Instant::now();
+        let status = loop {
+            match child.try_wait() {
+                Ok(Some(status)) => break status,
+                Ok(None) if started.elapsed() > SCRIPT_TIMEOUT => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    return Err(AppError::new(
+                        ErrorKind::Timeout,
+                        format!("Field script \"{}\" took too long", self.name),
+                    ));
+                }
+                Ok(None) => thread::sleep(POLL),
+                Err(e) => return Err(self.failed(e)),
+            }
+        };
+        let _ = writer.join();
+        let lines = reader.join().unwrap_or_default();
+        if !status.success() {
+            return Err(self.failed(format!("exit code {:?}", status.code())));
+        }
+
+        let mut out = HashMap::new();
+        for line in lines.iter().filter(|l| !l.trim().is_empty()) {
+            let answer: ScriptAnswer = match serde_json::from_str(line) {
+                Ok(a) => a,
+                Err(e) => {
+                    tracing::warn!(script = %self.name, "skipping field script line: {}", e);
+                    continue;
+                }
+            };
+            let fields: Fields = answer
+                .fields
+                .into_iter()
+                .filter_map(|(k, v)| {
+                    let value = match v {
+                        serde_json::Value::Null => return None,
+                        serde_json::Value::String(s) => s,
+                        other => other.to_string(),
+                    };
+                    Some((k.to_lowercase(), value))
+                })
+                .collect();
+            out.insert(answer.path_id, fields);
+        }
+        Ok(out)
+    }
+}
+
+// -----------------------------
+// Built-in extractors
+// -----------------------------
+// Sequence and shot numbers from names like `sq010_sh0020_anim.blend`
+#[cfg(feature = "shot-fields")]
+struct ShotNames {
+    re: Regex,
+}
+
+#[cfg(feature = "shot-fields")]
+impl Extractor for ShotNames {
+    fn name(&self) -> &str {
+        "shot names"
+    }
+
+    fn extract(&self, files: &[&FlatFile]) -> Result<HashMap<String, Fields>, AppError> {
+        let mut out = HashMap::new();
+        for f in files {
+            let mut fields = Fields::new();
+            for caps in self.re.captures_iter(&f.name) {
+                let field = if caps[1].eq_ignore_ascii_case("sh") {
+                    "shot"
+                } else {
+                    "sequence"
+                };
+                fields.insert(field.to_string(), caps[2].to_string());
+            }
+            if !fields.is_empty() {
+                out.insert(f.path_id.clone(), fields);
+            }
+        }
+        Ok(out)
+    }
+}
+
+fn builtin() -> Vec<Box<dyn Extractor>> {
+    vec![
+        #[cfg(feature = "shot-fields")]
+        Box::new(ShotNames {
+            re: Regex::new(r"(?i)(?:^|[_\-. ])(sq|seq|sh)(\d+)").expect("valid regex"),
+        }),
+    ]
+}
+
+fn configured(config: &ExtractorConfig) -> Result<Box<dyn Extractor>, AppError> {
+    Ok(match config {
+        ExtractorConfig::Pattern { pattern, source } => {
+            Box::new(PatternExtractor::new(pattern, *source)?)
+        }
+        ExtractorConfig::Script {
+            name,
+            program,
+            args,
+        } => {
+            if name.trim().is_empty() || program.trim().is_empty() {
+                return Err(AppError::new(
+                    ErrorKind::InvalidInput,
+                    "A field script needs a name and a program",
+                ));
+            }
+            Box::new(ScriptExtractor {
+                name: name.clone(),
+                program: program.clone(),
+                args: args.clone(),
+            })
+        }
+    })
+}
+
+// Rejects configurations that could not run
+pub fn check(configs: &[ExtractorConfig]) -> Result<(), AppError> {
+    for config in configs {
+        configured(config)?;
+    }
+    Ok(())
+}
+
+// Built-in extractors, then those in settings; ones that no longer build
+// are skipped
+fn registered() -> Vec<Box<dyn Extractor>> {
+    let mut all = builtin();
+    for config in &settings::get().extractors {
+        match configured(config) {
+            Ok(e) => all.push(e),
+            Err(e) => tracing::warn!("skipping field extractor: {}", e),
+        }
+    }
+    all
+}
+
+// Replaces the fields of `files`. A failing extractor is logged and
+// leaves its fields out.
+pub fn apply<'a>(files: impl IntoIterator<Item = &'a mut FlatFile>) {
+    let mut files: Vec<&mut FlatFile> = files.into_iter().collect();
+    let extractors = registered();
+    if files.is_empty() || extractors.is_empty() {
+        for f in files {
+            f.fields.clear();
+        }
+        return;
+    }
+    let mut found: HashMap<String, Fields> = HashMap::new();
+    {
+        let view: Vec<&FlatFile> = files.iter().map(|f| &**f).collect();
+        for extractor in &extractors {
+            match extractor.extract(&view) {
+                Ok(by_file) => {
+                    for (path_id, fields) in by_file {
+                        let kept = fields.into_iter().filter(|(k, _)| valid_name(k));
+                        found.entry(path_id).or_default().extend(kept);
+                    }
+                }
+                Err(e) => tracing::warn!(
+                    extractor = extractor.name(),
+                    "field extraction failed: {}",
+                    e
+                ),
+            }
+        }
+    }
+    for f in files.iter_mut() {
+        f.fields = found.remove(&f.path_id).unwrap_or_default();
+    }
+}
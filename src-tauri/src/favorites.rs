@@ -0,0 +1,80 @@
+use crate::db;
+use crate::error::AppError;
+use crate::paths;
+use crate::FlatFile;
+use chrono::Local;
+use rusqlite::params;
+use serde::Serialize;
+use std::collections::HashSet;
+
+// -----------------------------
+// Favorites (pinned files)
+// -----------------------------
+// Keyed by path id like tags, so they survive rescans; a favorite whose
+// file is gone stays listed (flagged) until the user unpins it.
+
+#[derive(Serialize, Clone)]
+pub struct Favorite {
+    pub path: String,
+    pub path_id: String,
+    pub name: String,
+    pub added_at: String,
+    pub exists: bool,
+}
+
+// Returns the new state: true when the file is now a favorite
+pub fn toggle(path_id: &str) -> Result<bool, AppError> {
+    db::with(|conn| {
+        let removed = conn.execute("DELETE FROM favorites WHERE path_id = ?1", [path_id])?;
+        if removed > 0 {
+            return Ok(false);
+        }
+        conn.execute(
+            "INSERT INTO favorites (path_id, added_at) VALUES (?1, ?2)",
+            params![path_id, Local::now().to_rfc3339()],
+        )?;
+        Ok(true)
+    })
+}
+
+pub fn list() -> Result<Vec<Favorite>, AppError> {
+    let rows: Vec<(String, String)> = db::with(|conn| {
+        let mut stmt =
+            conn.prepare("SELECT path_id, added_at FROM favorites ORDER BY added_at DESC")?;
+        let rows = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?;
+        rows.collect()
+    })?;
+    Ok(rows
+        .into_iter()
+        .map(|(path_id, added_at)| {
+            let path = paths::from_id(&path_id);
+            Favorite {
+                name: path
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                path: path.to_string_lossy().to_string(),
+                exists: path.is_file(),
+                path_id,
+                added_at,
+            }
+        })
+        .collect())
+}
+
+// Fills `favorite` on result files
+pub fn annotate(files: &mut [FlatFile]) {
+    let ids: Result<HashSet<String>, AppError> = db::with(|conn| {
+        let mut stmt = conn.prepare("SELECT path_id FROM favorites")?;
+        let rows = stmt.query_map([], |r| r.get(0))?;
+        rows.collect()
+    });
+    match ids {
+        Ok(ids) => {
+            for f in files {
+                f.favorite = ids.contains(&f.path_id);
+            }
+        }
+        Err(e) => tracing::warn!("loading favorites failed: {}", e),
+    }
+}
@@ -0,0 +1,106 @@
+use crate::db;
+use crate::error::AppError;
+use crate::FlatFile;
+use rusqlite::{params, OptionalExtension};
+use std::collections::HashMap;
+
+// -----------------------------
+// Stable file ids
+// -----------------------------
+// Every indexed file gets a random id (`fid:` + 32 hex digits) that commands
+// accept wherever they take a path. User data stays keyed by path id; when
+// a file moves, `moved` re-points its id and carries the data along, so
+// favorites, tags and collections follow the file.
+
+// Like `paths`' raw prefix, it can't start an absolute path
+pub const PREFIX: &str = "fid:";
+
+// Tables holding per-file user data, keyed by `path_id`
+const USER_DATA_TABLES: &[&str] = &[
+    "file_tags",
+    "favorites",
+    "ratings",
+    "labels",
+    "collection_files",
+    "notes",
+];
+
+// Gives ids to files that don't have one yet
+pub fn assign(path_ids: &[String]) {
+    let res = db::with(|conn| {
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR IGNORE INTO file_ids (id, path_id)
+                 VALUES (?1 || lower(hex(randomblob(16))), ?2)",
+            )?;
+            for p in path_ids {
+                stmt.execute(params![PREFIX, p])?;
+            }
+        }
+        tx.commit()
+    });
+    if let Err(e) = res {
+        tracing::warn!("assigning file ids failed: {}", e);
+    }
+}
+
+// Path id behind a file id; None for unknown ids
+pub fn path_of(id: &str) -> Option<String> {
+    db::with(|conn| {
+        conn.query_row("SELECT path_id FROM file_ids WHERE id = ?1", [id], |r| {
+            r.get(0)
+        })
+        .optional()
+    })
+    .ok()
+    .flatten()
+}
+
+// The file now lives at `to`: its id and user data move with it, replacing
+// whatever `to` had
+pub fn moved(from: &str, to: &str) -> Result<(), AppError> {
+    if from == to {
+        return Ok(());
+    }
+    db::with(|conn| {
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM file_ids WHERE path_id = ?1", [to])?;
+        tx.execute(
+            "UPDATE file_ids SET path_id = ?2 WHERE path_id = ?1",
+            params![from, to],
+        )?;
+        for table in USER_DATA_TABLES {
+            tx.execute(
+                &format!(
+                    "UPDATE OR REPLACE {} SET path_id = ?2 WHERE path_id = ?1",
+                    table
+                ),
+                params![from, to],
+            )?;
+        }
+        tx.commit()
+    })
+}
+
+fn all() -> Result<HashMap<String, String>, AppError> {
+    db::with(|conn| {
+        let mut stmt = conn.prepare("SELECT path_id, id FROM file_ids")?;
+        let rows = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?;
+        rows.collect()
+    })
+}
+
+// Fills `file_id` on result files
+pub fn annotate(files: &mut [FlatFile]) {
+    let map = match all() {
+        Ok(map) => map,
+        Err(e) => {
+            tracing::warn!("loading file ids failed: {}", e);
+            return;
+        }
+    };
+    for f in files {
+        f.file_id = map.get(&f.path_id).cloned();
+    }
+}
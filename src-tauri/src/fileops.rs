@@ -0,0 +1,619 @@
+use crate::error::{AppError, ErrorKind};
+use crate::handles;
+use crate::jobs::JobCtx;
+use crate::paths;
+use crate::volume;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::fs::{self, OpenOptions};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, UNIX_EPOCH};
+
+// -----------------------------
+// Long-running file operations (run inside jobs)
+// -----------------------------
+const CHUNK_SIZE: usize = 1024 * 1024;
+
+#[derive(Serialize, Clone)]
+pub struct CopiedFile {
+    pub source: String,
+    pub destination: String,
+}
+
+#[derive(Serialize, Clone)]
+pub struct FileHash {
+    pub path: String,
+    pub sha256: String,
+    pub size_bytes: u64,
+}
+
+fn total_size(paths: &[PathBuf]) -> Result<u64, AppError> {
+    let mut total = 0;
+    for p in paths {
+        total += fs::metadata(p).map_err(|e| AppError::io(&e, p))?.len();
+    }
+    Ok(total)
+}
+
+fn human_bytes(n: u64) -> String {
+    const UNITS: &[&str] = &["B", "KB", "MB", "GB", "TB"];
+    let mut value = n as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", value, UNITS[unit])
+}
+
+// Fails before any byte is written if `dest` can't hold `needed` bytes.
+// Unknown free space (unsupported platform, odd share) lets the job run.
+fn ensure_space(dest: &Path, needed: u64) -> Result<(), AppError> {
+    let Some(free) = volume::free_space(dest) else {
+        return Ok(());
+    };
+    if needed <= free {
+        return Ok(());
+    }
+    Err(AppError::new(
+        ErrorKind::InsufficientSpace,
+        format!(
+            "Not enough free space: needs {}, only {} available",
+            human_bytes(needed),
+            human_bytes(free)
+        ),
+    )
+    .with_param("needed", needed)
+    .with_param("available", free)
+    .with_path(dest))
+}
+
+// Streams `reader` into `sink` in chunks, reporting bytes and honouring cancel
+fn pump<R: Read>(
+    ctx: &JobCtx,
+    reader: &mut R,
+    src: &Path,
+    mut sink: impl FnMut(&[u8]) -> Result<(), AppError>,
+) -> Result<(), AppError> {
+    let mut buf = vec![0u8; CHUNK_SIZE];
+    loop {
+        ctx.check_cancelled()?;
+        let n = reader.read(&mut buf).map_err(|e| AppError::io(&e, src))?;
+        if n == 0 {
+            return Ok(());
+        }
+        sink(&buf[..n])?;
+        ctx.add_bytes(n as u64);
+    }
+}
+
+// Never replaces an existing file: `dest` is created here or the copy fails
+fn copy_one(ctx: &JobCtx, src: &Path, dest: &Path) -> Result<(), AppError> {
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent).map_err(|e| AppError::io(&e, parent))?;
+    }
+    let mut input = handles::open(src)?;
+    let mut output = handles::open_with(dest, OpenOptions::new().write(true).create_new(true))?;
+
+    let copied = pump(ctx, &mut input, src, |chunk| {
+        output.write_all(chunk).map_err(|e| AppError::io(&e, dest))
+    });
+    if copied.is_err() {
+        // Don't leave half-written files behind on cancel/error; this call
+        // created `dest`, so nothing else is lost
+        drop(output);
+        let _ = fs::remove_file(dest);
+    }
+    copied
+}
+
+// Picks `name`, or `name (2)`, `name (3)`… if the destination already exists
+fn unique_destination(dir: &Path, name: &str) -> PathBuf {
+    batch_destination(dir, name, &mut HashSet::new())
+}
+
+// Like `unique_destination`, also skipping names in `taken` (lowercase path
+// ids already given to other files of the batch); adds the pick to it
+fn batch_destination(dir: &Path, name: &str, taken: &mut HashSet<String>) -> PathBuf {
+    let mut free = |c: &Path| !c.exists() && taken.insert(paths::to_id(c).to_lowercase());
+    let candidate = dir.join(name);
+    if free(&candidate) {
+        return candidate;
+    }
+    let p = Path::new(name);
+    let stem = p.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = p
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    (2..)
+        .map(|i| dir.join(format!("{} ({}){}", stem, i, ext)))
+        .find(|c| free(c))
+        .unwrap()
+}
+
+fn file_name(p: &Path) -> Result<String, AppError> {
+    p.file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .ok_or_else(|| AppError::new(ErrorKind::InvalidInput, "Path has no file name").with_path(p))
+}
+
+fn copy_all(
+    ctx: &JobCtx,
+    plan: &[(PathBuf, PathBuf)],
+    dest_dir: &Path,
+) -> Result<Vec<CopiedFile>, AppError> {
+    // A destination that is the source (dest_dir inside the sources, links)
+    // would be read while being written
+    for (src, dest) in plan {
+        let same = fs::canonicalize(src)
+            .ok()
+            .is_some_and(|s| fs::canonicalize(dest).is_ok_and(|d| d == s));
+        if same || src == dest {
+            return Err(AppError::new(
+                ErrorKind::InvalidInput,
+                "The destination is the file itself",
+            )
+            .with_code("copy_onto_source")
+            .with_path(src));
+        }
+    }
+    let sources: Vec<PathBuf> = plan.iter().map(|(s, _)| s.clone()).collect();
+    let total = total_size(&sources)?;
+    ensure_space(dest_dir, total)?;
+    ctx.set_totals(plan.len() as u64, total);
+
+    let mut copied = Vec::with_capacity(plan.len());
+    for (src, dest) in plan {
+        ctx.set_current(src);
+        // Resolved up front: after an unmount the path maps to another volume
+        let volumes = [volume::volume_of(src), volume::volume_of(dest)];
+        if let Err(e) = copy_one(ctx, src, dest) {
+            for v in &volumes {
+                volume::check(v)?;
+            }
+            return Err(e);
+        }
+        copied.push(CopiedFile {
+            source: src.to_string_lossy().to_string(),
+            destination: dest.to_string_lossy().to_string(),
+        });
+        ctx.item_done();
+    }
+    Ok(copied)
+}
+
+// Copies files flat into `dest_dir`, renaming on name clashes
+pub fn copy_files(
+    ctx: &JobCtx,
+    paths: &[PathBuf],
+    dest_dir: &Path,
+) -> Result<Vec<CopiedFile>, AppError> {
+    fs::create_dir_all(dest_dir).map_err(|e| AppError::io(&e, dest_dir))?;
+    let plan = flat_plan(paths, dest_dir)?;
+    copy_all(ctx, &plan, dest_dir)
+}
+
+// Two sources with one name (`a/scene.blend`, `b/scene.blend`) get
+// `scene.blend` and `scene (2).blend`
+fn flat_plan(paths: &[PathBuf], dest_dir: &Path) -> Result<Vec<(PathBuf, PathBuf)>, AppError> {
+    let mut taken = HashSet::new();
+    paths
+        .iter()
+        .map(|p| {
+            Ok((
+                p.clone(),
+                batch_destination(dest_dir, &file_name(p)?, &mut taken),
+            ))
+        })
+        .collect()
+}
+
+fn common_ancestor(paths: &[PathBuf]) -> Option<PathBuf> {
+    let mut iter = paths.iter();
+    let mut common = iter.next()?.parent()?.to_path_buf();
+    for p in iter {
+        while !p.starts_with(&common) {
+            if !common.pop() {
+                return None;
+            }
+        }
+    }
+    Some(common)
+}
+
+// Gathers files into `dest_dir`, keeping their layout relative to their
+// deepest common folder; files already there get `name (2)`… like copies
+pub fn consolidate_files(
+    ctx: &JobCtx,
+    paths: &[PathBuf],
+    dest_dir: &Path,
+) -> Result<Vec<CopiedFile>, AppError> {
+    // Without one (files on two drives) the layout can't be kept
+    let base = common_ancestor(paths).ok_or_else(|| {
+        AppError::new(
+            ErrorKind::InvalidInput,
+            "The files have no folder in common; copy them instead",
+        )
+        .with_code("no_common_folder")
+    })?;
+    let mut plan = Vec::with_capacity(paths.len());
+    let mut taken = HashSet::new();
+    for p in paths {
+        let dest = dest_dir.join(p.strip_prefix(&base).unwrap_or(p));
+        let dest = match dest.parent() {
+            Some(dir) => batch_destination(dir, &file_name(&dest)?, &mut taken),
+            None => dest,
+        };
+        plan.push((p.clone(), dest));
+    }
+    copy_all(ctx, &plan, dest_dir)
+}
+
+// -----------------------------
+// Resumable copies
+// -----------------------------
+// For huge files over links that drop (Wi-Fi to a NAS). Data goes to
+// `<name>.part` in chunks, each read back and compared by SHA-256 before
+// it counts; `<name>.part.json` lists the verified chunks. The read-back
+// is usually served from the local page cache, so it catches failed
+// writes and short files, not what the server stored on disk. Running the same
+// copy again continues after the last one, and failed chunks are retried
+// a few times before the job gives up. A source whose size or mtime
+// changed starts over.
+const RESUME_CHUNK: u64 = 8 * 1024 * 1024;
+const RESUME_RETRIES: u32 = 5;
+
+#[derive(Serialize, Deserialize)]
+struct Manifest {
+    source: String,
+    size_bytes: u64,
+    modified_ms: Option<i64>,
+    chunk_size: u64,
+    chunks: Vec<String>, // SHA-256 of each verified chunk, in order
+}
+
+fn hex(digest: &[u8]) -> String {
+    digest.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn manifest_path(part: &Path) -> PathBuf {
+    let mut name = part.as_os_str().to_owned();
+    name.push(".json");
+    PathBuf::from(name)
+}
+
+fn load_manifest(part: &Path) -> Option<Manifest> {
+    let text = fs::read_to_string(manifest_path(part)).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+fn save_manifest(part: &Path, manifest: &Manifest) -> Result<(), AppError> {
+    let path = manifest_path(part);
+    let tmp = path.with_extension("json.tmp");
+    let text = serde_json::to_string(manifest).unwrap_or_default();
+    fs::write(&tmp, text).map_err(|e| AppError::io(&e, &tmp))?;
+    fs::rename(&tmp, &path).map_err(|e| AppError::io(&e, &path))
+}
+
+// `<name>.part`, or `<name> (2).part`… when another source of the same
+// name already has a copy going in `dir`
+fn part_path(dir: &Path, name: &str, src: &str) -> PathBuf {
+    let p = Path::new(name);
+    let stem = p.file_stem().unwrap_or_default().to_string_lossy();
+    let ext = p
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|i| match i {
+            1 => dir.join(format!("{}.part", name)),
+            i => dir.join(format!("{} ({}){}.part", stem, i, ext)),
+        })
+        .find(|part| load_manifest(part).is_none_or(|m| m.source == src))
+        .unwrap()
+}
+
+// Files are opened again for every chunk: after a dropped connection the
+// old handles are dead even when the share is back
+fn copy_chunk(src: &Path, part: &Path, offset: u64, len: u64) -> Result<String, AppError> {
+    let mut buf = vec![0u8; len as usize];
+    let mut input = handles::open(src)?;
+    input
+        .seek(SeekFrom::Start(offset))
+        .and_then(|_| input.read_exact(&mut buf))
+        .map_err(|e| AppError::io(&e, src))?;
+    let hash = hex(&Sha256::digest(&buf));
+
+    let mut output = handles::open_with(
+        part,
+        OpenOptions::new().write(true).create(true).truncate(false),
+    )?;
+    // Drops whatever an interrupted chunk left past the verified data
+    output
+        .set_len(offset)
+        .and_then(|_| output.seek(SeekFrom::Start(offset)))
+        .and_then(|_| output.write_all(&buf))
+        .and_then(|_| output.sync_data())
+        .map_err(|e| AppError::io(&e, part))?;
+    drop(output);
+
+    // Likely from the page cache, not the share (see above)
+    let mut back = handles::open(part)?;
+    back.seek(SeekFrom::Start(offset))
+        .and_then(|_| back.read_exact(&mut buf))
+        .map_err(|e| AppError::io(&e, part))?;
+    if hex(&Sha256::digest(&buf)) != hash {
+        return Err(AppError::new(
+            ErrorKind::Io,
+            format!("Chunk at {} did not verify", human_bytes(offset)),
+        )
+        .with_code("chunk_mismatch")
+        .with_param("offset", offset)
+        .with_path(part));
+    }
+    Ok(hash)
+}
+
+// Waits 2, 4, 8… seconds between attempts, watching for cancel
+fn copy_chunk_retrying(
+    ctx: &JobCtx,
+    src: &Path,
+    part: &Path,
+    offset: u64,
+    len: u64,
+) -> Result<String, AppError> {
+    let mut attempt = 0;
+    loop {
+        ctx.check_cancelled()?;
+        match copy_chunk(src, part, offset, len) {
+            Ok(hash) => return Ok(hash),
+            Err(e) if attempt < RESUME_RETRIES => {
+                attempt += 1;
+                tracing::warn!(path = %src.display(), attempt, "chunk failed, retrying: {}", e);
+                for _ in 0..(2u64 << attempt) {
+                    ctx.check_cancelled()?;
+                    std::thread::sleep(Duration::from_millis(500));
+                }
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+fn copy_resumable(ctx: &JobCtx, src: &Path, dest_dir: &Path) -> Result<PathBuf, AppError> {
+    let meta = fs::metadata(src).map_err(|e| AppError::io(&e, src))?;
+    let source = src.to_string_lossy().to_string();
+    let name = file_name(src)?;
+    let part = part_path(dest_dir, &name, &source);
+    let fresh = Manifest {
+        source,
+        size_bytes: meta.len(),
+        modified_ms: meta
+            .modified()
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_millis() as i64),
+        chunk_size: RESUME_CHUNK,
+        chunks: Vec::new(),
+    };
+    // The part must still hold every listed chunk: one truncated or
+    // replaced since would leave a zero-filled gap below the resume point
+    let part_len = fs::metadata(&part).map(|m| m.len()).ok();
+    let mut manifest = match load_manifest(&part) {
+        Some(m)
+            if m.size_bytes == fresh.size_bytes
+                && m.modified_ms == fresh.modified_ms
+                && m.chunk_size == fresh.chunk_size
+                && part_len.is_some_and(|len| {
+                    len >= (m.chunks.len() as u64 * m.chunk_size).min(m.size_bytes)
+                }) =>
+        {
+            m
+        }
+        _ => fresh,
+    };
+    let mut offset = manifest.chunks.len() as u64 * manifest.chunk_size;
+    if offset > 0 {
+        tracing::info!(path = %src.display(), resumed_at = offset, "resuming copy");
+    }
+    ctx.add_bytes(offset.min(manifest.size_bytes));
+
+    while offset < manifest.size_bytes {
+        let len = manifest.chunk_size.min(manifest.size_bytes - offset);
+        let hash = copy_chunk_retrying(ctx, src, &part, offset, len)?;
+        manifest.chunks.push(hash);
+        save_manifest(&part, &manifest)?;
+        offset += len;
+        ctx.add_bytes(len);
+    }
+    if manifest.size_bytes == 0 {
+        handles::create(&part)?;
+    }
+
+    let dest = unique_destination(dest_dir, &name);
+    fs::rename(&part, &dest).map_err(|e| AppError::io(&e, &dest))?;
+    let _ = fs::remove_file(manifest_path(&part));
+    Ok(dest)
+}
+
+// Copies files flat into `dest_dir` like `copy_files`, but resumably: a
+// failed or cancelled job keeps its verified chunks for the next run
+pub fn copy_files_resumable(
+    ctx: &JobCtx,
+    paths: &[PathBuf],
+    dest_dir: &Path,
+) -> Result<Vec<CopiedFile>, AppError> {
+    fs::create_dir_all(dest_dir).map_err(|e| AppError::io(&e, dest_dir))?;
+    let total = total_size(paths)?;
+    // Parts of an earlier run already take their share
+    let mut started = 0;
+    for src in paths {
+        let part = part_path(dest_dir, &file_name(src)?, &src.to_string_lossy());
+        started += fs::metadata(&part).map(|m| m.len()).unwrap_or(0);
+    }
+    ensure_space(dest_dir, total.saturating_sub(started))?;
+    ctx.set_totals(paths.len() as u64, total);
+
+    let mut copied = Vec::with_capacity(paths.len());
+    for src in paths {
+        ctx.set_current(src);
+        let volumes = [volume::volume_of(src), volume::volume_of(dest_dir)];
+        let dest = match copy_resumable(ctx, src, dest_dir) {
+            Ok(dest) => dest,
+            Err(e) => {
+                for v in &volumes {
+                    volume::check(v)?;
+                }
+                return Err(e);
+            }
+        };
+        copied.push(CopiedFile {
+            source: src.to_string_lossy().to_string(),
+            destination: dest.to_string_lossy().to_string(),
+        });
+        ctx.item_done();
+    }
+    Ok(copied)
+}
+
+pub fn zip_files(ctx: &JobCtx, paths: &[PathBuf], zip_path: &Path) -> Result<String, AppError> {
+    use zip::write::SimpleFileOptions;
+
+    // Blends compress well, but stored data is the worst case
+    let total = total_size(paths)?;
+    ensure_space(zip_path, total)?;
+    ctx.set_totals(paths.len() as u64, total);
+    let base = common_ancestor(paths).unwrap_or_default();
+    // Written next to it and moved over `zip_path` once complete, so a
+    // cancel or error leaves an archive already there as it was
+    let dir = zip_path.parent().unwrap_or(Path::new("."));
+    let part = unique_destination(dir, &format!("{}.part", file_name(zip_path)?));
+    let out = handles::open_with(&part, OpenOptions::new().write(true).create_new(true))?;
+    let mut zip = zip::ZipWriter::new(out);
+    let options = SimpleFileOptions::default()
+        .compression_method(zip::CompressionMethod::Deflated)
+        .large_file(true);
+
+    let zip_err =
+        |e: zip::result::ZipError| AppError::new(ErrorKind::Io, e.to_string()).with_path(zip_path);
+
+    let written = (|| {
+        for p in paths {
+            ctx.set_current(p);
+            let rel = p.strip_prefix(&base).unwrap_or(p);
+            let entry = rel.to_string_lossy().replace('\\', "/");
+            zip.start_file(entry, options).map_err(zip_err)?;
+            let mut input = handles::open(p)?;
+            pump(ctx, &mut input, p, |chunk| {
+                zip.write_all(chunk).map_err(|e| AppError::io(&e, zip_path))
+            })?;
+            ctx.item_done();
+        }
+        zip.finish().map_err(zip_err)?;
+        Ok(())
+    })();
+
+    // `finish` consumed the writer, or the error dropped it: `part` is closed
+    if let Err(e) =
+        written.and_then(|()| fs::rename(&part, zip_path).map_err(|e| AppError::io(&e, zip_path)))
+    {
+        let _ = fs::remove_file(&part);
+        return Err(e);
+    }
+    Ok(zip_path.to_string_lossy().to_string())
+}
+
+pub fn hash_file(ctx: &JobCtx, path: &Path) -> Result<FileHash, AppError> {
+    let mut input = handles::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut size = 0u64;
+    pump(ctx, &mut input, path, |chunk| {
+        hasher.update(chunk);
+        size += chunk.len() as u64;
+        Ok(())
+    })?;
+    Ok(FileHash {
+        path: path.to_string_lossy().to_string(),
+        sha256: hex(&hasher.finalize()),
+        size_bytes: size,
+    })
+}
+
+pub fn hash_files(ctx: &JobCtx, paths: &[PathBuf]) -> Result<Vec<FileHash>, AppError> {
+    ctx.set_totals(paths.len() as u64, total_size(paths)?);
+    let mut hashes = Vec::with_capacity(paths.len());
+    for p in paths {
+        ctx.set_current(p);
+        hashes.push(hash_file(ctx, p)?);
+        ctx.item_done();
+    }
+    Ok(hashes)
+}
+
+// Exchanges two files in the same folder through a temporary name; a
+// failed step puts back what it moved
+pub fn swap_files(a: &Path, b: &Path) -> Result<(), AppError> {
+    let tmp = unique_destination(
+        a.parent().unwrap_or(Path::new(".")),
+        &format!("{}.swap", file_name(a)?),
+    );
+    fs::rename(a, &tmp).map_err(|e| AppError::io(&e, a))?;
+    if let Err(e) = fs::rename(b, a) {
+        let _ = fs::rename(&tmp, a);
+        return Err(AppError::io(&e, b));
+    }
+    if let Err(e) = fs::rename(&tmp, b) {
+        let _ = fs::rename(a, b);
+        let _ = fs::rename(&tmp, a);
+        return Err(AppError::io(&e, b));
+    }
+    Ok(())
+}
+
+// Sets or clears the platform read-only flag. On Unix, locking drops every
+// write bit and unlocking gives the owner write access back (not everyone).
+pub fn set_read_only(path: &Path, read_only: bool) -> Result<(), AppError> {
+    let mut perms = fs::metadata(path)
+        .map_err(|e| AppError::io(&e, path))?
+        .permissions();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = perms.mode();
+        perms.set_mode(if read_only {
+            mode & !0o222
+        } else {
+            mode | 0o200
+        });
+    }
+    #[cfg(not(unix))]
+    perms.set_readonly(read_only);
+    fs::set_permissions(path, perms).map_err(|e| AppError::io(&e, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn same_names_in_a_batch_get_numbered() {
+        let dir = std::env::temp_dir().join(format!("bff-copy-plan-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("scene (2).blend"), b"").unwrap();
+        let sources = [
+            PathBuf::from("/a/scene.blend"),
+            PathBuf::from("/b/scene.blend"),
+        ];
+        let names: Vec<PathBuf> = flat_plan(&sources, &dir)
+            .unwrap()
+            .into_iter()
+            .map(|(_, dest)| dest.strip_prefix(&dir).unwrap().to_path_buf())
+            .collect();
+        assert_eq!(names, ["scene.blend", "scene (3).blend"].map(PathBuf::from));
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,89 @@
+use crate::{DirError, FlatFile, TreeNode};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// -----------------------------
+// Flat tree encoding
+// -----------------------------
+// Deeply nested JSON is slow to produce and parse for 100k-node trees.
+// This encoding keeps one entry per node in parallel arrays: node `i` is
+// `names[i]` under node `parents[i]`, and `meta[i]` points into the
+// result's `files` (file nodes) or into `dirs` (folders).
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TreeFormat {
+    #[default]
+    Nested,
+    Flat,
+}
+
+#[derive(Serialize, Clone)]
+pub struct DirMeta {
+    pub path: String,
+    pub path_id: String,
+    pub child_count: usize,
+    pub blend_count: u64,
+    pub total_size: u64,
+    pub newest_modified: Option<String>,
+    pub dominant_version: Option<String>,
+    pub label: Option<String>,
+    pub is_new: bool,
+    pub is_modified_since_last_scan: bool,
+    pub error: Option<DirError>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct FlatTree {
+    pub names: Vec<String>,
+    pub parents: Vec<i32>, // -1 for the root; parents always come first
+    pub is_dir: Vec<bool>,
+    pub meta: Vec<i32>, // -1 for a file missing from `files`
+    pub dirs: Vec<DirMeta>,
+}
+
+pub fn encode(tree: &TreeNode, files: &[FlatFile]) -> FlatTree {
+    let file_index: HashMap<&str, i32> = files
+        .iter()
+        .enumerate()
+        .map(|(i, f)| (f.path_id.as_str(), i as i32))
+        .collect();
+    let mut out = FlatTree {
+        names: Vec::new(),
+        parents: Vec::new(),
+        is_dir: Vec::new(),
+        meta: Vec::new(),
+        dirs: Vec::new(),
+    };
+    // Depth first, children in their display order
+    let mut stack: Vec<(&TreeNode, i32)> = vec![(tree, -1)];
+    while let Some((node, parent)) = stack.pop() {
+        let index = out.names.len() as i32;
+        out.names.push(node.name.clone());
+        out.parents.push(parent);
+        out.is_dir.push(node.is_dir());
+        let meta = if node.is_dir() {
+            out.dirs.push(DirMeta {
+                path: node.path.clone(),
+                path_id: node.path_id.clone(),
+                child_count: node.child_count.unwrap_or(0),
+                blend_count: node.blend_count.unwrap_or(0),
+                total_size: node.total_size.unwrap_or(0),
+                newest_modified: node.newest_modified.clone(),
+                dominant_version: node.dominant_version.clone(),
+                label: node.label.clone(),
+                is_new: node.is_new,
+                is_modified_since_last_scan: node.is_modified_since_last_scan,
+                error: node.error.clone(),
+            });
+            out.dirs.len() as i32 - 1
+        } else {
+            file_index.get(node.path_id.as_str()).copied().unwrap_or(-1)
+        };
+        out.meta.push(meta);
+        for kid in node.children.iter().flatten().rev() {
+            stack.push((kid, index));
+        }
+    }
+    out
+}
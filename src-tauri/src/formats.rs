@@ -0,0 +1,62 @@
+use std::path::{Path, PathBuf};
+
+// -----------------------------
+// Indexed file formats
+// -----------------------------
+// Blends are parsed; the other 3D formats (opt-in) are listed with file
+// system metadata only, so mixed asset folders still get one inventory.
+
+pub const BLEND: &str = "blend";
+
+// extension -> format flag
+const OTHER_FORMATS: &[(&str, &str)] = &[
+    ("fbx", "fbx"),
+    ("obj", "obj"),
+    ("gltf", "gltf"),
+    ("glb", "gltf"),
+    ("usd", "usd"),
+    ("usda", "usd"),
+    ("usdc", "usd"),
+    ("usdz", "usd"),
+    ("abc", "abc"),
+];
+
+// `scene.blend1` -> 1: numbered backups Blender keeps next to the file
+pub fn backup_number(path: &Path) -> Option<u32> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    let n = ext.strip_prefix(BLEND)?;
+    if n.is_empty() || !n.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    n.parse().ok()
+}
+
+// Blender keeps at most this many numbered backups
+const MAX_BACKUPS: u32 = 32;
+
+// Existing backups of `path`, newest (`.blend1`) first. Blender shifts
+// them up one number per save, so the first gap ends the run.
+pub fn backups_of(path: &Path) -> Vec<PathBuf> {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return Vec::new();
+    };
+    (1..=MAX_BACKUPS)
+        .map(|n| path.with_file_name(format!("{}{}", name, n)))
+        .take_while(|p| p.is_file())
+        .collect()
+}
+
+// Format flag for `path` by extension; other formats only when `others`
+pub fn of_path(path: &Path, others: bool) -> Option<&'static str> {
+    let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+    if ext == BLEND {
+        return Some(BLEND);
+    }
+    if !others {
+        return None;
+    }
+    OTHER_FORMATS
+        .iter()
+        .find(|(e, _)| *e == ext)
+        .map(|(_, format)| *format)
+}
@@ -0,0 +1,192 @@
+use crate::error::{AppError, ErrorKind};
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::ops::{Deref, DerefMut};
+use std::path::Path;
+use std::sync::{Condvar, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// -----------------------------
+// Open file budget
+// -----------------------------
+// Parser workers, network timeouts (which can leave a read hanging) and
+// jobs all open files; the budget keeps the process well below the OS
+// descriptor limit so it never surfaces as "too many open files". Slots
+// held by stuck network reads only come back when the read does, so a wait
+// for one gives up after a while with that error instead of hanging.
+
+const MIN_OPEN_FILES: usize = 16;
+const MAX_OPEN_FILES: usize = 256;
+const OPEN_WAIT: Duration = Duration::from_secs(60);
+
+// Counting semaphore
+pub struct Semaphore {
+    available: Mutex<usize>,
+    freed: Condvar,
+}
+
+pub struct Permit<'a>(&'a Semaphore);
+
+impl Semaphore {
+    pub fn new(permits: usize) -> Self {
+        Self {
+            available: Mutex::new(permits.max(1)),
+            freed: Condvar::new(),
+        }
+    }
+
+    pub fn acquire(&self) -> Permit<'_> {
+        let mut available = self.available.lock().unwrap();
+        while *available == 0 {
+            available = self.freed.wait(available).unwrap();
+        }
+        *available -= 1;
+        Permit(self)
+    }
+
+    // None when no permit was freed within `timeout`
+    pub fn acquire_timeout(&self, timeout: Duration) -> Option<Permit<'_>> {
+        let available = self.available.lock().unwrap();
+        let (mut available, _) = self
+            .freed
+            .wait_timeout_while(available, timeout, |n| *n == 0)
+            .unwrap();
+        if *available == 0 {
+            return None;
+        }
+        *available -= 1;
+        Some(Permit(self))
+    }
+}
+
+impl Drop for Permit<'_> {
+    fn drop(&mut self) {
+        *self.0.available.lock().unwrap() += 1;
+        self.0.freed.notify_one();
+    }
+}
+
+// Spaces operations at least `interval` apart, across all threads
+pub struct RateLimiter {
+    interval: Duration,
+    next: Mutex<Instant>,
+}
+
+impl RateLimiter {
+    pub fn per_second(n: u32) -> Self {
+        Self {
+            interval: Duration::from_secs(1) / n.max(1),
+            next: Mutex::new(Instant::now()),
+        }
+    }
+
+    pub fn wait(&self) {
+        let now = Instant::now();
+        let slot = {
+            let mut next = self.next.lock().unwrap();
+            let slot = (*next).max(now);
+            *next = slot + self.interval;
+            slot
+        };
+        if slot > now {
+            thread::sleep(slot - now);
+        }
+    }
+}
+
+// A quarter of the soft descriptor limit; the rest is left to the webview,
+// sockets and plugins
+#[cfg(unix)]
+fn budget() -> usize {
+    let mut limit = libc::rlimit {
+        rlim_cur: 0,
+        rlim_max: 0,
+    };
+    // SAFETY: getrlimit only writes into `limit`
+    let soft = if unsafe { libc::getrlimit(libc::RLIMIT_NOFILE, &mut limit) } == 0 {
+        limit.rlim_cur as usize
+    } else {
+        256
+    };
+    (soft / 4).clamp(MIN_OPEN_FILES, MAX_OPEN_FILES)
+}
+
+// Windows handles are effectively unlimited; cap anyway for the disk's sake
+#[cfg(not(unix))]
+fn budget() -> usize {
+    MAX_OPEN_FILES
+}
+
+fn open_files() -> &'static Semaphore {
+    static OPEN_FILES: OnceLock<Semaphore> = OnceLock::new();
+    OPEN_FILES.get_or_init(|| Semaphore::new(budget()))
+}
+
+// A file that holds one slot of the budget until dropped
+pub struct OpenFile {
+    file: File,
+    _permit: Permit<'static>,
+}
+
+pub fn open(path: &Path) -> Result<OpenFile, AppError> {
+    open_with(path, OpenOptions::new().read(true))
+}
+
+pub fn open_with(path: &Path, options: &OpenOptions) -> Result<OpenFile, AppError> {
+    let Some(permit) = open_files().acquire_timeout(OPEN_WAIT) else {
+        return Err(AppError::new(
+            ErrorKind::TooManyOpenFiles,
+            "Too many files are open; try again when running jobs finish",
+        )
+        .with_path(path));
+    };
+    let file = options.open(path).map_err(|e| AppError::io(&e, path))?;
+    Ok(OpenFile {
+        file,
+        _permit: permit,
+    })
+}
+
+pub fn create(path: &Path) -> Result<OpenFile, AppError> {
+    open_with(
+        path,
+        OpenOptions::new().write(true).create(true).truncate(true),
+    )
+}
+
+impl Deref for OpenFile {
+    type Target = File;
+
+    fn deref(&self) -> &File {
+        &self.file
+    }
+}
+
+impl DerefMut for OpenFile {
+    fn deref_mut(&mut self) -> &mut File {
+        &mut self.file
+    }
+}
+
+impl Read for OpenFile {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        self.file.read(buf)
+    }
+}
+
+impl Write for OpenFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.file.write(buf)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+impl Seek for OpenFile {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        self.file.seek(pos)
+    }
+}
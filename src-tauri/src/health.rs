@@ -0,0 +1,271 @@
+use crate::error::{AppError, ErrorKind};
+use crate::magic::{self, Compression};
+use crate::{
+    archive, cloud, contents, handles, parse_blocks, paths, read_blend_header, staleness, triage,
+};
+use serde::Serialize;
+use std::fs::{self, OpenOptions};
+use std::io::BufReader;
+use std::path::Path;
+
+// -----------------------------
+// File health check
+// -----------------------------
+// Every check the app knows for one blend, in one report:
+//   parse          header, block structure (see triage.rs) and struct layouts
+//   compression    none, gzip or zstd
+//   dependencies   linked libraries and world images not on disk
+//   absolute_paths the same, saved without `//` (break on another machine)
+//   autorun        registered text blocks, run on load with Auto Run on
+//   lock           read-only, or can't be opened for writing (in use)
+// Compressed blends are checked for damage but not read further.
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[serde(rename_all = "snake_case")]
+pub enum Severity {
+    Ok,
+    Info,
+    Warning,
+    Error,
+}
+
+#[derive(Serialize, Clone)]
+pub struct Check {
+    pub id: &'static str,
+    pub severity: Severity,
+    pub message: String,
+    pub items: Vec<String>, // paths or names behind the message
+}
+
+#[derive(Serialize, Clone)]
+pub struct HealthReport {
+    pub path: String,
+    pub path_id: String,
+    pub severity: Severity, // the worst check
+    pub checks: Vec<Check>,
+}
+
+fn check(id: &'static str, severity: Severity, message: impl Into<String>) -> Check {
+    Check {
+        id,
+        severity,
+        message: message.into(),
+        items: Vec::new(),
+    }
+}
+
+fn with_items(mut c: Check, items: Vec<String>) -> Check {
+    c.items = items;
+    c
+}
+
+// What the block scan of an uncompressed blend found
+struct Contents {
+    libraries: Vec<String>,
+    images: Vec<String>, // unpacked world textures
+    scripts: Vec<String>,
+}
+
+fn read_contents(path: &Path) -> Result<Option<Contents>, AppError> {
+    let mut info = read_blend_header(path)?;
+    let ptr_size = info.pointer_size;
+    let file = handles::open(path)?;
+    let mut reader = BufReader::new(&*file);
+    let index = parse_blocks(&mut info, &mut reader, ptr_size, true)
+        .map_err(|e| AppError::new(ErrorKind::CorruptFile, e.to_string()).with_path(path))?;
+    Ok(index.map(|index| Contents {
+        libraries: contents::libraries(&index, &mut reader),
+        images: contents::environments(&index, &mut reader)
+            .into_iter()
+            .filter(|e| !e.packed)
+            .map(|e| e.path)
+            .collect(),
+        scripts: contents::registered_texts(&index, &mut reader),
+    }))
+}
+
+fn parse_check(path: &Path, compression: Compression) -> Result<Check, AppError> {
+    let header = match read_blend_header(path) {
+        Ok(info) => info.version.map(|v| format!("Blender {}", v)),
+        Err(_) if compression != Compression::None => None,
+        Err(e) => return Ok(check("parse", Severity::Error, e.message)),
+    };
+    Ok(match triage::classify(path)? {
+        Some(f) if f.recoverable => check(
+            "parse",
+            Severity::Warning,
+            format!("{}; Blender may still open it", f.detail),
+        ),
+        Some(f) => check("parse", Severity::Error, f.detail),
+        None => check(
+            "parse",
+            Severity::Ok,
+            match header {
+                Some(v) => format!("Saved by {}; blocks intact", v),
+                None => "Blocks intact".to_string(),
+            },
+        ),
+    })
+}
+
+fn compression_check(compression: Compression) -> Check {
+    match compression {
+        Compression::None => check("compression", Severity::Ok, "Not compressed"),
+        Compression::Gzip => check(
+            "compression",
+            Severity::Info,
+            "gzip (Blender 2.x); dependencies and scripts are not checked",
+        ),
+        Compression::Zstd => check(
+            "compression",
+            Severity::Info,
+            "Zstandard; dependencies and scripts are not checked",
+        ),
+    }
+}
+
+fn dependency_checks(path: &Path, c: &Contents) -> [Check; 2] {
+    let saved: Vec<&String> = c.libraries.iter().chain(&c.images).collect();
+    let missing: Vec<String> = saved
+        .iter()
+        .map(|s| staleness::resolve(path, s))
+        .filter(|p| !p.exists())
+        .map(|p| p.to_string_lossy().to_string())
+        .collect();
+    let absolute: Vec<String> = saved
+        .iter()
+        .filter(|s| !s.starts_with("//"))
+        .map(|s| s.to_string())
+        .collect();
+    let summary = format!(
+        "{} linked librar{}, {} external image{}",
+        c.libraries.len(),
+        if c.libraries.len() == 1 { "y" } else { "ies" },
+        c.images.len(),
+        if c.images.len() == 1 { "" } else { "s" },
+    );
+    [
+        if missing.is_empty() {
+            check("dependencies", Severity::Ok, summary)
+        } else {
+            with_items(
+                check(
+                    "dependencies",
+                    Severity::Warning,
+                    format!("{} not found ({})", missing.len(), summary),
+                ),
+                missing,
+            )
+        },
+        if absolute.is_empty() {
+            check("absolute_paths", Severity::Ok, "All paths are relative")
+        } else {
+            with_items(
+                check(
+                    "absolute_paths",
+                    Severity::Warning,
+                    format!(
+                        "{} absolute path{}; they break when the project moves",
+                        absolute.len(),
+                        if absolute.len() == 1 { "" } else { "s" }
+                    ),
+                ),
+                absolute,
+            )
+        },
+    ]
+}
+
+fn autorun_check(c: &Contents) -> Check {
+    if c.scripts.is_empty() {
+        return check("autorun", Severity::Ok, "No registered scripts");
+    }
+    with_items(
+        check(
+            "autorun",
+            Severity::Warning,
+            format!(
+                "{} registered script{} run on load when Auto Run Python Scripts is on",
+                c.scripts.len(),
+                if c.scripts.len() == 1 { "" } else { "s" }
+            ),
+        ),
+        c.scripts.clone(),
+    )
+}
+
+// Opening for writing changes nothing, but fails while another program
+// holds the file exclusively
+fn lock_check(path: &Path, read_only: bool) -> Check {
+    if read_only {
+        return check(
+            "lock",
+            Severity::Info,
+            "Read-only; Blender can't save over it",
+        );
+    }
+    match OpenOptions::new().write(true).open(path) {
+        Ok(_) => check("lock", Severity::Ok, "Writable"),
+        Err(e) => check(
+            "lock",
+            Severity::Warning,
+            format!(
+                "Can't be opened for writing: {}",
+                AppError::io(&e, path).message
+            ),
+        ),
+    }
+}
+
+pub fn check_file(path: &Path) -> Result<HealthReport, AppError> {
+    if archive::split(path).is_some() {
+        return Err(AppError::new(
+            ErrorKind::InvalidInput,
+            "Extract the file from its archive to check it",
+        )
+        .with_code("health_archive")
+        .with_path(path));
+    }
+    let meta = fs::metadata(path).map_err(|e| AppError::io(&e, path))?;
+    let mut checks = Vec::new();
+    // Reading a placeholder would download it
+    if cloud::is_placeholder(&meta) {
+        checks.push(check(
+            "parse",
+            Severity::Info,
+            "Online-only; download it to check the contents",
+        ));
+    } else {
+        let compression = magic::compression(path)?;
+        checks.push(parse_check(path, compression)?);
+        checks.push(compression_check(compression));
+        if compression == Compression::None && checks[0].severity != Severity::Error {
+            match read_contents(path) {
+                Ok(Some(c)) => {
+                    checks.extend(dependency_checks(path, &c));
+                    checks.push(autorun_check(&c));
+                }
+                Ok(None) => checks.push(check(
+                    "parse",
+                    Severity::Warning,
+                    "No struct layouts (DNA1); contents could not be read",
+                )),
+                Err(e) => checks.push(check("parse", Severity::Error, e.message)),
+            }
+        }
+    }
+    checks.push(lock_check(path, meta.permissions().readonly()));
+
+    let severity = checks
+        .iter()
+        .map(|c| c.severity)
+        .max()
+        .unwrap_or(Severity::Ok);
+    tracing::debug!(path = %path.display(), ?severity, "health check");
+    Ok(HealthReport {
+        path: path.to_string_lossy().to_string(),
+        path_id: paths::to_id(path),
+        severity,
+        checks,
+    })
+}
@@ -0,0 +1,175 @@
+use crate::db;
+use crate::error::AppError;
+use crate::{paths, quick_find, FlatFile, ScanResult, TreeNode};
+use chrono::{DateTime, Local};
+use rusqlite::{params, OptionalExtension};
+use std::collections::HashMap;
+use std::path::Path;
+
+// -----------------------------
+// What changed since the previous scan
+// -----------------------------
+// Every finished scan stores its files per scan root; the next scan of the
+// same root compares against it. The first scan of a root has nothing to
+// compare with, so nothing is flagged.
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Change {
+    New,
+    Modified,
+}
+
+pub type Changes = HashMap<String, Change>; // path id -> change
+
+struct Seen {
+    size_bytes: u64,
+    modified: Option<String>,
+}
+
+// Timestamps are compared as instants; the offset in the text follows DST
+fn same_time(a: Option<&str>, b: Option<&str>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => match (
+            DateTime::parse_from_rfc3339(a),
+            DateTime::parse_from_rfc3339(b),
+        ) {
+            (Ok(a), Ok(b)) => a == b,
+            _ => a == b,
+        },
+        (a, b) => a == b,
+    }
+}
+
+fn previous(root_id: &str) -> Result<Option<HashMap<String, Seen>>, AppError> {
+    db::with(|conn| {
+        let scanned: Option<String> = conn
+            .query_row(
+                "SELECT last_scan_at FROM scanned_roots WHERE root_id = ?1",
+                [root_id],
+                |r| r.get(0),
+            )
+            .optional()?;
+        if scanned.is_none() {
+            return Ok(None);
+        }
+        let mut stmt = conn
+            .prepare("SELECT path_id, size_bytes, modified FROM scan_files WHERE root_id = ?1")?;
+        let rows = stmt.query_map([root_id], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                Seen {
+                    size_bytes: r.get(1)?,
+                    modified: r.get(2)?,
+                },
+            ))
+        })?;
+        rows.collect::<rusqlite::Result<HashMap<_, _>>>().map(Some)
+    })
+}
+
+fn save(root_id: &str, files: &[FlatFile]) -> Result<(), AppError> {
+    db::with(|conn| {
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM scan_files WHERE root_id = ?1", [root_id])?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT OR REPLACE INTO scan_files (root_id, path_id, size_bytes, modified)
+                 VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for f in files {
+                stmt.execute(params![root_id, f.path_id, f.size_bytes, f.modified])?;
+            }
+        }
+        tx.execute(
+            "INSERT OR REPLACE INTO scanned_roots (root_id, last_scan_at) VALUES (?1, ?2)",
+            params![root_id, Local::now().to_rfc3339()],
+        )?;
+        tx.commit()
+    })
+}
+
+// Compares `files` with the previous scan of `root`, then makes them the
+// new baseline. Truncated scans don't replace it: files the limit cut off
+// would otherwise come back as new.
+pub fn record(root: &Path, files: &[FlatFile], replace: bool) -> Changes {
+    let root_id = paths::to_id(root);
+    let mut changes = Changes::new();
+    match previous(&root_id) {
+        Ok(Some(prev)) => {
+            for f in files {
+                let change = match prev.get(&f.path_id) {
+                    None => Some(Change::New),
+                    Some(s)
+                        if s.size_bytes != f.size_bytes
+                            || !same_time(s.modified.as_deref(), f.modified.as_deref()) =>
+                    {
+                        Some(Change::Modified)
+                    }
+                    Some(_) => None,
+                };
+                if let Some(c) = change {
+                    changes.insert(f.path_id.clone(), c);
+                }
+            }
+        }
+        Ok(None) => {}
+        Err(e) => tracing::warn!("loading scan history failed: {}", e),
+    }
+    if replace {
+        match save(&root_id, files) {
+            Ok(()) => quick_find::invalidate(),
+            Err(e) => tracing::warn!("saving scan history failed: {}", e),
+        }
+    }
+    changes
+}
+
+// Flags already set on a result, so a rebuilt result can keep them
+pub fn of_result(result: &ScanResult) -> Changes {
+    result
+        .files
+        .iter()
+        .filter_map(|f| {
+            let change = if f.is_new {
+                Change::New
+            } else if f.is_modified_since_last_scan {
+                Change::Modified
+            } else {
+                return None;
+            };
+            Some((f.path_id.clone(), change))
+        })
+        .collect()
+}
+
+// Sets the flags on files and file nodes; folders get them when anything
+// below them is flagged
+pub fn apply(result: &mut ScanResult, changes: &Changes) {
+    for f in &mut result.files {
+        let change = changes.get(&f.path_id);
+        f.is_new = change == Some(&Change::New);
+        f.is_modified_since_last_scan = change == Some(&Change::Modified);
+    }
+    mark(&mut result.tree, changes);
+}
+
+fn mark(node: &mut TreeNode, changes: &Changes) -> (bool, bool) {
+    // Files carry their own change; backups under them only mark themselves
+    let change = changes.get(&node.path_id);
+    let own = (
+        change == Some(&Change::New),
+        change == Some(&Change::Modified),
+    );
+    let below = node
+        .children
+        .iter_mut()
+        .flatten()
+        .fold((false, false), |acc, kid| {
+            let (n, m) = mark(kid, changes);
+            (acc.0 || n, acc.1 || m)
+        });
+    let (is_new, modified) = if node.is_dir() { below } else { own };
+    node.is_new = is_new;
+    node.is_modified_since_last_scan = modified;
+    (is_new, modified)
+}
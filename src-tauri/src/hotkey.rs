@@ -0,0 +1,79 @@
+use crate::deeplink::{self, AppRequest};
+use crate::error::{AppError, ErrorKind};
+use crate::settings;
+use std::str::FromStr;
+use tauri_plugin_global_shortcut::{GlobalShortcutExt, Shortcut, ShortcutState};
+
+// -----------------------------
+// Global quick-find shortcut
+// -----------------------------
+// A system-wide accelerator (e.g. `CommandOrControl+Shift+Space`) that
+// raises the main window with the quick-find palette open. It is stored in
+// settings and registered at startup; `None` turns it off.
+
+pub const DEFAULT_SHORTCUT: &str = "CommandOrControl+Shift+Space";
+
+pub fn plugin() -> tauri::plugin::TauriPlugin<tauri::Wry> {
+    tauri_plugin_global_shortcut::Builder::new()
+        .with_handler(|app, _shortcut, event| {
+            if event.state() == ShortcutState::Pressed {
+                deeplink::dispatch(app, AppRequest::QuickFind);
+            }
+        })
+        .build()
+}
+
+fn parse(accelerator: &str) -> Result<Shortcut, AppError> {
+    Shortcut::from_str(accelerator).map_err(|e| {
+        AppError::new(
+            ErrorKind::InvalidInput,
+            format!("Invalid shortcut '{}': {}", accelerator, e),
+        )
+        .with_code("shortcut_invalid")
+        .with_param("shortcut", accelerator)
+    })
+}
+
+fn register(app: &tauri::AppHandle, accelerator: &str) -> Result<(), AppError> {
+    let shortcut = parse(accelerator)?;
+    app.global_shortcut().register(shortcut).map_err(|e| {
+        AppError::new(
+            ErrorKind::Shortcut,
+            format!("Could not register '{}': {}", accelerator, e),
+        )
+        .with_param("shortcut", accelerator)
+    })
+}
+
+// Registers the saved shortcut; one taken by another app only costs a warning
+pub fn init(app: &tauri::AppHandle) {
+    if let Some(accelerator) = settings::get().quick_find_shortcut {
+        if let Err(e) = register(app, &accelerator) {
+            tracing::warn!("global shortcut unavailable: {}", e);
+        }
+    }
+}
+
+// Swaps the registered shortcut and saves it; on failure the old one stays
+pub fn set(app: &tauri::AppHandle, accelerator: Option<String>) -> Result<(), AppError> {
+    let accelerator = accelerator
+        .map(|a| a.trim().to_string())
+        .filter(|a| !a.is_empty());
+    if let Some(a) = &accelerator {
+        parse(a)?;
+    }
+    let old = settings::get().quick_find_shortcut;
+    if let Some(old) = old.as_deref().and_then(|o| parse(o).ok()) {
+        let _ = app.global_shortcut().unregister(old);
+    }
+    if let Some(a) = &accelerator {
+        if let Err(e) = register(app, a) {
+            if let Some(old) = &old {
+                let _ = register(app, old);
+            }
+            return Err(e);
+        }
+    }
+    settings::update(|s| s.quick_find_shortcut = accelerator)?;
+    Ok(())
+}
@@ -0,0 +1,358 @@
+use crate::error::{AppError, ErrorKind};
+use crate::{annotate_user_data, for_each_latest_result, list_scans, FlatFile};
+use crate::{catalog, ws};
+use base64::prelude::*;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use tiny_http::{Header, Method, Request, Response, Server};
+
+// -----------------------------
+// Optional localhost HTTP API
+// -----------------------------
+//   GET /api/scans                    known scans
+//   GET /api/search?q=…&limit=…       name/path search over the latest scans
+//   GET /api/search?sort=stale        … stalest first, for cleanup
+//   GET /api/file?path=…              one file's metadata
+//   GET /api/thumbnail?path=…         embedded thumbnail as PNG
+//   WS  /api/events                   live scan events (see ws.rs)
+//   GET /catalog/…                    shared catalog (see catalog.rs)
+// Published as a catalog, the server listens on every interface but
+// answers other machines on /catalog/ only. No CORS headers are sent, so
+// web pages can't read the responses, and local requests must name the
+// server itself as Host, which defeats DNS rebinding.
+pub const DEFAULT_PORT: u16 = 47820;
+const DEFAULT_SEARCH_LIMIT: usize = 200;
+// A stopped server's listener closes on tiny_http's accept thread, shortly
+// after; binding the port again waits for it this long
+const REBIND_TIMEOUT: Duration = Duration::from_secs(2);
+const REBIND_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Serialize, Clone)]
+pub struct HttpApiStatus {
+    pub running: bool,
+    pub port: Option<u16>,
+    pub url: Option<String>,
+    pub shared: bool, // published as a catalog
+}
+
+pub type HttpResponse = Response<std::io::Cursor<Vec<u8>>>;
+
+struct RunningServer {
+    server: Arc<Server>,
+    worker: thread::JoinHandle<()>,
+    port: u16,
+    shared: bool,
+}
+
+static SERVER: OnceLock<Mutex<Option<RunningServer>>> = OnceLock::new();
+
+fn server_slot() -> &'static Mutex<Option<RunningServer>> {
+    SERVER.get_or_init(|| Mutex::new(None))
+}
+
+pub fn status() -> HttpApiStatus {
+    let slot = server_slot().lock().unwrap();
+    match slot.as_ref() {
+        Some(s) => HttpApiStatus {
+            running: true,
+            port: Some(s.port),
+            url: Some(format!("http://127.0.0.1:{}/api", s.port)),
+            shared: s.shared,
+        },
+        None => HttpApiStatus {
+            running: false,
+            port: None,
+            url: None,
+            shared: false,
+        },
+    }
+}
+
+pub fn start(port: Option<u16>) -> Result<HttpApiStatus, AppError> {
+    bind(port, None)
+}
+
+// Restarts a loopback-only server on every interface; `key` guards it and
+// is required, as every indexed path becomes readable on the network
+pub fn publish(port: Option<u16>, key: Option<String>) -> Result<HttpApiStatus, AppError> {
+    let Some(key) = key.filter(|k| !k.trim().is_empty()) else {
+        return Err(
+            AppError::new(ErrorKind::InvalidInput, "Publishing a catalog needs a key")
+                .with_code("catalog_key_required"),
+        );
+    };
+    stop();
+    bind(port, Some(key))
+}
+
+// `shared` is the catalog key of a published server
+fn bind(port: Option<u16>, shared: Option<String>) -> Result<HttpApiStatus, AppError> {
+    let mut slot = server_slot().lock().unwrap();
+    if slot.is_none() {
+        // Loopback only unless published as a catalog
+        let host = if shared.is_some() {
+            "0.0.0.0"
+        } else {
+            "127.0.0.1"
+        };
+        let addr = format!("{}:{}", host, port.unwrap_or(DEFAULT_PORT));
+        let server = listen(&addr)?;
+        let port = server
+            .server_addr()
+            .to_ip()
+            .map(|a| a.port())
+            .unwrap_or_default();
+        let server = Arc::new(server);
+        tracing::info!(port, shared = shared.is_some(), "http api listening");
+
+        let worker = server.clone();
+        let key = shared.clone();
+        let worker = thread::spawn(move || {
+            for request in worker.incoming_requests() {
+                handle(request, port, key.as_deref());
+            }
+        });
+        *slot = Some(RunningServer {
+            server,
+            worker,
+            port,
+            shared: shared.is_some(),
+        });
+    }
+    drop(slot);
+    Ok(status())
+}
+
+// Retries while the port is still held by a server just stopped
+fn listen(addr: &str) -> Result<Server, AppError> {
+    let started = Instant::now();
+    loop {
+        match Server::http(addr) {
+            Ok(server) => return Ok(server),
+            Err(e) => {
+                let in_use = e
+                    .downcast_ref::<std::io::Error>()
+                    .is_some_and(|e| e.kind() == std::io::ErrorKind::AddrInUse);
+                if !in_use || started.elapsed() >= REBIND_TIMEOUT {
+                    return Err(AppError::new(ErrorKind::Server, format!("{}: {}", addr, e)));
+                }
+                thread::sleep(REBIND_INTERVAL);
+            }
+        }
+    }
+}
+
+// Returns once the worker has finished its request and dropped the server,
+// which starts closing the listener
+pub fn stop() {
+    let running = server_slot().lock().unwrap().take();
+    if let Some(running) = running {
+        tracing::info!(port = running.port, "http api stopped");
+        running.server.unblock();
+        drop(running.server);
+        let _ = running.worker.join();
+    }
+}
+
+// -----------------------------
+// Request handling
+// -----------------------------
+fn query_params(url: &str) -> HashMap<String, String> {
+    let Some((_, query)) = url.split_once('?') else {
+        return HashMap::new();
+    };
+    tauri::Url::parse(&format!("http://localhost/?{}", query))
+        .map(|u| u.query_pairs().into_owned().collect())
+        .unwrap_or_default()
+}
+
+pub(crate) fn json_response<T: Serialize>(status: u16, body: &T) -> HttpResponse {
+    let data = serde_json::to_vec(body).unwrap_or_default();
+    Response::from_data(data)
+        .with_status_code(status)
+        .with_header(Header::from_bytes("Content-Type", "application/json").unwrap())
+}
+
+pub(crate) fn error_response(status: u16, kind: ErrorKind, msg: &str) -> HttpResponse {
+    json_response(status, &AppError::new(kind, msg))
+}
+
+pub(crate) fn header<'a>(request: &'a Request, name: &'static str) -> Option<&'a str> {
+    request
+        .headers()
+        .iter()
+        .find(|h| h.field.equiv(name))
+        .map(|h| h.value.as_str())
+}
+
+// A page on a rebound name (evil.example -> 127.0.0.1) still sends its
+// own name as Host
+pub(crate) fn host_allowed(request: &Request, port: u16) -> bool {
+    header(request, "Host").is_some_and(|host| {
+        ["127.0.0.1", "localhost"]
+            .iter()
+            .any(|name| host.eq_ignore_ascii_case(&format!("{}:{}", name, port)))
+    })
+}
+
+// Constant time in the key's contents, so guesses can't be timed
+fn same_key(given: &str, key: &str) -> bool {
+    given.len() == key.len()
+        && given
+            .bytes()
+            .zip(key.bytes())
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn key_matches(request: &Request, params: &HashMap<String, String>, key: &str) -> bool {
+    params.get("key").is_some_and(|k| same_key(k, key))
+        || header(request, "X-Catalog-Key").is_some_and(|k| same_key(k, key))
+}
+
+fn handle(request: Request, port: u16, key: Option<&str>) {
+    let url = request.url().to_string();
+    let path = url.split('?').next().unwrap_or("").to_string();
+    let params = query_params(&url);
+
+    let local = request.remote_addr().is_some_and(|a| a.ip().is_loopback());
+    if !local {
+        let response = if !path.starts_with("/catalog/") {
+            error_response(403, ErrorKind::PermissionDenied, "Only /catalog/ is shared")
+        } else if !key.is_some_and(|k| key_matches(&request, &params, k)) {
+            error_response(
+                401,
+                ErrorKind::PermissionDenied,
+                "Wrong or missing catalog key",
+            )
+        } else if *request.method() != Method::Get {
+            error_response(405, ErrorKind::InvalidInput, "Only GET is supported")
+        } else {
+            catalog::serve(&path, &params)
+        };
+        let _ = request.respond(response);
+        return;
+    }
+
+    if !host_allowed(&request, port) {
+        let response = error_response(403, ErrorKind::PermissionDenied, "Unexpected Host");
+        let _ = request.respond(response);
+        return;
+    }
+
+    if path == "/api/events" && ws::is_upgrade(&request) {
+        ws::accept(request, port);
+        return;
+    }
+
+    let response = if *request.method() != Method::Get {
+        error_response(405, ErrorKind::InvalidInput, "Only GET is supported")
+    } else {
+        match path.as_str() {
+            p if p.starts_with("/catalog/") => catalog::serve(p, &params),
+            "/api/scans" => json_response(200, &list_scans(None)),
+            "/api/search" => {
+                let q = params.get("q").cloned().unwrap_or_default();
+                let limit = params
+                    .get("limit")
+                    .and_then(|l| l.parse().ok())
+                    .unwrap_or(DEFAULT_SEARCH_LIMIT);
+                let min_rating = params.get("min_rating").and_then(|r| r.parse().ok());
+                let sort = params.get("sort").map(|s| s.as_str());
+                json_response(200, &search(&q, limit, min_rating, sort))
+            }
+            "/api/file" => match params.get("path").and_then(|p| find_file(p)) {
+                Some(f) => json_response(200, &f),
+                None => error_response(404, ErrorKind::NotFound, "File not in catalog"),
+            },
+            "/api/thumbnail" => match params.get("path").and_then(|p| find_file(p)) {
+                Some(f) => match thumbnail_png(&f) {
+                    Some(png) => Response::from_data(png)
+                        .with_header(Header::from_bytes("Content-Type", "image/png").unwrap()),
+                    None => error_response(404, ErrorKind::NotFound, "File has no thumbnail"),
+                },
+                None => error_response(404, ErrorKind::NotFound, "File not in catalog"),
+            },
+            _ => error_response(404, ErrorKind::NotFound, "Unknown endpoint"),
+        }
+    };
+    let _ = request.respond(response);
+}
+
+// Thumbnails are stripped from search hits to keep responses small.
+// `min_rating` keeps files with at least that many stars; `sort` is
+// "rating" (best first), "stale" (highest staleness score first) or "name",
+// otherwise scan order.
+fn search(q: &str, limit: usize, min_rating: Option<u8>, sort: Option<&str>) -> Vec<FlatFile> {
+    let q = q.to_lowercase();
+    let mut hits = Vec::new();
+    for_each_latest_result(|result| {
+        for f in &result.files {
+            if q.is_empty()
+                || f.name.to_lowercase().contains(&q)
+                || f.path.to_lowercase().contains(&q)
+            {
+                hits.push(FlatFile {
+                    thumbnail: None,
+                    ..f.clone()
+                });
+            }
+        }
+    });
+    annotate_user_data(&mut hits);
+    if let Some(min) = min_rating {
+        hits.retain(|f| f.rating.unwrap_or(0) >= min);
+    }
+    match sort {
+        Some("rating") => hits.sort_by_key(|f| std::cmp::Reverse(f.rating)),
+        Some("stale") => {
+            hits.sort_by_key(|f| std::cmp::Reverse(f.staleness.as_ref().map(|s| s.score)))
+        }
+        Some("name") => hits.sort_by_key(|f| f.name.to_lowercase()),
+        _ => {}
+    }
+    hits.truncate(limit);
+    hits
+}
+
+pub(crate) fn find_file(path: &str) -> Option<FlatFile> {
+    let mut found = None;
+    for_each_latest_result(|result| {
+        if found.is_none() {
+            found = result
+                .files
+                .iter()
+                .find(|f| f.path_id == path || f.path == path || f.file_id.as_deref() == Some(path))
+                .cloned();
+        }
+    });
+    if let Some(f) = found.as_mut() {
+        annotate_user_data(std::slice::from_mut(f));
+    }
+    found
+}
+
+// Blender stores thumbnails as bottom-up RGBA rows
+pub(crate) fn thumbnail_png(file: &FlatFile) -> Option<Vec<u8>> {
+    let rgba = BASE64_STANDARD.decode(file.thumbnail.as_ref()?).ok()?;
+    let (w, h) = (file.thumb_width? as usize, file.thumb_height? as usize);
+    if w == 0 || h == 0 || rgba.len() < w * h * 4 {
+        return None;
+    }
+    let mut flipped = Vec::with_capacity(w * h * 4);
+    for row in (0..h).rev() {
+        flipped.extend_from_slice(&rgba[row * w * 4..(row + 1) * w * 4]);
+    }
+
+    let mut out = Vec::new();
+    let mut encoder = png::Encoder::new(&mut out, w as u32, h as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer = encoder.write_header().ok()?;
+    writer.write_image_data(&flipped).ok()?;
+    drop(writer);
+    Some(out)
+}
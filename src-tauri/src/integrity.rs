@@ -0,0 +1,180 @@
+use crate::db;
+use crate::error::{AppError, ErrorKind};
+use crate::fileops::{self, FileHash};
+use crate::jobs::JobCtx;
+use crate::{cloud, paths};
+use chrono::{DateTime, Local};
+use rusqlite::{params, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// -----------------------------
+// Checksums and bitrot checks
+// -----------------------------
+// A file's SHA-256 is stored with the size and mtime it had when hashed.
+// Hashing it again later with size and mtime unchanged should give the same
+// digest; a different one means the bytes changed without a save (failing
+// drive, bad copy). Files saved since are hashed again and re-recorded.
+
+// What `verify_integrity` checks
+#[derive(Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Scope {
+    Scan { scan_id: u64 }, // the blends of a finished scan
+    Paths { paths: Vec<String> },
+    Stored, // every file with a stored checksum
+}
+
+#[derive(Serialize, Clone)]
+pub struct Mismatch {
+    pub path: String,
+    pub path_id: String,
+    pub expected: String,
+    pub actual: String,
+    pub hashed_at: String, // when the stored checksum was taken
+}
+
+#[derive(Serialize, Clone, Default)]
+pub struct IntegrityReport {
+    pub checked: usize,
+    pub verified: usize, // hash matched the stored one
+    pub recorded: usize, // no checksum yet, or saved since; stored now
+    pub mismatches: Vec<Mismatch>,
+    pub missing: Vec<String>, // stored files that are gone, as path ids
+    pub skipped: Vec<String>, // online-only placeholders, not downloaded
+    pub errors: Vec<AppError>,
+}
+
+struct Stored {
+    sha256: String,
+    size_bytes: u64,
+    modified: Option<String>,
+    hashed_at: String,
+}
+
+fn modified_of(meta: &fs::Metadata) -> Option<String> {
+    meta.modified()
+        .ok()
+        .map(|t| DateTime::<Local>::from(t).to_rfc3339())
+}
+
+fn load(path_id: &str) -> Result<Option<Stored>, AppError> {
+    db::with(|conn| {
+        conn.query_row(
+            "SELECT sha256, size_bytes, modified, hashed_at FROM checksums WHERE path_id = ?1",
+            [path_id],
+            |r| {
+                Ok(Stored {
+                    sha256: r.get(0)?,
+                    size_bytes: r.get(1)?,
+                    modified: r.get(2)?,
+                    hashed_at: r.get(3)?,
+                })
+            },
+        )
+        .optional()
+    })
+}
+
+fn store(path_id: &str, hash: &FileHash, modified: Option<&str>) -> Result<(), AppError> {
+    db::with(|conn| {
+        conn.execute(
+            "INSERT OR REPLACE INTO checksums (path_id, sha256, size_bytes, modified, hashed_at)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![
+                path_id,
+                hash.sha256,
+                hash.size_bytes,
+                modified,
+                Local::now().to_rfc3339()
+            ],
+        )
+        .map(|_| ())
+    })
+}
+
+// Keeps the digests of a hash job as the files' checksums
+pub fn remember(hashes: &[FileHash]) {
+    for h in hashes {
+        let path = Path::new(&h.path);
+        let modified = fs::metadata(path).ok().and_then(|m| modified_of(&m));
+        if let Err(e) = store(&paths::to_id(path), h, modified.as_deref()) {
+            tracing::warn!("storing checksum failed: {}", e);
+        }
+    }
+}
+
+// Path ids of every file with a stored checksum
+pub fn stored_paths() -> Result<Vec<String>, AppError> {
+    db::with(|conn| {
+        let mut stmt = conn.prepare("SELECT path_id FROM checksums ORDER BY path_id")?;
+        let rows = stmt.query_map([], |r| r.get(0))?;
+        rows.collect()
+    })
+}
+
+fn verify_one(ctx: &JobCtx, path: &Path, report: &mut IntegrityReport) -> Result<(), AppError> {
+    let path_id = paths::to_id(path);
+    let stored = load(&path_id)?;
+    let meta = match fs::metadata(path) {
+        Ok(m) => m,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && stored.is_some() => {
+            report.missing.push(path_id);
+            return Ok(());
+        }
+        Err(e) => return Err(AppError::io(&e, path)),
+    };
+    if cloud::is_placeholder(&meta) {
+        report.skipped.push(path_id);
+        ctx.add_bytes(meta.len());
+        return Ok(());
+    }
+    let modified = modified_of(&meta);
+    let hash = fileops::hash_file(ctx, path)?;
+    match stored {
+        Some(s) if s.size_bytes == meta.len() && s.modified == modified => {
+            if s.sha256 == hash.sha256 {
+                report.verified += 1;
+            } else {
+                tracing::warn!(path = %path.display(), "checksum mismatch");
+                report.mismatches.push(Mismatch {
+                    path: hash.path,
+                    path_id,
+                    expected: s.sha256,
+                    actual: hash.sha256,
+                    hashed_at: s.hashed_at,
+                });
+            }
+        }
+        _ => {
+            store(&path_id, &hash, modified.as_deref())?;
+            report.recorded += 1;
+        }
+    }
+    Ok(())
+}
+
+// Hashes `files` and compares against stored checksums; per-file failures
+// are collected, not fatal
+pub fn verify(ctx: &JobCtx, files: &[PathBuf]) -> Result<IntegrityReport, AppError> {
+    let total = files
+        .iter()
+        .filter_map(|p| fs::metadata(p).ok())
+        .map(|m| m.len())
+        .sum();
+    ctx.set_totals(files.len() as u64, total);
+    let mut report = IntegrityReport::default();
+    for p in files {
+        ctx.check_cancelled()?;
+        ctx.set_current(p);
+        match verify_one(ctx, p, &mut report) {
+            Err(e) if e.kind == ErrorKind::Cancelled => return Err(e),
+            Err(e) => report.errors.push(e),
+            Ok(()) => {}
+        }
+        report.checked += 1;
+        ctx.item_done();
+    }
+    Ok(report)
+}
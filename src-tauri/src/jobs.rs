@@ -0,0 +1,224 @@
+use crate::emit_to_owner;
+use crate::error::{AppError, ErrorKind};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::path::Path;
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    Arc, Mutex, OnceLock,
+};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// -----------------------------
+// Models returned to frontend
+// -----------------------------
+#[derive(Serialize, Clone)]
+pub struct JobPoll {
+    pub job_id: u64,
+    // "copy" | "zip" | "hash" | "consolidate" | "elevated_scan" | "update_check"
+    // | "update_install"
+    pub kind: String,
+    pub status: String, // "running" | "done" | "error" | "cancelled"
+    pub items_done: u64,
+    pub items_total: u64,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+    pub current_path: Option<String>,
+    pub error: Option<AppError>,
+    pub result: Option<serde_json::Value>, // only when done
+}
+
+const EVENT_JOB_PROGRESS: &str = "job://progress";
+const EVENT_JOB_DONE: &str = "job://done";
+const PROGRESS_EMIT_INTERVAL: Duration = Duration::from_millis(100);
+
+// -----------------------------
+// Internal job state
+// -----------------------------
+pub struct JobState {
+    kind: String,
+    owner: Option<String>, // label of the window that started the job
+    cancel: AtomicBool,
+    items_done: AtomicU64,
+    items_total: AtomicU64,
+    bytes_done: AtomicU64,
+    bytes_total: AtomicU64,
+    status: Mutex<String>,
+    current_path: Mutex<Option<String>>,
+    error: Mutex<Option<AppError>>,
+    result: Mutex<Option<serde_json::Value>>,
+}
+
+impl JobState {
+    fn new(kind: &str, owner: Option<&str>) -> Self {
+        Self {
+            kind: kind.to_string(),
+            owner: owner.map(|o| o.to_string()),
+            cancel: AtomicBool::new(false),
+            items_done: AtomicU64::new(0),
+            items_total: AtomicU64::new(0),
+            bytes_done: AtomicU64::new(0),
+            bytes_total: AtomicU64::new(0),
+            status: Mutex::new("running".to_string()),
+            current_path: Mutex::new(None),
+            error: Mutex::new(None),
+            result: Mutex::new(None),
+        }
+    }
+
+    fn snapshot(&self, job_id: u64) -> JobPoll {
+        let status = self.status.lock().unwrap().clone();
+        let result = if status == "done" {
+            self.result.lock().unwrap().clone()
+        } else {
+            None
+        };
+        JobPoll {
+            job_id,
+            kind: self.kind.clone(),
+            status,
+            items_done: self.items_done.load(Ordering::Relaxed),
+            items_total: self.items_total.load(Ordering::Relaxed),
+            bytes_done: self.bytes_done.load(Ordering::Relaxed),
+            bytes_total: self.bytes_total.load(Ordering::Relaxed),
+            current_path: self.current_path.lock().unwrap().clone(),
+            error: self.error.lock().unwrap().clone(),
+            result,
+        }
+    }
+}
+
+static NEXT_JOB_ID: AtomicU64 = AtomicU64::new(1);
+static JOBS: OnceLock<Mutex<HashMap<u64, Arc<JobState>>>> = OnceLock::new();
+
+fn jobs_map() -> &'static Mutex<HashMap<u64, Arc<JobState>>> {
+    JOBS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn get_job(job_id: u64) -> Result<Arc<JobState>, AppError> {
+    let map = jobs_map().lock().unwrap();
+    map.get(&job_id).cloned().ok_or_else(|| {
+        AppError::new(
+            ErrorKind::JobNotFound,
+            format!("Job id {} not found", job_id),
+        )
+        .with_param("job_id", job_id)
+    })
+}
+
+// -----------------------------
+// Job context handed to the worker
+// -----------------------------
+pub struct JobCtx {
+    app: tauri::AppHandle,
+    job_id: u64,
+    state: Arc<JobState>,
+    last_emit: Mutex<Instant>,
+}
+
+impl JobCtx {
+    pub fn check_cancelled(&self) -> Result<(), AppError> {
+        if self.state.cancel.load(Ordering::Relaxed) {
+            Err(AppError::new(ErrorKind::Cancelled, "Job was cancelled"))
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn set_totals(&self, items: u64, bytes: u64) {
+        self.state.items_total.store(items, Ordering::Relaxed);
+        self.state.bytes_total.store(bytes, Ordering::Relaxed);
+        self.emit_progress(true);
+    }
+
+    pub fn set_current(&self, path: &Path) {
+        if let Ok(mut cp) = self.state.current_path.lock() {
+            *cp = Some(path.to_string_lossy().to_string());
+        }
+        self.emit_progress(false);
+    }
+
+    pub fn add_bytes(&self, n: u64) {
+        self.state.bytes_done.fetch_add(n, Ordering::Relaxed);
+        self.emit_progress(false);
+    }
+
+    pub fn item_done(&self) {
+        self.state.items_done.fetch_add(1, Ordering::Relaxed);
+        self.emit_progress(false);
+    }
+
+    fn emit_progress(&self, force: bool) {
+        let mut last = self.last_emit.lock().unwrap();
+        if force || last.elapsed() >= PROGRESS_EMIT_INTERVAL {
+            *last = Instant::now();
+            let _ = emit_to_owner(
+                &self.app,
+                self.state.owner.as_deref(),
+                EVENT_JOB_PROGRESS,
+                self.state.snapshot(self.job_id),
+            );
+        }
+    }
+}
+
+// Runs `work` on a background thread and tracks it under a new job id
+pub fn spawn_job<F>(app: tauri::AppHandle, owner: Option<&str>, kind: &str, work: F) -> u64
+where
+    F: FnOnce(&JobCtx) -> Result<serde_json::Value, AppError> + Send + 'static,
+{
+    let job_id = NEXT_JOB_ID.fetch_add(1, Ordering::Relaxed);
+    let state = Arc::new(JobState::new(kind, owner));
+
+    {
+        let mut map = jobs_map().lock().unwrap();
+        map.insert(job_id, state.clone());
+    }
+
+    tracing::info!(job_id, kind, "job started");
+    thread::spawn(move || {
+        let ctx = JobCtx {
+            app,
+            job_id,
+            state,
+            last_emit: Mutex::new(Instant::now()),
+        };
+
+        let outcome = work(&ctx);
+        let state = &ctx.state;
+        match outcome {
+            Ok(value) => {
+                tracing::info!(job_id, "job finished");
+                *state.result.lock().unwrap() = Some(value);
+                *state.status.lock().unwrap() = "done".to_string();
+            }
+            Err(e) if e.kind == ErrorKind::Cancelled => {
+                tracing::info!(job_id, "job cancelled");
+                *state.status.lock().unwrap() = "cancelled".to_string();
+            }
+            Err(e) => {
+                tracing::warn!(job_id, "job failed: {}", e);
+                *state.error.lock().unwrap() = Some(e);
+                *state.status.lock().unwrap() = "error".to_string();
+            }
+        }
+        let _ = emit_to_owner(
+            &ctx.app,
+            state.owner.as_deref(),
+            EVENT_JOB_DONE,
+            state.snapshot(job_id),
+        );
+    });
+
+    job_id
+}
+
+pub fn poll_job(job_id: u64) -> Result<JobPoll, AppError> {
+    Ok(get_job(job_id)?.snapshot(job_id))
+}
+
+pub fn cancel_job(job_id: u64) -> Result<(), AppError> {
+    get_job(job_id)?.cancel.store(true, Ordering::Relaxed);
+    Ok(())
+}
@@ -0,0 +1,125 @@
+use crate::db;
+use crate::error::{AppError, ErrorKind};
+use crate::paths;
+use crate::settings;
+use crate::{FlatFile, TreeNode};
+use rusqlite::params;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// -----------------------------
+// Color labels (Finder-style)
+// -----------------------------
+// One label per file or folder, by palette name. Files without their own
+// label inherit the nearest labeled folder's, so a folder label is enough
+// to search or group a whole shot.
+
+#[derive(Serialize, Deserialize, Clone)]
+pub struct LabelColor {
+    pub name: String,
+    pub color: String, // CSS color
+}
+
+pub fn default_palette() -> Vec<LabelColor> {
+    [
+        ("red", "#ff5f57"),
+        ("orange", "#ff9f0a"),
+        ("yellow", "#ffd60a"),
+        ("green", "#32d74b"),
+        ("blue", "#0a84ff"),
+        ("purple", "#bf5af2"),
+        ("gray", "#8e8e93"),
+    ]
+    .into_iter()
+    .map(|(name, color)| LabelColor {
+        name: name.to_string(),
+        color: color.to_string(),
+    })
+    .collect()
+}
+
+// `None` clears the label
+// Fails unless `name` is in the palette
+pub fn check(name: &str) -> Result<(), AppError> {
+    if !settings::get().label_palette.iter().any(|l| l.name == name) {
+        return Err(AppError::new(
+            ErrorKind::InvalidInput,
+            format!("\"{}\" is not in the label palette", name),
+        )
+        .with_code("label_unknown")
+        .with_param("name", name));
+    }
+    Ok(())
+}
+
+pub fn set(path_ids: &[String], label: Option<&str>) -> Result<(), AppError> {
+    if let Some(name) = label {
+        check(name)?;
+    }
+    db::with(|conn| {
+        let tx = conn.transaction()?;
+        {
+            let mut upsert = tx.prepare(
+                "INSERT INTO labels (path_id, label) VALUES (?1, ?2)
+                 ON CONFLICT(path_id) DO UPDATE SET label = excluded.label",
+            )?;
+            let mut delete = tx.prepare("DELETE FROM labels WHERE path_id = ?1")?;
+            for p in path_ids {
+                match label {
+                    Some(name) => upsert.execute(params![p, name])?,
+                    None => delete.execute([p])?,
+                };
+            }
+        }
+        tx.commit()
+    })
+}
+
+// Labels set on files and folders themselves, by path id
+pub fn all() -> Result<HashMap<String, String>, AppError> {
+    db::with(|conn| {
+        let mut stmt = conn.prepare("SELECT path_id, label FROM labels")?;
+        let rows = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?;
+        rows.collect()
+    })
+}
+
+// Own label, else the nearest labeled ancestor folder's
+fn effective(map: &HashMap<String, String>, path_id: &str) -> Option<String> {
+    if let Some(own) = map.get(path_id) {
+        return Some(own.clone());
+    }
+    paths::from_id(path_id)
+        .ancestors()
+        .skip(1)
+        .find_map(|a| map.get(&paths::to_id(a)).cloned())
+}
+
+pub fn annotate(files: &mut [FlatFile]) {
+    let map = match all() {
+        Ok(map) => map,
+        Err(e) => {
+            tracing::warn!("loading labels failed: {}", e);
+            return;
+        }
+    };
+    for f in files {
+        f.label = if map.is_empty() {
+            None
+        } else {
+            effective(&map, &f.path_id)
+        };
+    }
+}
+
+// Tree nodes show their own label only; inheritance is visible from the parent
+pub fn annotate_tree(root: &mut TreeNode) {
+    let Ok(map) = all() else {
+        return;
+    };
+    let mut stack = vec![root];
+    while let Some(node) = stack.pop() {
+        node.label = map.get(&node.path_id).cloned();
+        stack.extend(node.children.iter_mut().flatten());
+    }
+}
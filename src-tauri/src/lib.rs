@@ -1,22 +1,86 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 use chrono::{DateTime, Local};
-use serde::Serialize;
-use std::collections::{BTreeMap, HashMap};
-use std::fs::File;
-use std::io::Read;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::ffi::OsString;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
 use std::path::{Path, PathBuf};
 use std::sync::{
-    atomic::{AtomicBool, AtomicU64, Ordering},
-    Arc, Mutex, OnceLock,
+    atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering},
+    Arc, Condvar, Mutex, OnceLock,
 };
 use std::thread;
-use walkdir::WalkDir;
+use std::time::{Duration, Instant};
+
+use tauri::Emitter;
+
+use error::{AppError, ErrorKind};
+
+mod archive;
+mod bulk;
+mod catalog;
+mod checkpoint;
+mod cloud;
+mod collections;
+mod compare;
+mod contents;
+mod db;
+mod deeplink;
+mod diff;
+mod elevate;
+mod enrich;
+mod error;
+mod exclude;
+mod export;
+mod extractors;
+mod favorites;
+mod file_ids;
+mod fileops;
+mod flat_tree;
+mod formats;
+mod handles;
+mod health;
+mod history;
+mod hotkey;
+mod http_api;
+mod integrity;
+mod jobs;
+mod labels;
+mod links;
+mod logging;
+mod magic;
+mod netfs;
+mod notes;
+mod paths;
+mod projects;
+mod query;
+mod quick_find;
+mod ratings;
+mod rename;
+mod rules;
+mod scan;
+mod scripts;
+mod sdna;
+mod settings;
+mod sidecar;
+mod staleness;
+mod tags;
+mod tools;
+mod trash;
+mod tray;
+mod triage;
+mod updater;
+mod usage;
+mod volume;
+mod watch;
+mod ws;
+mod xattrs;
 
 // -----------------------------
 // Models returned to frontend
 // -----------------------------
-#[derive(Serialize, Clone)]
+#[derive(Serialize, Deserialize, Clone, Default)]
 pub struct BlendInfo {
     pub version: Option<String>,
     pub raw: Option<String>,
@@ -25,8 +89,32 @@ pub struct BlendInfo {
     pub thumbnail: Option<String>, // Base64 RGBA
     pub thumb_width: Option<i32>,
     pub thumb_height: Option<i32>,
-    pub render_engine: Option<String>,
+    pub render_engine: Option<String>, // friendly name, e.g. `Eevee`
+    pub render_engine_id: Option<String>, // as saved, e.g. `BLENDER_EEVEE_NEXT`
     pub error: Option<String>,
+    #[serde(default)]
+    pub assets: Vec<contents::AssetInfo>, // marked assets, for asset libraries
+    pub compositing: Option<bool>, // a scene runs more than the default comp tree
+    pub autopack: Option<bool>,    // resources are packed on every save
+    #[serde(default)]
+    pub simulations: Vec<contents::SimCache>, // point caches and fluid domains
+    pub sequencer: Option<contents::Sequencer>, // VSE strips, when there are any
+    #[serde(default)]
+    pub environments: Vec<contents::EnvTexture>, // world HDRIs
+    #[serde(default)]
+    pub libraries: Vec<String>, // linked blends, as saved
+    // 0 before 5.0; 1 since (64-bit block sizes); later ones are not read
+    // past the header
+    #[serde(default)]
+    pub file_format: u8,
+}
+
+// Timestamps are local RFC3339 strings; the `_ms` twins are for sorting and
+// comparing without parsing
+pub(crate) fn epoch_ms(stamp: Option<&str>) -> Option<i64> {
+    DateTime::parse_from_rfc3339(stamp?)
+        .ok()
+        .map(|t| t.timestamp_millis())
 }
 
 #[derive(Serialize, Clone)]
@@ -34,36 +122,234 @@ pub struct FileMeta {
     pub size_bytes: u64,
     pub created: Option<String>,
     pub modified: Option<String>,
+    pub created_ms: Option<i64>, // the same instants as Unix epoch millis
+    pub modified_ms: Option<i64>,
     pub folder: String,
+    pub cloud_only: bool, // online-only placeholder, not downloaded
+    pub read_only: bool,  // locked against saving, see `set_read_only`
+    pub trash: Option<trash::TrashInfo>, // in the OS trash; restorable
+    pub format: String,   // "blend", or another 3D format (not parsed)
     pub blender: BlendInfo,
+    pub aliases: Vec<String>, // other paths to the same file (file symlinks)
+    pub archive: Option<String>, // the .zip holding it, see archive.rs
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TreeOrder {
+    #[default]
+    Name, // folders first, then files, each alphabetical
+    FilesFirst,
+    Newest, // most recently modified content first
+    Size,   // largest first (folders by total size)
 }
 
 #[derive(Serialize, Clone)]
 pub struct TreeNode {
     pub node_type: String, // "dir" | "file"
     pub name: String,
-    pub path: String,
+    pub path: String,    // for display
+    pub path_id: String, // for commands, see paths.rs
     pub meta: Option<FileMeta>,
-    pub children: Option<Vec<TreeNode>>,
+    pub children: Option<Vec<TreeNode>>, // None on dirs of a lazy tree; backups on files
+    pub child_count: Option<usize>,      // dir nodes and files with backups
+    // Aggregates over all descendant files (dir nodes only)
+    pub blend_count: Option<u64>,
+    pub total_size: Option<u64>,
+    pub newest_modified: Option<String>,
+    pub dominant_version: Option<String>,
+    pub label: Option<String>, // color label set on this node
+    // Since the previous scan of the root; on dirs, anything below
+    pub is_new: bool,
+    pub is_modified_since_last_scan: bool,
+    pub error: Option<DirError>, // dir nodes the scan could not fully read
+}
+
+// Why a folder's listing is incomplete
+#[derive(Serialize, Clone)]
+pub struct DirError {
+    pub kind: ErrorKind,
+    pub partial: bool, // something below was unreadable, not the folder itself
+}
+
+impl TreeNode {
+    fn without_children(&self) -> TreeNode {
+        TreeNode {
+            node_type: self.node_type.clone(),
+            name: self.name.clone(),
+            path: self.path.clone(),
+            path_id: self.path_id.clone(),
+            meta: self.meta.clone(),
+            children: None,
+            child_count: self.child_count,
+            blend_count: self.blend_count,
+            total_size: self.total_size,
+            newest_modified: self.newest_modified.clone(),
+            dominant_version: self.dominant_version.clone(),
+            label: self.label.clone(),
+            is_new: self.is_new,
+            is_modified_since_last_scan: self.is_modified_since_last_scan,
+            error: self.error.clone(),
+        }
+    }
+
+    // This node and its direct children; deeper levels are fetched with
+    // `get_tree_children`
+    fn one_level(&self) -> TreeNode {
+        TreeNode {
+            children: self.children.as_ref().map(|kids| {
+                kids.iter()
+                    .map(|k| {
+                        if k.is_dir() {
+                            k.without_children()
+                        } else {
+                            k.clone()
+                        }
+                    })
+                    .collect()
+            }),
+            ..self.without_children()
+        }
+    }
+
+    fn is_dir(&self) -> bool {
+        self.node_type == "dir"
+    }
+
+    fn newest(&self) -> Option<&str> {
+        self.newest_modified
+            .as_deref()
+            .or_else(|| self.meta.as_ref()?.modified.as_deref())
+    }
+
+    fn size(&self) -> u64 {
+        self.total_size
+            .or_else(|| self.meta.as_ref().map(|m| m.size_bytes))
+            .unwrap_or(0)
+    }
+
+    // Orders children at every level; ties go by name. Backups under a
+    // file stay in number order.
+    fn sort(&mut self, order: TreeOrder) {
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            if let (true, Some(kids)) = (node.is_dir(), node.children.as_mut()) {
+                kids.sort_by(|a, b| {
+                    let by_name = a.name.cmp(&b.name);
+                    match order {
+                        TreeOrder::Name => b.is_dir().cmp(&a.is_dir()).then(by_name),
+                        TreeOrder::FilesFirst => a.is_dir().cmp(&b.is_dir()).then(by_name),
+                        TreeOrder::Newest => b.newest().cmp(&a.newest()).then(by_name),
+                        TreeOrder::Size => b.size().cmp(&a.size()).then(by_name),
+                    }
+                });
+                stack.extend(kids.iter_mut());
+            }
+        }
+    }
+
+    fn find_file(&self, path_id: &str) -> Option<&TreeNode> {
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            if node.node_type == "file" && node.path_id == path_id {
+                return Some(node);
+            }
+            stack.extend(node.children.iter().flatten());
+        }
+        None
+    }
+
+    fn find_file_mut(&mut self, path_id: &str) -> Option<&mut TreeNode> {
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            if node.node_type == "file" && node.path_id == path_id {
+                return Some(node);
+            }
+            stack.extend(node.children.iter_mut().flatten());
+        }
+        None
+    }
+
+    fn find_dir(&self, dir: &str) -> Option<&TreeNode> {
+        let mut stack = vec![self];
+        while let Some(node) = stack.pop() {
+            if node.node_type == "dir" && (node.path_id == dir || node.path == dir) {
+                return Some(node);
+            }
+            stack.extend(node.children.iter().flatten());
+        }
+        None
+    }
 }
 
 #[derive(Serialize, Clone)]
 pub struct FlatFile {
     pub name: String,
     pub path: String,
+    pub path_id: String,
+    pub rel_path: Option<String>, // from the scan root, `/`-separated; None outside it
+    pub file_id: Option<String>,  // stable across moves, see file_ids.rs
     pub folder: String,
     pub size_bytes: u64,
     pub created: Option<String>,
     pub modified: Option<String>,
+    pub created_ms: Option<i64>, // the same instants as Unix epoch millis
+    pub modified_ms: Option<i64>,
     pub blender_version: Option<String>,
     pub thumbnail: Option<String>,
+    pub thumb_width: Option<i32>,
+    pub thumb_height: Option<i32>,
     pub render_engine: Option<String>,
+    pub render_engine_id: Option<String>,
+    pub assets: Vec<contents::AssetInfo>,
+    pub compositing: Option<bool>,
+    pub autopack: Option<bool>,
+    pub simulations: Vec<contents::SimCache>,
+    pub sequencer: Option<contents::Sequencer>,
+    pub environments: Vec<contents::EnvTexture>,
+    pub libraries: Vec<String>,
+    pub staleness: Option<staleness::Staleness>, // cleanup candidate score
+    pub fields: extractors::Fields,              // custom fields, see extractors.rs
+    pub cloud_only: bool,
+    pub read_only: bool,
+    pub trash: Option<trash::TrashInfo>,
+    pub format: String,
+    pub aliases: Vec<String>, // other paths to the same file (file symlinks)
+    pub archive: Option<String>, // the .zip holding it, see archive.rs
+    pub tags: Vec<String>,
+    pub favorite: bool,
+    pub rating: Option<u8>,    // 1–5 stars
+    pub label: Option<String>, // color label, own or inherited from a folder
+    pub collections: Vec<String>,
+    pub note: Option<String>,
+    pub is_new: bool, // since the previous scan of the root
+    pub is_modified_since_last_scan: bool,
 }
 
 #[derive(Serialize, Clone)]
 pub struct ScanResult {
     pub tree: TreeNode,
+    pub roots: Vec<String>, // path ids; several for `start_multi_scan`
     pub files: Vec<FlatFile>,
+    pub errors: Vec<AppError>, // non-fatal problems collected during the scan
+    pub skipped: Vec<SkippedPath>, // folders/files the walk could not read
+    pub links: Vec<links::LinkEntry>, // directory symlinks/junctions met on the way
+    pub truncated: bool,       // a resource limit cut the scan short
+    pub limits_hit: Vec<String>, // which ScanOptions limits were reached
+    pub compact_tree: bool,    // single-child folder chains are merged
+    pub relative_paths: bool,  // display paths below the root are relative to it
+    pub counts_only: bool,     // lazy polls get the root's counts only, no children
+    pub tree_order: TreeOrder,
+    pub flat_tree: Option<flat_tree::FlatTree>, // requested instead of nested `tree` children
+    pub parse_depth: scan::ParseDepth,          // below deep, files are read on demand (enrich.rs)
+}
+
+#[derive(Serialize, Clone)]
+pub struct SkippedPath {
+    pub path: String,
+    pub reason: ErrorKind, // permission_denied, not_found, io, timeout…
+    pub decision: Option<settings::ElevationDecision>, // for protected folders
+    pub is_dir: bool,
 }
 
 #[derive(Serialize, Clone)]
@@ -72,34 +358,306 @@ pub struct ScanPoll {
     pub status: String, // "scanning" | "done" | "error"
     pub scanned_entries: u64,
     pub found_blends: u64,
+    pub phase: String, // see SCAN_PHASES, or "finished"
+    pub phases: Vec<PhaseProgress>,
     pub current_path: Option<String>,
-    pub error: Option<String>,
+    pub paused: bool, // see `pause_scan`
+    // Files read so far, from the `found_from` a poll asked for; events and
+    // polls without it leave these empty
+    pub found: Vec<FlatFile>,
+    pub found_next: Option<usize>, // `found_from` of the next poll
+    pub error: Option<AppError>,
     pub result: Option<ScanResult>, // only when done
 }
 
+#[derive(Serialize, Clone)]
+pub struct PhaseProgress {
+    pub name: String,
+    pub state: String, // "pending" | "active" | "done"
+    pub completed: u64,
+    pub total: Option<u64>, // unknown while walking
+}
+
+#[derive(Serialize, Clone)]
+pub struct ScanSummary {
+    pub scan_id: u64,
+    pub root: String,
+    pub roots: Vec<String>,
+    pub window_label: Option<String>,
+    pub status: String,
+    pub found_blends: u64,
+    pub started_at: String,
+    pub delivered: bool,
+    pub offline: bool, // its drive/share was disconnected; results are stale
+    pub watched: bool, // its root is watched for changes, see `watch_scan`
+}
+
+// One file of a watched root, changed on disk after the scan
+#[derive(Serialize, Clone)]
+pub struct WatchChange {
+    pub scan_id: u64,
+    pub path_id: String,
+    pub file: Option<FlatFile>, // None when removed
+}
+
+#[derive(Serialize, Clone)]
+pub struct ScanFileFound {
+    pub scan_id: u64,
+    pub file: FlatFile, // sent when found, then with header data; thumbnails arrive with scan://done
+}
+
+const MAIN_WINDOW: &str = "main";
+
+// Event names emitted by the scan thread
+const EVENT_SCAN_PROGRESS: &str = "scan://progress";
+// Files per poll while a scan is streaming them (see `poll_scan`)
+const FOUND_BATCH: usize = 5000;
+const EVENT_SCAN_FILE_FOUND: &str = "scan://file-found";
+const EVENT_SCAN_DONE: &str = "scan://done";
+const EVENT_FILE_ENRICHED: &str = "scan://file-enriched";
+const EVENT_FILE_ADDED: &str = "scan://file-added";
+const EVENT_FILE_REMOVED: &str = "scan://file-removed";
+const EVENT_FILE_MODIFIED: &str = "scan://file-modified";
+const PROGRESS_EMIT_INTERVAL: Duration = Duration::from_millis(100);
+
+// Finished scans are evicted once their result was delivered and this grace
+// period passed, or after SCAN_TTL regardless. The newest finished scan per
+// root is always kept so exports/diffs of what the UI shows keep working.
+const DELIVERED_GRACE: Duration = Duration::from_secs(15 * 60);
+const SCAN_TTL: Duration = Duration::from_secs(60 * 60);
+
+// Scan phases, in execution order
+const PHASE_WALKING: usize = 0;
+const PHASE_PARSING_HEADERS: usize = 1;
+const PHASE_EXTRACTING_THUMBNAILS: usize = 2;
+const PHASE_BUILDING_TREE: usize = 3;
+const PHASE_FINISHED: usize = 4;
+const SCAN_PHASES: [&str; 4] = [
+    "walking",
+    "parsing_headers",
+    "extracting_thumbnails",
+    "building_tree",
+];
+
 // -----------------------------
 // Internal scan state
 // -----------------------------
 struct ScanState {
+    // Keys the scan's results by what was scanned: the root's path id (see
+    // paths.rs), or those of all roots joined by newlines
+    root: String,
+    roots: Vec<String>,
+    options: scan::ScanOptions,
+    globs: Arc<exclude::GlobFilter>,
+    owner: Option<String>, // label of the window that started the scan
+    started_at: DateTime<Local>,
+    finished_at: Mutex<Option<Instant>>,
+    delivered: AtomicBool,
     scanned_entries: AtomicU64,
     found_blends: AtomicU64,
+    headers_parsed: AtomicU64,
+    thumbnails_scanned: AtomicU64,
+    tree_inserted: AtomicU64,
+    phase: AtomicUsize,
     done: AtomicBool,
+    offline: AtomicBool,
+    // Workers wait on `resumed` between entries while `paused` is set
+    paused: Mutex<bool>,
+    resumed: Condvar,
+    // Header data of every file read, in order, until the result is in
+    found: Mutex<Vec<FlatFile>>,
     status: Mutex<String>,
     current_path: Mutex<Option<String>>,
-    error: Mutex<Option<String>>,
+    error: Mutex<Option<AppError>>,
     result: Mutex<Option<ScanResult>>,
 }
 
 impl ScanState {
-    fn new() -> Self {
-        Self {
+    fn new(
+        roots: &[PathBuf],
+        options: &scan::ScanOptions,
+        owner: Option<String>,
+    ) -> Result<Self, AppError> {
+        let roots: Vec<String> = roots.iter().map(|r| paths::to_id(r)).collect();
+        Ok(Self {
+            root: roots.join("\n"),
+            roots,
+            options: options.clone(),
+            globs: Arc::new(options.globs()?),
+            owner,
+            started_at: Local::now(),
+            finished_at: Mutex::new(None),
+            delivered: AtomicBool::new(false),
             scanned_entries: AtomicU64::new(0),
             found_blends: AtomicU64::new(0),
+            headers_parsed: AtomicU64::new(0),
+            thumbnails_scanned: AtomicU64::new(0),
+            tree_inserted: AtomicU64::new(0),
+            phase: AtomicUsize::new(PHASE_WALKING),
             done: AtomicBool::new(false),
+            offline: AtomicBool::new(false),
+            paused: Mutex::new(false),
+            resumed: Condvar::new(),
+            found: Mutex::new(Vec::new()),
             status: Mutex::new("scanning".to_string()),
             current_path: Mutex::new(None),
             error: Mutex::new(None),
             result: Mutex::new(None),
+        })
+    }
+
+    fn summary(&self, scan_id: u64) -> ScanSummary {
+        ScanSummary {
+            scan_id,
+            root: self.root.clone(),
+            roots: self.roots.clone(),
+            window_label: self.owner.clone(),
+            status: self.status.lock().unwrap().clone(),
+            found_blends: self.found_blends.load(Ordering::Relaxed),
+            started_at: self.started_at.to_rfc3339(),
+            delivered: self.delivered.load(Ordering::Relaxed),
+            offline: self.offline.load(Ordering::Relaxed),
+            watched: self.roots.iter().any(|r| watch::is_watching(r)),
+        }
+    }
+
+    fn set_paused(&self, paused: bool) {
+        *self.paused.lock().unwrap() = paused;
+        if !paused {
+            self.resumed.notify_all();
+        }
+    }
+
+    fn is_paused(&self) -> bool {
+        *self.paused.lock().unwrap()
+    }
+
+    // Blocks the calling worker until the scan is resumed
+    fn wait_while_paused(&self) {
+        let paused = self.paused.lock().unwrap();
+        drop(self.resumed.wait_while(paused, |p| *p).unwrap());
+    }
+
+    // Up to FOUND_BATCH files from `from` on, and where the next batch starts
+    fn found_since(&self, from: usize) -> (Vec<FlatFile>, usize) {
+        let found = self.found.lock().unwrap();
+        let batch: Vec<FlatFile> = found
+            .get(from..)
+            .unwrap_or_default()
+            .iter()
+            .take(FOUND_BATCH)
+            .cloned()
+            .collect();
+        let next = from + batch.len();
+        (batch, next)
+    }
+
+    fn has_result(&self) -> bool {
+        self.result.lock().unwrap().is_some()
+    }
+
+    // A watched file the scan's include/exclude globs would have left out
+    fn filtered_out(&self, path: &Path) -> bool {
+        self.roots.iter().any(|root| {
+            path.strip_prefix(paths::from_id(root))
+                .is_ok_and(|rel| self.globs.skips_file(rel))
+        })
+    }
+
+    fn is_expired(&self, now: Instant) -> bool {
+        let Some(finished) = *self.finished_at.lock().unwrap() else {
+            return false;
+        };
+        let age = now.duration_since(finished);
+        age >= SCAN_TTL || (self.delivered.load(Ordering::Relaxed) && age >= DELIVERED_GRACE)
+    }
+
+    // `lazy_tree` sends only the top tree level (see `TreeNode::one_level`)
+    fn snapshot(&self, scan_id: u64, lazy_tree: bool) -> ScanPoll {
+        let status = self.status.lock().unwrap().clone();
+        let current_path = self.current_path.lock().unwrap().clone();
+        let error = self.error.lock().unwrap().clone();
+
+        // User data is read fresh, so edits show up without a rescan
+        let result = if status == "done" {
+            self.result.lock().unwrap().as_ref().map(|r| {
+                let mut r = if lazy_tree {
+                    ScanResult {
+                        tree: if r.counts_only {
+                            r.tree.without_children()
+                        } else {
+                            r.tree.one_level()
+                        },
+                        roots: r.roots.clone(),
+                        files: r.files.clone(),
+                        errors: r.errors.clone(),
+                        skipped: r.skipped.clone(),
+                        links: r.links.clone(),
+                        truncated: r.truncated,
+                        limits_hit: r.limits_hit.clone(),
+                        compact_tree: r.compact_tree,
+                        relative_paths: r.relative_paths,
+                        counts_only: r.counts_only,
+                        tree_order: r.tree_order,
+                        flat_tree: None,
+                        parse_depth: r.parse_depth,
+                    }
+                } else {
+                    r.clone()
+                };
+                annotate_user_data(&mut r.files);
+                labels::annotate_tree(&mut r.tree);
+                r
+            })
+        } else {
+            None
+        };
+
+        let phase = self.phase.load(Ordering::Relaxed);
+        let found_blends = self.found_blends.load(Ordering::Relaxed);
+        let counters = [
+            self.scanned_entries.load(Ordering::Relaxed),
+            self.headers_parsed.load(Ordering::Relaxed),
+            self.thumbnails_scanned.load(Ordering::Relaxed),
+            self.tree_inserted.load(Ordering::Relaxed),
+        ];
+        let phases = SCAN_PHASES
+            .iter()
+            .enumerate()
+            .map(|(i, name)| PhaseProgress {
+                name: name.to_string(),
+                state: match i.cmp(&phase) {
+                    std::cmp::Ordering::Less => "done",
+                    std::cmp::Ordering::Equal => "active",
+                    std::cmp::Ordering::Greater => "pending",
+                }
+                .to_string(),
+                completed: counters[i],
+                total: if i == PHASE_WALKING || phase == PHASE_WALKING {
+                    None
+                } else {
+                    Some(found_blends)
+                },
+            })
+            .collect();
+
+        ScanPoll {
+            scan_id,
+            status,
+            scanned_entries: self.scanned_entries.load(Ordering::Relaxed),
+            found_blends,
+            phase: SCAN_PHASES
+                .get(phase)
+                .copied()
+                .unwrap_or("finished")
+                .to_string(),
+            phases,
+            current_path,
+            paused: self.is_paused(),
+            found: Vec::new(),
+            found_next: None,
+            error,
+            result,
         }
     }
 }
@@ -107,118 +665,360 @@ impl ScanState {
 static NEXT_SCAN_ID: AtomicU64 = AtomicU64::new(1);
 static SCANS: OnceLock<Mutex<HashMap<u64, Arc<ScanState>>>> = OnceLock::new();
 
+// Routes an event to the window that owns the scan/job, or broadcasts it
+fn emit_to_owner<S: Serialize + Clone>(
+    app: &tauri::AppHandle,
+    owner: Option<&str>,
+    event: &str,
+    payload: S,
+) -> tauri::Result<()> {
+    match owner {
+        Some(label) => app.emit_to(label, event, payload),
+        None => app.emit(event, payload),
+    }
+}
+
 fn scans_map() -> &'static Mutex<HashMap<u64, Arc<ScanState>>> {
     SCANS.get_or_init(|| Mutex::new(HashMap::new()))
 }
 
-// -----------------------------
-// .blend header parsing
-// -----------------------------
-fn parse_blend_header(path: &Path) -> BlendInfo {
-    let mut file = match File::open(path) {
-        Ok(f) => f,
-        Err(e) => {
-            return BlendInfo {
-                version: None,
-                raw: None,
-                pointer_size: None,
-                endianness: None,
-                thumbnail: None,
-                thumb_width: None,
-                thumb_height: None,
-                render_engine: None,
-                error: Some(e.to_string()),
+// Tags, favorites, ratings and labels live in the index DB, not in scan results
+fn annotate_user_data(files: &mut [FlatFile]) {
+    tags::annotate(files);
+    favorites::annotate(files);
+    ratings::annotate(files);
+    labels::annotate(files);
+    collections::annotate(files);
+    notes::annotate(files);
+    file_ids::annotate(files);
+}
+
+// Index key for a command's path argument: a path, path id or file id
+fn path_key(path: &str) -> String {
+    paths::to_id(&paths::from_id(path))
+}
+
+fn path_keys(paths: &[String]) -> Vec<String> {
+    paths.iter().map(|p| path_key(p)).collect()
+}
+
+// Copies changed user data to whichever on-disk stores are switched on
+fn mirror_user_data(path_ids: &[String]) {
+    sidecar::sync(path_ids);
+    xattrs::sync(path_ids);
+}
+
+fn get_scan(scan_id: u64) -> Result<Arc<ScanState>, AppError> {
+    let map = scans_map().lock().unwrap();
+    map.get(&scan_id)
+        .cloned()
+        .ok_or_else(|| scan_not_found(scan_id))
+}
+
+// Visits the newest finished result of every scanned root, by root, so
+// pages over them line up between calls
+fn for_each_latest_result(mut f: impl FnMut(&ScanResult)) {
+    let states: Vec<Arc<ScanState>> = {
+        let map = scans_map().lock().unwrap();
+        let mut newest: HashMap<&str, (u64, &Arc<ScanState>)> = HashMap::new();
+        for (id, st) in map.iter() {
+            if !st.has_result() {
+                continue;
+            }
+            let entry = newest.entry(st.root.as_str()).or_insert((*id, st));
+            if *id > entry.0 {
+                *entry = (*id, st);
             }
         }
+        let mut newest: Vec<_> = newest.into_iter().collect();
+        newest.sort_by_key(|(root, _)| *root);
+        newest.into_iter().map(|(_, (_, st))| st.clone()).collect()
     };
+    for st in states {
+        if let Some(result) = st.result.lock().unwrap().as_ref() {
+            f(result);
+        }
+    }
+}
 
-    let mut buf = [0u8; 12];
-    if file.read_exact(&mut buf).is_err() {
-        return BlendInfo {
-            version: None,
-            raw: None,
-            pointer_size: None,
-            endianness: None,
-            thumbnail: None,
-            thumb_width: None,
-            thumb_height: None,
-            render_engine: None,
-            error: Some("Unable to read header".into()),
-        };
+// Results on a disconnected volume are kept (flagged) rather than dropped
+fn set_volume_offline(volume: &Path, offline: bool) {
+    let map = scans_map().lock().unwrap();
+    for st in map.values() {
+        if st
+            .roots
+            .iter()
+            .any(|r| paths::from_id(r).starts_with(volume))
+        {
+            st.offline.store(offline, Ordering::Relaxed);
+        }
+    }
+}
+
+fn evict_expired_scans() {
+    let now = Instant::now();
+    let mut map = scans_map().lock().unwrap();
+
+    let mut newest_per_root: HashMap<String, u64> = HashMap::new();
+    for (id, st) in map.iter() {
+        if st.has_result() {
+            let newest = newest_per_root.entry(st.root.clone()).or_insert(*id);
+            *newest = (*newest).max(*id);
+        }
+    }
+
+    map.retain(|id, st| newest_per_root.get(&st.root) == Some(id) || !st.is_expired(now));
+}
+
+// -----------------------------
+// .blend header parsing
+// -----------------------------
+// `BLENDER-v402` (pointer size, endianness, version), or since 5.0
+// `BLENDER17-01v0500`: header length, file format, endianness, version.
+// Newer layouts keep the length up front, so their version still reads.
+const MAX_HEADER_LEN: usize = 64;
+
+// Length of a header in the 5.0 form, from its first 12 bytes
+pub(crate) fn header_len(head: &[u8]) -> Option<usize> {
+    let digits = head.get(7..9)?;
+    if !digits.iter().all(u8::is_ascii_digit) || head.get(9) != Some(&b'-') {
+        return None;
+    }
+    std::str::from_utf8(digits)
+        .ok()?
+        .parse()
+        .ok()
+        .filter(|n| (13..=MAX_HEADER_LEN).contains(n))
+}
+
+fn read_header_bytes(data: &mut dyn Read) -> std::io::Result<Vec<u8>> {
+    let mut buf = vec![0u8; 12];
+    data.read_exact(&mut buf)?;
+    if let Some(len) = header_len(&buf) {
+        buf.resize(len, 0);
+        data.read_exact(&mut buf[12..])?;
     }
+    Ok(buf)
+}
+
+// Reads only the fixed file header (cheap; no block scan). Blends saved
+// with "Compress" (gzip before 3.0, zstd since) are read through the
+// decompressor.
+fn read_blend_header(path: &Path) -> Result<BlendInfo, AppError> {
+    let read = if archive::split(path).is_some() {
+        archive::with_entry(path, read_header_bytes)?
+    } else {
+        let file = handles::open(path)?;
+        let mut reader = BufReader::new(&*file);
+        let compression = reader
+            .fill_buf()
+            .map(magic::compression_of)
+            .unwrap_or(magic::Compression::None);
+        magic::decompress(compression, reader, read_header_bytes).and_then(|read| read)
+    };
+    let Ok(buf) = read else {
+        return Err(
+            AppError::new(ErrorKind::CorruptFile, "Unable to read header")
+                .with_code("header_unreadable")
+                .with_path(path),
+        );
+    };
 
     if &buf[0..7] != b"BLENDER" {
-        return BlendInfo {
-            version: None,
-            raw: None,
-            pointer_size: None,
-            endianness: None,
-            thumbnail: None,
-            thumb_width: None,
-            thumb_height: None,
-            render_engine: None,
-            error: Some("Not a blend file".into()),
-        };
+        return Err(AppError::new(ErrorKind::NotABlend, "Not a blend file").with_path(path));
     }
 
-    let pointer_size = match buf[7] {
-        b'-' => Some(64),
-        b'_' => Some(32),
-        _ => None,
+    // The 5.0 form always has 8-byte pointers; an unreadable format number
+    // counts as newer than any known
+    let (pointer_size, endian, raw, file_format) = match header_len(&buf) {
+        Some(len) => {
+            let file_format = std::str::from_utf8(&buf[10..12])
+                .ok()
+                .and_then(|f| f.parse().ok())
+                .unwrap_or(u8::MAX);
+            (Some(64), buf[12], &buf[13..len], file_format)
+        }
+        None => {
+            let pointer_size = match buf[7] {
+                b'-' => Some(64),
+                b'_' => Some(32),
+                _ => None,
+            };
+            (pointer_size, buf[8], &buf[9..12], 0)
+        }
     };
 
-    let endianness = match buf[8] {
+    let endianness = match endian {
         b'v' => Some("little".into()),
         b'V' => Some("big".into()),
         _ => Some("unknown".into()),
     };
 
-    let raw = String::from_utf8_lossy(&buf[9..12]).to_string();
-    let chars: Vec<char> = raw.chars().collect();
-    let version = if chars.len() == 3 {
-        Some(format!("{}.{}.{}", chars[0], chars[1], chars[2]))
-    } else {
-        None
-    };
+    // `402` reads 4.0.2, `0500` 5.0.0
+    let raw = String::from_utf8_lossy(raw).to_string();
+    let version = raw
+        .parse::<u32>()
+        .ok()
+        .filter(|_| raw.chars().all(|c| c.is_ascii_digit()))
+        .map(|v| format!("{}.{}.{}", v / 100, v / 10 % 10, v % 10));
 
-    let mut info = BlendInfo {
+    Ok(BlendInfo {
         version,
         raw: Some(raw),
         pointer_size,
         endianness,
-        thumbnail: None,
-        thumb_width: None,
-        thumb_height: None,
-        render_engine: None,
-        error: None,
+        file_format,
+        ..Default::default()
+    })
+}
+
+// Block scan for thumbnail and metadata, on top of a parsed header
+// With `deep`, files are walked to their struct layouts and scene
+// settings, links and assets are read; without, only the thumbnail and the
+// render engine guess
+fn extract_block_info(path: &Path, info: &mut BlendInfo, deep: bool) -> Result<(), AppError> {
+    let ptr_size = info.pointer_size;
+    let parsed = if archive::split(path).is_some() {
+        archive::with_entry(path, |entry| {
+            parse_blocks(info, &mut Streamed(entry), ptr_size, false).map(|_| ())
+        })?
+    } else {
+        let file = handles::open(path)?;
+        let mut reader = BufReader::new(&*file);
+        let compression =
+            magic::compression_of(reader.fill_buf().map_err(|e| AppError::io(&e, path))?);
+        if compression != magic::Compression::None {
+            // Streamed like archive entries: thumbnail and engine guess only
+            return magic::decompress(compression, reader, |data| {
+                parse_blocks(info, &mut Streamed(data), ptr_size, false).map(|_| ())
+            })
+            .map_err(|e| AppError::io(&e, path))?
+            .map_err(|e| block_scan_failed(path, info, e));
+        }
+        parse_blocks(info, &mut reader, ptr_size, deep).map(|index| {
+            if let Some(index) = index {
+                // Read from the scene's settings; the block-text guess
+                // stays for streamed files
+                if let Some(id) = contents::render_engine(&index, &mut reader) {
+                    info.render_engine = Some(contents::engine_name(&id));
+                    info.render_engine_id = Some(id);
+                }
+                info.assets = contents::assets(&index, &mut reader);
+                info.compositing = Some(contents::compositing(&index, &mut reader));
+                info.autopack = Some(contents::autopack(&index, &mut reader));
+                info.simulations = contents::simulations(&index, &mut reader);
+                info.sequencer = contents::sequencer(&index, &mut reader);
+                info.environments = contents::environments(&index, &mut reader);
+                info.libraries = contents::libraries(&index, &mut reader);
+            }
+        })
     };
+    parsed.map_err(|e| block_scan_failed(path, info, e))
+}
+
+// Non-fatal error for advanced parsing
+fn block_scan_failed(path: &Path, info: &mut BlendInfo, e: Box<dyn std::error::Error>) -> AppError {
+    let msg = format!("Header OK, but block scan failed: {}", e);
+    info.error = Some(msg.clone());
+    AppError::new(ErrorKind::CorruptFile, msg).with_path(path)
+}
+
+// Block data is skipped, not read: seeking in files, reading through in
+// streams (archive entries). Only files can go back to a block.
+pub(crate) trait BlockReader: Read {
+    fn skip(&mut self, n: u64) -> std::io::Result<()>;
 
-    // Advanced parsing for thumbnail and metadata
-    if let Err(e) = parse_blocks(&mut info, &mut file, pointer_size) {
-        // Non-fatal error for advanced parsing
-        info.error = Some(format!("Header OK, but block scan failed: {}", e));
+    fn seekable(&self) -> bool {
+        false
     }
 
-    info
+    fn read_at(&mut self, _offset: u64, _buf: &mut [u8]) -> std::io::Result<()> {
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+}
+
+impl<R: Read + Seek> BlockReader for BufReader<R> {
+    fn skip(&mut self, n: u64) -> std::io::Result<()> {
+        self.seek_relative(n as i64)
+    }
+
+    fn seekable(&self) -> bool {
+        true
+    }
+
+    fn read_at(&mut self, offset: u64, buf: &mut [u8]) -> std::io::Result<()> {
+        self.seek(SeekFrom::Start(offset))?;
+        self.read_exact(buf)
+    }
 }
 
+struct Streamed<R>(R);
+
+impl<R: Read> Read for Streamed<R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.0.read(buf)
+    }
+}
+
+impl<R: Read> BlockReader for Streamed<R> {
+    fn skip(&mut self, n: u64) -> std::io::Result<()> {
+        std::io::copy(&mut self.0.by_ref().take(n), &mut std::io::sink()).map(|_| ())
+    }
+}
+
+// Scene blocks are read whole for the engine guess; a bigger size is a
+// misread header, skipped rather than allocated
+const MAX_SCENE_BLOCK: u64 = 16 * 1024 * 1024;
+
+// `file` is at the start of the blend. Seekable files read with `index`
+// come back with an index of their blocks for reading them again.
 fn parse_blocks(
     info: &mut BlendInfo,
-    file: &mut File,
+    file: &mut impl BlockReader,
     ptr_size: Option<u8>,
-) -> Result<(), Box<dyn std::error::Error>> {
+    index: bool,
+) -> Result<Option<contents::Index>, Box<dyn std::error::Error>> {
     use base64::prelude::*;
-    use std::io::{Read, Seek, SeekFrom};
 
     let is_little = info.endianness.as_deref() != Some("big");
     let ptr_size = ptr_size.unwrap_or(64) / 8;
+    // Header is 12 bytes, or as long as it says in file format 1 (5.0)
+    let large = match info.file_format {
+        0 => false,
+        1 => true,
+        n => return Err(format!("file format {} is newer than this app reads", n).into()),
+    };
+    let mut head = [0u8; 12];
+    file.read_exact(&mut head)?;
+    let file_header_len = match (large, header_len(&head)) {
+        (false, _) => 12,
+        (true, Some(len)) => len as u64,
+        (true, None) => return Err("file header has no length".into()),
+    };
+    file.skip(file_header_len - 12)?;
 
-    // Header is 12 bytes
-    file.seek(SeekFrom::Start(12))?;
-
-    let header_len = 4 + 4 + ptr_size as usize + 4 + 4;
+    // code, len, old, sdna, nr before 5.0; code, sdna, old, len (64-bit),
+    // nr after
+    let header_len = if large {
+        32
+    } else {
+        4 + 4 + ptr_size as usize + 4 + 4
+    };
     let mut header_buf = vec![0u8; header_len];
+    let int = |b: &[u8]| -> u64 {
+        let v = sdna::View {
+            data: b,
+            little: is_little,
+        };
+        v.uint_at(0, b.len()).unwrap_or(0)
+    };
 
+    // Streams are only read as far as the thumbnail and scene; indexed files
+    // are walked to the struct layouts at the end
+    let indexed = index && file.seekable();
+    let mut heads = Vec::new();
+    let mut layouts = None;
+    let mut pos = file_header_len;
     let mut searched_blocks = 0;
     loop {
         if file.read_exact(&mut header_buf).is_err() {
@@ -227,11 +1027,28 @@ fn parse_blocks(
         searched_blocks += 1;
 
         let id = String::from_utf8_lossy(&header_buf[0..4]);
-        let size = if is_little {
-            u32::from_le_bytes(header_buf[4..8].try_into()?)
+        let p = ptr_size as usize;
+        let (size, old, sdna) = if large {
+            (
+                int(&header_buf[16..24]),
+                int(&header_buf[8..16]),
+                int(&header_buf[4..8]),
+            )
         } else {
-            u32::from_be_bytes(header_buf[4..8].try_into()?)
-        } as u64;
+            (
+                int(&header_buf[4..8]),
+                int(&header_buf[8..8 + p]),
+                int(&header_buf[8 + p..12 + p]),
+            )
+        };
+        let head = contents::BlockHead {
+            code: header_buf[0..4].try_into()?,
+            size,
+            old,
+            sdna: sdna as u32,
+            offset: pos + header_len as u64,
+        };
+        pos = head.offset + size;
 
         if id.starts_with("TEST") {
             let mut thumb_header = [0u8; 8];
@@ -249,22 +1066,23 @@ fn parse_blocks(
                 };
 
                 let data_size = (width * height * 4) as usize;
-                if data_size > 0 && data_size < 1024 * 1024 * 10 {
+                let mut read_so_far = 8;
+                if data_size > 0 && data_size < 1024 * 1024 * 10 && 8 + data_size as u64 <= size {
                     let mut rgba = vec![0u8; data_size];
                     if file.read_exact(&mut rgba).is_ok() {
                         info.thumbnail = Some(BASE64_STANDARD.encode(&rgba));
                         info.thumb_width = Some(width);
                         info.thumb_height = Some(height);
                     }
+                    read_so_far += data_size as u64;
                 }
 
                 // Ensure we skip the rest of the block if size was different
-                let read_so_far = 8 + data_size as u64;
                 if size > read_so_far {
-                    file.seek(SeekFrom::Current((size - read_so_far) as i64))?;
+                    file.skip(size - read_so_far)?;
                 }
             }
-        } else if id.starts_with("SC") {
+        } else if id.starts_with("SC") && size <= MAX_SCENE_BLOCK {
             let mut sc_data = vec![0u8; size as usize];
             if file.read_exact(&mut sc_data).is_ok() {
                 let sc_str = String::from_utf8_lossy(&sc_data).to_uppercase();
@@ -276,14 +1094,39 @@ fn parse_blocks(
                     info.render_engine = Some("Workbench".into());
                 }
             }
-        } else if id.starts_with("DNA1") || id.starts_with("ENDB") || searched_blocks > 3000 {
+            heads.push(head);
+        } else if id.starts_with("DNA1") && indexed {
+            let mut dna = vec![0u8; size as usize];
+            file.read_exact(&mut dna)?;
+            layouts = sdna::Sdna::parse(&dna, is_little, ptr_size as usize);
+            break;
+        } else if id.starts_with("DNA1")
+            || id.starts_with("ENDB")
+            || (!indexed && searched_blocks > 3000)
+        {
             break;
         } else {
-            file.seek(SeekFrom::Current(size as i64))?;
+            file.skip(size)?;
+            heads.push(head);
         }
     }
 
-    Ok(())
+    Ok(layouts.map(|layouts| contents::Index::new(heads, layouts, is_little)))
+}
+
+// Large preview of one asset in a blend, read on demand
+fn read_asset_preview(
+    path: &Path,
+    code: &str,
+    name: &str,
+) -> Result<Option<contents::AssetPreview>, AppError> {
+    let mut info = read_blend_header(path)?;
+    let ptr_size = info.pointer_size;
+    let file = handles::open(path)?;
+    let mut reader = BufReader::new(&*file);
+    let index = parse_blocks(&mut info, &mut reader, ptr_size, true)
+        .map_err(|e| AppError::new(ErrorKind::CorruptFile, e.to_string()).with_path(path))?;
+    Ok(index.and_then(|index| contents::asset_preview(&index, &mut reader, code, name)))
 }
 
 // -----------------------------
@@ -291,83 +1134,340 @@ fn parse_blocks(
 // -----------------------------
 #[derive(Default)]
 struct DirNode {
-    dirs: BTreeMap<String, DirNode>,
+    dirs: BTreeMap<OsString, DirNode>, // raw names, so node paths stay exact
     files: Vec<(String, PathBuf, FileMeta)>, // (name, full_path, meta)
 }
 
+// Folder without files of its own (e.g. one the scan could not read)
+fn insert_dir(root: &mut DirNode, rel_parts: &[OsString]) {
+    let mut cur = root;
+    for part in rel_parts {
+        cur = cur.dirs.entry(part.clone()).or_default();
+    }
+}
+
 fn insert_file(
     root: &mut DirNode,
-    rel_parts: &[String],
+    rel_parts: &[OsString],
     file_name: &str,
     full_path: &Path,
     meta: FileMeta,
 ) {
     let mut cur = root;
     for part in rel_parts {
-        cur = cur
-            .dirs
-            .entry(part.clone())
-            .or_insert_with(DirNode::default);
+        cur = cur.dirs.entry(part.clone()).or_default();
     }
     cur.files
         .push((file_name.to_string(), full_path.to_path_buf(), meta));
 }
 
-fn build_tree_nodes(dir: &DirNode, name: &str, path: &Path) -> TreeNode {
+#[derive(Default)]
+struct DirAggregate {
+    blend_count: u64,
+    total_size: u64,
+    newest_modified: Option<String>,
+    versions: HashMap<String, u64>,
+}
+
+impl DirAggregate {
+    fn add_file(&mut self, meta: &FileMeta) {
+        self.blend_count += 1;
+        self.total_size += meta.size_bytes;
+        self.note_modified(meta.modified.as_deref());
+        if let Some(v) = &meta.blender.version {
+            *self.versions.entry(v.clone()).or_insert(0) += 1;
+        }
+    }
+
+    fn merge(&mut self, other: DirAggregate) {
+        self.blend_count += other.blend_count;
+        self.total_size += other.total_size;
+        self.note_modified(other.newest_modified.as_deref());
+        for (v, n) in other.versions {
+            *self.versions.entry(v).or_insert(0) += n;
+        }
+    }
+
+    fn note_modified(&mut self, modified: Option<&str>) {
+        let Some(m) = modified else { return };
+        let newer = match &self.newest_modified {
+            None => true,
+            Some(cur) => match (
+                DateTime::parse_from_rfc3339(m),
+                DateTime::parse_from_rfc3339(cur),
+            ) {
+                (Ok(a), Ok(b)) => a > b,
+                _ => m > cur.as_str(),
+            },
+        };
+        if newer {
+            self.newest_modified = Some(m.to_string());
+        }
+    }
+
+    // Most common version; ties go to the newer-sorting version string
+    fn dominant_version(&self) -> Option<String> {
+        self.versions
+            .iter()
+            .max_by(|a, b| a.1.cmp(b.1).then_with(|| a.0.cmp(b.0)))
+            .map(|(v, _)| v.clone())
+    }
+}
+
+// `compact` merges folder chains that hold nothing but the next folder
+// (`assets/characters/hero`) into one node; the root keeps its own node
+fn build_tree_nodes(
+    dir: &DirNode,
+    name: &str,
+    path: &Path,
+    compact: bool,
+    order: TreeOrder,
+) -> TreeNode {
+    let mut tree = build_tree_nodes_agg(dir, name, path, compact).0;
+    tree.sort(order);
+    tree
+}
+
+// The trees of a multi-root scan under one node, each named by its full
+// path; a single tree is returned as it is
+fn join_trees(mut trees: Vec<TreeNode>, order: TreeOrder) -> TreeNode {
+    if trees.len() == 1 {
+        return trees.remove(0);
+    }
+    let mut agg = DirAggregate::default();
+    for t in &mut trees {
+        t.name = t.path.clone();
+        agg.blend_count += t.blend_count.unwrap_or(0);
+        agg.total_size += t.total_size.unwrap_or(0);
+        agg.note_modified(t.newest_modified.as_deref());
+    }
+    // Version counts aren't kept on nodes; the biggest root's stands in
+    let dominant_version = trees
+        .iter()
+        .max_by_key(|t| t.blend_count)
+        .and_then(|t| t.dominant_version.clone());
+    let path = trees
+        .iter()
+        .map(|t| t.path.as_str())
+        .collect::<Vec<_>>()
+        .join("; ");
+    let mut tree = TreeNode {
+        node_type: "dir".into(),
+        name: format!("{} folders", trees.len()),
+        path,
+        path_id: String::new(), // no folder of its own
+        meta: None,
+        child_count: Some(trees.len()),
+        children: Some(trees),
+        blend_count: Some(agg.blend_count),
+        total_size: Some(agg.total_size),
+        newest_modified: agg.newest_modified,
+        dominant_version,
+        label: None,
+        is_new: false,
+        is_modified_since_last_scan: false,
+        error: None,
+    };
+    tree.sort(order);
+    tree
+}
+
+fn build_tree_nodes_agg(
+    dir: &DirNode,
+    name: &str,
+    path: &Path,
+    compact: bool,
+) -> (TreeNode, DirAggregate) {
     let mut children: Vec<TreeNode> = Vec::new();
+    let mut agg = DirAggregate::default();
 
     // Directories first
     for (dname, dnode) in dir.dirs.iter() {
         let child_path = path.join(dname);
-        children.push(build_tree_nodes(dnode, dname, &child_path));
+        let (child, child_agg) =
+            build_tree_nodes_agg(dnode, &dname.to_string_lossy(), &child_path, compact);
+        // Children are built first, so chains collapse from the bottom up
+        children.push(if compact {
+            collapse_chain(child)
+        } else {
+            child
+        });
+        agg.merge(child_agg);
     }
 
-    // Files
+    // Files; listed backups (`scene.blend1`, `scene.blend2`) nest under
+    // `scene.blend`, or stay siblings when it's gone
+    let mut files: Vec<TreeNode> = Vec::new();
+    let mut backups: Vec<(u32, TreeNode)> = Vec::new();
     for (fname, fpath, meta) in dir.files.iter() {
-        children.push(TreeNode {
+        agg.add_file(meta);
+        let node = TreeNode {
             node_type: "file".into(),
             name: fname.clone(),
             path: fpath.to_string_lossy().to_string(),
+            path_id: paths::to_id(fpath),
             meta: Some(meta.clone()),
             children: None,
-        });
+            child_count: None,
+            blend_count: None,
+            total_size: None,
+            newest_modified: None,
+            dominant_version: None,
+            label: None,
+            is_new: false,
+            is_modified_since_last_scan: false,
+            error: None,
+        };
+        match formats::backup_number(fpath) {
+            Some(n) => backups.push((n, node)),
+            None => files.push(node),
+        }
+    }
+    backups.sort_by_key(|(n, _)| *n);
+    let primaries: HashMap<String, usize> = files
+        .iter()
+        .enumerate()
+        .map(|(i, f)| (f.name.clone(), i))
+        .collect();
+    for (_, node) in backups {
+        let primary = node.name.trim_end_matches(|c: char| c.is_ascii_digit());
+        match primaries.get(primary) {
+            Some(&i) => {
+                let file = &mut files[i];
+                let kids = file.children.get_or_insert_with(Vec::new);
+                kids.push(node);
+                file.child_count = Some(kids.len());
+            }
+            None => files.push(node),
+        }
     }
+    children.extend(files);
 
-    TreeNode {
+    let node = TreeNode {
         node_type: "dir".into(),
         name: name.to_string(),
         path: path.to_string_lossy().to_string(),
+        path_id: paths::to_id(path),
         meta: None,
+        child_count: Some(children.len()),
         children: Some(children),
+        blend_count: Some(agg.blend_count),
+        total_size: Some(agg.total_size),
+        newest_modified: agg.newest_modified.clone(),
+        dominant_version: agg.dominant_version(),
+        label: None,
+        is_new: false,
+        is_modified_since_last_scan: false,
+        error: None,
+    };
+    (node, agg)
+}
+
+// A folder whose only entry is another folder becomes that folder, named
+// after both; aggregates are the same by definition
+fn collapse_chain(node: TreeNode) -> TreeNode {
+    let only_dir = matches!(node.children.as_deref(), Some([only]) if only.node_type == "dir");
+    if !only_dir {
+        return node;
     }
+    let mut only = node.children.unwrap_or_default().remove(0);
+    only.name = format!("{}{}{}", node.name, std::path::MAIN_SEPARATOR, only.name);
+    only
 }
 
 // -----------------------------
 // Commands
 // -----------------------------
 #[tauri::command]
-fn pick_folder(app: tauri::AppHandle) -> Result<Option<String>, String> {
+fn pick_folder(app: tauri::AppHandle) -> Result<Option<String>, AppError> {
     use tauri_plugin_dialog::DialogExt;
 
     let res = app.dialog().file().blocking_pick_folder();
 
     if let Some(fp) = res {
-        let path = fp.into_path().map_err(|e| e.to_string())?;
-        Ok(Some(path.to_string_lossy().to_string()))
+        let path = fp
+            .into_path()
+            .map_err(|e| AppError::new(ErrorKind::Dialog, e.to_string()))?;
+        Ok(Some(paths::to_id(&path)))
     } else {
         Ok(None)
     }
 }
 
 #[tauri::command]
-fn start_scan(folder_path: String) -> Result<u64, String> {
-    let root = PathBuf::from(&folder_path);
+fn start_scan(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    folder_path: String,
+    options: Option<scan::ScanOptions>,
+) -> Result<u64, AppError> {
+    let root = existing_folder(&folder_path)?;
+    spawn_scan(app, &window, vec![root], options.unwrap_or_default())
+}
+
+// Folders on several drives or shares as one scan: each root is read like
+// a scan of its own and the result lists them side by side, each a top
+// level folder of the tree
+#[tauri::command]
+fn start_multi_scan(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    roots: Vec<String>,
+    options: Option<scan::ScanOptions>,
+) -> Result<u64, AppError> {
+    let mut dirs = roots
+        .iter()
+        .map(|r| existing_folder(r))
+        .collect::<Result<Vec<_>, _>>()?;
+    dirs.sort();
+    dirs.dedup();
+    // A root inside another one is scanned with it
+    let all = dirs.clone();
+    dirs.retain(|d| !all.iter().any(|o| o != d && d.starts_with(o)));
+    if dirs.is_empty() {
+        return Err(
+            AppError::new(ErrorKind::InvalidInput, "No folders to scan").with_code("roots_empty")
+        );
+    }
+    spawn_scan(app, &window, dirs, options.unwrap_or_default())
+}
+
+fn existing_folder(folder_path: &str) -> Result<PathBuf, AppError> {
+    let root = paths::from_id(folder_path);
     if !root.exists() {
-        return Err("Folder does not exist".into());
+        return Err(AppError::new(ErrorKind::NotFound, "Folder does not exist")
+            .with_code("folder_missing")
+            .with_path(&root));
     }
+    Ok(root)
+}
+
+fn spawn_scan(
+    app: tauri::AppHandle,
+    window: &tauri::WebviewWindow,
+    roots: Vec<PathBuf>,
+    options: scan::ScanOptions,
+) -> Result<u64, AppError> {
+    evict_expired_scans();
 
     let scan_id = NEXT_SCAN_ID.fetch_add(1, Ordering::Relaxed);
-    let state = Arc::new(ScanState::new());
+    let state = Arc::new(ScanState::new(
+        &roots,
+        &options,
+        Some(window.label().to_string()),
+    )?);
+    let mut ctxs: Vec<_> = roots
+        .iter()
+        .map(|root| {
+            scan::ScanCtx::new(
+                app.clone(),
+                scan_id,
+                state.clone(),
+                options.clone(),
+                root.clone(),
+            )
+        })
+        .collect();
 
     // Store scan state
     {
@@ -375,177 +1475,1686 @@ fn start_scan(folder_path: String) -> Result<u64, String> {
         map.insert(scan_id, state.clone());
     }
 
+    tracing::info!(scan_id, roots = ?roots, "scan started");
+
     // Background scan thread
     thread::spawn(move || {
-        let mut status = state.status.lock().unwrap();
-        *status = "scanning".to_string();
-        drop(status);
-
-        let mut files: Vec<FlatFile> = Vec::new();
-        let mut builder = DirNode::default();
-
-        // Root node name (folder name)
-        let root_name = root
-            .file_name()
-            .map(|s| s.to_string_lossy().to_string())
-            .unwrap_or_else(|| folder_path.clone());
-
-        for entry in WalkDir::new(&root).into_iter() {
-            match entry {
-                Ok(e) => {
-                    state.scanned_entries.fetch_add(1, Ordering::Relaxed);
-
-                    // Current path (for UI)
-                    if let Ok(mut cp) = state.current_path.lock() {
-                        *cp = Some(e.path().to_string_lossy().to_string());
-                    }
+        if ctxs.len() == 1 {
+            scan::run_scan(ctxs.remove(0))
+        } else {
+            scan::run_multi_scan(ctxs)
+        }
+    });
 
-                    let p = e.path();
-                    if !p.is_file() {
-                        continue;
-                    }
+    Ok(scan_id)
+}
 
-                    if p.extension()
-                        .and_then(|x| x.to_str())
-                        .unwrap_or("")
-                        .to_lowercase()
-                        != "blend"
-                    {
-                        continue;
-                    }
+// A scan that reads only files changed since the last finished scan of
+// `folder_path`; the rest come from the index (see checkpoint.rs)
+#[tauri::command]
+fn rescan_indexed(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    folder_path: String,
+    options: Option<scan::ScanOptions>,
+) -> Result<u64, AppError> {
+    let options = scan::ScanOptions {
+        use_index: Some(true),
+        ..options.unwrap_or_default()
+    };
+    start_scan(app, window, folder_path, Some(options))
+}
 
-                    state.found_blends.fetch_add(1, Ordering::Relaxed);
-
-                    let meta_fs = match p.metadata() {
-                        Ok(m) => m,
-                        Err(_) => continue,
-                    };
-
-                    let created = meta_fs
-                        .created()
-                        .ok()
-                        .map(|t| DateTime::<Local>::from(t).to_rfc3339());
-                    let modified = meta_fs
-                        .modified()
-                        .ok()
-                        .map(|t| DateTime::<Local>::from(t).to_rfc3339());
-
-                    let blend = parse_blend_header(p);
-                    let folder = p.parent().unwrap_or(&root).to_string_lossy().to_string();
-                    let path_str = p.to_string_lossy().to_string();
-                    let name = p
-                        .file_name()
-                        .unwrap_or_default()
-                        .to_string_lossy()
-                        .to_string();
-
-                    let file_meta = FileMeta {
-                        size_bytes: meta_fs.len(),
-                        created: created.clone(),
-                        modified: modified.clone(),
-                        folder: folder.clone(),
-                        blender: blend.clone(),
-                    };
-
-                    // Flat list for search
-                    files.push(FlatFile {
-                        name: name.clone(),
-                        path: path_str.clone(),
-                        folder,
-                        size_bytes: meta_fs.len(),
-                        created,
-                        modified,
-                        blender_version: blend.version.clone(),
-                        thumbnail: blend.thumbnail.clone(),
-                        render_engine: blend.render_engine.clone(),
-                    });
-
-                    // Tree insert (relative directories)
-                    let rel = p.strip_prefix(&root).unwrap_or(p);
-                    let mut parts: Vec<String> = Vec::new();
-                    if let Some(parent) = rel.parent() {
-                        for comp in parent.components() {
-                            parts.push(comp.as_os_str().to_string_lossy().to_string());
-                        }
-                    }
-                    insert_file(&mut builder, &parts, &name, p, file_meta);
-                }
-                Err(err) => {
-                    // Non-fatal: keep scanning
-                    if let Ok(mut cp) = state.current_path.lock() {
-                        *cp = Some(format!("(walk error) {}", err));
-                    }
+// Job result: a scan::Benchmark of `folder_path` with these options; the
+// files are read like a scan would, but nothing is kept
+#[tauri::command]
+fn benchmark_scan(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    folder_path: String,
+    options: Option<scan::ScanOptions>,
+) -> Result<u64, AppError> {
+    let root = paths::from_id(&folder_path);
+    if !root.exists() {
+        return Err(AppError::new(ErrorKind::NotFound, "Folder does not exist")
+            .with_code("folder_missing")
+            .with_path(&root));
+    }
+    // Every phase is timed, whatever depth the options ask for
+    let options = scan::ScanOptions {
+        parse_depth: Some(scan::ParseDepth::Deep),
+        ..options.unwrap_or_default()
+    };
+    // Its own scan id, never registered, so its progress events match no scan
+    let scan_id = NEXT_SCAN_ID.fetch_add(1, Ordering::Relaxed);
+    let state = Arc::new(ScanState::new(
+        std::slice::from_ref(&root),
+        &options,
+        Some(window.label().to_string()),
+    )?);
+    let ctx = scan::ScanCtx::new(app.clone(), scan_id, state, options, root);
+    Ok(jobs::spawn_job(
+        app,
+        Some(window.label()),
+        "benchmark",
+        move |job| job_value(scan::benchmark(&ctx, job)?),
+    ))
+}
+
+// `lazy_tree` returns only the top tree level; deeper levels come from
+// `get_tree_children`. `tree_format: "flat"` sends the whole tree in the
+// parent-index encoding instead (see flat_tree.rs). With `found_from`, a
+// running scan also sends the files it read since then, in batches.
+#[tauri::command]
+fn poll_scan(
+    scan_id: u64,
+    lazy_tree: Option<bool>,
+    tree_format: Option<flat_tree::TreeFormat>,
+    found_from: Option<usize>,
+) -> Result<ScanPoll, AppError> {
+    let state = get_scan(scan_id)?;
+    // The flat encoding always carries the whole tree; `tree` keeps the root
+    let flat = tree_format == Some(flat_tree::TreeFormat::Flat);
+    let mut poll = state.snapshot(scan_id, lazy_tree.unwrap_or(false) && !flat);
+    if let Some(from) = found_from {
+        let (found, next) = state.found_since(from);
+        poll.found = found;
+        poll.found_next = Some(next);
+    }
+    if let (true, Some(r)) = (flat, poll.result.as_mut()) {
+        r.flat_tree = Some(flat_tree::encode(&r.tree, &r.files));
+        r.tree = r.tree.without_children();
+    }
+    if poll.result.is_some() {
+        state.delivered.store(true, Ordering::Relaxed);
+    }
+    Ok(poll)
+}
+
+// Holds a running scan between entries, e.g. to free a network share for
+// a while; its workers keep their place until `resume_scan`
+#[tauri::command]
+fn pause_scan(app: tauri::AppHandle, scan_id: u64) -> Result<(), AppError> {
+    set_scan_paused(&app, scan_id, true)
+}
+
+#[tauri::command]
+fn resume_scan(app: tauri::AppHandle, scan_id: u64) -> Result<(), AppError> {
+    set_scan_paused(&app, scan_id, false)
+}
+
+fn set_scan_paused(app: &tauri::AppHandle, scan_id: u64, paused: bool) -> Result<(), AppError> {
+    let state = get_scan(scan_id)?;
+    if state.done.load(Ordering::Relaxed) {
+        return Err(AppError::new(
+            ErrorKind::InvalidInput,
+            format!("Scan {} has already finished", scan_id),
+        )
+        .with_code("scan_finished")
+        .with_param("scan_id", scan_id));
+    }
+    state.set_paused(paused);
+    tracing::info!(scan_id, paused, "scan pause toggled");
+    let poll = state.snapshot(scan_id, false);
+    ws::broadcast(EVENT_SCAN_PROGRESS, &poll);
+    let _ = emit_to_owner(app, state.owner.as_deref(), EVENT_SCAN_PROGRESS, poll);
+    Ok(())
+}
+
+// Re-orders a finished result's tree in place; later polls return it sorted
+#[tauri::command]
+fn resort_tree(scan_id: u64, order: TreeOrder) -> Result<(), AppError> {
+    let state = get_scan(scan_id)?;
+    let mut result = state.result.lock().unwrap();
+    let result = result.as_mut().ok_or_else(|| scan_not_finished(scan_id))?;
+    result.tree_order = order;
+    result.tree.sort(order);
+    Ok(())
+}
+
+// One level below `dir_path` (path id or display path); sub-folders carry
+// `child_count` instead of their children
+#[tauri::command]
+fn get_tree_children(scan_id: u64, dir_path: String) -> Result<Vec<TreeNode>, AppError> {
+    let state = get_scan(scan_id)?;
+    let result = state.result.lock().unwrap();
+    let result = result.as_ref().ok_or_else(|| scan_not_finished(scan_id))?;
+    let dir = result.tree.find_dir(&dir_path).ok_or_else(|| {
+        AppError::new(ErrorKind::NotFound, "Folder not in scan result")
+            .with_path(&paths::from_id(&dir_path))
+    })?;
+    let mut level = dir.one_level();
+    labels::annotate_tree(&mut level);
+    Ok(level.children.unwrap_or_default())
+}
+
+#[tauri::command]
+fn list_scans(window_label: Option<String>) -> Vec<ScanSummary> {
+    evict_expired_scans();
+    let map = scans_map().lock().unwrap();
+    let mut scans: Vec<ScanSummary> = map
+        .iter()
+        .filter(|(_, st)| window_label.is_none() || st.owner == window_label)
+        .map(|(id, st)| st.summary(*id))
+        .collect();
+    scans.sort_by_key(|s| s.scan_id);
+    scans
+}
+
+// Reads files of a finished scan that it left unparsed (see enrich.rs);
+// `visible` files go first, ahead of earlier requests
+#[tauri::command]
+fn enrich_files(
+    app: tauri::AppHandle,
+    scan_id: u64,
+    paths: Vec<String>,
+    visible: bool,
+) -> Result<(), AppError> {
+    if !scans_map().lock().unwrap().contains_key(&scan_id) {
+        return Err(scan_not_found(scan_id));
+    }
+    enrich::request(&app, scan_id, path_keys(&paths), visible);
+    Ok(())
+}
+
+#[tauri::command]
+fn drop_scan(scan_id: u64) -> Result<(), AppError> {
+    let mut map = scans_map().lock().unwrap();
+    // A paused scan is let go, or its workers would wait forever
+    let state = map
+        .remove(&scan_id)
+        .ok_or_else(|| scan_not_found(scan_id))?;
+    state.set_paused(false);
+    // The last result of a root takes its watcher along
+    for root in &state.roots {
+        if !map.values().any(|st| st.roots.contains(root)) {
+            watch::stop(root);
+        }
+    }
+    Ok(())
+}
+
+fn scan_not_found(scan_id: u64) -> AppError {
+    AppError::new(
+        ErrorKind::ScanNotFound,
+        format!("Scan id {} not found", scan_id),
+    )
+    .with_param("scan_id", scan_id)
+}
+
+fn scan_not_finished(scan_id: u64) -> AppError {
+    AppError::new(
+        ErrorKind::ScanNotFinished,
+        format!("Scan {} has not finished", scan_id),
+    )
+    .with_param("scan_id", scan_id)
+}
+
+fn scan_result(scan_id: u64) -> Result<ScanResult, AppError> {
+    let result = get_scan(scan_id)?.result.lock().unwrap().clone();
+    result.ok_or_else(|| scan_not_finished(scan_id))
+}
+
+#[tauri::command]
+fn diff_scans(old_scan_id: u64, new_scan_id: u64) -> Result<diff::ScanDiff, AppError> {
+    let old = scan_result(old_scan_id)?;
+    let new = scan_result(new_scan_id)?;
+    Ok(diff::diff_results(old_scan_id, &old, new_scan_id, &new))
+}
+
+#[tauri::command]
+fn export_scan_diff(
+    old_scan_id: u64,
+    new_scan_id: u64,
+    format: String,
+    output_path: String,
+) -> Result<(), AppError> {
+    let d = diff_scans(old_scan_id, new_scan_id)?;
+    diff::export_diff(&d, &format, Path::new(&output_path))
+}
+
+#[tauri::command]
+fn export_template(
+    scan_id: u64,
+    template_path: String,
+    output_path: String,
+) -> Result<(), AppError> {
+    let result = scan_result(scan_id)?;
+    export::export_with_template(Path::new(&template_path), Path::new(&output_path), &result)
+}
+
+// Local time or UTC for timestamps in template and diff exports
+#[tauri::command]
+fn get_export_utc() -> bool {
+    settings::get().export_utc
+}
+
+#[tauri::command]
+fn set_export_utc(enabled: bool) -> Result<bool, AppError> {
+    settings::update(|s| s.export_utc = enabled)?;
+    Ok(enabled)
+}
+
+// -----------------------------
+// File operation jobs
+// -----------------------------
+fn to_paths(paths: &[String]) -> Vec<PathBuf> {
+    paths.iter().map(|p| paths::from_id(p)).collect()
+}
+
+fn job_value<T: Serialize>(value: T) -> Result<serde_json::Value, AppError> {
+    serde_json::to_value(value).map_err(|e| AppError::new(ErrorKind::Io, e.to_string()))
+}
+
+#[tauri::command]
+fn start_copy_job(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    paths: Vec<String>,
+    dest_dir: String,
+) -> u64 {
+    let paths = to_paths(&paths);
+    jobs::spawn_job(app, Some(window.label()), "copy", move |ctx| {
+        job_value(fileops::copy_files(ctx, &paths, Path::new(&dest_dir))?)
+    })
+}
+
+// Chunked and verified; running it again resumes (see fileops.rs)
+#[tauri::command]
+fn start_resumable_copy_job(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    paths: Vec<String>,
+    dest_dir: String,
+) -> u64 {
+    let paths = to_paths(&paths);
+    jobs::spawn_job(app, Some(window.label()), "copy", move |ctx| {
+        job_value(fileops::copy_files_resumable(
+            ctx,
+            &paths,
+            Path::new(&dest_dir),
+        )?)
+    })
+}
+
+#[tauri::command]
+fn start_consolidate_job(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    paths: Vec<String>,
+    dest_dir: String,
+) -> u64 {
+    let paths = to_paths(&paths);
+    jobs::spawn_job(app, Some(window.label()), "consolidate", move |ctx| {
+        job_value(fileops::consolidate_files(
+            ctx,
+            &paths,
+            Path::new(&dest_dir),
+        )?)
+    })
+}
+
+#[tauri::command]
+fn start_zip_job(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    paths: Vec<String>,
+    zip_path: String,
+) -> u64 {
+    let paths = to_paths(&paths);
+    jobs::spawn_job(app, Some(window.label()), "zip", move |ctx| {
+        job_value(fileops::zip_files(ctx, &paths, Path::new(&zip_path))?)
+    })
+}
+
+#[tauri::command]
+fn start_hash_job(app: tauri::AppHandle, window: tauri::WebviewWindow, paths: Vec<String>) -> u64 {
+    let paths = to_paths(&paths);
+    jobs::spawn_job(app, Some(window.label()), "hash", move |ctx| {
+        let hashes = fileops::hash_files(ctx, &paths)?;
+        integrity::remember(&hashes);
+        job_value(hashes)
+    })
+}
+
+// Job result: an integrity::IntegrityReport. Files without a checksum (or
+// saved since) get one; the others are compared against theirs.
+#[tauri::command]
+fn verify_integrity(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    scope: integrity::Scope,
+) -> Result<u64, AppError> {
+    let files = match scope {
+        integrity::Scope::Scan { scan_id } => scan_result(scan_id)?
+            .files
+            .iter()
+            .filter(|f| f.archive.is_none())
+            .map(|f| paths::from_id(&f.path_id))
+            .collect(),
+        integrity::Scope::Paths { paths } => to_paths(&paths),
+        integrity::Scope::Stored => to_paths(&integrity::stored_paths()?),
+    };
+    Ok(jobs::spawn_job(
+        app,
+        Some(window.label()),
+        "verify",
+        move |ctx| job_value(integrity::verify(ctx, &files)?),
+    ))
+}
+
+// Job result: a triage::TriageReport of the scan's damaged blends
+#[tauri::command]
+fn start_triage_job(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    scan_id: u64,
+) -> Result<u64, AppError> {
+    let files = scan_result(scan_id)?.files;
+    Ok(jobs::spawn_job(
+        app,
+        Some(window.label()),
+        "triage",
+        move |ctx| job_value(triage::triage_files(ctx, scan_id, &files)?),
+    ))
+}
+
+#[tauri::command]
+fn poll_job(job_id: u64) -> Result<jobs::JobPoll, AppError> {
+    jobs::poll_job(job_id)
+}
+
+#[tauri::command]
+fn cancel_job(job_id: u64) -> Result<(), AppError> {
+    jobs::cancel_job(job_id)
+}
+
+// -----------------------------
+// Windows
+// -----------------------------
+static NEXT_WINDOW_ID: AtomicU64 = AtomicU64::new(1);
+
+fn percent_encode(s: &str) -> String {
+    s.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+// Opens another finder window sharing this process' scan registry
+#[tauri::command]
+fn open_window(app: tauri::AppHandle, folder_path: Option<String>) -> Result<String, AppError> {
+    let label = format!("finder-{}", NEXT_WINDOW_ID.fetch_add(1, Ordering::Relaxed));
+    let mut url = "index.html?secondary=1".to_string();
+    if let Some(folder) = folder_path {
+        url.push_str("&folder=");
+        url.push_str(&percent_encode(&folder));
+    }
+
+    tauri::WebviewWindowBuilder::new(&app, &label, tauri::WebviewUrl::App(url.into()))
+        .title("Blender File Finder")
+        .inner_size(800.0, 600.0)
+        .build()
+        .map_err(|e| AppError::new(ErrorKind::Window, e.to_string()))?;
+    Ok(label)
+}
+
+// Called by the frontend once its listeners are ready; returns links that
+// arrived earlier (and clears them so they aren't handled twice)
+#[tauri::command]
+fn take_pending_requests() -> Vec<deeplink::AppRequest> {
+    deeplink::take_pending()
+}
+
+// -----------------------------
+// Local HTTP API (opt-in)
+// -----------------------------
+#[tauri::command]
+fn start_http_api(port: Option<u16>) -> Result<http_api::HttpApiStatus, AppError> {
+    http_api::start(port)
+}
+
+#[tauri::command]
+fn stop_http_api() -> http_api::HttpApiStatus {
+    http_api::stop();
+    http_api::status()
+}
+
+#[tauri::command]
+fn http_api_status() -> http_api::HttpApiStatus {
+    http_api::status()
+}
+
+// Serves the latest results to other machines (see catalog.rs)
+#[tauri::command]
+fn publish_catalog(
+    port: Option<u16>,
+    key: Option<String>,
+) -> Result<http_api::HttpApiStatus, AppError> {
+    http_api::publish(port, key)
+}
+
+#[tauri::command]
+fn get_catalogs() -> Vec<catalog::RemoteCatalog> {
+    settings::get().catalogs
+}
+
+#[tauri::command]
+fn set_catalogs(
+    catalogs: Vec<catalog::RemoteCatalog>,
+) -> Result<Vec<catalog::RemoteCatalog>, AppError> {
+    catalog::check(&catalogs)?;
+    Ok(settings::update(|s| s.catalogs = catalogs)?.catalogs)
+}
+
+fn remote_catalog(name: &str) -> Result<catalog::RemoteCatalog, AppError> {
+    settings::get()
+        .catalogs
+        .into_iter()
+        .find(|c| c.name == name)
+        .ok_or_else(|| {
+            AppError::new(
+                ErrorKind::NotFound,
+                format!("No catalog named \"{}\"", name),
+            )
+            .with_code("catalog_not_found")
+            .with_param("name", name)
+        })
+}
+
+// Job result: a catalog::RemoteSnapshot with every file of the catalog
+#[tauri::command]
+fn attach_catalog(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    name: String,
+) -> Result<u64, AppError> {
+    let remote = remote_catalog(&name)?;
+    Ok(jobs::spawn_job(
+        app,
+        Some(window.label()),
+        "catalog",
+        move |ctx| job_value(catalog::fetch(ctx, &remote)?),
+    ))
+}
+
+// Base64 PNG, read from the publisher when the file is shown
+#[tauri::command]
+fn get_catalog_thumbnail(name: String, path: String) -> Result<Option<String>, AppError> {
+    catalog::thumbnail(&remote_catalog(&name)?, &path)
+}
+
+// Puts a trashed file back where it was deleted from; returns its new path id
+#[tauri::command]
+fn restore_from_trash(path: String) -> Result<String, AppError> {
+    let from = paths::from_id(&path);
+    let restored = trash::restore(&from)?;
+    tracing::info!(path = %restored.display(), "restored from trash");
+    let to = paths::to_id(&restored);
+    // Tags, ratings and the rest come back with the file
+    file_ids::moved(&paths::to_id(&from), &to)?;
+    mirror_user_data(std::slice::from_ref(&to));
+    Ok(to)
+}
+
+// -----------------------------
+// Backups
+// -----------------------------
+#[derive(Serialize, Clone)]
+pub struct BackupSide {
+    pub path: String,
+    pub path_id: String,
+    pub size_bytes: u64,
+    pub modified: Option<String>,
+    pub version: Option<String>, // Blender version that saved it
+}
+
+// The two files a restore exchanges, for confirming before and reporting
+// after
+#[derive(Serialize, Clone)]
+pub struct BackupSwap {
+    pub current: BackupSide,
+    pub backup: BackupSide,
+}
+
+fn backup_side(path: &Path) -> Result<BackupSide, AppError> {
+    let meta = std::fs::metadata(path).map_err(|e| AppError::io(&e, path))?;
+    Ok(BackupSide {
+        path: path.to_string_lossy().to_string(),
+        path_id: paths::to_id(path),
+        size_bytes: meta.len(),
+        modified: meta
+            .modified()
+            .ok()
+            .map(|t| DateTime::<Local>::from(t).to_rfc3339()),
+        version: read_blend_header(path).ok().and_then(|i| i.version),
+    })
+}
+
+// `path` and its newest backup (`.blend1`)
+fn backup_swap(path: &Path) -> Result<BackupSwap, AppError> {
+    if formats::backup_number(path).is_some() {
+        return Err(AppError::new(
+            ErrorKind::InvalidInput,
+            "Pick the file, not one of its backups",
+        )
+        .with_code("backup_picked")
+        .with_path(path));
+    }
+    let backup = formats::backups_of(path)
+        .into_iter()
+        .next()
+        .ok_or_else(|| {
+            AppError::new(ErrorKind::NotFound, "No backup next to the file")
+                .with_code("backup_missing")
+                .with_path(path)
+        })?;
+    Ok(BackupSwap {
+        current: backup_side(path)?,
+        backup: backup_side(&backup)?,
+    })
+}
+
+// What `restore_backup` would exchange, for the confirmation
+#[tauri::command]
+fn get_backup_restore(path: String) -> Result<BackupSwap, AppError> {
+    backup_swap(&paths::from_id(&path))
+}
+
+// Swaps the file with its newest backup, so the current state becomes that
+// backup and can be restored the same way; returns the files as they are now
+#[tauri::command]
+fn restore_backup(path: String) -> Result<BackupSwap, AppError> {
+    let file = paths::from_id(&path);
+    let before = backup_swap(&file)?;
+    let backup = paths::from_id(&before.backup.path_id);
+    fileops::swap_files(&file, &backup)?;
+    tracing::info!(path = %file.display(), backup = %backup.display(), "restored backup");
+    backup_swap(&file)
+}
+
+// -----------------------------
+// Read-only protection
+// -----------------------------
+// Locks finished files against re-saving (Blender refuses to overwrite a
+// read-only file) or unlocks them; scan results are updated in place
+#[tauri::command]
+fn set_read_only(paths: Vec<String>, read_only: bool) -> Vec<bulk::BulkItem> {
+    let items: Vec<bulk::BulkItem> = paths
+        .into_iter()
+        .map(|path| {
+            let file = paths::from_id(&path_key(&path));
+            let res = fileops::set_read_only(&file, read_only);
+            bulk::BulkItem {
+                path,
+                ok: res.is_ok(),
+                error: res.err(),
+            }
+        })
+        .collect();
+    let changed: Vec<String> = items
+        .iter()
+        .filter(|i| i.ok)
+        .map(|i| path_key(&i.path))
+        .collect();
+    tracing::info!(files = changed.len(), read_only, "changed read-only flag");
+    let states: Vec<Arc<ScanState>> = scans_map().lock().unwrap().values().cloned().collect();
+    for state in states {
+        let mut guard = state.result.lock().unwrap();
+        let Some(result) = guard.as_mut() else {
+            continue;
+        };
+        for f in result
+            .files
+            .iter_mut()
+            .filter(|f| changed.contains(&f.path_id))
+        {
+            f.read_only = read_only;
+        }
+        for id in &changed {
+            if let Some(meta) = result.tree.find_file_mut(id).and_then(|n| n.meta.as_mut()) {
+                meta.read_only = read_only;
+            }
+        }
+    }
+    items
+}
+
+// -----------------------------
+// Tags
+// -----------------------------
+#[tauri::command]
+fn list_tags() -> Result<Vec<tags::Tag>, AppError> {
+    tags::list()
+}
+
+#[tauri::command]
+fn create_tag(name: String, color: Option<String>) -> Result<tags::Tag, AppError> {
+    tags::create(&name, color)
+}
+
+#[tauri::command]
+fn rename_tag(tag_id: i64, name: String) -> Result<tags::Tag, AppError> {
+    let tag = tags::rename(tag_id, &name)?;
+    mirror_user_data(&tags::files(tag_id)?);
+    Ok(tag)
+}
+
+#[tauri::command]
+fn set_tag_color(tag_id: i64, color: Option<String>) -> Result<tags::Tag, AppError> {
+    tags::set_color(tag_id, color)
+}
+
+#[tauri::command]
+fn delete_tag(tag_id: i64) -> Result<(), AppError> {
+    let tagged = tags::files(tag_id)?;
+    tags::delete(tag_id)?;
+    mirror_user_data(&tagged);
+    Ok(())
+}
+
+// Creates the tag on first use
+#[tauri::command]
+fn tag_files(paths: Vec<String>, name: String) -> Result<tags::Tag, AppError> {
+    let paths = path_keys(&paths);
+    let tag = tags::assign(&paths, &name)?;
+    mirror_user_data(&paths);
+    usage::record(&paths, usage::Event::Tag);
+    Ok(tag)
+}
+
+#[tauri::command]
+fn untag_files(paths: Vec<String>, tag_id: i64) -> Result<(), AppError> {
+    let paths = path_keys(&paths);
+    tags::unassign(&paths, tag_id)?;
+    mirror_user_data(&paths);
+    Ok(())
+}
+
+// -----------------------------
+// Favorites
+// -----------------------------
+// Returns whether the file is a favorite afterwards
+#[tauri::command]
+fn toggle_favorite(path: String) -> Result<bool, AppError> {
+    favorites::toggle(&path_key(&path))
+}
+
+#[tauri::command]
+fn get_favorites() -> Result<Vec<favorites::Favorite>, AppError> {
+    favorites::list()
+}
+
+// -----------------------------
+// Ratings
+// -----------------------------
+// `rating` 1–5; `None` or 0 clears it
+#[tauri::command]
+fn set_rating(path: String, rating: Option<u8>) -> Result<Option<u8>, AppError> {
+    let path = path_key(&path);
+    let rating = ratings::set(&path, rating)?;
+    mirror_user_data(&[path]);
+    Ok(rating)
+}
+
+#[tauri::command]
+fn get_rating(path: String) -> Result<Option<u8>, AppError> {
+    ratings::get(&path_key(&path))
+}
+
+// -----------------------------
+// Notes
+// -----------------------------
+#[tauri::command]
+fn set_note(path: String, note: Option<String>) -> Result<Option<String>, AppError> {
+    let path = path_key(&path);
+    let note = notes::set(&path, note.as_deref())?;
+    sidecar::sync(&[path]);
+    Ok(note)
+}
+
+#[tauri::command]
+fn get_note(path: String) -> Result<Option<String>, AppError> {
+    notes::get(&path_key(&path))
+}
+
+// -----------------------------
+// Sidecar files
+// -----------------------------
+#[tauri::command]
+fn get_sidecar_mode() -> bool {
+    sidecar::enabled()
+}
+
+// Turning it on writes sidecars, in the background, for everything that
+// already has user data
+#[tauri::command]
+fn set_sidecar_mode(enabled: bool) -> Result<bool, AppError> {
+    settings::update(|s| s.sidecars = enabled)?;
+    if enabled {
+        thread::spawn(|| match sidecar::export_all() {
+            Ok(n) => tracing::info!(files = n, "sidecars written"),
+            Err(e) => tracing::warn!("writing sidecars failed: {}", e),
+        });
+    }
+    Ok(enabled)
+}
+
+// -----------------------------
+// Extended attributes
+// -----------------------------
+#[tauri::command]
+fn get_xattr_mode() -> bool {
+    xattrs::enabled()
+}
+
+#[tauri::command]
+fn set_xattr_mode(enabled: bool) -> Result<bool, AppError> {
+    if enabled && !xattrs::supported() {
+        return Err(AppError::new(
+            ErrorKind::InvalidInput,
+            "Extended attributes are not supported on this platform",
+        ));
+    }
+    settings::update(|s| s.xattrs = enabled)?;
+    if enabled {
+        thread::spawn(|| match xattrs::export_all() {
+            Ok(n) => tracing::info!(files = n, "extended attributes written"),
+            Err(e) => tracing::warn!("writing extended attributes failed: {}", e),
+        });
+    }
+    Ok(enabled)
+}
+
+// -----------------------------
+// Collections
+// -----------------------------
+#[tauri::command]
+fn list_collections() -> Result<Vec<collections::Collection>, AppError> {
+    collections::list()
+}
+
+// With a `query` it is a smart collection (see query.rs)
+#[tauri::command]
+fn create_collection(
+    name: String,
+    query: Option<String>,
+) -> Result<collections::Collection, AppError> {
+    collections::create(&name, query.as_deref())
+}
+
+#[tauri::command]
+fn set_collection_query(
+    collection_id: i64,
+    query: String,
+) -> Result<collections::Collection, AppError> {
+    collections::set_query(collection_id, &query)
+}
+
+#[tauri::command]
+fn rename_collection(
+    collection_id: i64,
+    name: String,
+) -> Result<collections::Collection, AppError> {
+    collections::rename(collection_id, &name)
+}
+
+#[tauri::command]
+fn delete_collection(collection_id: i64) -> Result<(), AppError> {
+    collections::delete(collection_id)
+}
+
+#[tauri::command]
+fn add_to_collection(
+    collection_id: i64,
+    paths: Vec<String>,
+) -> Result<collections::Collection, AppError> {
+    collections::add(collection_id, &path_keys(&paths))
+}
+
+#[tauri::command]
+fn remove_from_collection(
+    collection_id: i64,
+    paths: Vec<String>,
+) -> Result<collections::Collection, AppError> {
+    collections::remove(collection_id, &path_keys(&paths))
+}
+
+#[tauri::command]
+fn get_collection_files(collection_id: i64) -> Result<Vec<collections::CollectionEntry>, AppError> {
+    collections::files(collection_id)
+}
+
+// -----------------------------
+// Color labels
+// -----------------------------
+// Files or folders; `None` clears
+#[tauri::command]
+fn set_label(paths: Vec<String>, label: Option<String>) -> Result<(), AppError> {
+    labels::set(&path_keys(&paths), label.as_deref())
+}
+
+#[tauri::command]
+fn get_label_palette() -> Vec<labels::LabelColor> {
+    settings::get().label_palette
+}
+
+// `None` restores the default palette
+#[tauri::command]
+fn set_label_palette(
+    palette: Option<Vec<labels::LabelColor>>,
+) -> Result<Vec<labels::LabelColor>, AppError> {
+    let updated = settings::update(|s| {
+        s.label_palette = palette.unwrap_or_else(labels::default_palette);
+    })?;
+    Ok(updated.label_palette)
+}
+
+// -----------------------------
+// Bulk edits
+// -----------------------------
+// One round trip for a whole selection; results are per path
+#[tauri::command]
+fn bulk_edit(paths: Vec<String>, edit: bulk::BulkEdit) -> Result<Vec<bulk::BulkItem>, AppError> {
+    let results = bulk::apply(&path_keys(&paths), &edit)?;
+    let changed: Vec<String> = results
+        .iter()
+        .filter(|r| r.ok)
+        .map(|r| r.path.clone())
+        .collect();
+    mirror_user_data(&changed);
+    if !edit.add_tags.is_empty() {
+        usage::record(&changed, usage::Event::Tag);
+    }
+    Ok(results)
+}
+
+// -----------------------------
+// File details
+// -----------------------------
+#[derive(Serialize)]
+pub struct FileMetaItem {
+    pub path: String, // as passed in
+    pub path_id: String,
+    pub meta: Option<FileMeta>,
+    pub error: Option<AppError>,
+}
+
+// File system metadata read now; blend details come from the latest scan
+// while size and mtime are unchanged (unless `reparse`), otherwise the file
+// is parsed again (never for cloud placeholders, which reading would download)
+fn fresh_meta(
+    path: &Path,
+    scanned: Option<&FileMeta>,
+    reparse: bool,
+) -> Result<FileMeta, AppError> {
+    // Archive entries are only re-read with the whole archive, by a rescan
+    if archive::split(path).is_some() {
+        return scanned.cloned().ok_or_else(|| {
+            AppError::new(ErrorKind::NotFound, "Not in the scan results").with_path(path)
+        });
+    }
+    let fs_meta = std::fs::metadata(path).map_err(|e| AppError::io(&e, path))?;
+    let stamp = |t: std::io::Result<std::time::SystemTime>| {
+        t.ok().map(|t| DateTime::<Local>::from(t).to_rfc3339())
+    };
+    let modified = stamp(fs_meta.modified());
+    let cloud_only = cloud::is_placeholder(&fs_meta);
+    let format = scanned
+        .map(|m| m.format.clone())
+        .or_else(|| formats::of_path(path, true).map(str::to_string))
+        .unwrap_or_else(|| formats::BLEND.to_string());
+    let unchanged =
+        scanned.filter(|m| !reparse && m.size_bytes == fs_meta.len() && m.modified == modified);
+    let blender = match unchanged {
+        Some(m) => m.blender.clone(),
+        None if format == formats::BLEND && !cloud_only => {
+            let mut info = read_blend_header(path)?;
+            let _ = extract_block_info(path, &mut info, true);
+            info
+        }
+        None => scanned.map(|m| m.blender.clone()).unwrap_or_default(),
+    };
+    let created = stamp(fs_meta.created());
+    Ok(FileMeta {
+        size_bytes: fs_meta.len(),
+        created_ms: epoch_ms(created.as_deref()),
+        modified_ms: epoch_ms(modified.as_deref()),
+        created,
+        modified,
+        folder: path
+            .parent()
+            .map(|p| p.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        cloud_only,
+        read_only: fs_meta.permissions().readonly(),
+        trash: scanned.and_then(|m| m.trash.clone()),
+        format,
+        blender,
+        aliases: scanned.map(|m| m.aliases.clone()).unwrap_or_default(),
+        archive: None,
+    })
+}
+
+// Every validation for one blend at once (see health.rs)
+#[tauri::command]
+fn check_file(path: String) -> Result<health::HealthReport, AppError> {
+    health::check_file(&paths::from_id(&path))
+}
+
+// What differs between two blends (see compare.rs)
+#[tauri::command]
+fn compare_files(path_a: String, path_b: String) -> Result<compare::Comparison, AppError> {
+    compare::compare(&paths::from_id(&path_a), &paths::from_id(&path_b))
+}
+
+// Fresh metadata for a whole selection in one call; results are per path
+#[tauri::command]
+fn get_files_meta(paths: Vec<String>) -> Vec<FileMetaItem> {
+    let keys = path_keys(&paths);
+    let wanted: std::collections::HashSet<&str> = keys.iter().map(String::as_str).collect();
+    let mut scanned: HashMap<String, FileMeta> = HashMap::new();
+    for_each_latest_result(|result| {
+        let mut stack = vec![&result.tree];
+        while let Some(node) = stack.pop() {
+            if let Some(meta) = &node.meta {
+                if wanted.contains(node.path_id.as_str()) {
+                    scanned.insert(node.path_id.clone(), meta.clone());
                 }
             }
+            stack.extend(node.children.iter().flatten());
         }
+    });
+    paths
+        .into_iter()
+        .zip(keys)
+        .map(|(path, path_id)| {
+            let res = fresh_meta(&paths::from_id(&path_id), scanned.get(&path_id), false);
+            let (meta, error) = match res {
+                Ok(m) => (Some(m), None),
+                Err(e) => (None, Some(e)),
+            };
+            FileMetaItem {
+                path,
+                path_id,
+                meta,
+                error,
+            }
+        })
+        .collect()
+}
 
-        // Build final tree
-        let tree = build_tree_nodes(&builder, &root_name, &root);
-        let result = ScanResult { tree, files };
+fn found_blend(path: &Path, meta: FileMeta) -> scan::FoundBlend {
+    scan::FoundBlend {
+        path: path.to_path_buf(),
+        size_bytes: meta.size_bytes,
+        created: meta.created,
+        modified: meta.modified,
+        cloud_only: meta.cloud_only,
+        read_only: meta.read_only,
+        trash: meta.trash,
+        format: meta.format,
+        blend: meta.blender,
+        aliases: meta.aliases,
+    }
+}
 
-        if let Ok(mut r) = state.result.lock() {
-            *r = Some(result);
+// Re-reads one file (e.g. after saving it in Blender) and patches it into
+// every finished result listing it; returns the updated entry
+#[tauri::command]
+fn refresh_file(path: String) -> Result<FlatFile, AppError> {
+    let path_id = path_key(&path);
+    let file = paths::from_id(&path_id);
+    let states: Vec<Arc<ScanState>> = scans_map().lock().unwrap().values().cloned().collect();
+    let mut meta: Option<FileMeta> = None;
+    let mut refreshed = None;
+    for state in states {
+        let mut guard = state.result.lock().unwrap();
+        let Some(result) = guard.as_mut() else {
+            continue;
+        };
+        if !result.files.iter().any(|f| f.path_id == path_id) {
+            continue;
         }
-        if let Ok(mut st) = state.status.lock() {
-            *st = "done".to_string();
+        let meta = match &meta {
+            Some(m) => m.clone(),
+            None => {
+                let scanned = result.tree.find_file(&path_id).and_then(|n| n.meta.clone());
+                let m = fresh_meta(&file, scanned.as_ref(), true)?;
+                meta.insert(m).clone()
+            }
+        };
+        scan::merge_found(result, &file, vec![found_blend(&file, meta)]);
+        refreshed = result.files.iter().find(|f| f.path_id == path_id).cloned();
+    }
+    let mut flat = refreshed.ok_or_else(|| {
+        AppError::new(ErrorKind::NotFound, "File is not in any scan result").with_path(&file)
+    })?;
+    tracing::info!(path = %file.display(), "refreshed file");
+    annotate_user_data(std::slice::from_mut(&mut flat));
+    Ok(flat)
+}
+
+// Preview of an asset listed in `BlendInfo::assets`; None when it has none
+#[tauri::command]
+fn get_asset_preview(
+    path: String,
+    code: String,
+    name: String,
+) -> Result<Option<contents::AssetPreview>, AppError> {
+    read_asset_preview(&paths::from_id(&path), &code, &name)
+}
+
+// -----------------------------
+// Watched roots
+// -----------------------------
+// Keeps the results of the scan's root current as files change on disk
+// (see watch.rs), until `unwatch_scan` or the root's last scan is dropped
+#[tauri::command]
+fn watch_scan(app: tauri::AppHandle, scan_id: u64) -> Result<(), AppError> {
+    let state = get_scan(scan_id)?;
+    if !state.has_result() {
+        return Err(scan_not_finished(scan_id));
+    }
+    for root in &state.roots {
+        watch::start(&app, &paths::from_id(root))?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn unwatch_scan(scan_id: u64) -> Result<(), AppError> {
+    for root in &get_scan(scan_id)?.roots {
+        watch::stop(root);
+    }
+    Ok(())
+}
+
+// Settled paths under a watched root, applied to each of its finished
+// results. New blends join them; other formats and backups are only
+// updated when listed, since the scan may have left them out.
+fn apply_watched(app: &tauri::AppHandle, root_id: &str, changed: Vec<PathBuf>) {
+    let states: Vec<(u64, Arc<ScanState>)> = scans_map()
+        .lock()
+        .unwrap()
+        .iter()
+        .filter(|(_, st)| st.roots.iter().any(|r| r == root_id) && st.has_result())
+        .map(|(id, st)| (*id, st.clone()))
+        .collect();
+
+    // Read once for all results
+    let mut read: HashMap<PathBuf, FileMeta> = HashMap::new();
+    let mut gone: Vec<PathBuf> = Vec::new();
+    for path in changed {
+        if !path.exists() {
+            gone.push(path);
+            continue;
         }
-        state.done.store(true, Ordering::Relaxed);
-    });
+        let path_id = paths::to_id(&path);
+        let scanned = states.iter().find_map(|(_, st)| {
+            let guard = st.result.lock().unwrap();
+            guard.as_ref()?.tree.find_file(&path_id)?.meta.clone()
+        });
+        if scanned.is_none() && formats::of_path(&path, false).is_none() {
+            continue;
+        }
+        match fresh_meta(&path, scanned.as_ref(), true) {
+            Ok(meta) => {
+                read.insert(path, meta);
+            }
+            Err(e) => {
+                tracing::debug!(path = %path.display(), "watched file not read: {}", e.message)
+            }
+        }
+    }
 
-    Ok(scan_id)
+    for (scan_id, state) in states {
+        let mut changes = Vec::new();
+        {
+            let mut guard = state.result.lock().unwrap();
+            let Some(result) = guard.as_mut() else {
+                continue;
+            };
+            let listed: HashSet<String> = result.files.iter().map(|f| f.path_id.clone()).collect();
+            let fresh: Vec<(&PathBuf, String)> = read
+                .keys()
+                .map(|p| (p, paths::to_id(p)))
+                .filter(|(p, id)| listed.contains(id) || formats::of_path(p, false).is_some())
+                .filter(|(p, _)| !state.filtered_out(p))
+                .collect();
+            let removed: HashSet<PathBuf> = result
+                .files
+                .iter()
+                .map(|f| paths::from_id(&f.path_id))
+                .filter(|p| gone.iter().any(|g| p.starts_with(g)))
+                .collect();
+            if fresh.is_empty() && removed.is_empty() {
+                continue;
+            }
+            let found = fresh
+                .iter()
+                .map(|(p, _)| found_blend(p, read[*p].clone()))
+                .collect();
+            scan::update_found(result, found, &removed);
+            for (_, id) in fresh {
+                let event = if listed.contains(&id) {
+                    EVENT_FILE_MODIFIED
+                } else {
+                    EVENT_FILE_ADDED
+                };
+                let file = result.files.iter().find(|f| f.path_id == id).cloned();
+                changes.push((
+                    event,
+                    WatchChange {
+                        scan_id,
+                        path_id: id,
+                        file,
+                    },
+                ));
+            }
+            changes.extend(removed.iter().map(|p| {
+                let change = WatchChange {
+                    scan_id,
+                    path_id: paths::to_id(p),
+                    file: None,
+                };
+                (EVENT_FILE_REMOVED, change)
+            }));
+        }
+        tracing::info!(scan_id, changes = changes.len(), "watched changes applied");
+        for (event, mut change) in changes {
+            if let Some(file) = change.file.as_mut() {
+                annotate_user_data(std::slice::from_mut(file));
+            }
+            ws::broadcast(event, &change);
+            let _ = emit_to_owner(app, state.owner.as_deref(), event, change);
+        }
+    }
+    quick_find::invalidate();
 }
 
+// -----------------------------
+// Quick open
+// -----------------------------
+// Matches file names from every folder scanned so far, not just the open one
 #[tauri::command]
-fn poll_scan(scan_id: u64) -> Result<ScanPoll, String> {
-    let state = {
-        let map = scans_map().lock().unwrap();
-        map.get(&scan_id).cloned()
-    };
+fn quick_find(query: String, limit: usize) -> Result<Vec<quick_find::QuickHit>, AppError> {
+    quick_find::search(&query, limit)
+}
 
-    let Some(state) = state else {
-        return Err("Scan id not found".into());
-    };
+// System-wide shortcut that raises the window on quick find; None = off
+#[tauri::command]
+fn get_quick_find_shortcut() -> Option<String> {
+    settings::get().quick_find_shortcut
+}
 
-    let status = state.status.lock().unwrap().clone();
-    let current_path = state.current_path.lock().unwrap().clone();
-    let error = state.error.lock().unwrap().clone();
+#[tauri::command]
+fn set_quick_find_shortcut(
+    app: tauri::AppHandle,
+    shortcut: Option<String>,
+) -> Result<Option<String>, AppError> {
+    hotkey::set(&app, shortcut)?;
+    Ok(settings::get().quick_find_shortcut)
+}
 
-    let result = if status == "done" {
-        state.result.lock().unwrap().clone()
-    } else {
-        None
-    };
+// -----------------------------
+// Scan exclusions
+// -----------------------------
+#[tauri::command]
+fn get_exclusions() -> Vec<String> {
+    settings::get().exclusions
+}
+
+// `None` restores the built-in list
+#[tauri::command]
+fn set_exclusions(exclusions: Option<Vec<String>>) -> Result<Vec<String>, AppError> {
+    let updated = settings::update(|s| {
+        s.exclusions = exclusions.unwrap_or_else(exclude::defaults);
+    })?;
+    Ok(updated.exclusions)
+}
+
+// -----------------------------
+// Batch rename
+// -----------------------------
+// Dry run: the names `start_rename_job` would give, with what blocks any
+#[tauri::command]
+fn preview_rename(
+    paths: Vec<String>,
+    pattern: rename::RenamePattern,
+) -> Result<Vec<rename::RenameItem>, AppError> {
+    let files: Vec<PathBuf> = paths.iter().map(|p| paths::from_id(p)).collect();
+    rename::plan(&files, &pattern)
+}
+
+// Job result: a rename::RenameReport. Finished results list the files
+// under their new names.
+#[tauri::command]
+fn start_rename_job(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    paths: Vec<String>,
+    pattern: rename::RenamePattern,
+) -> Result<u64, AppError> {
+    let files: Vec<PathBuf> = paths.iter().map(|p| paths::from_id(p)).collect();
+    Ok(jobs::spawn_job(
+        app,
+        Some(window.label()),
+        "rename",
+        move |ctx| {
+            let report = rename::execute(ctx, &files, &pattern)?;
+            let renamed: HashMap<PathBuf, PathBuf> = report
+                .renamed
+                .iter()
+                .map(|r| (paths::from_id(&r.from_id), paths::from_id(&r.to_id)))
+                .collect();
+            if !renamed.is_empty() {
+                let states: Vec<Arc<ScanState>> =
+                    scans_map().lock().unwrap().values().cloned().collect();
+                for state in states {
+                    if let Some(result) = state.result.lock().unwrap().as_mut() {
+                        scan::rename_found(result, &renamed);
+                    }
+                }
+                let ids: Vec<String> = report.renamed.iter().map(|r| r.to_id.clone()).collect();
+                mirror_user_data(&ids);
+                quick_find::invalidate();
+            }
+            job_value(report)
+        },
+    ))
+}
+
+// -----------------------------
+// Auto-tagging rules
+// -----------------------------
+#[tauri::command]
+fn get_tag_rules() -> Vec<rules::Rule> {
+    settings::get().tag_rules
+}
+
+// Saved rules apply from the next scan on; see `apply_tag_rules`
+#[tauri::command]
+fn set_tag_rules(tag_rules: Vec<rules::Rule>) -> Result<Vec<rules::Rule>, AppError> {
+    rules::check(&tag_rules)?;
+    Ok(settings::update(|s| s.tag_rules = tag_rules)?.tag_rules)
+}
+
+// Applies the saved rules to a finished result now
+#[tauri::command]
+fn apply_tag_rules(scan_id: u64) -> Result<rules::Applied, AppError> {
+    let applied = rules::apply(&scan_result(scan_id)?.files)?;
+    mirror_user_data(&applied.path_ids);
+    Ok(applied)
+}
+
+// -----------------------------
+// Projects
+// -----------------------------
+#[tauri::command]
+fn get_projects(scan_id: u64) -> Result<Vec<projects::ProjectSummary>, AppError> {
+    Ok(projects::summaries(&scan_result(scan_id)?))
+}
+
+#[tauri::command]
+fn get_project_roots() -> Vec<String> {
+    settings::get().project_roots
+}
+
+// Folders that group their files as a project even without a marker
+#[tauri::command]
+fn set_project_roots(roots: Vec<String>) -> Result<Vec<String>, AppError> {
+    let mut ids = Vec::with_capacity(roots.len());
+    for root in roots {
+        let path = paths::from_id(&root);
+        if !path.is_dir() {
+            return Err(
+                AppError::new(ErrorKind::NotFound, "Project folder not found").with_path(&path),
+            );
+        }
+        ids.push(paths::to_id(&path));
+    }
+    ids.sort();
+    ids.dedup();
+    Ok(settings::update(|s| s.project_roots = ids)?.project_roots)
+}
+
+// -----------------------------
+// External tools
+// -----------------------------
+#[tauri::command]
+fn get_tools() -> Vec<tools::ExternalTool> {
+    settings::get().tools
+}
+
+#[tauri::command]
+fn set_tools(tools: Vec<tools::ExternalTool>) -> Result<Vec<tools::ExternalTool>, AppError> {
+    tools::check(&tools)?;
+    Ok(settings::update(|s| s.tools = tools)?.tools)
+}
+
+// Job result: a tools::ToolReport with each file's exit code and output
+#[tauri::command]
+fn run_tool(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    name: String,
+    paths: Vec<String>,
+) -> Result<u64, AppError> {
+    let tool = settings::get()
+        .tools
+        .into_iter()
+        .find(|t| t.name == name)
+        .ok_or_else(|| AppError::new(ErrorKind::NotFound, format!("No tool named \"{}\"", name)))?;
+    let files: Vec<PathBuf> = paths.iter().map(|p| paths::from_id(p)).collect();
+    Ok(jobs::spawn_job(
+        app,
+        Some(window.label()),
+        "tool",
+        move |ctx| job_value(tools::run(ctx, &tool, &files)?),
+    ))
+}
+
+// -----------------------------
+// Custom fields
+// -----------------------------
+#[tauri::command]
+fn get_extractors() -> Vec<extractors::ExtractorConfig> {
+    settings::get().extractors
+}
+
+// Saved extractors run from the next scan on; see `apply_extractors`
+#[tauri::command]
+fn set_extractors(
+    extractors: Vec<extractors::ExtractorConfig>,
+) -> Result<Vec<extractors::ExtractorConfig>, AppError> {
+    extractors::check(&extractors)?;
+    Ok(settings::update(|s| s.extractors = extractors)?.extractors)
+}
+
+// Reads the fields of a finished result again
+#[tauri::command]
+fn apply_extractors(scan_id: u64) -> Result<(), AppError> {
+    let state = get_scan(scan_id)?;
+    let mut files = scan_result(scan_id)?.files;
+    extractors::apply(&mut files);
+    let fields: HashMap<String, extractors::Fields> =
+        files.into_iter().map(|f| (f.path_id, f.fields)).collect();
+    if let Some(result) = state.result.lock().unwrap().as_mut() {
+        for f in &mut result.files {
+            f.fields = fields.get(&f.path_id).cloned().unwrap_or_default();
+        }
+    }
+    Ok(())
+}
+
+// -----------------------------
+// Scripts
+// -----------------------------
+#[tauri::command]
+fn get_scripts() -> Vec<scripts::SavedScript> {
+    settings::get().scripts
+}
+
+#[tauri::command]
+fn set_scripts(scripts: Vec<scripts::SavedScript>) -> Result<Vec<scripts::SavedScript>, AppError> {
+    scripts::check(&scripts)?;
+    Ok(settings::update(|s| s.scripts = scripts)?.scripts)
+}
+
+// Job result: a scripts::ScriptReport. Without `apply` the script's
+// actions are only counted.
+#[tauri::command]
+fn run_script(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    scan_id: u64,
+    source: String,
+    apply: bool,
+) -> Result<u64, AppError> {
+    let mut files = scan_result(scan_id)?.files;
+    annotate_user_data(&mut files);
+    Ok(jobs::spawn_job(
+        app,
+        Some(window.label()),
+        "script",
+        move |ctx| {
+            let report = scripts::run(ctx, &files, &source, apply)?;
+            mirror_user_data(&report.touched);
+            job_value(report)
+        },
+    ))
+}
+
+// -----------------------------
+// Usage statistics
+// -----------------------------
+// With a scan, lists only its files and adds the never touched ones
+#[tauri::command]
+fn get_usage_stats(scan_id: Option<u64>, limit: usize) -> Result<usage::UsageStats, AppError> {
+    let files = scan_id.map(scan_result).transpose()?.map(|r| r.files);
+    usage::stats(files.as_deref(), limit)
+}
+
+// A file picked from search results or quick find
+#[tauri::command]
+fn record_search_pick(path: String) {
+    usage::record(&[path_key(&path)], usage::Event::Search);
+}
+
+#[tauri::command]
+fn get_usage_tracking() -> bool {
+    settings::get().usage_stats
+}
+
+#[tauri::command]
+fn set_usage_tracking(enabled: bool) -> Result<bool, AppError> {
+    settings::update(|s| s.usage_stats = enabled)?;
+    Ok(enabled)
+}
+
+#[tauri::command]
+fn clear_usage_stats() -> Result<(), AppError> {
+    usage::clear()
+}
+
+// -----------------------------
+// Staleness
+// -----------------------------
+#[tauri::command]
+fn get_staleness_weights() -> staleness::Weights {
+    settings::get().staleness
+}
 
-    Ok(ScanPoll {
-        scan_id,
-        status,
-        scanned_entries: state.scanned_entries.load(Ordering::Relaxed),
-        found_blends: state.found_blends.load(Ordering::Relaxed),
-        current_path,
-        error,
-        result,
+// `None` restores the defaults; finished results are scored again
+#[tauri::command]
+fn set_staleness_weights(
+    weights: Option<staleness::Weights>,
+) -> Result<staleness::Weights, AppError> {
+    let updated = settings::update(|s| s.staleness = weights.unwrap_or_default())?;
+    let states: Vec<Arc<ScanState>> = scans_map().lock().unwrap().values().cloned().collect();
+    for state in states {
+        if let Some(result) = state.result.lock().unwrap().as_mut() {
+            staleness::apply(result);
+        }
+    }
+    Ok(updated.staleness)
+}
+
+// -----------------------------
+// Protected folders
+// -----------------------------
+// Re-indexes a folder the scan could not read with admin rights and merges
+// the files into the scan's result; `remember` records the choice.
+#[tauri::command]
+fn request_elevated_access(
+    app: tauri::AppHandle,
+    window: tauri::WebviewWindow,
+    scan_id: u64,
+    path: String,
+    remember: Option<bool>,
+) -> Result<u64, AppError> {
+    let state = get_scan(scan_id)?;
+    let dir = paths::from_id(&path);
+    // Listed as the scan would have: below the innermost of its roots, and
+    // only up to what is left of its file limit
+    let root = state
+        .roots
+        .iter()
+        .map(|r| paths::from_id(r))
+        .filter(|r| dir.starts_with(r))
+        .max_by_key(|r| r.components().count())
+        .unwrap_or_else(|| dir.clone());
+    let listed = state
+        .result
+        .lock()
+        .unwrap()
+        .as_ref()
+        .map_or(0, |r| r.files.len());
+    let left = state
+        .options
+        .max_files
+        .map(|max| max.saturating_sub(listed as u64));
+    let filter = state.options.helper_filter(&root, left);
+    Ok(jobs::spawn_job(
+        app,
+        Some(window.label()),
+        "elevated_scan",
+        move |ctx| {
+            ctx.set_current(&dir);
+            let found = elevate::scan_elevated(&dir, &filter)?;
+            // Remembered only once the helper ran
+            if remember.unwrap_or(false) {
+                settings::update(|s| {
+                    s.elevation
+                        .insert(path.clone(), settings::ElevationDecision::Allow);
+                })?;
+            }
+            let count = found.len();
+            if let Some(result) = state.result.lock().unwrap().as_mut() {
+                scan::merge_found(result, &dir, found);
+            }
+            job_value(count)
+        },
+    ))
+}
+
+// `None` forgets the decision so the folder is offered again
+#[tauri::command]
+fn set_elevation_decision(
+    path: String,
+    decision: Option<settings::ElevationDecision>,
+) -> Result<(), AppError> {
+    settings::update(|s| match decision {
+        Some(d) => {
+            s.elevation.insert(path, d);
+        }
+        None => {
+            s.elevation.remove(&path);
+        }
+    })
+    .map(|_| ())
+}
+
+// -----------------------------
+// Updates
+// -----------------------------
+// Job result: the newer release, or null when up to date; a newer one is
+// also announced with `update://available`
+#[tauri::command]
+fn check_for_updates(app: tauri::AppHandle, window: tauri::WebviewWindow) -> u64 {
+    let handle = app.clone();
+    jobs::spawn_job(app, Some(window.label()), "update_check", move |_| {
+        job_value(updater::check(&handle)?)
+    })
+}
+
+// Restarts the app when the install succeeds
+#[tauri::command]
+fn install_update(app: tauri::AppHandle, window: tauri::WebviewWindow) -> u64 {
+    let handle = app.clone();
+    jobs::spawn_job(app, Some(window.label()), "update_install", move |ctx| {
+        updater::install(&handle, ctx)?;
+        job_value(())
     })
 }
 
 #[tauri::command]
-fn open_file(app: tauri::AppHandle, path: String) -> Result<(), String> {
-    use tauri_plugin_opener::OpenerExt;
-    app.opener()
-        .open_path(path, None::<&str>)
-        .map_err(|e| e.to_string())
+fn get_update_checks() -> bool {
+    settings::get().check_updates
+}
+
+#[tauri::command]
+fn set_update_checks(enabled: bool) -> Result<bool, AppError> {
+    settings::update(|s| s.check_updates = enabled)?;
+    Ok(enabled)
+}
+
+// -----------------------------
+// Logs
+// -----------------------------
+#[tauri::command]
+fn get_recent_logs(level: Option<String>, limit: Option<usize>) -> Vec<logging::LogEntry> {
+    logging::recent(level.as_deref(), limit)
+}
+
+#[tauri::command]
+fn log_folder() -> Option<String> {
+    logging::log_dir().map(|p| p.to_string_lossy().to_string())
+}
+
+#[tauri::command]
+fn get_close_to_tray() -> bool {
+    tray::close_to_tray()
+}
+
+#[tauri::command]
+fn set_close_to_tray(enabled: bool) -> Result<bool, AppError> {
+    tray::set_close_to_tray(enabled)?;
+    Ok(enabled)
+}
+
+#[tauri::command]
+fn open_file(app: tauri::AppHandle, path: String) -> Result<(), AppError> {
+    // Path-based variant: doesn't require the path to be valid UTF-8
+    let file = paths::from_id(&path);
+    if archive::split(&file).is_some() {
+        return extract_and_open(app, path).map(|_| ());
+    }
+    tauri_plugin_opener::open_path(&file, None::<&str>)
+        .map_err(|e| AppError::new(ErrorKind::Opener, e.to_string()).with_path(&file))?;
+    tray::note_recent_file(&app, &path);
+    usage::record(&[path_key(&path)], usage::Event::Open);
+    Ok(())
 }
 
+// Archive entries are extracted to the app cache first (see archive.rs);
+// returns the extracted file's path id
 #[tauri::command]
-fn reveal_file(app: tauri::AppHandle, path: String) -> Result<(), String> {
-    use tauri_plugin_opener::OpenerExt;
+fn extract_and_open(app: tauri::AppHandle, path: String) -> Result<String, AppError> {
+    let file = archive::extract(&paths::from_id(&path))?;
+    tauri_plugin_opener::open_path(&file, None::<&str>)
+        .map_err(|e| AppError::new(ErrorKind::Opener, e.to_string()).with_path(&file))?;
+    let extracted = paths::to_id(&file);
+    tray::note_recent_file(&app, &extracted);
+    // Counted for the entry, not the temp copy
+    usage::record(&[path_key(&path)], usage::Event::Open);
+    Ok(extracted)
+}
 
-    // Cross-platform: open the parent folder
-    let p = PathBuf::from(&path);
+#[tauri::command]
+fn reveal_file(path: String) -> Result<(), AppError> {
+    // Cross-platform: open the parent folder (of the archive, for entries)
+    let p = paths::from_id(&path);
+    let p = archive::split(&p).map_or(p, |(archive, _)| archive);
     let folder = p.parent().map(|x| x.to_path_buf()).unwrap_or(p);
 
-    app.opener()
-        .open_path(folder.to_string_lossy().to_string(), None::<&str>)
-        .map_err(|e| e.to_string())
+    tauri_plugin_opener::open_path(&folder, None::<&str>)
+        .map_err(|e| AppError::new(ErrorKind::Opener, e.to_string()).with_path(&folder))
 }
 
 // -----------------------------
@@ -553,16 +3162,435 @@ fn reveal_file(app: tauri::AppHandle, path: String) -> Result<(), String> {
 // -----------------------------
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
+    // Elevated helper instances index one folder and exit before any UI
+    if let Some(code) = elevate::helper_main() {
+        std::process::exit(code);
+    }
+
     tauri::Builder::default()
+        // Must be registered first: a second launch forwards its arguments
+        // here and exits instead of starting its own scan state
+        .plugin(tauri_plugin_single_instance::init(|app, argv, cwd| {
+            let args = argv.get(1..).unwrap_or_default();
+            let requests = deeplink::requests_from_args(args, Path::new(&cwd));
+            if requests.is_empty() {
+                deeplink::focus_main(app);
+            }
+            for request in requests {
+                deeplink::dispatch(app, request);
+            }
+        }))
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_opener::init())
+        .plugin(tauri_plugin_deep_link::init())
+        .plugin(hotkey::plugin())
+        .plugin(tauri_plugin_updater::Builder::new().build())
+        .setup(|app| {
+            use tauri::Manager;
+            use tauri_plugin_deep_link::DeepLinkExt;
+
+            logging::init(app.path().app_log_dir().ok());
+            settings::init(app.path().app_config_dir().ok());
+            db::init(app.path().app_data_dir().ok());
+            archive::init(app.path().app_cache_dir().ok());
+            tracing::info!(version = env!("CARGO_PKG_VERSION"), "starting");
+
+            // Linux and Windows only learn about the scheme at runtime
+            #[cfg(any(windows, target_os = "linux"))]
+            app.deep_link().register_all()?;
+
+            // `blender-file-finder /path/to/project` or `… shot.blend`
+            let args: Vec<String> = std::env::args().skip(1).collect();
+            let cwd = std::env::current_dir().unwrap_or_default();
+            for request in deeplink::requests_from_args(&args, &cwd) {
+                deeplink::dispatch(app.handle(), request);
+            }
+
+            if let Some(urls) = app.deep_link().get_current()? {
+                deeplink::handle_urls(app.handle(), urls);
+            }
+            // Also gets the file:// URLs of macOS "Open with" and drops on
+            // the dock icon (the plugin forwards every RunEvent::Opened)
+            let handle = app.handle().clone();
+            app.deep_link().on_open_url(move |event| {
+                deeplink::handle_urls(&handle, event.urls());
+            });
+
+            tray::init(app)?;
+            hotkey::init(app.handle());
+            updater::check_in_background(app.handle());
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            // Keep scanning/watching in the tray instead of exiting
+            if let tauri::WindowEvent::CloseRequested { api, .. } = event {
+                if window.label() == MAIN_WINDOW && tray::close_to_tray() {
+                    let _ = window.hide();
+                    api.prevent_close();
+                }
+            }
+        })
         .invoke_handler(tauri::generate_handler![
             pick_folder,
             start_scan,
+            start_multi_scan,
+            rescan_indexed,
+            benchmark_scan,
             poll_scan,
+            pause_scan,
+            resume_scan,
+            get_tree_children,
+            resort_tree,
+            list_scans,
+            drop_scan,
+            watch_scan,
+            unwatch_scan,
+            enrich_files,
+            diff_scans,
+            export_scan_diff,
+            export_template,
+            get_export_utc,
+            set_export_utc,
+            start_copy_job,
+            start_resumable_copy_job,
+            start_consolidate_job,
+            start_zip_job,
+            start_hash_job,
+            start_triage_job,
+            verify_integrity,
+            poll_job,
+            cancel_job,
+            open_window,
+            take_pending_requests,
+            get_close_to_tray,
+            set_close_to_tray,
+            start_http_api,
+            stop_http_api,
+            http_api_status,
+            publish_catalog,
+            get_catalogs,
+            set_catalogs,
+            attach_catalog,
+            get_catalog_thumbnail,
+            restore_from_trash,
+            get_backup_restore,
+            restore_backup,
+            set_read_only,
+            get_exclusions,
+            toggle_favorite,
+            get_favorites,
+            set_note,
+            get_note,
+            get_sidecar_mode,
+            set_sidecar_mode,
+            get_xattr_mode,
+            set_xattr_mode,
+            list_collections,
+            create_collection,
+            rename_collection,
+            set_collection_query,
+            delete_collection,
+            add_to_collection,
+            remove_from_collection,
+            get_collection_files,
+            set_label,
+            bulk_edit,
+            get_files_meta,
+            refresh_file,
+            check_file,
+            compare_files,
+            get_asset_preview,
+            quick_find,
+            get_quick_find_shortcut,
+            set_quick_find_shortcut,
+            check_for_updates,
+            install_update,
+            get_update_checks,
+            set_update_checks,
+            get_label_palette,
+            set_label_palette,
+            set_rating,
+            get_rating,
+            list_tags,
+            create_tag,
+            rename_tag,
+            set_tag_color,
+            delete_tag,
+            tag_files,
+            untag_files,
+            set_exclusions,
+            preview_rename,
+            start_rename_job,
+            get_tag_rules,
+            set_tag_rules,
+            apply_tag_rules,
+            get_projects,
+            get_project_roots,
+            set_project_roots,
+            get_tools,
+            set_tools,
+            run_tool,
+            get_extractors,
+            set_extractors,
+            apply_extractors,
+            get_scripts,
+            set_scripts,
+            run_script,
+            get_usage_stats,
+            record_search_pick,
+            get_usage_tracking,
+            set_usage_tracking,
+            clear_usage_stats,
+            get_staleness_weights,
+            set_staleness_weights,
+            request_elevated_access,
+            set_elevation_decision,
+            get_recent_logs,
+            log_folder,
             open_file,
+            extract_and_open,
             reveal_file
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::io::Cursor;
+
+    const LEGACY: &[u8] = b"BLENDER-v402";
+    const V5: &[u8] = b"BLENDER17-01v0500";
+
+    // A block as saved before 5.0 (24-byte head with 8-byte pointers) or
+    // since (32-byte head)
+    fn block(large: bool, code: &[u8; 4], sdna: u32, old: u64, body: &[u8]) -> Vec<u8> {
+        let mut out = code.to_vec();
+        if large {
+            out.extend(sdna.to_le_bytes());
+            out.extend(old.to_le_bytes());
+            out.extend((body.len() as u64).to_le_bytes());
+            out.extend(1u64.to_le_bytes());
+        } else {
+            out.extend((body.len() as u32).to_le_bytes());
+            out.extend(old.to_le_bytes());
+            out.extend(sdna.to_le_bytes());
+            out.extend(1u32.to_le_bytes());
+        }
+        out.extend(body);
+        out
+    }
+
+    // 2×1 RGBA thumbnail
+    fn thumbnail() -> Vec<u8> {
+        let mut body = 2i32.to_le_bytes().to_vec();
+        body.extend(1i32.to_le_bytes());
+        body.extend([1, 2, 3, 4, 5, 6, 7, 8]);
+        body
+    }
+
+    fn temp_file(name: &str, data: &[u8]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bff-header-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(name);
+        fs::write(&path, data).unwrap();
+        path
+    }
+
+    fn info_for(header: &[u8]) -> BlendInfo {
+        let path = temp_file(&String::from_utf8_lossy(&header[..12]), header);
+        let info = read_blend_header(&path).unwrap();
+        let _ = fs::remove_file(path);
+        info
+    }
+
+    #[test]
+    fn header_len_reads_the_new_form_only() {
+        assert_eq!(header_len(LEGACY), None);
+        assert_eq!(header_len(V5), Some(17));
+        assert_eq!(header_len(b"BLENDER64-01v"), Some(64));
+        // Out of range, or not a length at all
+        assert_eq!(header_len(b"BLENDER12-01v"), None);
+        assert_eq!(header_len(b"BLENDER65-01v"), None);
+        assert_eq!(header_len(b"BLENDER1a-01v"), None);
+        assert_eq!(header_len(b"BLENDER17_01v"), None);
+        assert_eq!(header_len(b"BLENDER1"), None);
+    }
+
+    #[test]
+    fn header_bytes_stop_at_the_header() {
+        let mut legacy = Cursor::new([LEGACY, b"REND"].concat());
+        assert_eq!(read_header_bytes(&mut legacy).unwrap(), LEGACY);
+        let mut v5 = Cursor::new([V5, b"REND"].concat());
+        assert_eq!(read_header_bytes(&mut v5).unwrap(), V5);
+        // Truncated, in either form
+        assert!(read_header_bytes(&mut Cursor::new(&V5[..15])).is_err());
+        assert!(read_header_bytes(&mut Cursor::new(&LEGACY[..8])).is_err());
+    }
+
+    #[test]
+    fn legacy_header() {
+        let info = info_for(LEGACY);
+        assert_eq!(info.version.as_deref(), Some("4.0.2"));
+        assert_eq!(info.pointer_size, Some(64));
+        assert_eq!(info.endianness.as_deref(), Some("little"));
+        assert_eq!(info.file_format, 0);
+
+        let info = info_for(b"BLENDER_V279");
+        assert_eq!(info.version.as_deref(), Some("2.7.9"));
+        assert_eq!(info.pointer_size, Some(32));
+        assert_eq!(info.endianness.as_deref(), Some("big"));
+    }
+
+    #[test]
+    fn v5_header() {
+        let info = info_for(V5);
+        assert_eq!(info.version.as_deref(), Some("5.0.0"));
+        assert_eq!(info.pointer_size, Some(64));
+        assert_eq!(info.file_format, 1);
+        // A newer file format still has its version read
+        let info = info_for(b"BLENDER17-02v0600");
+        assert_eq!(info.version.as_deref(), Some("6.0.0"));
+        assert_eq!(info.file_format, 2);
+    }
+
+    #[test]
+    fn bad_headers_are_errors() {
+        let path = temp_file("short", &V5[..14]);
+        let err = read_blend_header(&path).err().unwrap();
+        assert_eq!(err.code, "header_unreadable");
+        let _ = fs::remove_file(path);
+        let path = temp_file("other", b"PK\x03\x04 not a blend");
+        let err = read_blend_header(&path).err().unwrap();
+        assert_eq!(err.kind, ErrorKind::NotABlend);
+        let _ = fs::remove_file(path);
+    }
+
+    #[test]
+    fn compressed_headers_read_through() {
+        use std::io::Write;
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gz.write_all(&[V5, b"REND"].concat()).unwrap();
+        let path = temp_file("gzip", &gz.finish().unwrap());
+        assert_eq!(
+            read_blend_header(&path).unwrap().version.as_deref(),
+            Some("5.0.0")
+        );
+        let _ = fs::remove_file(path);
+        let zstd = zstd::encode_all(&[LEGACY, b"REND"].concat()[..], 0).unwrap();
+        let path = temp_file("zstd", &zstd);
+        assert_eq!(
+            read_blend_header(&path).unwrap().version.as_deref(),
+            Some("4.0.2")
+        );
+        let _ = fs::remove_file(path);
+    }
+
+    fn scan(data: Vec<u8>, info: &mut BlendInfo) -> Option<contents::Index> {
+        parse_blocks(info, &mut BufReader::new(Cursor::new(data)), Some(64), true).unwrap()
+    }
+
+    #[test]
+    fn legacy_blocks() {
+        let data = [
+            LEGACY.to_vec(),
+            block(false, b"REND", 0, 0, &[0; 16]),
+            block(false, b"TEST", 0, 0, &thumbnail()),
+            block(false, b"ENDB", 0, 0, &[]),
+        ]
+        .concat();
+        let mut info = BlendInfo {
+            endianness: Some("little".into()),
+            ..Default::default()
+        };
+        assert!(scan(data, &mut info).is_none());
+        assert_eq!((info.thumb_width, info.thumb_height), (Some(2), Some(1)));
+        assert_eq!(info.thumbnail.as_deref(), Some("AQIDBAUGBwg="));
+    }
+
+    // Blocks with 32-byte heads after a header of `header` bytes, read
+    // back through the struct layouts
+    fn large_blocks(header: &[u8]) {
+        let dna = sdna::tests::dna(
+            &[("char", 1), ("void", 0), ("ID", 82), ("AssetMetaData", 64)],
+            &[
+                (
+                    "ID",
+                    &[
+                        ("void", "*next"),
+                        ("char", "name[66]"),
+                        ("AssetMetaData", "*asset_data"),
+                    ],
+                ),
+                ("AssetMetaData", &[("char", "catalog_simple_name[64]")]),
+            ],
+        );
+        let mut id = vec![0u8; 82];
+        id[8..14].copy_from_slice(b"MAWood");
+        id[74..82].copy_from_slice(&0x2000u64.to_le_bytes());
+        let mut meta = vec![0u8; 64];
+        meta[..5].copy_from_slice(b"Props");
+        let data = [
+            header.to_vec(),
+            block(true, b"TEST", 0, 0, &thumbnail()),
+            block(true, b"MA\0\0", 0, 0x1000, &id),
+            block(true, b"DATA", 1, 0x2000, &meta),
+            block(true, b"DNA1", 0, 0, &dna),
+            block(true, b"ENDB", 0, 0, &[]),
+        ]
+        .concat();
+        let mut info = BlendInfo {
+            endianness: Some("little".into()),
+            file_format: 1,
+            ..Default::default()
+        };
+        let mut reader = BufReader::new(Cursor::new(data));
+        let index = parse_blocks(&mut info, &mut reader, Some(64), true)
+            .unwrap()
+            .unwrap();
+        assert_eq!((info.thumb_width, info.thumb_height), (Some(2), Some(1)));
+        let assets = contents::assets(&index, &mut reader);
+        assert_eq!(assets.len(), 1);
+        assert_eq!(
+            (
+                assets[0].name.as_str(),
+                assets[0].code.as_str(),
+                assets[0].id_type.as_str()
+            ),
+            ("Wood", "MA", "Material")
+        );
+        assert_eq!(assets[0].catalog.as_deref(), Some("Props"));
+    }
+
+    #[test]
+    fn v5_blocks() {
+        large_blocks(V5);
+    }
+
+    #[test]
+    fn longer_v5_header_is_skipped_whole() {
+        large_blocks(b"BLENDER20-01v0500xyz");
+    }
+
+    #[test]
+    fn unknown_file_format_is_not_block_scanned() {
+        let mut info = BlendInfo {
+            file_format: 2,
+            ..Default::default()
+        };
+        let data = [
+            b"BLENDER17-02v0600".to_vec(),
+            block(true, b"ENDB", 0, 0, &[]),
+        ]
+        .concat();
+        assert!(parse_blocks(
+            &mut info,
+            &mut BufReader::new(Cursor::new(data)),
+            None,
+            false
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,85 @@
+use same_file::Handle;
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+
+// -----------------------------
+// Directory links met during a walk
+// -----------------------------
+// Symlinks, NTFS junctions and other name-surrogate reparse points are
+// reported (not silently descended into), so looping or duplicated trees
+// show up in the results instead of in the file list.
+// Followed links that lead back into a walked folder are cycles: skipped,
+// and flagged as such.
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum LinkKind {
+    Symlink,
+    Junction,
+    ReparsePoint,
+}
+
+#[derive(Serialize, Clone)]
+pub struct LinkEntry {
+    pub path: String,
+    pub path_id: String,
+    pub kind: LinkKind,
+    pub target: Option<String>,
+    pub followed: bool,
+    pub duplicate_of: Option<String>, // already indexed via this canonical path
+    pub cycle: bool,                  // leads back to a folder it sits in, or a walked start
+}
+
+// Compared by device and inode (volume and file index on Windows), so
+// hard-linked folders and bind mounts are caught where paths differ.
+// `visited` holds the folders walks started from.
+pub fn is_cycle(link: &Path, visited: &HashSet<Handle>) -> bool {
+    let Ok(target) = Handle::from_path(link) else {
+        return false;
+    };
+    visited.contains(&target)
+        || link
+            .ancestors()
+            .skip(1)
+            .filter_map(|dir| Handle::from_path(dir).ok())
+            .any(|dir| dir == target)
+}
+
+#[cfg(windows)]
+pub fn link_kind(path: &Path) -> LinkKind {
+    const IO_REPARSE_TAG_MOUNT_POINT: u32 = 0xA000_0003;
+    const IO_REPARSE_TAG_SYMLINK: u32 = 0xA000_000C;
+    match reparse_tag(path) {
+        Some(IO_REPARSE_TAG_MOUNT_POINT) => LinkKind::Junction,
+        Some(IO_REPARSE_TAG_SYMLINK) | None => LinkKind::Symlink,
+        Some(_) => LinkKind::ReparsePoint,
+    }
+}
+
+#[cfg(not(windows))]
+pub fn link_kind(_path: &Path) -> LinkKind {
+    LinkKind::Symlink
+}
+
+// The find data of a reparse point carries its tag in dwReserved0
+#[cfg(windows)]
+fn reparse_tag(path: &Path) -> Option<u32> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Foundation::INVALID_HANDLE_VALUE;
+    use windows_sys::Win32::Storage::FileSystem::{
+        FindClose, FindFirstFileW, FILE_ATTRIBUTE_REPARSE_POINT, WIN32_FIND_DATAW,
+    };
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    // SAFETY: `wide` is NUL-terminated and `data` is a valid out-pointer
+    unsafe {
+        let mut data: WIN32_FIND_DATAW = std::mem::zeroed();
+        let handle = FindFirstFileW(wide.as_ptr(), &mut data);
+        if handle == INVALID_HANDLE_VALUE {
+            return None;
+        }
+        FindClose(handle);
+        (data.dwFileAttributes & FILE_ATTRIBUTE_REPARSE_POINT != 0).then_some(data.dwReserved0)
+    }
+}
@@ -0,0 +1,217 @@
+use chrono::Local;
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::fmt::{self, Write as _};
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tracing::field::{Field, Visit};
+use tracing::level_filters::LevelFilter;
+use tracing::{span, Event, Level, Metadata, Subscriber};
+
+// -----------------------------
+// Logging
+// -----------------------------
+// `tracing` events go to a size-rotated file in the app log dir and to an
+// in-memory ring that the frontend can read back (get_recent_logs).
+
+pub const LOG_FILE_NAME: &str = "blendfinder.log";
+const MAX_LOG_BYTES: u64 = 2 * 1024 * 1024;
+const KEEP_ROTATED: usize = 3; // blendfinder.log.1 … .3
+const RING_CAPACITY: usize = 2000;
+
+// `BLENDFINDER_LOG=debug` raises verbosity for bug reports
+const LEVEL_ENV: &str = "BLENDFINDER_LOG";
+
+#[derive(Serialize, Clone)]
+pub struct LogEntry {
+    pub timestamp: String,
+    pub level: String,
+    pub target: String,
+    pub message: String,
+}
+
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+}
+
+struct Logger {
+    max_level: LevelFilter,
+    file: Mutex<Option<LogFile>>,
+}
+
+static RING: OnceLock<Mutex<VecDeque<(Level, LogEntry)>>> = OnceLock::new();
+static LOG_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+fn ring() -> &'static Mutex<VecDeque<(Level, LogEntry)>> {
+    RING.get_or_init(|| Mutex::new(VecDeque::with_capacity(RING_CAPACITY)))
+}
+
+pub fn parse_level(s: &str) -> Option<Level> {
+    match s.trim().to_ascii_lowercase().as_str() {
+        "trace" => Some(Level::TRACE),
+        "debug" => Some(Level::DEBUG),
+        "info" => Some(Level::INFO),
+        "warn" | "warning" => Some(Level::WARN),
+        "error" => Some(Level::ERROR),
+        _ => None,
+    }
+}
+
+// Installs the global subscriber; file logging is skipped if the dir is unusable
+pub fn init(log_dir: Option<PathBuf>) {
+    let max_level = std::env::var(LEVEL_ENV)
+        .ok()
+        .and_then(|v| parse_level(&v))
+        .unwrap_or(Level::INFO);
+
+    let file = log_dir.and_then(|dir| {
+        fs::create_dir_all(&dir).ok()?;
+        let path = dir.join(LOG_FILE_NAME);
+        let opened = open_append(&path).ok()?;
+        let _ = LOG_DIR.set(dir);
+        Some(opened)
+    });
+
+    let logger = Logger {
+        max_level: LevelFilter::from_level(max_level),
+        file: Mutex::new(file),
+    };
+    let _ = tracing::subscriber::set_global_default(logger);
+}
+
+pub fn log_dir() -> Option<&'static Path> {
+    LOG_DIR.get().map(|p| p.as_path())
+}
+
+// Newest last; `level` keeps that severity and anything more severe
+pub fn recent(level: Option<&str>, limit: Option<usize>) -> Vec<LogEntry> {
+    let min = level.and_then(parse_level).unwrap_or(Level::TRACE);
+    let limit = limit.unwrap_or(500);
+    let ring = ring().lock().unwrap();
+    let mut out: Vec<LogEntry> = ring
+        .iter()
+        .rev()
+        .filter(|(lvl, _)| *lvl <= min)
+        .take(limit)
+        .map(|(_, e)| e.clone())
+        .collect();
+    out.reverse();
+    out
+}
+
+fn open_append(path: &Path) -> std::io::Result<LogFile> {
+    let file = OpenOptions::new().create(true).append(true).open(path)?;
+    let size = file.metadata().map(|m| m.len()).unwrap_or(0);
+    Ok(LogFile {
+        path: path.to_path_buf(),
+        file,
+        size,
+    })
+}
+
+fn rotated(path: &Path, n: usize) -> PathBuf {
+    let mut s = path.as_os_str().to_owned();
+    s.push(format!(".{}", n));
+    PathBuf::from(s)
+}
+
+impl LogFile {
+    fn write_line(&mut self, line: &str) {
+        if self.size + line.len() as u64 > MAX_LOG_BYTES {
+            self.rotate();
+        }
+        if self.file.write_all(line.as_bytes()).is_ok() {
+            self.size += line.len() as u64;
+        }
+    }
+
+    fn rotate(&mut self) {
+        let _ = fs::remove_file(rotated(&self.path, KEEP_ROTATED));
+        for n in (1..KEEP_ROTATED).rev() {
+            let _ = fs::rename(rotated(&self.path, n), rotated(&self.path, n + 1));
+        }
+        let _ = fs::rename(&self.path, rotated(&self.path, 1));
+        if let Ok(fresh) = open_append(&self.path) {
+            *self = fresh;
+        }
+    }
+}
+
+// Collects `message` plus any extra `key = value` fields
+#[derive(Default)]
+struct MessageVisitor {
+    message: String,
+    fields: String,
+}
+
+impl Visit for MessageVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.fields, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+impl Subscriber for Logger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        *metadata.level() <= self.max_level
+    }
+
+    fn max_level_hint(&self) -> Option<LevelFilter> {
+        Some(self.max_level)
+    }
+
+    // Spans aren't used; events carry their context as fields
+    fn new_span(&self, _span: &span::Attributes<'_>) -> span::Id {
+        span::Id::from_u64(1)
+    }
+
+    fn record(&self, _span: &span::Id, _values: &span::Record<'_>) {}
+
+    fn record_follows_from(&self, _span: &span::Id, _follows: &span::Id) {}
+
+    fn event(&self, event: &Event<'_>) {
+        let meta = event.metadata();
+        let mut visitor = MessageVisitor::default();
+        event.record(&mut visitor);
+
+        let entry = LogEntry {
+            timestamp: Local::now().format("%Y-%m-%d %H:%M:%S%.3f").to_string(),
+            level: meta.level().to_string(),
+            target: meta.target().to_string(),
+            message: visitor.message + visitor.fields.as_str(),
+        };
+
+        if let Some(file) = self.file.lock().unwrap().as_mut() {
+            let line = format!(
+                "{} {:5} {}: {}\n",
+                entry.timestamp, entry.level, entry.target, entry.message
+            );
+            file.write_line(&line);
+        }
+
+        let mut ring = ring().lock().unwrap();
+        if ring.len() == RING_CAPACITY {
+            ring.pop_front();
+        }
+        ring.push_back((*meta.level(), entry));
+    }
+
+    fn enter(&self, _span: &span::Id) {}
+
+    fn exit(&self, _span: &span::Id) {}
+}
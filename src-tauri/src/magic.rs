@@ -0,0 +1,163 @@
+use crate::error::AppError;
+use crate::formats;
+use crate::handles;
+use flate2::read::GzDecoder;
+use std::io::{Read, Seek};
+use std::path::Path;
+
+// -----------------------------
+// Content sniffing for blends without a .blend extension
+// -----------------------------
+const BLEND_MAGIC: &[u8] = b"BLENDER";
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip, // "Compress" before Blender 3.0
+    Zstd, // "Compress" since 3.0
+}
+
+// From the first bytes only; says nothing about what is inside
+pub fn compression(path: &Path) -> Result<Compression, AppError> {
+    let mut file = handles::open(path)?;
+    let mut head = [0u8; 4];
+    let got = file.read(&mut head).map_err(|e| AppError::io(&e, path))?;
+    Ok(compression_of(&head[..got]))
+}
+
+pub fn compression_of(head: &[u8]) -> Compression {
+    if head.starts_with(GZIP_MAGIC) {
+        Compression::Gzip
+    } else if head.starts_with(ZSTD_MAGIC) {
+        Compression::Zstd
+    } else {
+        Compression::None
+    }
+}
+
+// Runs `f` on the blend data behind `reader`, decompressed when it is.
+// Only a stream: going back means decompressing from the start again.
+pub fn decompress<R: Read, T>(
+    compression: Compression,
+    mut reader: R,
+    f: impl FnOnce(&mut dyn Read) -> T,
+) -> std::io::Result<T> {
+    Ok(match compression {
+        Compression::None => f(&mut reader),
+        Compression::Gzip => f(&mut GzDecoder::new(reader)),
+        Compression::Zstd => f(&mut zstd::stream::read::Decoder::new(reader)?),
+    })
+}
+
+// Common non-blend files next to blends; never worth opening
+const SKIP_EXTENSIONS: &[&str] = &[
+    "png", "jpg", "jpeg", "exr", "hdr", "tif", "tiff", "tga", "psd", "webp", "mp4", "mov", "avi",
+    "mkv", "wav", "mp3", "ogg", "flac", "fbx", "obj", "mtl", "glb", "gltf", "abc", "usd", "usda",
+    "usdc", "stl", "ply", "py", "txt", "md", "json", "xml", "html", "pdf", "zip", "7z", "rar",
+    "exe", "dll", "so", "dylib", "vdb", "bphys",
+];
+
+// Extensionless or unusual names; `.blend1`-style backups are left alone
+pub fn worth_sniffing(path: &Path) -> bool {
+    let Some(ext) = path.extension().and_then(|e| e.to_str()) else {
+        return true;
+    };
+    let ext = ext.to_ascii_lowercase();
+    formats::backup_number(path).is_none() && !SKIP_EXTENSIONS.contains(&ext.as_str())
+}
+
+// True for plain, gzip- and zstd-compressed blends
+pub fn is_blend(path: &Path) -> bool {
+    let Ok(mut file) = handles::open(path) else {
+        return false;
+    };
+    let mut head = [0u8; 7];
+    if file.read_exact(&mut head).is_err() {
+        return false;
+    }
+    if head.starts_with(BLEND_MAGIC) {
+        return true;
+    }
+
+    // Compressed: the magic only says "compressed", so peek inside
+    if file.rewind().is_err() {
+        return false;
+    }
+    let mut inner = [0u8; 7];
+    let read = if head.starts_with(GZIP_MAGIC) {
+        GzDecoder::new(&*file).read_exact(&mut inner)
+    } else if head.starts_with(ZSTD_MAGIC) {
+        zstd::stream::read::Decoder::new(&*file).and_then(|mut d| d.read_exact(&mut inner))
+    } else {
+        return false;
+    };
+    read.is_ok() && inner.starts_with(BLEND_MAGIC)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::write::GzEncoder;
+    use std::io::Write;
+
+    const BLEND: &[u8] = b"BLENDER-v402REND....";
+
+    fn gzip(data: &[u8]) -> Vec<u8> {
+        let mut enc = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        enc.write_all(data).unwrap();
+        enc.finish().unwrap()
+    }
+
+    fn read_all(compression: Compression, data: &[u8]) -> std::io::Result<Vec<u8>> {
+        decompress(compression, data, |r| {
+            let mut out = Vec::new();
+            r.read_to_end(&mut out).map(|_| out)
+        })?
+    }
+
+    #[test]
+    fn compression_from_first_bytes() {
+        assert_eq!(compression_of(BLEND), Compression::None);
+        assert_eq!(compression_of(&gzip(BLEND)), Compression::Gzip);
+        let zstd = zstd::encode_all(BLEND, 0).unwrap();
+        assert_eq!(compression_of(&zstd), Compression::Zstd);
+        assert_eq!(compression_of(&[0x1f]), Compression::None);
+        assert_eq!(compression_of(&[]), Compression::None);
+    }
+
+    #[test]
+    fn decompress_reads_through() {
+        assert_eq!(read_all(Compression::None, BLEND).unwrap(), BLEND);
+        assert_eq!(read_all(Compression::Gzip, &gzip(BLEND)).unwrap(), BLEND);
+        let zstd = zstd::encode_all(BLEND, 0).unwrap();
+        assert_eq!(read_all(Compression::Zstd, &zstd).unwrap(), BLEND);
+    }
+
+    #[test]
+    fn damaged_streams_fail() {
+        let gz = gzip(BLEND);
+        assert!(read_all(Compression::Gzip, &gz[..gz.len() / 2]).is_err());
+        assert!(read_all(Compression::Zstd, b"\x28\xb5\x2f\xfdjunk").is_err());
+    }
+
+    #[test]
+    fn sniffs_plain_and_compressed_blends() {
+        let dir = std::env::temp_dir().join(format!("bff-magic-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let cases: [(&str, Vec<u8>, bool); 5] = [
+            ("plain", BLEND.to_vec(), true),
+            ("gzip", gzip(BLEND), true),
+            ("zstd", zstd::encode_all(BLEND, 0).unwrap(), true),
+            ("gzip-other", gzip(b"PNG and more"), false),
+            ("short", b"BLEN".to_vec(), false),
+        ];
+        for (name, data, blend) in cases {
+            let path = dir.join(name);
+            std::fs::write(&path, data).unwrap();
+            assert_eq!(is_blend(&path), blend, "{}", name);
+        }
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,202 @@
+use crate::error::{AppError, ErrorKind};
+use crate::handles::Semaphore;
+use crate::volume;
+use std::path::Path;
+use std::sync::atomic::{AtomicU8, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+
+// -----------------------------
+// Network shares (UNC paths, SMB/NFS mounts)
+// -----------------------------
+// A flaky NAS can block a read for minutes. In network mode every file
+// operation runs with a timeout, transient failures are retried with
+// backoff, and only a few operations hit the share at once. Operations
+// (and directory listings, see scan.rs) run on worker threads that are
+// abandoned when they time out; past a few stuck ones, new operations fail
+// at once instead of piling up threads and open handles.
+
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(20);
+pub const DEFAULT_MAX_CONCURRENT: usize = 2;
+const RETRIES: u32 = 3;
+const BACKOFF_BASE: Duration = Duration::from_millis(250);
+const MAX_ABANDONED: usize = 16;
+
+// Abandoned workers still stuck in the kernel, across all scans
+static ABANDONED: AtomicUsize = AtomicUsize::new(0);
+
+const NETWORK_FS_TYPES: &[&str] = &[
+    "cifs",
+    "smb3",
+    "smbfs",
+    "nfs",
+    "nfs4",
+    "afpfs",
+    "webdav",
+    "davfs",
+    "fuse.sshfs",
+    "9p",
+];
+
+// OS codes worth another attempt: the share hiccuped, the file is fine
+#[cfg(windows)]
+const TRANSIENT_OS_CODES: &[i32] = &[
+    53,   // ERROR_BAD_NETPATH
+    59,   // ERROR_UNEXP_NET_ERR
+    64,   // ERROR_NETNAME_DELETED
+    121,  // ERROR_SEM_TIMEOUT
+    1231, // ERROR_NETWORK_UNREACHABLE
+];
+#[cfg(not(windows))]
+const TRANSIENT_OS_CODES: &[i32] = &[
+    4,   // EINTR
+    5,   // EIO
+    11,  // EAGAIN
+    110, // ETIMEDOUT
+    112, // EHOSTDOWN
+    116, // ESTALE
+];
+
+pub fn is_network_path(path: &Path) -> bool {
+    let s = path.to_string_lossy();
+    if s.starts_with(r"\\?\UNC\") {
+        return true;
+    }
+    if s.starts_with(r"\\") && !s.starts_with(r"\\?\") && !s.starts_with(r"\\.\") {
+        return true;
+    }
+
+    volume::mount_entry(path).is_some_and(|(_, fs)| NETWORK_FS_TYPES.contains(&fs.as_str()))
+}
+
+// Timeouts aren't retried: a share that hung once usually hangs again
+fn is_transient(err: &AppError) -> bool {
+    err.os_code.is_some_and(|c| TRANSIENT_OS_CODES.contains(&c))
+}
+
+pub fn timed_out(path: &Path, timeout: Duration) -> AppError {
+    AppError::new(
+        ErrorKind::Timeout,
+        format!("No response after {} s", timeout.as_secs()),
+    )
+    .with_param("seconds", timeout.as_secs())
+    .with_path(path)
+}
+
+const RUNNING: u8 = 0;
+const FINISHED: u8 = 1;
+const GAVE_UP: u8 = 2;
+
+// State shared by a worker thread and the one waiting on it
+#[derive(Clone)]
+pub struct Worker(Arc<AtomicU8>);
+
+impl Worker {
+    pub fn start(path: &Path) -> Result<Self, AppError> {
+        if ABANDONED.load(Ordering::Relaxed) >= MAX_ABANDONED {
+            return Err(
+                AppError::new(ErrorKind::Timeout, "The network share stopped responding")
+                    .with_code("share_stuck")
+                    .with_path(path),
+            );
+        }
+        Ok(Self(Arc::new(AtomicU8::new(RUNNING))))
+    }
+
+    // Worker side, when its operation returned
+    pub fn finished(&self) {
+        if self.0.swap(FINISHED, Ordering::AcqRel) == GAVE_UP {
+            ABANDONED.fetch_sub(1, Ordering::Relaxed);
+        }
+    }
+
+    // Waiting side, on timeout; false when the worker finished meanwhile
+    pub fn abandon(&self) -> bool {
+        ABANDONED.fetch_add(1, Ordering::Relaxed);
+        let gave_up = self
+            .0
+            .compare_exchange(RUNNING, GAVE_UP, Ordering::AcqRel, Ordering::Acquire)
+            .is_ok();
+        if !gave_up {
+            ABANDONED.fetch_sub(1, Ordering::Relaxed);
+        }
+        gave_up
+    }
+}
+
+pub struct NetIo {
+    enabled: bool,
+    timeout: Duration,
+    limiter: Semaphore, // concurrent operations on the share
+}
+
+impl NetIo {
+    pub fn new(enabled: bool, timeout: Duration, max_concurrent: usize) -> Self {
+        Self {
+            enabled,
+            timeout,
+            limiter: Semaphore::new(max_concurrent),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.enabled
+    }
+
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    // Runs a file operation; a no-op wrapper for local disks
+    pub fn run<T, F>(&self, path: &Path, op: F) -> Result<T, AppError>
+    where
+        T: Send + 'static,
+        F: Fn() -> Result<T, AppError> + Send + Sync + 'static,
+    {
+        if !self.enabled {
+            return op();
+        }
+        let op = Arc::new(op);
+        let mut attempt = 0;
+        loop {
+            let result = {
+                let _permit = self.limiter.acquire();
+                self.with_timeout(path, op.clone())
+            };
+            match result {
+                Err(e) if attempt < RETRIES && is_transient(&e) => {
+                    attempt += 1;
+                    tracing::debug!(path = %path.display(), attempt, "retrying: {}", e);
+                    thread::sleep(BACKOFF_BASE * 2u32.pow(attempt - 1));
+                }
+                other => return other,
+            }
+        }
+    }
+
+    // The worker is abandoned on timeout: a read stuck in the kernel can't be
+    // interrupted, but the scan moves on (and frees its permit)
+    fn with_timeout<T, F>(&self, path: &Path, op: Arc<F>) -> Result<T, AppError>
+    where
+        T: Send + 'static,
+        F: Fn() -> Result<T, AppError> + Send + Sync + 'static,
+    {
+        let worker = Worker::start(path)?;
+        let (tx, rx) = mpsc::channel();
+        let w = worker.clone();
+        thread::spawn(move || {
+            let _ = tx.send(op());
+            w.finished();
+        });
+        match rx.recv_timeout(self.timeout) {
+            Ok(result) => result,
+            Err(_) if worker.abandon() => Err(timed_out(path, self.timeout)),
+            // Finished as the timeout hit
+            Err(_) => rx
+                .try_recv()
+                .unwrap_or_else(|_| Err(timed_out(path, self.timeout))),
+        }
+    }
+}
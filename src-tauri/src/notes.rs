@@ -0,0 +1,54 @@
+use crate::db;
+use crate::error::AppError;
+use crate::FlatFile;
+use chrono::Local;
+use rusqlite::{params, OptionalExtension};
+use std::collections::HashMap;
+
+// -----------------------------
+// Free-text notes (one per file, keyed by path id)
+// -----------------------------
+
+// Blank text removes the note
+pub fn set(path_id: &str, note: Option<&str>) -> Result<Option<String>, AppError> {
+    let note = note.map(str::trim).filter(|n| !n.is_empty());
+    db::with(|conn| {
+        match note {
+        Some(n) => conn.execute(
+            "INSERT INTO notes (path_id, note, updated_at) VALUES (?1, ?2, ?3)
+             ON CONFLICT(path_id) DO UPDATE SET note = excluded.note, updated_at = excluded.updated_at",
+            params![path_id, n, Local::now().to_rfc3339()],
+        ),
+        None => conn.execute("DELETE FROM notes WHERE path_id = ?1", [path_id]),
+    }
+    })?;
+    Ok(note.map(str::to_string))
+}
+
+pub fn get(path_id: &str) -> Result<Option<String>, AppError> {
+    db::with(|conn| {
+        conn.query_row(
+            "SELECT note FROM notes WHERE path_id = ?1",
+            [path_id],
+            |r| r.get(0),
+        )
+        .optional()
+    })
+}
+
+// Fills `note` on result files
+pub fn annotate(files: &mut [FlatFile]) {
+    let map: Result<HashMap<String, String>, AppError> = db::with(|conn| {
+        let mut stmt = conn.prepare("SELECT path_id, note FROM notes")?;
+        let rows = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?;
+        rows.collect()
+    });
+    match map {
+        Ok(map) => {
+            for f in files {
+                f.note = map.get(&f.path_id).cloned();
+            }
+        }
+        Err(e) => tracing::warn!("loading notes failed: {}", e),
+    }
+}
@@ -0,0 +1,184 @@
+use crate::file_ids;
+use base64::prelude::*;
+use std::ffi::OsString;
+use std::path::{Component, Path, PathBuf, Prefix, PrefixComponent};
+
+// -----------------------------
+// Path identifiers
+// -----------------------------
+// Paths go to the frontend twice: `path` (lossy, for display) and `path_id`
+// (round-trips exactly, for commands). For valid Unicode paths both are the
+// same string; otherwise the id carries the raw OS bytes, which matters for
+// non-UTF-8 names on Linux and unpaired surrogates on Windows.
+
+// Can't start an absolute path (drive letters are a single character)
+const RAW_PREFIX: &str = "os:";
+
+pub fn to_id(path: &Path) -> String {
+    match path.to_str() {
+        Some(s) => s.to_string(),
+        None => format!(
+            "{}{}",
+            RAW_PREFIX,
+            BASE64_URL_SAFE_NO_PAD.encode(raw_bytes(path))
+        ),
+    }
+}
+
+// Accepts ids, stable file ids and plain paths alike; the result is
+// normalized
+pub fn from_id(id: &str) -> PathBuf {
+    if id.starts_with(file_ids::PREFIX) {
+        if let Some(path_id) = file_ids::path_of(id) {
+            return from_id(&path_id);
+        }
+    }
+    let path = id
+        .strip_prefix(RAW_PREFIX)
+        .and_then(|enc| BASE64_URL_SAFE_NO_PAD.decode(enc).ok())
+        .and_then(|bytes| from_raw_bytes(&bytes))
+        .unwrap_or_else(|| PathBuf::from(id));
+    normalize(&path)
+}
+
+// Lexical cleanup so one file has one id however its path was typed: `.`
+// and `..` resolved, doubled and trailing separators dropped, Windows
+// separators and drive letters in their usual form. Symlinks aren't
+// followed, so `link/..` is taken literally.
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for c in path.components() {
+        match c {
+            Component::Prefix(p) => out.push(prefix(p)),
+            Component::RootDir => out.push(Component::RootDir),
+            Component::CurDir => {}
+            Component::ParentDir => match out.components().next_back() {
+                Some(Component::Normal(_)) => {
+                    out.pop();
+                }
+                // Nothing above the root
+                Some(Component::RootDir) | Some(Component::Prefix(_)) => {}
+                _ => out.push(".."),
+            },
+            Component::Normal(s) => out.push(s),
+        }
+    }
+    if out.as_os_str().is_empty() && !path.as_os_str().is_empty() {
+        out.push(".");
+    }
+    out
+}
+
+// `c:` -> `C:`; other prefixes (UNC, device) are kept as typed
+fn prefix(p: PrefixComponent) -> OsString {
+    match p.kind() {
+        Prefix::Disk(d) => format!("{}:", d.to_ascii_uppercase() as char).into(),
+        Prefix::VerbatimDisk(d) => format!(r"\\?\{}:", d.to_ascii_uppercase() as char).into(),
+        _ => p.as_os_str().to_owned(),
+    }
+}
+
+// `path` below `root` as `a/b/c.blend` on every platform, for exports and
+// remapping an index to another root
+pub fn portable_relative(path: &Path, root: &Path) -> Option<String> {
+    let rel = path.strip_prefix(root).ok()?;
+    let parts: Vec<String> = rel
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    Some(parts.join("/"))
+}
+
+// `path` for display relative to `root` (`.` for the root itself); paths
+// outside it stay absolute
+pub fn display_relative(path: &Path, root: &Path) -> String {
+    match path.strip_prefix(root) {
+        Ok(rel) if rel.as_os_str().is_empty() => ".".to_string(),
+        Ok(rel) => rel.to_string_lossy().to_string(),
+        Err(_) => path.to_string_lossy().to_string(),
+    }
+}
+
+#[cfg(unix)]
+fn raw_bytes(path: &Path) -> Vec<u8> {
+    use std::os::unix::ffi::OsStrExt;
+    path.as_os_str().as_bytes().to_vec()
+}
+
+#[cfg(unix)]
+fn from_raw_bytes(bytes: &[u8]) -> Option<PathBuf> {
+    use std::os::unix::ffi::OsStrExt;
+    Some(PathBuf::from(std::ffi::OsStr::from_bytes(bytes)))
+}
+
+// UTF-16 code units, little-endian
+#[cfg(windows)]
+fn raw_bytes(path: &Path) -> Vec<u8> {
+    use std::os::windows::ffi::OsStrExt;
+    path.as_os_str()
+        .encode_wide()
+        .flat_map(|u| u.to_le_bytes())
+        .collect()
+}
+
+#[cfg(windows)]
+fn from_raw_bytes(bytes: &[u8]) -> Option<PathBuf> {
+    use std::os::windows::ffi::OsStringExt;
+    if bytes.len() % 2 != 0 {
+        return None;
+    }
+    let wide: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .collect();
+    Some(PathBuf::from(std::ffi::OsString::from_wide(&wide)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unicode_path_is_its_own_id() {
+        let path = Path::new("/shots/größe/szene.blend");
+        assert_eq!(to_id(path), "/shots/größe/szene.blend");
+        assert_eq!(from_id(&to_id(path)), path);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn non_utf8_path_round_trips() {
+        use std::os::unix::ffi::OsStrExt;
+        let path = Path::new(std::ffi::OsStr::from_bytes(b"/shots/sc\xe8ne.blend"));
+        let id = to_id(path);
+        assert!(id.starts_with(RAW_PREFIX));
+        // Safe in URLs and query strings
+        assert!(!id[RAW_PREFIX.len()..].contains(['/', '+', '=']));
+        assert_eq!(from_id(&id), path);
+    }
+
+    #[cfg(windows)]
+    #[test]
+    fn unpaired_surrogate_round_trips() {
+        use std::os::windows::ffi::OsStringExt;
+        let mut wide: Vec<u16> = r"C:\shots\sc".encode_utf16().collect();
+        wide.push(0xD800);
+        wide.extend(".blend".encode_utf16());
+        let path = PathBuf::from(OsString::from_wide(&wide));
+        let id = to_id(&path);
+        assert!(id.starts_with(RAW_PREFIX));
+        assert_eq!(from_id(&id), path);
+    }
+
+    #[test]
+    fn bad_raw_id_is_taken_as_a_path() {
+        assert_eq!(from_id("os:not base64!"), Path::new("os:not base64!"));
+    }
+
+    #[test]
+    fn ids_are_normalized() {
+        assert_eq!(from_id("/a/./b//c/../d.blend"), Path::new("/a/b/d.blend"));
+        assert_eq!(from_id("/.."), Path::new("/"));
+        assert_eq!(from_id("a/../.."), Path::new(".."));
+    }
+}
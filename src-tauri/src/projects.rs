@@ -0,0 +1,148 @@
+use crate::{paths, settings, staleness, FlatFile, ScanResult};
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::path::{Path, PathBuf};
+
+// -----------------------------
+// Projects
+// -----------------------------
+// Files are grouped under the nearest project root above them: a folder
+// assigned in settings, else one holding a project marker (a Blender
+// project, a git or svn checkout) inside the scanned folder. Each group gets a summary for an
+// overview of a whole production.
+
+const MARKERS: &[&str] = &[".blender_project", ".git", ".svn"];
+
+#[derive(Serialize, Clone)]
+pub struct ProjectSummary {
+    pub name: String,
+    pub root: String,
+    pub root_id: String,
+    pub assigned: bool, // from settings, not detected
+    pub file_count: usize,
+    pub total_bytes: u64,
+    pub versions: BTreeMap<String, usize>, // Blender version -> files
+    pub missing_links: Vec<String>,        // linked libraries not on disk
+    pub missing_images: Vec<String>,       // unpacked environment images not on disk
+    pub last_modified: Option<String>,
+    pub last_modified_ms: Option<i64>,
+    pub last_file: Option<String>, // path id of the newest file
+}
+
+struct Finder {
+    scan_roots: Vec<PathBuf>,
+    assigned: Vec<PathBuf>,
+    marked: HashMap<PathBuf, bool>,
+}
+
+impl Finder {
+    fn new(scan_roots: Vec<PathBuf>) -> Self {
+        Self {
+            scan_roots,
+            assigned: settings::get()
+                .project_roots
+                .iter()
+                .map(|r| paths::from_id(r))
+                .collect(),
+            marked: HashMap::new(),
+        }
+    }
+
+    // Nearest root above `file`, and whether it was assigned
+    fn root_of(&mut self, file: &Path) -> Option<(PathBuf, bool)> {
+        let assigned = file
+            .ancestors()
+            .skip(1)
+            .find(|a| self.assigned.iter().any(|r| r == a));
+        if let Some(root) = assigned {
+            return Some((root.to_path_buf(), true));
+        }
+        let inside = |d: &&Path| self.scan_roots.iter().any(|r| d.starts_with(r));
+        for dir in file.ancestors().skip(1).take_while(inside) {
+            let marked = *self
+                .marked
+                .entry(dir.to_path_buf())
+                .or_insert_with(|| MARKERS.iter().any(|m| dir.join(m).exists()));
+            if marked {
+                return Some((dir.to_path_buf(), false));
+            }
+        }
+        None
+    }
+}
+
+fn missing(blend: &Path, saved: &str, seen: &mut HashMap<PathBuf, bool>) -> Option<String> {
+    if saved.is_empty() {
+        return None;
+    }
+    let path = staleness::resolve(blend, saved);
+    let exists = *seen.entry(path.clone()).or_insert_with(|| path.exists());
+    (!exists).then(|| path.to_string_lossy().to_string())
+}
+
+fn summarize(root: &Path, assigned: bool, files: &[&FlatFile]) -> ProjectSummary {
+    let mut versions = BTreeMap::new();
+    let mut seen = HashMap::new();
+    let (mut missing_links, mut missing_images) = (Vec::new(), Vec::new());
+    for f in files {
+        let version = f.blender_version.clone().unwrap_or_else(|| "?".to_string());
+        *versions.entry(version).or_insert(0) += 1;
+        let blend = paths::from_id(&f.path_id);
+        missing_links.extend(
+            f.libraries
+                .iter()
+                .filter_map(|l| missing(&blend, l, &mut seen)),
+        );
+        missing_images.extend(
+            f.environments
+                .iter()
+                .filter(|e| !e.packed)
+                .filter_map(|e| missing(&blend, &e.path, &mut seen)),
+        );
+    }
+    missing_links.sort();
+    missing_links.dedup();
+    missing_images.sort();
+    missing_images.dedup();
+    let newest = files.iter().max_by_key(|f| f.modified_ms);
+    ProjectSummary {
+        name: root
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_else(|| root.to_string_lossy().to_string()),
+        root: root.to_string_lossy().to_string(),
+        root_id: paths::to_id(root),
+        assigned,
+        file_count: files.len(),
+        total_bytes: files.iter().map(|f| f.size_bytes).sum(),
+        versions,
+        missing_links,
+        missing_images,
+        last_modified: newest.and_then(|f| f.modified.clone()),
+        last_modified_ms: newest.and_then(|f| f.modified_ms),
+        last_file: newest.map(|f| f.path_id.clone()),
+    }
+}
+
+// Summaries of the projects in `result`, most recently active first; files
+// outside any project are left out
+pub fn summaries(result: &ScanResult) -> Vec<ProjectSummary> {
+    let mut finder = Finder::new(result.roots.iter().map(|r| paths::from_id(r)).collect());
+    let mut groups: BTreeMap<PathBuf, (bool, Vec<&FlatFile>)> = BTreeMap::new();
+    for f in result
+        .files
+        .iter()
+        .filter(|f| f.archive.is_none() && f.trash.is_none())
+    {
+        if let Some((root, assigned)) = finder.root_of(&paths::from_id(&f.path_id)) {
+            let group = groups.entry(root).or_insert((assigned, Vec::new()));
+            group.1.push(f);
+        }
+    }
+    let mut out: Vec<ProjectSummary> = groups
+        .iter()
+        .map(|(root, (assigned, files))| summarize(root, *assigned, files))
+        .collect();
+    out.sort_by_key(|p| std::cmp::Reverse(p.last_modified_ms));
+    out
+}
@@ -0,0 +1,325 @@
+use crate::error::{AppError, ErrorKind};
+use crate::FlatFile;
+
+// -----------------------------
+// Saved queries (smart collections)
+// -----------------------------
+// `engine:cycles AND tag:approved`, `(label:red OR label:orange) -is:trash`.
+// Terms side by side are ANDed, OR binds looser than AND, and NOT or a
+// leading `-` negates. Fields:
+//   tag:, label:, engine:, format:, in:<collection>
+//   hdri:studio   a world's environment texture path contains "studio"
+//   version:4     version prefix, as shown in results (4 matches 4.x.x)
+//   rating:3      three stars or more
+//   stale:60      staleness score of 60 or more (see staleness.rs)
+//   is:fav, is:cloud, is:trash, is:readonly
+//   is:comp       a scene runs a compositor tree (deep scans only)
+//   is:autopack   Automatically Pack Resources is on (deep scans only)
+//   is:sim        has physics caches; is:baked when one is baked
+//   is:vse        has Video Sequencer strips
+//   is:new, is:modified   since the previous scan of the root
+//   <field>:value  a custom field contains value (see extractors.rs)
+// Anything else matches name, path or note. Quotes keep spaces together:
+// tag:"to review". Matching ignores case.
+
+#[derive(Debug)]
+pub enum Query {
+    And(Vec<Query>),
+    Or(Vec<Query>),
+    Not(Box<Query>),
+    Term(Term),
+}
+
+#[derive(Debug)]
+pub enum Term {
+    Tag(String),
+    Label(String),
+    Engine(String),
+    Hdri(String),
+    Format(String),
+    Version(String),
+    Rating(u8),
+    Stale(u8),
+    In(String),
+    Favorite,
+    Cloud,
+    ReadOnly,
+    Trash,
+    Compositing,
+    Autopack,
+    Simulation,
+    Baked,
+    Sequencer,
+    New,
+    Modified,
+    Field(String, String, String), // name, value, the whole word as text
+    Text(String),
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Word(String),
+}
+
+fn invalid(msg: impl Into<String>) -> AppError {
+    let msg = msg.into();
+    AppError::new(ErrorKind::InvalidInput, format!("Query: {}", msg))
+        .with_code("query_invalid")
+        .with_param("detail", msg)
+}
+
+fn tokenize(s: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut chars = s.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            _ => {
+                if c == '-' {
+                    chars.next();
+                    if chars.peek().is_some_and(|n| !n.is_whitespace()) {
+                        tokens.push(Token::Not);
+                        continue;
+                    }
+                }
+                let (mut word, mut quoted, mut in_quotes) = (String::new(), false, false);
+                if c == '-' {
+                    word.push('-');
+                }
+                while let Some(&c) = chars.peek() {
+                    if !in_quotes && (c.is_whitespace() || c == '(' || c == ')') {
+                        break;
+                    }
+                    chars.next();
+                    if c == '"' {
+                        in_quotes = !in_quotes;
+                        quoted = true;
+                    } else {
+                        word.push(c);
+                    }
+                }
+                tokens.push(match word.as_str() {
+                    "AND" if !quoted => Token::And,
+                    "OR" if !quoted => Token::Or,
+                    "NOT" if !quoted => Token::Not,
+                    _ => Token::Word(word),
+                });
+            }
+        }
+    }
+    tokens
+}
+
+fn term(word: &str) -> Result<Term, AppError> {
+    let Some((field, value)) = word.split_once(':') else {
+        return Ok(Term::Text(word.to_lowercase()));
+    };
+    let value = value.trim().to_lowercase();
+    let known = matches!(
+        field.to_lowercase().as_str(),
+        "tag"
+            | "label"
+            | "engine"
+            | "hdri"
+            | "format"
+            | "version"
+            | "rating"
+            | "stale"
+            | "in"
+            | "is"
+    );
+    if known && value.is_empty() {
+        return Err(invalid(format!("\"{}:\" needs a value", field)));
+    }
+    Ok(match field.to_lowercase().as_str() {
+        "tag" => Term::Tag(value),
+        "label" => Term::Label(value),
+        "engine" => Term::Engine(value),
+        "hdri" => Term::Hdri(value),
+        "format" => Term::Format(value),
+        "version" => Term::Version(value),
+        "in" => Term::In(value),
+        "rating" => Term::Rating(
+            value
+                .parse()
+                .map_err(|_| invalid(format!("rating needs a number, got \"{}\"", value)))?,
+        ),
+        "stale" => Term::Stale(
+            value
+                .parse()
+                .map_err(|_| invalid(format!("stale needs a number, got \"{}\"", value)))?,
+        ),
+        "is" => match value.as_str() {
+            "fav" | "favorite" => Term::Favorite,
+            "cloud" => Term::Cloud,
+            "readonly" | "locked" => Term::ReadOnly,
+            "trash" => Term::Trash,
+            "comp" => Term::Compositing,
+            "autopack" => Term::Autopack,
+            "sim" => Term::Simulation,
+            "baked" => Term::Baked,
+            "vse" => Term::Sequencer,
+            "new" => Term::New,
+            "modified" => Term::Modified,
+            _ => return Err(invalid(format!("unknown flag \"is:{}\"", value))),
+        },
+        _ => Term::Field(field.to_lowercase(), value, word.to_lowercase()),
+    })
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<&Token> {
+        self.pos += 1;
+        self.tokens.get(self.pos - 1)
+    }
+
+    fn or(&mut self) -> Result<Query, AppError> {
+        let mut parts = vec![self.and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            parts.push(self.and()?);
+        }
+        Ok(if parts.len() == 1 {
+            parts.remove(0)
+        } else {
+            Query::Or(parts)
+        })
+    }
+
+    fn and(&mut self) -> Result<Query, AppError> {
+        let mut parts = vec![self.unary()?];
+        loop {
+            match self.peek() {
+                None | Some(Token::Or) | Some(Token::Close) => break,
+                Some(Token::And) => {
+                    self.next();
+                }
+                _ => {}
+            }
+            parts.push(self.unary()?);
+        }
+        Ok(if parts.len() == 1 {
+            parts.remove(0)
+        } else {
+            Query::And(parts)
+        })
+    }
+
+    fn unary(&mut self) -> Result<Query, AppError> {
+        match self.next() {
+            Some(Token::Not) => Ok(Query::Not(Box::new(self.unary()?))),
+            Some(Token::Open) => {
+                let inner = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(inner),
+                    _ => Err(invalid("missing \")\"")),
+                }
+            }
+            Some(Token::Word(w)) => {
+                let w = w.clone();
+                Ok(Query::Term(term(&w)?))
+            }
+            Some(Token::Close) => Err(invalid("unexpected \")\"")),
+            Some(Token::And) | Some(Token::Or) => Err(invalid("AND/OR needs a term on both sides")),
+            None => Err(invalid("unexpected end")),
+        }
+    }
+}
+
+impl Query {
+    pub fn parse(s: &str) -> Result<Query, AppError> {
+        let tokens = tokenize(s);
+        if tokens.is_empty() {
+            return Err(invalid("empty"));
+        }
+        let mut parser = Parser { tokens, pos: 0 };
+        let q = parser.or()?;
+        match parser.peek() {
+            None => Ok(q),
+            Some(_) => Err(invalid("unexpected \")\"")),
+        }
+    }
+
+    // `f` must carry user data (see annotate_user_data)
+    pub fn matches(&self, f: &FlatFile) -> bool {
+        match self {
+            Query::And(parts) => parts.iter().all(|q| q.matches(f)),
+            Query::Or(parts) => parts.iter().any(|q| q.matches(f)),
+            Query::Not(q) => !q.matches(f),
+            Query::Term(t) => t.matches(f),
+        }
+    }
+}
+
+fn eq(a: Option<&str>, b: &str) -> bool {
+    a.is_some_and(|a| a.to_lowercase() == b)
+}
+
+impl Term {
+    fn matches(&self, f: &FlatFile) -> bool {
+        match self {
+            Term::Tag(t) => f.tags.iter().any(|x| x.to_lowercase() == *t),
+            Term::Label(l) => eq(f.label.as_deref(), l),
+            Term::Engine(e) => eq(f.render_engine.as_deref(), e),
+            Term::Hdri(h) => f
+                .environments
+                .iter()
+                .any(|e| e.path.to_lowercase().contains(h)),
+            Term::Format(x) => f.format == *x,
+            Term::In(c) => f.collections.iter().any(|x| x.to_lowercase() == *c),
+            // "4.2" matches 4.2 and 4.2.1 but not 4.20
+            Term::Version(v) => f.blender_version.as_deref().is_some_and(|have| {
+                have == v
+                    || have
+                        .strip_prefix(v.as_str())
+                        .is_some_and(|r| r.starts_with('.'))
+            }),
+            Term::Rating(min) => f.rating.unwrap_or(0) >= *min,
+            Term::Stale(min) => f.staleness.as_ref().is_some_and(|s| s.score >= *min),
+            Term::Favorite => f.favorite,
+            Term::Cloud => f.cloud_only,
+            Term::ReadOnly => f.read_only,
+            Term::Trash => f.trash.is_some(),
+            Term::Compositing => f.compositing == Some(true),
+            Term::Autopack => f.autopack == Some(true),
+            Term::Simulation => !f.simulations.is_empty(),
+            Term::Baked => f.simulations.iter().any(|s| s.baked),
+            Term::Sequencer => f.sequencer.is_some(),
+            Term::New => f.is_new,
+            Term::Modified => f.is_modified_since_last_scan,
+            // `c:\work` has no field "c", so words like it stay text
+            Term::Field(name, value, word) => match f.fields.get(name) {
+                Some(have) => have.to_lowercase().contains(value),
+                None => Term::Text(word.clone()).matches(f),
+            },
+            Term::Text(s) => [Some(&f.name), Some(&f.path), f.note.as_ref()]
+                .into_iter()
+                .flatten()
+                .any(|x| x.to_lowercase().contains(s)),
+        }
+    }
+}
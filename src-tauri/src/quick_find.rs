@@ -0,0 +1,144 @@
+use crate::db;
+use crate::error::AppError;
+use crate::paths;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+// -----------------------------
+// Quick open
+// -----------------------------
+// Name lookups for the "jump to blend" palette, across every folder ever
+// scanned (the per-root file lists history.rs keeps). File names are
+// indexed by character trigram, so a query only checks the files that
+// contain its rarest trigram. The index is built on first use and dropped
+// whenever a scan stores a new file list.
+
+#[derive(Serialize, Clone)]
+pub struct QuickHit {
+    pub name: String,
+    pub path: String,
+    pub path_id: String,
+    pub folder: String,
+}
+
+struct Entry {
+    hit: QuickHit,
+    lower: String,
+}
+
+struct NameIndex {
+    entries: Vec<Entry>,
+    grams: HashMap<u64, Vec<u32>>, // trigram -> entries whose name has it
+}
+
+static INDEX: Mutex<Option<NameIndex>> = Mutex::new(None);
+
+// Three chars in one key (a char fits in 21 bits)
+fn trigrams(s: &str) -> impl Iterator<Item = u64> + '_ {
+    let chars: Vec<char> = s.chars().collect();
+    (0..chars.len().saturating_sub(2))
+        .map(move |i| (chars[i] as u64) << 42 | (chars[i + 1] as u64) << 21 | chars[i + 2] as u64)
+}
+
+fn build() -> Result<NameIndex, AppError> {
+    let path_ids: Vec<String> = db::with(|conn| {
+        let mut stmt = conn.prepare("SELECT DISTINCT path_id FROM scan_files")?;
+        let rows = stmt.query_map([], |r| r.get(0))?;
+        rows.collect()
+    })?;
+    let mut index = NameIndex {
+        entries: Vec::with_capacity(path_ids.len()),
+        grams: HashMap::new(),
+    };
+    for path_id in path_ids {
+        let path = paths::from_id(&path_id);
+        let Some(name) = path.file_name().map(|n| n.to_string_lossy().to_string()) else {
+            continue;
+        };
+        let lower = name.to_lowercase();
+        let i = index.entries.len() as u32;
+        for g in trigrams(&lower) {
+            let list = index.grams.entry(g).or_default();
+            if list.last() != Some(&i) {
+                list.push(i);
+            }
+        }
+        index.entries.push(Entry {
+            hit: QuickHit {
+                name,
+                path: path.to_string_lossy().to_string(),
+                path_id,
+                folder: path
+                    .parent()
+                    .map(|p| p.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+            },
+            lower,
+        });
+    }
+    Ok(index)
+}
+
+// Lower is better: whole name, name without extension, prefix, word start,
+// anywhere
+fn score(name: &str, q: &str) -> Option<u8> {
+    let at = name.find(q)?;
+    let stem = name.rsplit_once('.').map_or(name, |(stem, _)| stem);
+    Some(if name == q {
+        0
+    } else if stem == q {
+        1
+    } else if at == 0 {
+        2
+    } else if name[..at].ends_with(|c: char| !c.is_alphanumeric()) {
+        3
+    } else {
+        4
+    })
+}
+
+impl NameIndex {
+    fn find(&self, q: &str, limit: usize) -> Vec<QuickHit> {
+        let grams: Vec<u64> = trigrams(q).collect();
+        let candidates: Box<dyn Iterator<Item = &Entry>> = if grams.is_empty() {
+            // One or two characters: nothing to narrow by
+            Box::new(self.entries.iter())
+        } else {
+            let rarest = grams
+                .iter()
+                .map(|g| self.grams.get(g).map_or(&[][..], Vec::as_slice))
+                .min_by_key(|list| list.len())
+                .unwrap_or(&[]);
+            Box::new(rarest.iter().map(|&i| &self.entries[i as usize]))
+        };
+        let mut hits: Vec<(u8, &Entry)> = candidates
+            .filter_map(|e| Some((score(&e.lower, q)?, e)))
+            .collect();
+        hits.sort_by(|a, b| {
+            (a.0, a.1.lower.len(), &a.1.lower).cmp(&(b.0, b.1.lower.len(), &b.1.lower))
+        });
+        hits.into_iter()
+            .take(limit)
+            .map(|(_, e)| e.hit.clone())
+            .collect()
+    }
+}
+
+// Best matches for `query` by file name, best first
+pub fn search(query: &str, limit: usize) -> Result<Vec<QuickHit>, AppError> {
+    let q = query.trim().to_lowercase();
+    if q.is_empty() || limit == 0 {
+        return Ok(Vec::new());
+    }
+    let mut index = INDEX.lock().unwrap();
+    if index.is_none() {
+        *index = Some(build()?);
+    }
+    Ok(index.as_ref().map_or_else(Vec::new, |i| i.find(&q, limit)))
+}
+
+// The stored file lists changed; the next search rebuilds
+pub fn invalidate() {
+    *INDEX.lock().unwrap() = None;
+}
@@ -0,0 +1,61 @@
+use crate::db;
+use crate::error::{AppError, ErrorKind};
+use crate::FlatFile;
+use rusqlite::{params, OptionalExtension};
+use std::collections::HashMap;
+
+// -----------------------------
+// Star ratings (1–5 per file, keyed by path id)
+// -----------------------------
+
+pub const MAX_RATING: u8 = 5;
+
+// `None` (or 0) clears the rating
+pub fn set(path_id: &str, rating: Option<u8>) -> Result<Option<u8>, AppError> {
+    let rating = rating.filter(|r| *r > 0);
+    if rating.is_some_and(|r| r > MAX_RATING) {
+        return Err(AppError::new(
+            ErrorKind::InvalidInput,
+            format!("Ratings go from 1 to {}", MAX_RATING),
+        )
+        .with_code("rating_range")
+        .with_param("max", MAX_RATING));
+    }
+    db::with(|conn| match rating {
+        Some(r) => conn.execute(
+            "INSERT INTO ratings (path_id, rating) VALUES (?1, ?2)
+             ON CONFLICT(path_id) DO UPDATE SET rating = excluded.rating",
+            params![path_id, r],
+        ),
+        None => conn.execute("DELETE FROM ratings WHERE path_id = ?1", [path_id]),
+    })?;
+    Ok(rating)
+}
+
+pub fn get(path_id: &str) -> Result<Option<u8>, AppError> {
+    db::with(|conn| {
+        conn.query_row(
+            "SELECT rating FROM ratings WHERE path_id = ?1",
+            [path_id],
+            |r| r.get(0),
+        )
+        .optional()
+    })
+}
+
+// Fills `rating` on result files
+pub fn annotate(files: &mut [FlatFile]) {
+    let map: Result<HashMap<String, u8>, AppError> = db::with(|conn| {
+        let mut stmt = conn.prepare("SELECT path_id, rating FROM ratings")?;
+        let rows = stmt.query_map([], |r| Ok((r.get(0)?, r.get(1)?)))?;
+        rows.collect()
+    });
+    match map {
+        Ok(map) => {
+            for f in files {
+                f.rating = map.get(&f.path_id).copied();
+            }
+        }
+        Err(e) => tracing::warn!("loading ratings failed: {}", e),
+    }
+}
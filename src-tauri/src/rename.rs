@@ -0,0 +1,454 @@
+use crate::error::{AppError, ErrorKind};
+use crate::jobs::JobCtx;
+use crate::{file_ids, formats, paths, read_blend_header, sidecar};
+use chrono::{DateTime, Local};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// -----------------------------
+// Batch rename
+// -----------------------------
+// New names come from a template of tokens, then an optional regex
+// find/replace over the result:
+//
+//   {name}     current name without extension
+//   {version}  Blender version that saved the file (4.2.1)
+//   {date}     modified date (2024-05-31)
+//   {parent}   folder name
+//   {n}        counter; {n:4} pads to 4 digits
+//
+// The extension always stays. A `.blend`'s backups (`.blend1`…) and
+// sidecar are renamed with it, and tags, ratings and the rest follow the
+// file (see file_ids.rs).
+
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub struct RenamePattern {
+    pub template: String,
+    pub find: Option<String>, // regex
+    pub replace: String,      // `$1` / `${name}` refer to `find`'s groups
+    pub start: u64,           // first counter value
+    pub step: u64,
+    pub padding: usize, // digits of a plain {n}
+}
+
+impl Default for RenamePattern {
+    fn default() -> Self {
+        Self {
+            template: "{name}".to_string(),
+            find: None,
+            replace: String::new(),
+            start: 1,
+            step: 1,
+            padding: 3,
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct RenameItem {
+    pub from: String,
+    pub from_id: String,
+    pub to: String,
+    pub to_id: String,
+    pub name: String,            // the new file name
+    pub problem: Option<String>, // why it can't be renamed; None when it can
+    pub unchanged: bool,
+}
+
+#[derive(Serialize, Clone, Default)]
+pub struct RenameReport {
+    pub renamed: Vec<RenameItem>,
+    pub skipped: Vec<RenameItem>, // with a problem, or unchanged
+    pub errors: Vec<AppError>,
+}
+
+const INVALID_CHARS: &[char] = &['/', '\\', ':', '*', '?', '"', '<', '>', '|'];
+// Counter digits; more is no name anyone wants, and the width is allocated
+const MAX_PADDING: usize = 12;
+
+fn modified_date(path: &Path) -> String {
+    fs::metadata(path)
+        .and_then(|m| m.modified())
+        .map(|t| DateTime::<Local>::from(t).format("%Y-%m-%d").to_string())
+        .unwrap_or_default()
+}
+
+fn fill(template: &str, path: &Path, counter: u64, padding: usize) -> String {
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let padding = padding.min(MAX_PADDING);
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        out.push_str(&rest[..start]);
+        let token = &rest[start + 1..start + len];
+        let value = match token.split_once(':') {
+            Some(("n", digits)) => {
+                let width = digits.parse().unwrap_or(padding).min(MAX_PADDING);
+                format!("{:0width$}", counter, width = width)
+            }
+            _ => match token {
+                "name" => stem.to_string(),
+                "n" => format!("{:0width$}", counter, width = padding),
+                "date" => modified_date(path),
+                "parent" => path
+                    .parent()
+                    .and_then(|p| p.file_name())
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default(),
+                "version" => read_blend_header(path)
+                    .ok()
+                    .and_then(|b| b.version)
+                    .unwrap_or_default(),
+                // Unknown tokens stay as typed
+                other => format!("{{{}}}", other),
+            },
+        };
+        out.push_str(&value);
+        rest = &rest[start + len + 1..];
+    }
+    out.push_str(rest);
+    out
+}
+
+// New names for `files`, in order (the counter follows it). Nothing is
+// touched on disk.
+pub fn plan(files: &[PathBuf], pattern: &RenamePattern) -> Result<Vec<RenameItem>, AppError> {
+    let mut items = plan_items(files, pattern)?;
+    schedule(&mut items);
+    Ok(items)
+}
+
+fn plan_items(files: &[PathBuf], pattern: &RenamePattern) -> Result<Vec<RenameItem>, AppError> {
+    let find = match pattern.find.as_deref().filter(|f| !f.is_empty()) {
+        Some(f) => Some(Regex::new(f).map_err(|e| {
+            AppError::new(
+                ErrorKind::InvalidInput,
+                format!("Invalid find pattern: {}", e),
+            )
+        })?),
+        None => None,
+    };
+    let sources: HashSet<String> = files.iter().map(|p| paths::to_id(p)).collect();
+    let mut taken: HashSet<String> = HashSet::new();
+    let mut items = Vec::with_capacity(files.len());
+    for (i, from) in files.iter().enumerate() {
+        let counter = pattern.start + pattern.step * i as u64;
+        let mut stem = fill(&pattern.template, from, counter, pattern.padding);
+        if let Some(re) = &find {
+            stem = re.replace_all(&stem, pattern.replace.as_str()).to_string();
+        }
+        let stem = stem.trim();
+        let name = match from.extension() {
+            Some(ext) => format!("{}.{}", stem, ext.to_string_lossy()),
+            None => stem.to_string(),
+        };
+        let to = from.with_file_name(&name);
+        let (from_id, to_id) = (paths::to_id(from), paths::to_id(&to));
+        let unchanged = from_id == to_id;
+        // Case-only renames on case-insensitive drives hit the file itself;
+        // on case-sensitive ones the other name is another file
+        let same_file = !unchanged && is_same_file(from, &to);
+        let problem = if !from.is_file() {
+            Some("File not found".to_string())
+        } else if stem.is_empty() {
+            Some("New name is empty".to_string())
+        } else if stem.contains(INVALID_CHARS) {
+            Some("New name has characters file names can't use".to_string())
+        } else if !taken.insert(to_id.to_lowercase()) {
+            Some("Another file in the batch gets this name".to_string())
+        } else if !unchanged && !same_file && to.exists() && !sources.contains(&to_id) {
+            Some("A file with this name already exists".to_string())
+        } else if companion_moves(from, &to)
+            .iter()
+            .any(|(a, b)| b.exists() && !is_same_file(a, b))
+        {
+            Some("Backups or a sidecar with this name already exist".to_string())
+        } else {
+            None
+        };
+        items.push(RenameItem {
+            from: from.to_string_lossy().to_string(),
+            from_id,
+            to: to.to_string_lossy().to_string(),
+            to_id,
+            name,
+            problem,
+            unchanged,
+        });
+    }
+    Ok(items)
+}
+
+// Order in which the cleared items are renamed: one taking a name another
+// item of the batch gives up goes after it. Items waiting on a name that
+// is never freed (a swap, or a file that is skipped) get a problem, so
+// the plan shows what `execute` will do.
+fn schedule(items: &mut [RenameItem]) -> Vec<usize> {
+    let moves = |item: &RenameItem| item.problem.is_none() && !item.unchanged;
+    // Lowercase, as on case-insensitive drives a name held in another case
+    // is held all the same
+    let mut held: HashSet<String> = items.iter().map(|i| i.from_id.to_lowercase()).collect();
+    let mut pending: Vec<usize> = (0..items.len()).filter(|&i| moves(&items[i])).collect();
+    let mut order = Vec::with_capacity(pending.len());
+    loop {
+        let before = pending.len();
+        pending.retain(|&i| {
+            let (from, to) = (
+                items[i].from_id.to_lowercase(),
+                items[i].to_id.to_lowercase(),
+            );
+            if from != to && held.contains(&to) {
+                return true;
+            }
+            held.remove(&from);
+            order.push(i);
+            false
+        });
+        if pending.is_empty() || pending.len() == before {
+            break;
+        }
+    }
+    for i in pending {
+        items[i].problem = Some("Another file of the batch keeps this name".to_string());
+    }
+    order
+}
+
+fn is_same_file(a: &Path, b: &Path) -> bool {
+    same_file::is_same_file(a, b).unwrap_or(false)
+}
+
+// Backups and the sidecar of `from`, with where they go
+fn companion_moves(from: &Path, to: &Path) -> Vec<(PathBuf, PathBuf)> {
+    let mut moves: Vec<(PathBuf, PathBuf)> = Vec::new();
+    if let Some(name) = to.file_name().and_then(|n| n.to_str()) {
+        for (i, backup) in formats::backups_of(from).into_iter().enumerate() {
+            moves.push((backup, to.with_file_name(format!("{}{}", name, i + 1))));
+        }
+    }
+    let sidecar = sidecar::path_for(from);
+    if sidecar.is_file() {
+        moves.push((sidecar, sidecar::path_for(to)));
+    }
+    moves
+}
+
+// Backups and the sidecar go along; failing to move those is logged only.
+// A target that appeared since `plan` is left alone, as rename replaces it.
+fn rename_companions(from: &Path, to: &Path) {
+    for (a, b) in companion_moves(from, to) {
+        if b.exists() && !is_same_file(&a, &b) {
+            tracing::warn!(path = %a.display(), "not renamed along, {} exists", b.display());
+            continue;
+        }
+        if let Err(e) = fs::rename(&a, &b) {
+            tracing::warn!(path = %a.display(), "renaming along failed: {}", e);
+        }
+    }
+}
+
+fn rename_one(item: &RenameItem) -> Result<(), AppError> {
+    let (from, to) = (Path::new(&item.from), Path::new(&item.to));
+    fs::rename(from, to).map_err(|e| AppError::io(&e, from))?;
+    rename_companions(from, to);
+    file_ids::moved(&item.from_id, &item.to_id)?;
+    Ok(())
+}
+
+// Renames what `plan` cleared, in `schedule` order. Targets are checked
+// again right before each rename, so a file that appeared since (or one a
+// failed rename left in place) is never replaced.
+pub fn execute(
+    ctx: &JobCtx,
+    files: &[PathBuf],
+    pattern: &RenamePattern,
+) -> Result<RenameReport, AppError> {
+    let mut items = plan_items(files, pattern)?;
+    let order = schedule(&mut items);
+    ctx.set_totals(items.len() as u64, 0);
+    let mut report = RenameReport::default();
+    let mut slots: Vec<Option<RenameItem>> = items.into_iter().map(Some).collect();
+    for i in order {
+        let Some(mut item) = slots[i].take() else {
+            continue;
+        };
+        ctx.check_cancelled()?;
+        ctx.set_current(Path::new(&item.from));
+        let (from, to) = (Path::new(&item.from), Path::new(&item.to));
+        if to.exists() && !is_same_file(from, to) {
+            item.problem = Some("A file with this name already exists".to_string());
+            report.skipped.push(item);
+        } else {
+            match rename_one(&item) {
+                Ok(()) => report.renamed.push(item),
+                Err(e) => report.errors.push(e),
+            }
+        }
+        ctx.item_done();
+    }
+    // Problems and unchanged names, in the batch's order
+    for item in slots.into_iter().flatten() {
+        report.skipped.push(item);
+        ctx.item_done();
+    }
+    tracing::info!(
+        renamed = report.renamed.len(),
+        skipped = report.skipped.len(),
+        errors = report.errors.len(),
+        "batch rename finished"
+    );
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // A fresh folder holding empty files named `names`
+    fn folder(test: &str, names: &[&str]) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bff-rename-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        for name in names {
+            fs::write(dir.join(name), b"").unwrap();
+        }
+        dir
+    }
+
+    fn pattern(template: &str) -> RenamePattern {
+        RenamePattern {
+            template: template.to_string(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn fill_expands_tokens() {
+        let path = Path::new("/shots/010/scene.blend");
+        assert_eq!(fill("{parent}_{name}_{n}", path, 7, 3), "010_scene_007");
+        assert_eq!(fill("{n:5}", path, 42, 3), "00042");
+        assert_eq!(fill("{what} {n", path, 1, 0), "{what} {n");
+    }
+
+    #[test]
+    fn fill_clamps_padding() {
+        let path = Path::new("a.blend");
+        assert_eq!(fill("{n:100}", path, 1, 3).len(), MAX_PADDING);
+        assert_eq!(fill("{n}", path, 1, usize::MAX).len(), MAX_PADDING);
+    }
+
+    #[test]
+    fn fill_leaves_braces_in_values_alone() {
+        let path = Path::new("/x/{n}{parent}.blend");
+        assert_eq!(fill("{name}-{n}", path, 1, 1), "{n}{parent}-1");
+    }
+
+    #[test]
+    fn same_name_twice_in_a_batch_is_a_problem() {
+        let dir = folder("collide", &["a.blend", "b.blend", "C.blend"]);
+        let files: Vec<PathBuf> = ["a.blend", "b.blend"].iter().map(|n| dir.join(n)).collect();
+        let items = plan(&files, &pattern("shot")).unwrap();
+        assert_eq!(items[0].problem, None);
+        assert!(items[1].problem.is_some());
+
+        // Names differing only in case collide too
+        let files = vec![dir.join("a.blend"), dir.join("C.blend")];
+        let mut p = pattern("{name}");
+        p.find = Some("^a$".to_string());
+        p.replace = "c".to_string();
+        let items = plan(&files, &p).unwrap();
+        assert_eq!(items[0].name, "c.blend");
+        assert!(items[1].problem.is_some());
+        // and `C.blend` keeps its name, so `a.blend` can't have it
+        assert!(items[0].problem.is_some());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn names_freed_by_the_batch_can_be_taken() {
+        let dir = folder("shift", &["1.blend", "2.blend"]);
+        let files = vec![dir.join("1.blend"), dir.join("2.blend")];
+        let mut p = pattern("{n}");
+        p.start = 2;
+        p.padding = 0;
+        let mut items = plan_items(&files, &p).unwrap();
+        assert_eq!(items[0].name, "2.blend");
+        // `2.blend` moves on before `1.blend` takes its name
+        assert_eq!(schedule(&mut items), vec![1, 0]);
+        assert!(items.iter().all(|i| i.problem.is_none()));
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    fn item(from: &str, to: &str, problem: Option<&str>) -> RenameItem {
+        RenameItem {
+            from: from.to_string(),
+            from_id: from.to_string(),
+            to: to.to_string(),
+            to_id: to.to_string(),
+            name: to.to_string(),
+            problem: problem.map(str::to_string),
+            unchanged: from == to,
+        }
+    }
+
+    #[test]
+    fn chains_go_in_order() {
+        let mut items = vec![
+            item("a", "b", None),
+            item("b", "c", None),
+            item("c", "d", None),
+        ];
+        assert_eq!(schedule(&mut items), vec![2, 1, 0]);
+    }
+
+    #[test]
+    fn names_never_freed_are_a_problem() {
+        // A swap
+        let mut items = vec![item("a", "b", None), item("b", "a", None)];
+        assert!(schedule(&mut items).is_empty());
+        assert!(items.iter().all(|i| i.problem.is_some()));
+
+        // The name of a file the batch skips, or leaves as it is
+        let mut items = vec![
+            item("a", "x", Some("skipped")),
+            item("b", "a", None),
+            item("c", "c", None),
+            item("d", "C", None),
+        ];
+        assert!(schedule(&mut items).is_empty());
+        assert!(items[1].problem.is_some() && items[3].problem.is_some());
+    }
+
+    #[test]
+    fn case_only_rename_onto_another_file_is_a_clash() {
+        let dir = folder("case-other", &["scene.blend", "Scene.blend"]);
+        let (lower, upper) = (dir.join("scene.blend"), dir.join("Scene.blend"));
+        // One file on case-insensitive drives; nothing to test there
+        if is_same_file(&lower, &upper) {
+            let _ = fs::remove_dir_all(&dir);
+            return;
+        }
+        let mut p = pattern("{name}");
+        p.find = Some("scene".to_string());
+        p.replace = "Scene".to_string();
+        let items = plan(&[lower], &p).unwrap();
+        assert_eq!(items[0].name, "Scene.blend");
+        assert!(items[0].problem.is_some());
+        let _ = fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn existing_companion_target_is_a_problem() {
+        let dir = folder("companion", &["a.blend", "a.blend1", "b.blend1"]);
+        let items = plan(&[dir.join("a.blend")], &pattern("b")).unwrap();
+        assert!(items[0].problem.is_some());
+        let _ = fs::remove_dir_all(&dir);
+    }
+}
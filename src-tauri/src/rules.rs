@@ -0,0 +1,223 @@
+use crate::error::{AppError, ErrorKind};
+use crate::{collections, labels, paths, settings, tags, FlatFile};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+
+// -----------------------------
+// Auto-tagging rules
+// -----------------------------
+// Rules match a file's path and give it tags, a label and a collection
+// (a project). Globs are the easy form: `**` spans folders, `*` and `?`
+// stay within one, and `{shot}` captures one folder or name part.
+// Regexes capture with named or numbered groups. Captures fill `{shot}` or
+// `{1}` in the tags and collection, so
+//
+//   **/{shot}/{department}/*.blend  ->  tags {shot}, {department}
+//
+// tags `.../SH010/anim/x.blend` with SH010 and anim. Rules only add: a
+// rule's tag removed by hand comes back on the next scan, and a label is
+// only set on files without one.
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+#[serde(default)]
+pub struct Rule {
+    pub pattern: String, // matched against the whole path, `/`-separated
+    pub regex: bool,     // else a glob
+    pub tags: Vec<String>,
+    pub label: Option<String>,      // from the label palette
+    pub collection: Option<String>, // created when missing
+}
+
+struct Compiled<'a> {
+    rule: &'a Rule,
+    re: Regex,
+}
+
+// Glob to an anchored, case-insensitive regex
+fn glob_regex(glob: &str) -> String {
+    let mut out = String::from("(?i)^");
+    let mut chars = glob.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '*' if chars.peek() == Some(&'*') => {
+                chars.next();
+                // `**/` also matches no folder at all
+                if chars.peek() == Some(&'/') {
+                    chars.next();
+                    out.push_str("(?:.*/)?");
+                } else {
+                    out.push_str(".*");
+                }
+            }
+            '*' => out.push_str("[^/]*"),
+            '?' => out.push_str("[^/]"),
+            '{' => {
+                let name: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                out.push_str(&format!("(?P<{}>[^/]+?)", name));
+            }
+            c => out.push_str(&regex::escape(&c.to_string())),
+        }
+    }
+    out.push('$');
+    out
+}
+
+fn compile(rule: &Rule) -> Result<Regex, AppError> {
+    let source = if rule.regex {
+        rule.pattern.clone()
+    } else {
+        glob_regex(&rule.pattern)
+    };
+    Regex::new(&source).map_err(|e| {
+        AppError::new(
+            ErrorKind::InvalidInput,
+            format!("Rule \"{}\" is not valid: {}", rule.pattern, e),
+        )
+        .with_code("rule_invalid")
+        .with_param("pattern", &rule.pattern)
+    })
+}
+
+// Rejects rules that would fail when applied
+pub fn check(rules: &[Rule]) -> Result<(), AppError> {
+    for rule in rules {
+        if rule.pattern.trim().is_empty() {
+            return Err(AppError::new(
+                ErrorKind::InvalidInput,
+                "A rule needs a pattern",
+            ));
+        }
+        compile(rule)?;
+        if let Some(label) = &rule.label {
+            labels::check(label)?;
+        }
+    }
+    Ok(())
+}
+
+// `{name}` / `{1}` filled from the match; None when a capture is missing
+fn expand(template: &str, caps: &regex::Captures) -> Option<String> {
+    let mut out = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        let end = rest[start..].find('}')? + start;
+        let key = &rest[start + 1..end];
+        let value = match key.parse::<usize>() {
+            Ok(i) => caps.get(i),
+            Err(_) => caps.name(key),
+        }?;
+        out.push_str(&rest[..start]);
+        out.push_str(value.as_str());
+        rest = &rest[end + 1..];
+    }
+    out.push_str(rest);
+    let out = out.trim().to_string();
+    (!out.is_empty()).then_some(out)
+}
+
+#[derive(Serialize, Clone, Default)]
+pub struct Applied {
+    pub matched: usize, // files at least one rule matched
+    #[serde(skip)]
+    pub path_ids: Vec<String>, // those files
+    pub tags: usize,    // tag assignments made or kept
+    pub labels: usize,
+    pub collections: usize,
+}
+
+// Applies the rules in settings to `files`; rules that no longer compile
+// are skipped
+pub fn apply(files: &[FlatFile]) -> Result<Applied, AppError> {
+    let rules = settings::get().tag_rules;
+    let compiled: Vec<Compiled> = rules
+        .iter()
+        .filter_map(|rule| match compile(rule) {
+            Ok(re) => Some(Compiled { rule, re }),
+            Err(e) => {
+                tracing::warn!("skipping tag rule: {}", e);
+                None
+            }
+        })
+        .collect();
+    let mut report = Applied::default();
+    if compiled.is_empty() {
+        return Ok(report);
+    }
+
+    let mut by_tag: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut label_of: HashMap<&str, &str> = HashMap::new(); // first matching rule's
+    let mut by_collection: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for f in files {
+        let path = paths::from_id(&f.path_id)
+            .to_string_lossy()
+            .replace('\\', "/");
+        let mut matched = false;
+        for c in &compiled {
+            let Some(caps) = c.re.captures(&path) else {
+                continue;
+            };
+            matched = true;
+            for t in &c.rule.tags {
+                if let Some(tag) = expand(t, &caps) {
+                    by_tag.entry(tag).or_default().push(f.path_id.clone());
+                }
+            }
+            if let Some(label) = &c.rule.label {
+                label_of.entry(&f.path_id).or_insert(label);
+            }
+            if let Some(name) = c.rule.collection.as_deref().and_then(|t| expand(t, &caps)) {
+                by_collection
+                    .entry(name)
+                    .or_default()
+                    .push(f.path_id.clone());
+            }
+        }
+        if matched {
+            report.matched += 1;
+            report.path_ids.push(f.path_id.clone());
+        }
+    }
+
+    for (tag, ids) in &by_tag {
+        tags::assign(ids, tag)?;
+        report.tags += ids.len();
+    }
+    // A label set by hand stays
+    let own = labels::all()?;
+    let mut by_label: BTreeMap<&str, Vec<String>> = BTreeMap::new();
+    for (id, label) in label_of
+        .into_iter()
+        .filter(|(id, _)| !own.contains_key(*id))
+    {
+        by_label.entry(label).or_default().push(id.to_string());
+    }
+    for (label, ids) in &by_label {
+        labels::set(ids, Some(label))?;
+        report.labels += ids.len();
+    }
+    if !by_collection.is_empty() {
+        let existing = collections::list()?;
+        for (name, ids) in &by_collection {
+            let id = match existing
+                .iter()
+                .find(|c| c.name == *name && c.query.is_none())
+            {
+                Some(c) => c.id,
+                None => collections::create(name, None)?.id,
+            };
+            collections::add(id, ids)?;
+            report.collections += ids.len();
+        }
+    }
+    if report.matched > 0 {
+        tracing::info!(
+            files = report.matched,
+            tags = report.tags,
+            labels = report.labels,
+            collections = report.collections,
+            "tag rules applied"
+        );
+    }
+    Ok(report)
+}
@@ -0,0 +1,1681 @@
+use crate::archive;
+use crate::checkpoint::{self, Checkpointer, Resumed};
+use crate::cloud;
+use crate::elevate;
+use crate::enrich;
+use crate::error::{AppError, ErrorKind};
+use crate::exclude::{Exclusions, GlobFilter};
+use crate::extractors;
+use crate::file_ids;
+use crate::formats;
+use crate::handles::RateLimiter;
+use crate::history;
+use crate::jobs::JobCtx;
+use crate::links::{self, LinkEntry, LinkKind};
+use crate::magic;
+use crate::netfs::{self, NetIo};
+use crate::paths;
+use crate::rules;
+use crate::settings;
+use crate::sidecar;
+use crate::staleness;
+use crate::trash::{self, TrashInfo};
+use crate::volume;
+use crate::ws;
+use crate::xattrs;
+use crate::{
+    build_tree_nodes, emit_to_owner, epoch_ms, extract_block_info, insert_dir, insert_file,
+    join_trees, mirror_user_data, read_blend_header, set_volume_offline, BlendInfo, DirError,
+    DirNode, FileMeta, FlatFile, ScanFileFound, ScanPoll, ScanResult, ScanState, SkippedPath,
+    TreeNode, TreeOrder, EVENT_SCAN_DONE, EVENT_SCAN_FILE_FOUND, EVENT_SCAN_PROGRESS,
+    PHASE_BUILDING_TREE, PHASE_EXTRACTING_THUMBNAILS, PHASE_FINISHED, PHASE_PARSING_HEADERS,
+    PROGRESS_EMIT_INTERVAL,
+};
+use chrono::{DateTime, Local};
+use same_file::Handle;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::ffi::OsString;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{
+    atomic::{AtomicBool, AtomicU64, Ordering},
+    mpsc, Arc, Mutex,
+};
+use std::thread;
+use std::time::{Duration, Instant};
+use walkdir::WalkDir;
+
+// -----------------------------
+// Scan options (all optional from the frontend)
+// -----------------------------
+#[derive(Deserialize, Clone, Default)]
+#[serde(default)]
+pub struct ScanOptions {
+    pub max_threads: Option<usize>,       // parser worker threads
+    pub max_thumbnail_bytes: Option<u64>, // total base64 thumbnail bytes kept
+    pub max_files: Option<u64>,           // stop indexing after this many blends
+    pub network: Option<bool>,            // force network-share handling on/off
+    pub network_timeout_ms: Option<u64>,  // per file operation on a share
+    pub max_network_io: Option<usize>,    // concurrent operations on a share
+    pub skip_cloud_files: Option<bool>,   // don't parse (and so download) placeholders
+    pub sniff_content: Option<bool>,      // also find blends by magic bytes, not just .blend
+    pub follow_junctions: Option<bool>,   // descend into NTFS junctions (deduped)
+    pub follow_symlinks: Option<bool>,    // descend into directory symlinks (cycles skipped)
+    pub skip_bundles: Option<bool>,       // treat macOS packages as opaque (default on macOS)
+    pub include_trash: Option<bool>,      // also index the OS trash / recycle bin
+    pub use_exclusions: Option<bool>,     // apply folder exclusions (default: drive roots only)
+    pub profile: Option<ScanProfile>,     // presets for the options below
+    pub max_opens_per_sec: Option<u32>,   // pace file opens (antivirus scans each one)
+    pub parse_depth: Option<ParseDepth>,  // how much of each blend is read
+    pub deep_parse: Option<bool>,         // older form: true = deep, false = header
+    pub compact_tree: Option<bool>,       // merge single-child folder chains in the tree
+    pub tree_order: Option<TreeOrder>,    // see `resort_tree` to change it afterwards
+    pub other_formats: Option<bool>,      // also list .fbx/.obj/.gltf/.usd/.abc (no parsing)
+    pub backups: Option<bool>,            // also list .blend1/.blend2…, grouped in the tree
+    pub relative_paths: Option<bool>,     // display paths from the scan root; ids stay absolute
+    pub counts_only_tree: Option<bool>,   // huge scans: every tree level fetched on expand
+    pub archives: Option<bool>,           // also list blends inside .zip files (read in place)
+    pub use_index: Option<bool>,          // reuse unchanged files of the last scan (checkpoint.rs)
+    pub include: Option<Vec<String>>,     // globs; only matching files are listed (exclude.rs)
+    pub exclude: Option<Vec<String>>,     // globs of folders and files to skip
+}
+
+#[derive(Deserialize, Clone, Copy, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScanProfile {
+    #[default]
+    Default,
+    // For machines where on-access antivirus scans every opened blend: one
+    // parser thread, paced opens, headers only
+    Gentle,
+}
+
+const GENTLE_OPENS_PER_SEC: u32 = 10;
+
+// How much of each blend a scan reads; each level includes the ones before
+#[derive(Deserialize, Serialize, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ParseDepth {
+    Discover, // list files only, never opened
+    Header,   // 12-byte header: version, pointer size, endianness
+    Standard, // block scan: thumbnail and render engine guess
+    #[default]
+    Deep, // struct layouts too: scene settings, links, assets, counts
+}
+
+impl ParseDepth {
+    // Stored in scan checkpoints
+    pub fn level(self) -> u8 {
+        self as u8
+    }
+}
+
+// macOS packages: directories Finder shows as a single file
+const BUNDLE_EXTENSIONS: &[&str] = &[
+    "app",
+    "bundle",
+    "framework",
+    "plugin",
+    "kext",
+    "appex",
+    "photoslibrary",
+    "musiclibrary",
+    "tvlibrary",
+    "xcarchive",
+];
+
+fn is_bundle(dir: &Path) -> bool {
+    let Some(ext) = dir.extension().and_then(|e| e.to_str()) else {
+        return false;
+    };
+    BUNDLE_EXTENSIONS.contains(&ext.to_ascii_lowercase().as_str())
+        || dir.join("Contents").join("Info.plist").is_file()
+}
+
+// Parsing is mostly I/O bound; more threads than this just thrash the disk
+const DEFAULT_MAX_THREADS: usize = 8;
+
+impl ScanOptions {
+    fn gentle(&self) -> bool {
+        self.profile == Some(ScanProfile::Gentle)
+    }
+
+    fn worker_threads(&self) -> usize {
+        let available = thread::available_parallelism()
+            .map(|n| n.get())
+            .unwrap_or(1)
+            .min(DEFAULT_MAX_THREADS);
+        let default = if self.gentle() { 1 } else { available };
+        self.max_threads.unwrap_or(default).max(1)
+    }
+
+    fn open_limiter(&self) -> Option<RateLimiter> {
+        self.max_opens_per_sec
+            .or(self.gentle().then_some(GENTLE_OPENS_PER_SEC))
+            .map(RateLimiter::per_second)
+    }
+
+    fn parse_depth(&self) -> ParseDepth {
+        self.parse_depth.unwrap_or(match self.deep_parse {
+            Some(true) => ParseDepth::Deep,
+            Some(false) => ParseDepth::Header,
+            None if self.gentle() => ParseDepth::Header,
+            None => ParseDepth::Deep,
+        })
+    }
+
+    fn net_io(&self, root: &Path) -> NetIo {
+        let enabled = self.network.unwrap_or_else(|| netfs::is_network_path(root));
+        NetIo::new(
+            enabled,
+            self.network_timeout_ms
+                .map(Duration::from_millis)
+                .unwrap_or(netfs::DEFAULT_TIMEOUT),
+            self.max_network_io.unwrap_or(netfs::DEFAULT_MAX_CONCURRENT),
+        )
+    }
+
+    // Shared by every root of the scan and its watched changes
+    pub(crate) fn globs(&self) -> Result<GlobFilter, AppError> {
+        GlobFilter::new(
+            self.include.as_deref().unwrap_or_default(),
+            self.exclude.as_deref().unwrap_or_default(),
+        )
+    }
+
+    fn exclusions(&self, root: &Path) -> Option<Exclusions> {
+        self.uses_exclusions(root)
+            .then(|| Exclusions::new(&settings::get().exclusions))
+    }
+
+    fn uses_exclusions(&self, root: &Path) -> bool {
+        let drive_root = root.parent().is_none() || volume::volume_of(root) == root;
+        self.use_exclusions.unwrap_or(drive_root)
+    }
+
+    // What the elevated helper lists of a folder below `root`, as this scan
+    // would have; settings are resolved here, the helper runs as another user
+    pub(crate) fn helper_filter(&self, root: &Path, max_files: Option<u64>) -> elevate::Filter {
+        elevate::Filter {
+            root: paths::to_id(root),
+            include: self.include.clone().unwrap_or_default(),
+            exclude: self.exclude.clone().unwrap_or_default(),
+            exclusions: self
+                .uses_exclusions(root)
+                .then(|| settings::get().exclusions),
+            other_formats: self.other_formats.unwrap_or(false),
+            backups: self.backups.unwrap_or(false),
+            max_files,
+        }
+    }
+}
+
+// -----------------------------
+// Per-scan context shared by all phases and workers
+// -----------------------------
+pub(crate) struct ScanCtx {
+    pub app: tauri::AppHandle,
+    pub scan_id: u64,
+    pub state: Arc<ScanState>,
+    pub options: ScanOptions,
+    net: NetIo,
+    root: PathBuf,
+    exclusions: Option<Exclusions>,
+    globs: Arc<GlobFilter>,
+    opens: Option<RateLimiter>,
+    volume: PathBuf,
+    offline: AtomicBool,
+    last_emit: Mutex<Instant>,
+    thumbnail_bytes: AtomicU64,
+    files_truncated: AtomicBool,
+    thumbnails_truncated: AtomicBool,
+}
+
+impl ScanCtx {
+    // One per root; a multi-root scan has several sharing `state`
+    pub fn new(
+        app: tauri::AppHandle,
+        scan_id: u64,
+        state: Arc<ScanState>,
+        options: ScanOptions,
+        root: PathBuf,
+    ) -> Self {
+        let globs = state.globs.clone();
+        let net = options.net_io(&root);
+        let volume = volume::volume_of(&root);
+        let exclusions = options.exclusions(&root);
+        let opens = options.open_limiter();
+        if net.enabled() {
+            tracing::info!(scan_id, root = %root.display(), "network share: timeouts and retries on");
+        }
+        Self {
+            app,
+            scan_id,
+            state,
+            options,
+            net,
+            exclusions,
+            globs,
+            opens,
+            root,
+            volume,
+            offline: AtomicBool::new(false),
+            last_emit: Mutex::new(Instant::now()),
+            thumbnail_bytes: AtomicU64::new(0),
+            files_truncated: AtomicBool::new(false),
+            thumbnails_truncated: AtomicBool::new(false),
+        }
+    }
+
+    // Events go to the window that started the scan, and to WebSocket
+    // listeners of the HTTP API
+    fn emit<S: Serialize + Clone>(&self, event: &str, payload: S) -> tauri::Result<()> {
+        ws::broadcast(event, &payload);
+        emit_to_owner(&self.app, self.state.owner.as_deref(), event, payload)
+    }
+
+    fn emit_progress(&self) {
+        let mut last = self.last_emit.lock().unwrap();
+        if last.elapsed() >= PROGRESS_EMIT_INTERVAL {
+            *last = Instant::now();
+            let _ = self.emit(
+                EVENT_SCAN_PROGRESS,
+                self.state.snapshot(self.scan_id, false),
+            );
+        }
+    }
+
+    fn set_phase(&self, phase: usize) {
+        tracing::debug!(scan_id = self.scan_id, phase, "scan phase");
+        self.state.phase.store(phase, Ordering::Relaxed);
+        let _ = self.emit(
+            EVENT_SCAN_PROGRESS,
+            self.state.snapshot(self.scan_id, false),
+        );
+    }
+
+    fn set_current_path(&self, path: &Path) {
+        tracing::trace!(scan_id = self.scan_id, path = %path.display(), "scanning");
+        if let Ok(mut cp) = self.state.current_path.lock() {
+            *cp = Some(path.to_string_lossy().to_string());
+        }
+    }
+
+    fn follows(&self, kind: LinkKind) -> bool {
+        match kind {
+            LinkKind::Junction | LinkKind::ReparsePoint => {
+                self.options.follow_junctions.unwrap_or(false)
+            }
+            LinkKind::Symlink => self.options.follow_symlinks.unwrap_or(false),
+        }
+    }
+
+    // Parks the worker while the scan is paused (see `pause_scan`)
+    fn wait_if_paused(&self) {
+        if self.state.is_paused() {
+            tracing::debug!(scan_id = self.scan_id, "scan worker paused");
+            self.state.wait_while_paused();
+        }
+    }
+
+    // Waits for the next file-open slot when opens are paced
+    fn pace_open(&self) {
+        if let Some(limiter) = &self.opens {
+            limiter.wait();
+        }
+    }
+
+    fn excluded(&self, dir: &Path) -> bool {
+        let excluded = self
+            .exclusions
+            .as_ref()
+            .is_some_and(|x| x.matches(&self.root, dir))
+            || dir
+                .strip_prefix(&self.root)
+                .is_ok_and(|rel| self.globs.skips_dir(rel));
+        if excluded {
+            tracing::debug!(scan_id = self.scan_id, path = %dir.display(), "excluded");
+        }
+        excluded
+    }
+
+    // Files left out by the include/exclude globs; those outside the root
+    // (trash) are not filtered
+    fn filtered_out(&self, file: &Path) -> bool {
+        file.strip_prefix(&self.root)
+            .is_ok_and(|rel| self.globs.skips_file(rel))
+    }
+
+    // Files go out as the walk finds them, and again with their header data;
+    // the later entry of a path replaces the earlier
+    fn stream_found(&self, fb: &FoundBlend) {
+        let file = flat_file(fb, &self.root);
+        self.state.found.lock().unwrap().push(file.clone());
+        let _ = self.emit(
+            EVENT_SCAN_FILE_FOUND,
+            ScanFileFound {
+                scan_id: self.scan_id,
+                file,
+            },
+        );
+    }
+
+    // Reading a cloud placeholder downloads it; optionally list it unparsed
+    fn should_parse(&self, fb: &FoundBlend) -> bool {
+        fb.format == formats::BLEND
+            && self.options.parse_depth() > ParseDepth::Discover
+            && !(fb.cloud_only && self.options.skip_cloud_files.unwrap_or(false))
+    }
+
+    // Called after an I/O error: the scan stops if the whole volume went away
+    fn volume_lost(&self) -> bool {
+        if !self.offline.load(Ordering::Relaxed) && !volume::is_present(&self.volume) {
+            self.offline.store(true, Ordering::Relaxed);
+        }
+        self.offline.load(Ordering::Relaxed)
+    }
+
+    // Drops the thumbnail if keeping it would exceed the per-scan budget
+    fn enforce_thumbnail_budget(&self, info: &mut BlendInfo) {
+        let (Some(cap), Some(thumb)) = (self.options.max_thumbnail_bytes, &info.thumbnail) else {
+            return;
+        };
+        let len = thumb.len() as u64;
+        let used = self.thumbnail_bytes.fetch_add(len, Ordering::Relaxed);
+        if used + len > cap {
+            self.thumbnail_bytes.fetch_sub(len, Ordering::Relaxed);
+            self.thumbnails_truncated.store(true, Ordering::Relaxed);
+            info.thumbnail = None;
+            info.thumb_width = None;
+            info.thumb_height = None;
+        }
+    }
+}
+
+// -----------------------------
+// Scan pipeline
+// -----------------------------
+// A blend discovered by the walk, enriched by the later phases
+pub(crate) struct FoundBlend {
+    pub path: PathBuf,
+    pub size_bytes: u64,
+    pub created: Option<String>,
+    pub modified: Option<String>,
+    pub cloud_only: bool,
+    pub read_only: bool,
+    pub trash: Option<TrashInfo>, // found in the OS trash
+    pub format: String,           // see formats.rs
+    pub blend: BlendInfo,
+    pub aliases: Vec<String>, // other paths (file symlinks) reaching the same file
+}
+
+// Tree folder grouping trashed files, which live outside the scanned root
+const TRASH_NODE_NAME: &str = "(Trash)";
+
+// Splits `items` across the worker threads; errors are collected per worker
+fn parallel_each<F>(ctx: &ScanCtx, items: &mut [FoundBlend], f: F) -> Vec<AppError>
+where
+    F: Fn(&mut FoundBlend, &mut Vec<AppError>) + Sync,
+{
+    if items.is_empty() {
+        return Vec::new();
+    }
+    let threads = ctx.options.worker_threads().min(items.len());
+    let chunk = items.len().div_ceil(threads);
+    let f = &f;
+
+    thread::scope(|scope| {
+        let handles: Vec<_> = items
+            .chunks_mut(chunk)
+            .map(|part| {
+                scope.spawn(move || {
+                    let mut errors = Vec::new();
+                    for fb in part {
+                        ctx.wait_if_paused();
+                        if ctx.offline.load(Ordering::Relaxed) {
+                            break;
+                        }
+                        f(fb, &mut errors);
+                    }
+                    errors
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap_or_default())
+            .collect()
+    })
+}
+
+// Walks `root`, then any followed directory links. A link back into its
+// own ancestors or a walked start is a cycle; one whose canonical target
+// overlaps an already walked tree is a duplicate. Both are reported but
+// not descended into.
+fn walk(
+    ctx: &ScanCtx,
+    root: &Path,
+    errors: &mut Vec<AppError>,
+) -> (Vec<FoundBlend>, Vec<LinkEntry>) {
+    let mut found: Vec<FoundBlend> = Vec::new();
+    let mut links: Vec<LinkEntry> = Vec::new();
+    let mut file_links: Vec<PathBuf> = Vec::new();
+    let mut visited = vec![fs::canonicalize(root).unwrap_or_else(|_| root.to_path_buf())];
+    let mut visited_ids: HashSet<Handle> = Handle::from_path(root).into_iter().collect();
+    // (walk start, its canonical path)
+    let mut starts = vec![(root.to_path_buf(), visited[0].clone())];
+    let mut queue = vec![root.to_path_buf()];
+    // Blends of roots walked before this one
+    let before = ctx.state.found_blends.load(Ordering::Relaxed);
+
+    while let Some(start) = queue.pop() {
+        let first_new = links.len();
+        if !walk_tree(ctx, &start, &mut found, &mut links, errors, &mut file_links) {
+            break;
+        }
+        for link in &mut links[first_new..] {
+            if !ctx.follows(link.kind) {
+                continue;
+            }
+            let path = paths::from_id(&link.path_id);
+            let Ok(target) = fs::canonicalize(&path) else {
+                continue;
+            };
+            if links::is_cycle(&path, &visited_ids) {
+                tracing::debug!(scan_id = ctx.scan_id, link = %link.path, "link cycle skipped");
+                link.cycle = true;
+                link.duplicate_of = Some(target.to_string_lossy().to_string());
+                continue;
+            }
+            if let Some(seen) = visited
+                .iter()
+                .find(|v| target.starts_with(v) || v.starts_with(&target))
+            {
+                link.duplicate_of = Some(seen.to_string_lossy().to_string());
+                continue;
+            }
+            link.followed = true;
+            visited_ids.extend(Handle::from_path(&target));
+            starts.push((path.clone(), target.clone()));
+            visited.push(target);
+            queue.push(path);
+        }
+    }
+    dedupe_file_links(&mut found, &starts, &file_links);
+    ctx.state
+        .found_blends
+        .store(before + found.len() as u64, Ordering::Relaxed);
+
+    if ctx.options.include_trash.unwrap_or(false) {
+        walk_trash(ctx, root, &mut found);
+    }
+
+    (found, links)
+}
+
+// File symlinks may reach blends that are also found under their real
+// path. Each physical file is kept once; the other paths become aliases,
+// so counts and sizes aren't inflated.
+fn dedupe_file_links(
+    found: &mut Vec<FoundBlend>,
+    starts: &[(PathBuf, PathBuf)],
+    file_links: &[PathBuf],
+) {
+    if file_links.is_empty() {
+        return;
+    }
+    let is_link: HashSet<&Path> = file_links.iter().map(PathBuf::as_path).collect();
+    // Real location of a plain file, from the walk start it was found under
+    let real = |p: &Path| {
+        starts
+            .iter()
+            .filter(|(start, _)| p.starts_with(start))
+            .max_by_key(|(start, _)| start.components().count())
+            .and_then(|(start, canon)| Some(canon.join(p.strip_prefix(start).ok()?)))
+    };
+    let mut by_real: HashMap<PathBuf, usize> = found
+        .iter()
+        .enumerate()
+        .filter(|(_, fb)| !is_link.contains(fb.path.as_path()))
+        .filter_map(|(i, fb)| Some((real(&fb.path)?, i)))
+        .collect();
+
+    let mut dropped = vec![false; found.len()];
+    for i in 0..found.len() {
+        if !is_link.contains(found[i].path.as_path()) {
+            continue;
+        }
+        let Ok(target) = fs::canonicalize(&found[i].path) else {
+            continue;
+        };
+        match by_real.get(&target) {
+            Some(&keep) => {
+                let alias = found[i].path.to_string_lossy().to_string();
+                found[keep].aliases.push(alias);
+                dropped[i] = true;
+            }
+            // Target outside the scan: the first link stands in for it
+            None => {
+                by_real.insert(target, i);
+            }
+        }
+    }
+    let mut i = 0;
+    found.retain(|_| {
+        i += 1;
+        !dropped[i - 1]
+    });
+}
+
+// Trash locations are best effort: unreadable ones (other users' bins,
+// macOS privacy prompts) are logged rather than reported as skipped
+fn walk_trash(ctx: &ScanCtx, root: &Path, found: &mut Vec<FoundBlend>) {
+    for loc in trash::locations(root) {
+        if loc.starts_with(root) || root.starts_with(&loc) {
+            continue; // already walked, or the scan is of the trash itself
+        }
+        let first = found.len();
+        let (mut links, mut errors) = (Vec::new(), Vec::new());
+        let more = walk_tree(ctx, &loc, found, &mut links, &mut errors, &mut Vec::new());
+        for e in &errors {
+            tracing::debug!(scan_id = ctx.scan_id, "trash walk error: {}", e);
+        }
+        for fb in &mut found[first..] {
+            fb.trash = Some(trash::info(&loc, &fb.path));
+        }
+        if !more {
+            break;
+        }
+    }
+}
+
+// The entries of one walk, without pruned folders. On a share the listing
+// runs on a worker thread (see netfs.rs) and every entry must arrive within
+// the network timeout; a stalled listing ends with a timeout error.
+fn walk_entries<'a>(
+    ctx: &'a ScanCtx,
+    start: &Path,
+    pruned: impl Fn(&walkdir::DirEntry) -> bool + 'a,
+) -> Box<dyn Iterator<Item = Result<walkdir::DirEntry, AppError>> + 'a> {
+    if !ctx.net.enabled() {
+        return Box::new(
+            WalkDir::new(start)
+                .into_iter()
+                .filter_entry(move |e| !pruned(e))
+                .map(|entry| entry.map_err(|err| AppError::walk(&err))),
+        );
+    }
+    let worker = match netfs::Worker::start(start) {
+        Ok(worker) => worker,
+        Err(err) => return Box::new(std::iter::once(Err(err))),
+    };
+    let asks = |e: &walkdir::DirEntry| e.depth() > 0 && e.file_type().is_dir();
+    let (tx, rx) = mpsc::channel();
+    // Per listed folder: whether to skip it
+    let (verdict_tx, verdict_rx) = mpsc::channel::<bool>();
+    let (w, owned) = (worker.clone(), start.to_path_buf());
+    thread::spawn(move || {
+        let mut it = WalkDir::new(owned).into_iter();
+        while let Some(entry) = it.next() {
+            let ask = entry.as_ref().is_ok_and(asks);
+            if tx.send(entry).is_err() {
+                break;
+            }
+            if ask {
+                match verdict_rx.recv() {
+                    Ok(true) => it.skip_current_dir(),
+                    Ok(false) => {}
+                    Err(_) => break,
+                }
+            }
+        }
+        w.finished();
+    });
+
+    let (start, timeout) = (start.to_path_buf(), ctx.net.timeout());
+    let mut stalled = false;
+    Box::new(std::iter::from_fn(move || loop {
+        if stalled {
+            return None;
+        }
+        match rx.recv_timeout(timeout) {
+            Ok(Ok(e)) => {
+                if asks(&e) {
+                    let prune = pruned(&e);
+                    let _ = verdict_tx.send(prune);
+                    if prune {
+                        continue;
+                    }
+                }
+                return Some(Ok(e));
+            }
+            Ok(Err(err)) => return Some(Err(AppError::walk(&err))),
+            Err(mpsc::RecvTimeoutError::Disconnected) => return None,
+            Err(mpsc::RecvTimeoutError::Timeout) if worker.abandon() => {
+                stalled = true;
+                return Some(Err(netfs::timed_out(&start, timeout)));
+            }
+            // Finished as the timeout hit; the rest is queued
+            Err(mpsc::RecvTimeoutError::Timeout) => {}
+        }
+    }))
+}
+
+// One walkdir pass; false when the scan must stop (limit hit, volume gone).
+// Blends reached through a file symlink are noted in `file_links`.
+fn walk_tree(
+    ctx: &ScanCtx,
+    start: &Path,
+    found: &mut Vec<FoundBlend>,
+    links: &mut Vec<LinkEntry>,
+    errors: &mut Vec<AppError>,
+    file_links: &mut Vec<PathBuf>,
+) -> bool {
+    let state = &ctx.state;
+    let skip_bundles = ctx
+        .options
+        .skip_bundles
+        .unwrap_or(cfg!(target_os = "macos"));
+
+    let entries = walk_entries(ctx, start, |e| {
+        e.depth() > 0
+            && e.file_type().is_dir()
+            && ((skip_bundles && is_bundle(e.path())) || ctx.excluded(e.path()))
+    });
+    for entry in entries {
+        ctx.wait_if_paused();
+        match entry {
+            Ok(e) => {
+                state.scanned_entries.fetch_add(1, Ordering::Relaxed);
+
+                // Current path (for UI)
+                ctx.set_current_path(e.path());
+                ctx.emit_progress();
+
+                let p = e.path();
+                // Only links need a stat to tell folders from files, and
+                // it can hang like any other on a share
+                let target = e.path_is_symlink().then(|| {
+                    let owned = p.to_path_buf();
+                    ctx.net
+                        .run(p, move || {
+                            fs::metadata(&owned).map_err(|err| AppError::io(&err, &owned))
+                        })
+                        .ok()
+                });
+                let target = target.flatten();
+                // Directory links are recorded; `walk` decides whether to follow
+                if e.depth() > 0 && target.as_ref().is_some_and(|m| m.is_dir()) {
+                    links.push(LinkEntry {
+                        path: p.to_string_lossy().to_string(),
+                        path_id: paths::to_id(p),
+                        kind: links::link_kind(p),
+                        target: fs::read_link(p)
+                            .ok()
+                            .map(|t| t.to_string_lossy().to_string()),
+                        followed: false,
+                        duplicate_of: None,
+                        cycle: false,
+                    });
+                    continue;
+                }
+                if !e.file_type().is_file() && !target.as_ref().is_some_and(|m| m.is_file()) {
+                    continue;
+                }
+                if ctx.options.archives.unwrap_or(false)
+                    && archive::is_archive(p)
+                    && archive::can_list(p)
+                {
+                    if !walk_archive(ctx, p, found, errors) {
+                        return false;
+                    }
+                    continue;
+                }
+
+                let backup =
+                    ctx.options.backups.unwrap_or(false) && formats::backup_number(p).is_some();
+                let by_ext = formats::of_path(p, ctx.options.other_formats.unwrap_or(false))
+                    .or(backup.then_some(formats::BLEND));
+                // Renamed/recovered blends: opt-in, as it opens every candidate
+                let sniffed = by_ext.is_none()
+                    && ctx.options.sniff_content.unwrap_or(false)
+                    && magic::worth_sniffing(p)
+                    && {
+                        ctx.pace_open();
+                        magic::is_blend(p)
+                    };
+                let Some(format) = by_ext.or(sniffed.then_some(formats::BLEND)) else {
+                    continue;
+                };
+                if ctx.filtered_out(p) {
+                    continue;
+                }
+
+                // Scan-wide, over every root
+                if let Some(max) = ctx.options.max_files {
+                    if state.found_blends.load(Ordering::Relaxed) >= max {
+                        ctx.files_truncated.store(true, Ordering::Relaxed);
+                        return false;
+                    }
+                }
+
+                let owned = p.to_path_buf();
+                let meta_fs = match ctx.net.run(p, move || {
+                    owned.metadata().map_err(|err| AppError::io(&err, &owned))
+                }) {
+                    Ok(m) => m,
+                    Err(err) => {
+                        errors.push(err);
+                        if ctx.volume_lost() {
+                            return false;
+                        }
+                        continue;
+                    }
+                };
+
+                state.found_blends.fetch_add(1, Ordering::Relaxed);
+                if e.path_is_symlink() {
+                    file_links.push(p.to_path_buf());
+                }
+                found.push(FoundBlend {
+                    path: p.to_path_buf(),
+                    size_bytes: meta_fs.len(),
+                    created: meta_fs
+                        .created()
+                        .ok()
+                        .map(|t| DateTime::<Local>::from(t).to_rfc3339()),
+                    modified: meta_fs
+                        .modified()
+                        .ok()
+                        .map(|t| DateTime::<Local>::from(t).to_rfc3339()),
+                    cloud_only: cloud::is_placeholder(&meta_fs),
+                    read_only: meta_fs.permissions().readonly(),
+                    trash: None,
+                    format: format.to_string(),
+                    blend: BlendInfo::default(),
+                    aliases: Vec::new(),
+                });
+                // Links wait for the header pass; they may turn out to be
+                // another path of a file listed already
+                if !e.path_is_symlink() {
+                    ctx.stream_found(&found[found.len() - 1]);
+                }
+            }
+            Err(err) => {
+                // Non-fatal: keep scanning
+                tracing::debug!(scan_id = ctx.scan_id, "walk error: {}", err);
+                if let Ok(mut cp) = state.current_path.lock() {
+                    *cp = Some(format!("(walk error) {}", err));
+                }
+                errors.push(err);
+                if ctx.volume_lost() {
+                    return false;
+                }
+            }
+        }
+    }
+
+    true
+}
+
+// Lists the blends stored in an archive; false when the scan must stop
+fn walk_archive(
+    ctx: &ScanCtx,
+    path: &Path,
+    found: &mut Vec<FoundBlend>,
+    errors: &mut Vec<AppError>,
+) -> bool {
+    // Listing reads the archive, which downloads a placeholder
+    if ctx.options.skip_cloud_files.unwrap_or(false)
+        && fs::metadata(path).is_ok_and(|m| cloud::is_placeholder(&m))
+    {
+        return true;
+    }
+    ctx.pace_open();
+    let owned = path.to_path_buf();
+    let backups = ctx.options.backups.unwrap_or(false);
+    let entries = match ctx.net.run(path, move || archive::list(&owned, backups)) {
+        Ok(entries) => entries,
+        Err(err) => {
+            errors.push(err);
+            return !ctx.volume_lost();
+        }
+    };
+    for entry in entries.into_iter().filter(|e| !ctx.filtered_out(&e.path)) {
+        if let Some(max) = ctx.options.max_files {
+            if ctx.state.found_blends.load(Ordering::Relaxed) >= max {
+                ctx.files_truncated.store(true, Ordering::Relaxed);
+                return false;
+            }
+        }
+        ctx.state.found_blends.fetch_add(1, Ordering::Relaxed);
+        found.push(FoundBlend {
+            path: entry.path,
+            size_bytes: entry.size_bytes,
+            created: None,
+            modified: entry.modified,
+            cloud_only: false,
+            read_only: false,
+            trash: None,
+            format: formats::BLEND.to_string(),
+            blend: BlendInfo::default(),
+            aliases: Vec::new(),
+        });
+        ctx.stream_found(&found[found.len() - 1]);
+    }
+    true
+}
+
+pub(crate) fn run_scan(ctx: ScanCtx) {
+    match scan_root(&ctx, &ctx.root) {
+        Some(result) => done(&ctx, result),
+        None => finish_offline(&ctx),
+    }
+}
+
+// The roots one after the other, each through every phase with its own
+// context (network handling, exclusions and limits are per root), then
+// joined into one result. A root whose volume went away (the NAS next to
+// two local drives) is listed as skipped; the others are kept.
+pub(crate) fn run_multi_scan(ctxs: Vec<ScanCtx>) {
+    let mut results = Vec::with_capacity(ctxs.len());
+    let mut offline = Vec::new();
+    for ctx in &ctxs {
+        match scan_root(ctx, &ctx.root) {
+            Some(result) => results.push(result),
+            None => {
+                tracing::warn!(scan_id = ctx.scan_id, root = %ctx.root.display(), "root went offline");
+                offline.push(ctx);
+            }
+        }
+    }
+    if results.is_empty() {
+        return finish_offline(&ctxs[0]);
+    }
+    let mut result = join_results(results);
+    for ctx in offline {
+        let path = paths::to_id(&ctx.root);
+        result
+            .errors
+            .push(volume::offline_error(&ctx.volume).with_path(&ctx.root));
+        result.skipped.push(SkippedPath {
+            decision: None,
+            is_dir: true,
+            path,
+            reason: ErrorKind::VolumeOffline,
+        });
+    }
+    done(&ctxs[0], result);
+}
+
+// One root through every phase; None when its volume went away
+fn scan_root(ctx: &ScanCtx, root: &Path) -> Option<ScanResult> {
+    let state = &ctx.state;
+
+    // Phase 1: walk the folder and collect .blend candidates
+    let mut errors: Vec<AppError> = Vec::new();
+    let (mut found, links) = walk(ctx, root, &mut errors);
+    let decisions = settings::get().elevation;
+    let skipped: Vec<SkippedPath> = errors
+        .iter()
+        .filter_map(|e| {
+            let path = e.path.clone()?;
+            Some(SkippedPath {
+                decision: decisions.get(&path).copied(),
+                is_dir: paths::from_id(&path).is_dir(),
+                path,
+                reason: e.kind,
+            })
+        })
+        .collect();
+
+    // Files an interrupted scan of the root already parsed are reused, and
+    // with `use_index` those of the last finished one; this one checkpoints
+    // as it goes
+    let resumed = Resumed::load(root, ctx.options.use_index.unwrap_or(false));
+    let depth = ctx.options.parse_depth();
+    let checkpoint = Checkpointer::new(root, depth);
+
+    // Phase 2: fixed-size headers (version, pointer size, endianness)
+    ctx.set_phase(PHASE_PARSING_HEADERS);
+    errors.extend(parallel_each(ctx, &mut found, |fb, errs| {
+        if let Some(saved) = resumed.get(fb, ParseDepth::Header) {
+            fb.blend = saved.clone();
+        } else if ctx.should_parse(fb) {
+            ctx.set_current_path(&fb.path);
+            ctx.pace_open();
+            let path = fb.path.clone();
+            fb.blend = ctx
+                .net
+                .run(&fb.path, move || read_blend_header(&path))
+                .unwrap_or_else(|e| {
+                    let info = BlendInfo {
+                        error: Some(e.message.clone()),
+                        ..Default::default()
+                    };
+                    errs.push(e);
+                    ctx.volume_lost();
+                    info
+                });
+        }
+        state.headers_parsed.fetch_add(1, Ordering::Relaxed);
+        ctx.emit_progress();
+        ctx.stream_found(fb);
+    }));
+
+    // Phase 3: block scan for thumbnails and render engine
+    ctx.set_phase(PHASE_EXTRACTING_THUMBNAILS);
+    errors.extend(parallel_each(ctx, &mut found, |fb, errs| {
+        let reused = resumed.get(fb, depth).is_some();
+        if reused {
+            ctx.enforce_thumbnail_budget(&mut fb.blend);
+        } else if fb.blend.error.is_none() && depth >= ParseDepth::Standard && ctx.should_parse(fb)
+        {
+            ctx.set_current_path(&fb.path);
+            ctx.pace_open();
+            let (path, base) = (fb.path.clone(), fb.blend.clone());
+            let deep = depth == ParseDepth::Deep;
+            match ctx.net.run(&fb.path, move || {
+                let mut info = base.clone();
+                extract_block_info(&path, &mut info, deep).map(|_| info)
+            }) {
+                Ok(info) => fb.blend = info,
+                Err(e) => {
+                    errs.push(e);
+                    ctx.volume_lost();
+                }
+            }
+            ctx.enforce_thumbnail_budget(&mut fb.blend);
+        }
+        if !reused && ctx.should_parse(fb) {
+            checkpoint.add(fb);
+        }
+        state.thumbnails_scanned.fetch_add(1, Ordering::Relaxed);
+        ctx.emit_progress();
+    }));
+
+    if ctx.offline.load(Ordering::Relaxed) {
+        checkpoint.flush();
+        return None;
+    }
+
+    // Sidecars and attributes fill in user data the index lacks (folders
+    // copied from another machine, files moved on the same volume)
+    let (sidecars, attrs) = (sidecar::enabled(), xattrs::enabled());
+    for fb in found
+        .iter()
+        .filter(|fb| fb.trash.is_none() && archive::split(&fb.path).is_none())
+    {
+        if sidecars {
+            if let Err(e) = sidecar::import(&fb.path) {
+                tracing::warn!("reading sidecar failed: {}", e);
+            }
+        }
+        if attrs {
+            if let Err(e) = xattrs::import(&fb.path) {
+                tracing::warn!("reading extended attribute failed: {}", e);
+            }
+        }
+    }
+
+    // Phase 4: flat list + tree
+    ctx.set_phase(PHASE_BUILDING_TREE);
+    let found_ids: HashSet<String> = found.iter().map(|fb| paths::to_id(&fb.path)).collect();
+    let mut files: Vec<FlatFile> = Vec::with_capacity(found.len());
+    let mut builder = DirNode::default();
+    for fb in found {
+        add_found(&mut builder, &mut files, root, fb);
+        state.tree_inserted.fetch_add(1, Ordering::Relaxed);
+        ctx.emit_progress();
+    }
+    add_skipped_dirs(&mut builder, root, &skipped);
+
+    let mut limits_hit = Vec::new();
+    if ctx.files_truncated.load(Ordering::Relaxed) {
+        limits_hit.push("max_files".to_string());
+    }
+    if ctx.thumbnails_truncated.load(Ordering::Relaxed) {
+        limits_hit.push("max_thumbnail_bytes".to_string());
+    }
+
+    tracing::info!(
+        scan_id = ctx.scan_id,
+        root = %root.display(),
+        files = files.len(),
+        errors = errors.len(),
+        skipped = skipped.len(),
+        elapsed_ms = (Local::now() - state.started_at).num_milliseconds(),
+        limits = ?limits_hit,
+        "scan finished"
+    );
+    for e in &errors {
+        tracing::debug!(scan_id = ctx.scan_id, "scan error: {}", e);
+    }
+
+    let compact_tree = ctx.options.compact_tree.unwrap_or(false);
+    let tree_order = ctx.options.tree_order.unwrap_or_default();
+    let tree = build_tree_nodes(&builder, &root_name(root), root, compact_tree, tree_order);
+    let mut result = ScanResult {
+        tree,
+        roots: vec![paths::to_id(root)],
+        files,
+        errors,
+        skipped,
+        links,
+        truncated: !limits_hit.is_empty(),
+        limits_hit,
+        compact_tree,
+        relative_paths: ctx.options.relative_paths.unwrap_or(false),
+        counts_only: ctx.options.counts_only_tree.unwrap_or(false),
+        tree_order,
+        flat_tree: None,
+        parse_depth: depth,
+    };
+    mark_errors(&mut result.tree, &result.skipped);
+    let changes = history::record(root, &result.files, !result.truncated);
+    history::apply(&mut result, &changes);
+    staleness::apply(&mut result);
+    relativize(&mut result);
+    file_ids::assign(
+        &result
+            .files
+            .iter()
+            .map(|f| f.path_id.clone())
+            .collect::<Vec<_>>(),
+    );
+    extractors::apply(&mut result.files);
+    match rules::apply(&result.files) {
+        Ok(applied) => mirror_user_data(&applied.path_ids),
+        Err(e) => tracing::warn!("applying tag rules failed: {}", e),
+    }
+    if let Err(e) = checkpoint::commit(root, &found_ids) {
+        tracing::warn!("updating the file index failed: {}", e);
+    }
+    set_volume_offline(&ctx.volume, false);
+    Some(result)
+}
+
+// Several roots as one result: their trees side by side under a common
+// node; files found under more than one root are kept once
+fn join_results(results: Vec<ScanResult>) -> ScanResult {
+    let changes: history::Changes = results.iter().flat_map(history::of_result).collect();
+    let found: Vec<FoundBlend> = results.iter().flat_map(found_in).collect();
+    let mut joined: Option<ScanResult> = None;
+    for r in results {
+        let Some(j) = joined.as_mut() else {
+            joined = Some(r);
+            continue;
+        };
+        j.roots.extend(r.roots);
+        j.files.extend(r.files);
+        j.errors.extend(r.errors);
+        j.skipped.extend(r.skipped);
+        j.links.extend(r.links);
+        j.truncated |= r.truncated;
+        for limit in r.limits_hit {
+            if !j.limits_hit.contains(&limit) {
+                j.limits_hit.push(limit);
+            }
+        }
+    }
+    let mut joined = joined.expect("at least one root");
+    rebuild(&mut joined, found.into_iter(), &changes);
+    joined
+}
+
+// Stores the result and tells the owner
+fn done(ctx: &ScanCtx, result: ScanResult) {
+    let state = &ctx.state;
+    let depth = result.parse_depth;
+
+    // Shallow scans are filled in behind the UI's requests for shown files;
+    // not for gentle ones, which keep file reads down on purpose
+    let background: Vec<String> =
+        if matches!(depth, ParseDepth::Header | ParseDepth::Standard) && !ctx.options.gentle() {
+            result
+                .files
+                .iter()
+                .filter(|f| f.format == formats::BLEND && !f.cloud_only)
+                .map(|f| f.path_id.clone())
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+    if let Ok(mut r) = state.result.lock() {
+        *r = Some(result);
+    }
+    if !background.is_empty() {
+        enrich::request(&ctx.app, ctx.scan_id, background, false);
+    }
+    if let Ok(mut st) = state.status.lock() {
+        *st = "done".to_string();
+    }
+    finish(ctx);
+}
+
+// -----------------------------
+// Benchmark
+// -----------------------------
+// Times each phase of a scan over a folder without keeping the result: the
+// walk, headers, the block scan (thumbnails, engine, contents), then
+// building and serializing the result. Phases run back to back, so later
+// ones read files the earlier ones pulled into the OS cache; a second run
+// shows warm numbers.
+
+#[derive(Serialize, Clone)]
+pub struct Benchmark {
+    pub root: String,
+    pub files: usize,
+    pub threads: usize,
+    pub errors: usize,
+    pub walk_ms: u64,
+    pub header_ms: u64,
+    pub block_ms: u64,
+    pub tree_ms: u64,
+    pub serialize_ms: u64,
+    pub thumbnail_bytes: u64,
+    pub result_bytes: u64, // JSON sent to the UI for this result
+    // Passes: the walk alone, walk + headers, everything
+    pub discovery_ms: u64,
+    pub header_pass_ms: u64,
+    pub full_pass_ms: u64,
+}
+
+fn millis(since: Instant) -> u64 {
+    since.elapsed().as_millis() as u64
+}
+
+pub(crate) fn benchmark(ctx: &ScanCtx, job: &JobCtx) -> Result<Benchmark, AppError> {
+    let root = ctx.root.clone();
+    job.set_current(&root);
+    tracing::info!(root = %root.display(), "benchmark started");
+
+    let start = Instant::now();
+    let mut errors: Vec<AppError> = Vec::new();
+    let (mut found, links) = walk(ctx, &root, &mut errors);
+    let walk_ms = millis(start);
+    job.check_cancelled()?;
+
+    let t = Instant::now();
+    errors.extend(parallel_each(ctx, &mut found, |fb, errs| {
+        if !ctx.should_parse(fb) {
+            return;
+        }
+        ctx.pace_open();
+        let path = fb.path.clone();
+        fb.blend = ctx
+            .net
+            .run(&fb.path, move || read_blend_header(&path))
+            .unwrap_or_else(|e| {
+                let info = BlendInfo {
+                    error: Some(e.message.clone()),
+                    ..Default::default()
+                };
+                errs.push(e);
+                info
+            });
+    }));
+    let header_ms = millis(t);
+    job.check_cancelled()?;
+
+    let t = Instant::now();
+    errors.extend(parallel_each(ctx, &mut found, |fb, errs| {
+        if fb.blend.error.is_some() || !ctx.should_parse(fb) {
+            return;
+        }
+        ctx.pace_open();
+        let (path, base) = (fb.path.clone(), fb.blend.clone());
+        match ctx.net.run(&fb.path, move || {
+            let mut info = base.clone();
+            extract_block_info(&path, &mut info, true).map(|_| info)
+        }) {
+            Ok(info) => fb.blend = info,
+            Err(e) => errs.push(e),
+        }
+    }));
+    let block_ms = millis(t);
+    job.check_cancelled()?;
+
+    let t = Instant::now();
+    let count = found.len();
+    let mut files: Vec<FlatFile> = Vec::with_capacity(count);
+    let mut builder = DirNode::default();
+    for fb in found {
+        add_found(&mut builder, &mut files, &root, fb);
+    }
+    let tree_order = TreeOrder::default();
+    let tree = build_tree_nodes(&builder, &root_name(&root), &root, false, tree_order);
+    let thumbnail_bytes = files
+        .iter()
+        .filter_map(|f| f.thumbnail.as_ref())
+        .map(|t| t.len() as u64)
+        .sum();
+    let error_count = errors.len();
+    let result = ScanResult {
+        tree,
+        roots: vec![paths::to_id(&root)],
+        files,
+        errors,
+        skipped: Vec::new(),
+        links,
+        truncated: false,
+        limits_hit: Vec::new(),
+        compact_tree: false,
+        relative_paths: false,
+        counts_only: false,
+        tree_order,
+        flat_tree: None,
+        parse_depth: ParseDepth::Deep,
+    };
+    let tree_ms = millis(t);
+
+    let t = Instant::now();
+    let result_bytes = serde_json::to_vec(&result).map_or(0, |b| b.len() as u64);
+    let serialize_ms = millis(t);
+
+    let report = Benchmark {
+        root: root.to_string_lossy().to_string(),
+        files: count,
+        threads: ctx.options.worker_threads(),
+        errors: error_count,
+        walk_ms,
+        header_ms,
+        block_ms,
+        tree_ms,
+        serialize_ms,
+        thumbnail_bytes,
+        result_bytes,
+        discovery_ms: walk_ms,
+        header_pass_ms: walk_ms + header_ms,
+        full_pass_ms: millis(start),
+    };
+    tracing::info!(
+        files = count,
+        walk_ms,
+        header_ms,
+        block_ms,
+        tree_ms,
+        serialize_ms,
+        "benchmark finished"
+    );
+    Ok(report)
+}
+
+// Aborts without a result; earlier results for the volume stay, flagged offline
+fn finish_offline(ctx: &ScanCtx) {
+    let state = &ctx.state;
+    tracing::warn!(scan_id = ctx.scan_id, volume = %ctx.volume.display(), "volume disconnected");
+    set_volume_offline(&ctx.volume, true);
+    *state.error.lock().unwrap() = Some(volume::offline_error(&ctx.volume));
+    *state.status.lock().unwrap() = "error".to_string();
+    finish(ctx);
+}
+
+fn finish(ctx: &ScanCtx) {
+    let state = &ctx.state;
+    // The result (or the error) supersedes the streamed files
+    *state.found.lock().unwrap() = Vec::new();
+    state.phase.store(PHASE_FINISHED, Ordering::Relaxed);
+    *state.finished_at.lock().unwrap() = Some(Instant::now());
+    state.done.store(true, Ordering::Relaxed);
+    // External listeners get the summary only; the window gets the result
+    // with the top tree level and fetches deeper ones on demand
+    let done = state.snapshot(ctx.scan_id, true);
+    ws::broadcast(
+        EVENT_SCAN_DONE,
+        &ScanPoll {
+            result: None,
+            ..done.clone()
+        },
+    );
+    if emit_to_owner(&ctx.app, state.owner.as_deref(), EVENT_SCAN_DONE, done).is_ok() {
+        state.delivered.store(true, Ordering::Relaxed);
+    }
+}
+
+fn root_name(root: &Path) -> String {
+    root.file_name()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_else(|| root.to_string_lossy().to_string())
+}
+
+// Adds one blend to the flat list and the tree builder
+fn add_found(builder: &mut DirNode, files: &mut Vec<FlatFile>, root: &Path, fb: FoundBlend) {
+    let flat = flat_file(&fb, root);
+    let file_meta = FileMeta {
+        size_bytes: fb.size_bytes,
+        created_ms: flat.created_ms,
+        modified_ms: flat.modified_ms,
+        created: fb.created,
+        modified: fb.modified,
+        folder: flat.folder.clone(),
+        cloud_only: fb.cloud_only,
+        read_only: fb.read_only,
+        trash: fb.trash,
+        format: fb.format,
+        blender: fb.blend,
+        aliases: fb.aliases,
+        archive: flat.archive.clone(),
+    };
+
+    // Tree insert (relative directories; trashed files share one folder)
+    let rel = fb.path.strip_prefix(root).unwrap_or(&fb.path);
+    let parts: Vec<OsString> = if file_meta.trash.is_some() {
+        vec![OsString::from(TRASH_NODE_NAME)]
+    } else {
+        rel.parent()
+            .map(|parent| {
+                parent
+                    .components()
+                    .map(|c| c.as_os_str().to_owned())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    insert_file(builder, &parts, &flat.name, &fb.path, file_meta);
+    files.push(flat);
+}
+
+// Folds blends found after the scan (e.g. by the elevated helper for a
+// protected subtree, or one file re-read) into a finished result,
+// rebuilding tree aggregates; they replace entries at the same path
+pub(crate) fn merge_found(result: &mut ScanResult, subtree: &Path, extra: Vec<FoundBlend>) {
+    let changes = history::of_result(result);
+    let existing = found_in(result);
+    result
+        .skipped
+        .retain(|s| !paths::from_id(&s.path).starts_with(subtree));
+    rebuild(result, extra.into_iter().chain(existing), &changes);
+}
+
+// Files renamed on disk (old path -> new) stay in `result` under their new
+// paths, metadata included
+pub(crate) fn rename_found(result: &mut ScanResult, renamed: &HashMap<PathBuf, PathBuf>) {
+    let changes = history::of_result(result);
+    let mut found = found_in(result);
+    if !found.iter().any(|fb| renamed.contains_key(&fb.path)) {
+        return;
+    }
+    for fb in &mut found {
+        if let Some(to) = renamed.get(&fb.path) {
+            fb.path = to.clone();
+        }
+    }
+    rebuild(result, found.into_iter(), &changes);
+}
+
+// Files changed on disk after the scan (see watch.rs): `changed` replace or
+// join the entries at their paths, `removed` leave the result
+pub(crate) fn update_found(
+    result: &mut ScanResult,
+    changed: Vec<FoundBlend>,
+    removed: &HashSet<PathBuf>,
+) {
+    let changes = history::of_result(result);
+    let kept: Vec<FoundBlend> = found_in(result)
+        .into_iter()
+        .filter(|fb| !removed.contains(&fb.path))
+        .collect();
+    rebuild(result, changed.into_iter().chain(kept), &changes);
+}
+
+// Files come back out of the tree, metadata included
+fn found_in(result: &ScanResult) -> Vec<FoundBlend> {
+    let mut found = Vec::with_capacity(result.files.len());
+    let mut stack = vec![&result.tree];
+    while let Some(node) = stack.pop() {
+        if let (Some(meta), "file") = (&node.meta, node.node_type.as_str()) {
+            found.push(FoundBlend {
+                path: paths::from_id(&node.path_id),
+                size_bytes: meta.size_bytes,
+                created: meta.created.clone(),
+                modified: meta.modified.clone(),
+                cloud_only: meta.cloud_only,
+                read_only: meta.read_only,
+                trash: meta.trash.clone(),
+                format: meta.format.clone(),
+                blend: meta.blender.clone(),
+                aliases: meta.aliases.clone(),
+            });
+        }
+        stack.extend(node.children.iter().flatten());
+    }
+    found
+}
+
+fn root_paths(result: &ScanResult) -> Vec<PathBuf> {
+    result.roots.iter().map(|r| paths::from_id(r)).collect()
+}
+
+// Index of the root holding `path`, the innermost one; trashed files,
+// outside every root, go with the first
+fn root_index(roots: &[PathBuf], path: &Path) -> usize {
+    roots
+        .iter()
+        .enumerate()
+        .filter(|(_, r)| path.starts_with(r))
+        .max_by_key(|(_, r)| r.components().count())
+        .map_or(0, |(i, _)| i)
+}
+
+// Files and tree anew from `found`; the first of a path wins
+fn rebuild(
+    result: &mut ScanResult,
+    found: impl Iterator<Item = FoundBlend>,
+    changes: &history::Changes,
+) {
+    let roots = root_paths(result);
+    let mut seen = HashSet::new();
+    let mut files = Vec::with_capacity(result.files.len());
+    let mut builders: Vec<DirNode> = roots.iter().map(|_| DirNode::default()).collect();
+    for fb in found {
+        if seen.insert(fb.path.clone()) {
+            let i = root_index(&roots, &fb.path);
+            add_found(&mut builders[i], &mut files, &roots[i], fb);
+        }
+    }
+    // Custom fields of files that did not change are kept, the rest read
+    let mut old_fields: HashMap<String, (Option<i64>, u64, extractors::Fields)> = result
+        .files
+        .drain(..)
+        .map(|f| (f.path_id, (f.modified_ms, f.size_bytes, f.fields)))
+        .collect();
+    let mut fresh = Vec::new();
+    for f in &mut files {
+        match old_fields.remove(&f.path_id) {
+            Some((modified_ms, size, fields))
+                if modified_ms == f.modified_ms && size == f.size_bytes =>
+            {
+                f.fields = fields
+            }
+            _ => fresh.push(f),
+        }
+    }
+    extractors::apply(fresh);
+    let trees = roots
+        .iter()
+        .zip(&mut builders)
+        .map(|(root, builder)| {
+            add_skipped_dirs(builder, root, &result.skipped);
+            build_tree_nodes(
+                builder,
+                &root_name(root),
+                root,
+                result.compact_tree,
+                result.tree_order,
+            )
+        })
+        .collect();
+    result.files = files;
+    result.tree = join_trees(trees, result.tree_order);
+    mark_errors(&mut result.tree, &result.skipped);
+    history::apply(result, changes);
+    staleness::apply(result);
+    relativize(result);
+    file_ids::assign(
+        &result
+            .files
+            .iter()
+            .map(|f| f.path_id.clone())
+            .collect::<Vec<_>>(),
+    );
+}
+
+// Unreadable folders get (empty) nodes of their own, so the tree shows
+// them rather than silently leaving them out
+fn add_skipped_dirs(builder: &mut DirNode, root: &Path, skipped: &[SkippedPath]) {
+    for s in skipped.iter().filter(|s| s.is_dir) {
+        let path = paths::from_id(&s.path);
+        let Ok(rel) = path.strip_prefix(root) else {
+            continue;
+        };
+        let parts: Vec<OsString> = rel.components().map(|c| c.as_os_str().to_owned()).collect();
+        if !parts.is_empty() {
+            insert_dir(builder, &parts);
+        }
+    }
+}
+
+// Flags unreadable folders, and every folder above an unreadable entry as
+// partly read
+fn mark_errors(tree: &mut TreeNode, skipped: &[SkippedPath]) {
+    let mut full: HashMap<String, ErrorKind> = HashMap::new();
+    let mut partial: HashMap<String, ErrorKind> = HashMap::new();
+    for s in skipped {
+        let path = paths::from_id(&s.path);
+        if s.is_dir {
+            full.entry(paths::to_id(&path)).or_insert(s.reason);
+        }
+        for dir in path.ancestors().skip(1) {
+            partial.entry(paths::to_id(dir)).or_insert(s.reason);
+        }
+    }
+    let mut stack = vec![tree];
+    while let Some(node) = stack.pop() {
+        if node.is_dir() {
+            node.error = match (full.get(&node.path_id), partial.get(&node.path_id)) {
+                (Some(&kind), _) => Some(DirError {
+                    kind,
+                    partial: false,
+                }),
+                (None, Some(&kind)) => Some(DirError {
+                    kind,
+                    partial: true,
+                }),
+                (None, None) => None,
+            };
+        }
+        stack.extend(node.children.iter_mut().flatten());
+    }
+}
+
+// With `relative_paths`, display paths (and folders) below the root lose
+// the root prefix; root nodes keep their full paths as anchors
+fn relativize(result: &mut ScanResult) {
+    if !result.relative_paths {
+        return;
+    }
+    let roots = root_paths(result);
+    let shown = |path_id: &str| {
+        let path = paths::from_id(path_id);
+        paths::display_relative(&path, &roots[root_index(&roots, &path)])
+    };
+    let folder_of = |path_id: &str| {
+        let path = paths::from_id(path_id);
+        let root = &roots[root_index(&roots, &path)];
+        paths::display_relative(path.parent().unwrap_or(root), root)
+    };
+    for f in &mut result.files {
+        f.path = shown(&f.path_id);
+        f.folder = folder_of(&f.path_id);
+    }
+    let top = result.tree.children.iter_mut().flatten();
+    let mut stack: Vec<&mut TreeNode> = if roots.len() > 1 {
+        top.flat_map(|root| root.children.iter_mut().flatten())
+            .collect()
+    } else {
+        top.collect()
+    };
+    while let Some(node) = stack.pop() {
+        node.path = shown(&node.path_id);
+        if let Some(meta) = node.meta.as_mut() {
+            meta.folder = folder_of(&node.path_id);
+        }
+        stack.extend(node.children.iter_mut().flatten());
+    }
+}
+
+// Replaces what `flat_file` took from the blend's own data, after the file
+// was read again
+pub(crate) fn apply_blend(f: &mut FlatFile, blend: &BlendInfo) {
+    f.blender_version = blend.version.clone();
+    f.thumbnail = blend.thumbnail.clone();
+    f.thumb_width = blend.thumb_width;
+    f.thumb_height = blend.thumb_height;
+    f.render_engine = blend.render_engine.clone();
+    f.render_engine_id = blend.render_engine_id.clone();
+    f.assets = blend.assets.clone();
+    f.compositing = blend.compositing;
+    f.autopack = blend.autopack;
+    f.simulations = blend.simulations.clone();
+    f.sequencer = blend.sequencer.clone();
+    f.environments = blend.environments.clone();
+    f.libraries = blend.libraries.clone();
+}
+
+pub(crate) fn flat_file(fb: &FoundBlend, root: &Path) -> FlatFile {
+    FlatFile {
+        name: fb
+            .path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string(),
+        path: fb.path.to_string_lossy().to_string(),
+        path_id: paths::to_id(&fb.path),
+        rel_path: paths::portable_relative(&fb.path, root),
+        file_id: None, // filled from the index DB when the result is read
+        folder: fb
+            .path
+            .parent()
+            .unwrap_or(root)
+            .to_string_lossy()
+            .to_string(),
+        size_bytes: fb.size_bytes,
+        created: fb.created.clone(),
+        modified: fb.modified.clone(),
+        created_ms: epoch_ms(fb.created.as_deref()),
+        modified_ms: epoch_ms(fb.modified.as_deref()),
+        blender_version: fb.blend.version.clone(),
+        thumbnail: fb.blend.thumbnail.clone(),
+        thumb_width: fb.blend.thumb_width,
+        thumb_height: fb.blend.thumb_height,
+        render_engine: fb.blend.render_engine.clone(),
+        render_engine_id: fb.blend.render_engine_id.clone(),
+        assets: fb.blend.assets.clone(),
+        compositing: fb.blend.compositing,
+        autopack: fb.blend.autopack,
+        simulations: fb.blend.simulations.clone(),
+        sequencer: fb.blend.sequencer.clone(),
+        environments: fb.blend.environments.clone(),
+        libraries: fb.blend.libraries.clone(),
+        staleness: None,
+        fields: extractors::Fields::new(),
+        cloud_only: fb.cloud_only,
+        read_only: fb.read_only,
+        trash: fb.trash.clone(),
+        format: fb.format.clone(),
+        aliases: fb.aliases.clone(),
+        archive: archive::split(&fb.path).map(|(a, _)| a.to_string_lossy().to_string()),
+        note: None,
+        tags: Vec::new(), // user data is filled from the index DB when the result is read
+        favorite: false,
+        rating: None,
+        label: None,
+        collections: Vec::new(),
+        is_new: false,
+        is_modified_since_last_scan: false,
+    }
+}
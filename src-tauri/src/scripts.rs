@@ -0,0 +1,330 @@
+use crate::error::{AppError, ErrorKind};
+use crate::jobs::JobCtx;
+use crate::{collections, labels, ratings, settings, tags, FlatFile};
+use rhai::module_resolvers::DummyModuleResolver;
+use rhai::{Dynamic, Engine, EvalAltResult, Map, Scope, AST};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::sync::{Arc, Mutex};
+
+// -----------------------------
+// Scripts
+// -----------------------------
+// Rhai scripts run once per file of a result, with the file as `file`:
+//
+//   file.name, file.path, file.folder, file.format, file.size (bytes),
+//   file.version, file.engine, file.modified_ms, file.label, file.rating,
+//   file.favorite, file.note, file.tags, file.collections, file.libraries
+//   (arrays), file.fields (custom fields, see extractors.rs)
+//
+// A script ending in `true` picks the file, so
+//
+//   file.size > 500_000_000 && file.engine == "CYCLES"
+//
+// is a filter. Scripts act through tag("x"), untag("x"), label("red") or
+// label(()), rate(3) (0 clears), collect("name") and print(...). A dry run
+// only counts actions; applying runs them once every file was seen, minus
+// those of files the script failed on. Scripts get no file system or
+// process access, `import` included.
+
+// Per file, so a runaway loop stops instead of hanging the job, and one
+// growing a string or array fails before it takes the app's memory
+const MAX_OPERATIONS: u64 = 1_000_000;
+const MAX_STRING_SIZE: usize = 1024 * 1024;
+const MAX_ARRAY_SIZE: usize = 100_000;
+const MAX_MAP_SIZE: usize = 10_000;
+const MAX_CALL_LEVELS: usize = 64;
+const MAX_EXPR_DEPTH: usize = 64;
+// Kept in a report; the rest are only counted
+const MAX_ERRORS: usize = 50;
+const MAX_LOG: usize = 200;
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+#[serde(default)]
+pub struct SavedScript {
+    pub name: String,
+    pub source: String,
+}
+
+#[derive(Clone, Debug)]
+enum Action {
+    Tag(String),
+    Untag(String),
+    Label(Option<String>),
+    Rate(Option<u8>),
+    Collect(String),
+}
+
+#[derive(Default)]
+struct Recorder {
+    current: String, // path id of the file being run
+    actions: Vec<(String, Action)>,
+    log: Vec<String>,
+}
+
+#[derive(Serialize, Clone)]
+pub struct ScriptError {
+    pub path_id: String,
+    pub message: String,
+}
+
+#[derive(Serialize, Clone, Default)]
+pub struct ScriptReport {
+    pub matched: Vec<String>, // path ids the script picked
+    pub actions: usize,
+    pub applied: bool,
+    #[serde(skip)]
+    pub touched: Vec<String>, // path ids whose user data changed
+    pub errors: Vec<ScriptError>,
+    pub error_count: usize,
+    pub log: Vec<String>,
+}
+
+fn engine(rec: &Arc<Mutex<Recorder>>) -> Engine {
+    let mut engine = Engine::new();
+    // `Engine::new` loads `import`ed modules from .rhai files on disk
+    engine
+        .set_module_resolver(DummyModuleResolver::new())
+        .set_max_modules(0)
+        .set_max_operations(MAX_OPERATIONS)
+        .set_max_string_size(MAX_STRING_SIZE)
+        .set_max_array_size(MAX_ARRAY_SIZE)
+        .set_max_map_size(MAX_MAP_SIZE)
+        .set_max_call_levels(MAX_CALL_LEVELS)
+        .set_max_expr_depths(MAX_EXPR_DEPTH, MAX_EXPR_DEPTH);
+
+    let push = |rec: &Arc<Mutex<Recorder>>, action: Action| {
+        let mut r = rec.lock().unwrap();
+        let current = r.current.clone();
+        r.actions.push((current, action));
+    };
+    let r = rec.clone();
+    engine.register_fn("tag", move |name: &str| {
+        push(&r, Action::Tag(name.to_string()))
+    });
+    let r = rec.clone();
+    engine.register_fn("untag", move |name: &str| {
+        push(&r, Action::Untag(name.to_string()))
+    });
+    // Checked here, as a bad one found while applying would leave the
+    // other actions half done
+    let palette: Vec<String> = settings::get()
+        .label_palette
+        .into_iter()
+        .map(|l| l.name)
+        .collect();
+    let r = rec.clone();
+    engine.register_fn(
+        "label",
+        move |name: &str| -> Result<(), Box<EvalAltResult>> {
+            if !palette.iter().any(|l| l == name) {
+                return Err(format!("\"{}\" is not in the label palette", name).into());
+            }
+            push(&r, Action::Label(Some(name.to_string())));
+            Ok(())
+        },
+    );
+    let r = rec.clone();
+    engine.register_fn("label", move |_: ()| push(&r, Action::Label(None)));
+    let r = rec.clone();
+    engine.register_fn(
+        "rate",
+        move |stars: i64| -> Result<(), Box<EvalAltResult>> {
+            if !(0..=ratings::MAX_RATING as i64).contains(&stars) {
+                return Err(format!("Ratings go from 0 to {}", ratings::MAX_RATING).into());
+            }
+            push(&r, Action::Rate((stars > 0).then_some(stars as u8)));
+            Ok(())
+        },
+    );
+    let r = rec.clone();
+    engine.register_fn("collect", move |name: &str| {
+        push(&r, Action::Collect(name.to_string()))
+    });
+    let r = rec.clone();
+    engine.on_print(move |s| {
+        let mut r = r.lock().unwrap();
+        if r.log.len() < MAX_LOG {
+            r.log.push(s.to_string());
+        }
+    });
+    engine
+}
+
+fn compile(engine: &Engine, source: &str) -> Result<AST, AppError> {
+    engine.compile(source).map_err(|e| {
+        AppError::new(
+            ErrorKind::InvalidInput,
+            format!("Script is not valid: {}", e),
+        )
+        .with_code("script_invalid")
+        .with_param("detail", e)
+    })
+}
+
+// Rejects scripts that don't compile
+pub fn check(scripts: &[SavedScript]) -> Result<(), AppError> {
+    let engine = engine(&Arc::default());
+    for s in scripts {
+        if s.name.trim().is_empty() {
+            return Err(AppError::new(
+                ErrorKind::InvalidInput,
+                "A script needs a name",
+            ));
+        }
+        compile(&engine, &s.source).map_err(|mut e| {
+            e.message = format!("{}: {}", s.name, e.message);
+            e
+        })?;
+    }
+    Ok(())
+}
+
+fn opt(s: &Option<String>) -> Dynamic {
+    s.clone().map_or(Dynamic::UNIT, Dynamic::from)
+}
+
+fn strings(list: &[String]) -> Dynamic {
+    Dynamic::from_array(list.iter().cloned().map(Dynamic::from).collect())
+}
+
+fn file_map(f: &FlatFile) -> Map {
+    let mut m = Map::new();
+    m.insert("name".into(), f.name.clone().into());
+    m.insert("path".into(), f.path.clone().into());
+    m.insert("path_id".into(), f.path_id.clone().into());
+    m.insert("folder".into(), f.folder.clone().into());
+    m.insert("format".into(), f.format.clone().into());
+    m.insert("size".into(), (f.size_bytes as i64).into());
+    m.insert("version".into(), opt(&f.blender_version));
+    m.insert("engine".into(), opt(&f.render_engine));
+    m.insert(
+        "modified_ms".into(),
+        f.modified_ms.map_or(Dynamic::UNIT, Dynamic::from),
+    );
+    m.insert("label".into(), opt(&f.label));
+    m.insert("rating".into(), (f.rating.unwrap_or(0) as i64).into());
+    m.insert("favorite".into(), f.favorite.into());
+    m.insert("note".into(), opt(&f.note));
+    m.insert("tags".into(), strings(&f.tags));
+    m.insert("collections".into(), strings(&f.collections));
+    m.insert("libraries".into(), strings(&f.libraries));
+    let fields: Map = f
+        .fields
+        .iter()
+        .map(|(k, v)| (k.as_str().into(), v.clone().into()))
+        .collect();
+    m.insert("fields".into(), fields.into());
+    m
+}
+
+// Runs `source` over `files` (with user data, see annotate_user_data)
+pub fn run(
+    ctx: &JobCtx,
+    files: &[FlatFile],
+    source: &str,
+    apply: bool,
+) -> Result<ScriptReport, AppError> {
+    let rec = Arc::new(Mutex::new(Recorder::default()));
+    let engine = engine(&rec);
+    let ast = compile(&engine, source)?;
+    ctx.set_totals(files.len() as u64, 0);
+
+    let mut report = ScriptReport::default();
+    for f in files {
+        ctx.check_cancelled()?;
+        let recorded = {
+            let mut r = rec.lock().unwrap();
+            r.current = f.path_id.clone();
+            r.actions.len()
+        };
+        let mut scope = Scope::new();
+        scope.push("file", file_map(f));
+        match engine.eval_ast_with_scope::<Dynamic>(&mut scope, &ast) {
+            Ok(v) if v.as_bool() == Ok(true) => report.matched.push(f.path_id.clone()),
+            Ok(_) => {}
+            Err(e) => {
+                // A file is scripted whole or not at all
+                rec.lock().unwrap().actions.truncate(recorded);
+                report.error_count += 1;
+                if report.errors.len() < MAX_ERRORS {
+                    report.errors.push(ScriptError {
+                        path_id: f.path_id.clone(),
+                        message: e.to_string(),
+                    });
+                }
+            }
+        }
+        ctx.item_done();
+    }
+
+    let Recorder { actions, log, .. } = std::mem::take(&mut *rec.lock().unwrap());
+    report.actions = actions.len();
+    report.log = log;
+    if apply && !actions.is_empty() {
+        report.touched = perform(actions)?;
+        report.applied = true;
+    }
+    tracing::info!(
+        files = files.len(),
+        matched = report.matched.len(),
+        actions = report.actions,
+        errors = report.error_count,
+        applied = report.applied,
+        "script finished"
+    );
+    Ok(report)
+}
+
+// Groups actions per kind and value; returns the files touched
+fn perform(actions: Vec<(String, Action)>) -> Result<Vec<String>, AppError> {
+    let mut tag: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut untag: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut label: BTreeMap<Option<String>, Vec<String>> = BTreeMap::new();
+    let mut rate: BTreeMap<String, Option<u8>> = BTreeMap::new(); // the last one wins
+    let mut collect: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut touched = BTreeSet::new();
+    for (id, action) in actions {
+        touched.insert(id.clone());
+        match action {
+            Action::Tag(t) => tag.entry(t).or_default().push(id),
+            Action::Untag(t) => untag.entry(t.to_lowercase()).or_default().push(id),
+            Action::Label(l) => label.entry(l).or_default().push(id),
+            Action::Rate(r) => {
+                rate.insert(id, r);
+            }
+            Action::Collect(c) => collect.entry(c).or_default().push(id),
+        }
+    }
+
+    for (name, ids) in &tag {
+        tags::assign(ids, name)?;
+    }
+    if !untag.is_empty() {
+        for t in tags::list()? {
+            if let Some(ids) = untag.get(&t.name.to_lowercase()) {
+                tags::unassign(ids, t.id)?;
+            }
+        }
+    }
+    for (name, ids) in &label {
+        labels::set(ids, name.as_deref())?;
+    }
+    for (id, stars) in &rate {
+        ratings::set(id, *stars)?;
+    }
+    if !collect.is_empty() {
+        let existing = collections::list()?;
+        for (name, ids) in &collect {
+            let id = match existing
+                .iter()
+                .find(|c| c.name == *name && c.query.is_none())
+            {
+                Some(c) => c.id,
+                None => collections::create(name, None)?.id,
+            };
+            collections::add(id, ids)?;
+        }
+    }
+    Ok(touched.into_iter().collect())
+}
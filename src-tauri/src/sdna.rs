@@ -0,0 +1,409 @@
+use std::collections::HashMap;
+
+// -----------------------------
+// Struct layouts of a blend (DNA1)
+// -----------------------------
+// Every blend ends with a description of its own structs: field names,
+// types and sizes as the saving Blender laid them out. Offsets are worked
+// out from that instead of hardcoded, so reads keep working across
+// versions that move fields around.
+
+// Names like `*next`, `name[66]` or `(*func)()`
+struct Name {
+    plain: String,
+    pointer: bool,
+    items: usize, // product of the array dimensions
+}
+
+impl Name {
+    fn parse(raw: &str) -> Self {
+        let pointer = raw.starts_with('*') || raw.starts_with('(');
+        let plain = raw
+            .trim_start_matches(['(', '*'])
+            .split(['[', ')'])
+            .next()
+            .unwrap_or_default()
+            .to_string();
+        let items = raw
+            .split('[')
+            .skip(1)
+            .filter_map(|d| d.split(']').next()?.parse::<usize>().ok())
+            .product();
+        Self {
+            plain,
+            pointer,
+            items,
+        }
+    }
+}
+
+struct StructDef {
+    type_idx: usize,
+    fields: Vec<(usize, usize)>, // (type index, name index)
+}
+
+pub(crate) struct Sdna {
+    names: Vec<Name>,
+    types: Vec<String>,
+    lens: Vec<usize>,
+    structs: Vec<StructDef>,
+    by_type: HashMap<String, usize>, // type name -> struct index
+    ptr_size: usize,
+}
+
+#[derive(Clone, Copy)]
+pub(crate) struct Field {
+    pub offset: usize,
+    pub size: usize,
+    pub pointer: bool,
+}
+
+struct Cursor<'a> {
+    data: &'a [u8],
+    at: usize,
+    little: bool,
+}
+
+impl Cursor<'_> {
+    fn tag(&mut self, tag: &[u8]) -> Option<()> {
+        self.at = (self.at + 3) & !3;
+        let ok = self.data.get(self.at..self.at + 4)? == tag;
+        self.at += 4;
+        ok.then_some(())
+    }
+
+    fn u32(&mut self) -> Option<usize> {
+        let b: [u8; 4] = self.data.get(self.at..self.at + 4)?.try_into().ok()?;
+        self.at += 4;
+        Some(if self.little {
+            u32::from_le_bytes(b)
+        } else {
+            u32::from_be_bytes(b)
+        } as usize)
+    }
+
+    fn u16(&mut self) -> Option<usize> {
+        let b: [u8; 2] = self.data.get(self.at..self.at + 2)?.try_into().ok()?;
+        self.at += 2;
+        Some(if self.little {
+            u16::from_le_bytes(b)
+        } else {
+            u16::from_be_bytes(b)
+        } as usize)
+    }
+
+    fn strings(&mut self, n: usize) -> Option<Vec<String>> {
+        (0..n)
+            .map(|_| {
+                let rest = self.data.get(self.at..)?;
+                let end = rest.iter().position(|&b| b == 0)?;
+                self.at += end + 1;
+                Some(String::from_utf8_lossy(&rest[..end]).to_string())
+            })
+            .collect()
+    }
+}
+
+impl Sdna {
+    // `data` is the DNA1 block body
+    pub fn parse(data: &[u8], little: bool, ptr_size: usize) -> Option<Self> {
+        let mut c = Cursor {
+            data,
+            at: 0,
+            little,
+        };
+        c.tag(b"SDNA")?;
+        c.tag(b"NAME")?;
+        let n = c.u32()?;
+        let names = c.strings(n)?.iter().map(|s| Name::parse(s)).collect();
+        c.tag(b"TYPE")?;
+        let n = c.u32()?;
+        let types = c.strings(n)?;
+        c.tag(b"TLEN")?;
+        let lens = (0..types.len())
+            .map(|_| c.u16())
+            .collect::<Option<Vec<_>>>()?;
+        c.tag(b"STRC")?;
+        let n = c.u32()?;
+        let mut structs = Vec::with_capacity(n);
+        for _ in 0..n {
+            let type_idx = c.u16()?;
+            let count = c.u16()?;
+            let fields = (0..count)
+                .map(|_| Some((c.u16()?, c.u16()?)))
+                .collect::<Option<Vec<_>>>()?;
+            structs.push(StructDef { type_idx, fields });
+        }
+        let by_type = structs
+            .iter()
+            .enumerate()
+            .filter_map(|(i, s)| Some((types.get(s.type_idx)?.clone(), i)))
+            .collect();
+        Some(Self {
+            names,
+            types,
+            lens,
+            structs,
+            by_type,
+            ptr_size,
+        })
+    }
+
+    pub fn ptr_size(&self) -> usize {
+        self.ptr_size
+    }
+
+    // Type of the struct a block holds, from the block header's index
+    pub fn struct_name(&self, sdna_nr: u32) -> Option<&str> {
+        let s = self.structs.get(sdna_nr as usize)?;
+        self.types.get(s.type_idx).map(String::as_str)
+    }
+
+    // Field by dotted path through embedded structs, e.g. `r.scemode` in
+    // `Scene`
+    pub fn field(&self, struct_name: &str, path: &str) -> Option<Field> {
+        let mut owner = struct_name;
+        let mut base = 0;
+        let mut parts = path.split('.').peekable();
+        while let Some(part) = parts.next() {
+            let s = &self.structs[*self.by_type.get(owner)?];
+            let mut offset = 0;
+            let mut hit = None;
+            for &(type_idx, name_idx) in &s.fields {
+                let name = self.names.get(name_idx)?;
+                let one = if name.pointer {
+                    self.ptr_size
+                } else {
+                    *self.lens.get(type_idx)?
+                };
+                if name.plain == part {
+                    hit = Some((type_idx, name, one));
+                    break;
+                }
+                offset += one * name.items;
+            }
+            let (type_idx, name, one) = hit?;
+            base += offset;
+            if parts.peek().is_none() {
+                return Some(Field {
+                    offset: base,
+                    size: one * name.items,
+                    pointer: name.pointer,
+                });
+            }
+            if name.pointer {
+                return None;
+            }
+            owner = self.types.get(type_idx)?;
+        }
+        None
+    }
+
+    // First of the names a field went by across versions
+    pub fn field_any(&self, struct_name: &str, paths: &[&str]) -> Option<Field> {
+        paths.iter().find_map(|p| self.field(struct_name, p))
+    }
+}
+
+// Typed reads from a block body; out-of-range reads come back empty
+pub(crate) struct View<'a> {
+    pub data: &'a [u8],
+    pub little: bool,
+}
+
+impl View<'_> {
+    fn bytes(&self, offset: usize, size: usize) -> Option<&[u8]> {
+        self.data.get(offset..offset.checked_add(size)?)
+    }
+
+    // Unsigned integer of 1–8 bytes
+    pub fn uint_at(&self, offset: usize, size: usize) -> Option<u64> {
+        let b = self.bytes(offset, size).filter(|_| size <= 8)?;
+        let mut v = 0u64;
+        for i in 0..size {
+            let byte = if self.little { b[size - 1 - i] } else { b[i] };
+            v = v << 8 | byte as u64;
+        }
+        Some(v)
+    }
+
+    pub fn uint(&self, f: Option<Field>) -> Option<u64> {
+        let f = f?;
+        self.uint_at(f.offset, f.size)
+    }
+
+    // Item `i` of an array field (`w[2]`, `rect[2]`)
+    pub fn item(&self, f: Option<Field>, i: usize, item_size: usize) -> Option<u64> {
+        let f = f?;
+        (item_size * (i + 1) <= f.size).then_some(())?;
+        self.uint_at(f.offset + item_size * i, item_size)
+    }
+
+    pub fn str(&self, f: Option<Field>) -> Option<String> {
+        let f = f?;
+        let b = self.bytes(f.offset, f.size)?;
+        let end = b.iter().position(|&c| c == 0).unwrap_or(b.len());
+        Some(String::from_utf8_lossy(&b[..end]).to_string()).filter(|s| !s.is_empty())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+
+    // A little-endian DNA1 body: `types` with their lengths, then structs
+    // of (type, name) fields
+    pub(crate) fn dna(types: &[(&str, u16)], structs: &[(&str, &[(&str, &str)])]) -> Vec<u8> {
+        let type_idx = |t: &str| types.iter().position(|(n, _)| *n == t).unwrap() as u16;
+        let mut names: Vec<&str> = Vec::new();
+        for (_, fields) in structs {
+            for (_, name) in fields.iter() {
+                if !names.contains(name) {
+                    names.push(name);
+                }
+            }
+        }
+        let align = |out: &mut Vec<u8>| out.resize(out.len().next_multiple_of(4), 0);
+        let strings = |out: &mut Vec<u8>, list: &[&str]| {
+            out.extend((list.len() as u32).to_le_bytes());
+            for s in list {
+                out.extend(s.as_bytes());
+                out.push(0);
+            }
+        };
+
+        let mut out = b"SDNANAME".to_vec();
+        strings(&mut out, &names);
+        align(&mut out);
+        out.extend(b"TYPE");
+        strings(&mut out, &types.iter().map(|(n, _)| *n).collect::<Vec<_>>());
+        align(&mut out);
+        out.extend(b"TLEN");
+        for (_, len) in types {
+            out.extend(len.to_le_bytes());
+        }
+        align(&mut out);
+        out.extend(b"STRC");
+        out.extend((structs.len() as u32).to_le_bytes());
+        for (name, fields) in structs {
+            out.extend(type_idx(name).to_le_bytes());
+            out.extend((fields.len() as u16).to_le_bytes());
+            for (t, n) in fields.iter() {
+                out.extend(type_idx(t).to_le_bytes());
+                let n = names.iter().position(|x| x == n).unwrap() as u16;
+                out.extend(n.to_le_bytes());
+            }
+        }
+        out
+    }
+
+    fn sample(ptr_size: usize) -> Sdna {
+        let data = dna(
+            &[
+                ("char", 1),
+                ("int", 4),
+                ("float", 4),
+                ("void", 0),
+                ("RenderData", 12),
+                ("Scene", 0),
+            ],
+            &[
+                ("RenderData", &[("int", "scemode"), ("float", "size[2]")]),
+                (
+                    "Scene",
+                    &[
+                        ("Scene", "*next"),
+                        ("char", "name[66]"),
+                        ("RenderData", "r"),
+                        ("void", "(*func)()"),
+                    ],
+                ),
+            ],
+        );
+        Sdna::parse(&data, true, ptr_size).unwrap()
+    }
+
+    #[test]
+    fn names_parse() {
+        let n = Name::parse("*next");
+        assert!(n.pointer && n.plain == "next" && n.items == 1);
+        let n = Name::parse("mat[4][4]");
+        assert!(!n.pointer && n.plain == "mat" && n.items == 16);
+        let n = Name::parse("(*func)()");
+        assert!(n.pointer && n.plain == "func");
+    }
+
+    #[test]
+    fn field_offsets_follow_pointer_size() {
+        let s = sample(8);
+        assert_eq!(s.struct_name(1), Some("Scene"));
+        let name = s.field("Scene", "name").unwrap();
+        assert_eq!((name.offset, name.size, name.pointer), (8, 66, false));
+        let scemode = s.field("Scene", "r.scemode").unwrap();
+        assert_eq!((scemode.offset, scemode.size), (74, 4));
+        let size = s.field("Scene", "r.size").unwrap();
+        assert_eq!((size.offset, size.size), (78, 8));
+        assert!(s.field("Scene", "func").unwrap().pointer);
+
+        let s = sample(4);
+        assert_eq!(s.field("Scene", "r.scemode").unwrap().offset, 70);
+    }
+
+    #[test]
+    fn unknown_fields_are_none() {
+        let s = sample(8);
+        assert!(s.field("Scene", "missing").is_none());
+        assert!(s.field("Missing", "name").is_none());
+        // No going through a pointer
+        assert!(s.field("Scene", "next.name").is_none());
+        assert_eq!(
+            s.field_any("Scene", &["old_name", "name"])
+                .map(|f| f.offset),
+            Some(8)
+        );
+    }
+
+    #[test]
+    fn truncated_or_mislabelled_data_is_rejected() {
+        let data = dna(&[("int", 4), ("A", 4)], &[("A", &[("int", "x")])]);
+        assert!(Sdna::parse(&data, true, 8).is_some());
+        for cut in [0, 4, 10, data.len() - 1] {
+            assert!(
+                Sdna::parse(&data[..cut], true, 8).is_none(),
+                "cut at {}",
+                cut
+            );
+        }
+        let mut bad = data.clone();
+        bad[4..8].copy_from_slice(b"NAMX");
+        assert!(Sdna::parse(&bad, true, 8).is_none());
+    }
+
+    #[test]
+    fn views_read_both_byte_orders() {
+        let data = [0x01, 0x02, 0x03, 0x04, b'h', b'i', 0, b'x'];
+        let little = View {
+            data: &data,
+            little: true,
+        };
+        let big = View {
+            data: &data,
+            little: false,
+        };
+        assert_eq!(little.uint_at(0, 4), Some(0x0403_0201));
+        assert_eq!(big.uint_at(0, 4), Some(0x0102_0304));
+        assert_eq!(little.uint_at(6, 4), None);
+        assert_eq!(little.uint_at(0, 9), None);
+        let field = |offset, size| {
+            Some(Field {
+                offset,
+                size,
+                pointer: false,
+            })
+        };
+        assert_eq!(little.str(field(4, 4)), Some("hi".to_string()));
+        assert_eq!(little.str(field(6, 1)), None);
+        assert_eq!(big.item(field(0, 4), 1, 2), Some(0x0304));
+        assert_eq!(big.item(field(0, 4), 2, 2), None);
+    }
+}
@@ -0,0 +1,142 @@
+use crate::catalog::RemoteCatalog;
+use crate::error::AppError;
+use crate::exclude;
+use crate::extractors::ExtractorConfig;
+use crate::hotkey;
+use crate::labels::{self, LabelColor};
+use crate::rules::Rule;
+use crate::scripts::SavedScript;
+use crate::staleness;
+use crate::tools::ExternalTool;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+
+// -----------------------------
+// Persistent settings (settings.json in the app config dir)
+// -----------------------------
+// Unknown or missing fields fall back to defaults, so older files keep
+// loading as settings are added.
+
+pub const SETTINGS_FILE_NAME: &str = "settings.json";
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ElevationDecision {
+    Allow, // rescan this folder with admin rights whenever it is skipped
+    Deny,  // never offer elevation for it again
+}
+
+#[derive(Serialize, Deserialize, Clone)]
+#[serde(default)]
+pub struct Settings {
+    // Keyed by folder path id
+    pub elevation: BTreeMap<String, ElevationDecision>,
+    // Folders skipped when scanning a drive root (see exclude.rs)
+    pub exclusions: Vec<String>,
+    // Color labels users can assign (see labels.rs)
+    pub label_palette: Vec<LabelColor>,
+    // Mirror tags/ratings/notes into .blendmeta.json files (see sidecar.rs)
+    pub sidecars: bool,
+    // Mirror tags/ratings into extended attributes (see xattrs.rs)
+    pub xattrs: bool,
+    // Timestamps in exports in UTC instead of local time (see export.rs)
+    pub export_utc: bool,
+    // Global accelerator that opens quick find (see hotkey.rs); None = off
+    pub quick_find_shortcut: Option<String>,
+    // Look for a newer release at startup (see updater.rs)
+    pub check_updates: bool,
+    // Closing the main window hides it to the tray (see tray.rs)
+    pub close_to_tray: bool,
+    // How much each part counts towards a file's staleness score (see
+    // staleness.rs)
+    pub staleness: staleness::Weights,
+    // Tags, labels and collections given by path (see rules.rs)
+    pub tag_rules: Vec<Rule>,
+    // Folders summarized as projects, as path ids (see projects.rs)
+    pub project_roots: Vec<String>,
+    // Context menu actions that run other programs (see tools.rs)
+    pub tools: Vec<ExternalTool>,
+    // Custom fields from file names and scripts (see extractors.rs)
+    pub extractors: Vec<ExtractorConfig>,
+    // Saved filter and action scripts (see scripts.rs)
+    pub scripts: Vec<SavedScript>,
+    // Count opens, search picks and tagging in index.db (see usage.rs)
+    pub usage_stats: bool,
+    // Catalogs other machines publish, to attach to (see catalog.rs)
+    pub catalogs: Vec<RemoteCatalog>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            elevation: BTreeMap::new(),
+            exclusions: exclude::defaults(),
+            label_palette: labels::default_palette(),
+            sidecars: false,
+            xattrs: false,
+            export_utc: false,
+            quick_find_shortcut: Some(hotkey::DEFAULT_SHORTCUT.to_string()),
+            check_updates: true,
+            close_to_tray: false,
+            staleness: staleness::Weights::default(),
+            tag_rules: Vec::new(),
+            project_roots: Vec::new(),
+            tools: Vec::new(),
+            extractors: Vec::new(),
+            scripts: Vec::new(),
+            usage_stats: true,
+            catalogs: Vec::new(),
+        }
+    }
+}
+
+static SETTINGS_PATH: OnceLock<PathBuf> = OnceLock::new();
+static SETTINGS: OnceLock<Mutex<Settings>> = OnceLock::new();
+
+fn settings() -> &'static Mutex<Settings> {
+    SETTINGS.get_or_init(|| Mutex::new(Settings::default()))
+}
+
+// Loads settings once at startup; without a config dir they live in memory only
+pub fn init(config_dir: Option<PathBuf>) {
+    let Some(dir) = config_dir else {
+        return;
+    };
+    let path = dir.join(SETTINGS_FILE_NAME);
+    let loaded = fs::read_to_string(&path)
+        .ok()
+        .and_then(|text| match serde_json::from_str::<Settings>(&text) {
+            Ok(s) => Some(s),
+            Err(e) => {
+                tracing::warn!(path = %path.display(), "ignoring unreadable settings: {}", e);
+                None
+            }
+        })
+        .unwrap_or_default();
+    *settings().lock().unwrap() = loaded;
+    let _ = SETTINGS_PATH.set(path);
+}
+
+pub fn get() -> Settings {
+    settings().lock().unwrap().clone()
+}
+
+// Applies `f` and writes the file (temp file + rename, so a crash never
+// leaves half a settings file behind)
+pub fn update(f: impl FnOnce(&mut Settings)) -> Result<Settings, AppError> {
+    let mut current = settings().lock().unwrap();
+    f(&mut current);
+    if let Some(path) = SETTINGS_PATH.get() {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).map_err(|e| AppError::io(&e, dir))?;
+        }
+        let tmp = path.with_extension("json.tmp");
+        let text = serde_json::to_string_pretty(&*current).unwrap_or_default();
+        fs::write(&tmp, text).map_err(|e| AppError::io(&e, &tmp))?;
+        fs::rename(&tmp, path).map_err(|e| AppError::io(&e, path))?;
+    }
+    Ok(current.clone())
+}
@@ -0,0 +1,128 @@
+use crate::error::AppError;
+use crate::{db, formats, notes, paths, ratings, settings, tags};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+// -----------------------------
+// Sidecar metadata files (`scene.blend` -> `scene.blendmeta.json`,
+// `hero.fbx` -> `hero.fbx.blendmeta.json`)
+// -----------------------------
+// Optional copy of a file's tags, rating and note kept next to it, so the
+// metadata travels when folders are copied between machines. The index DB
+// stays authoritative; on scan, sidecars only fill in what it lacks.
+
+pub const SIDECAR_EXTENSION: &str = "blendmeta.json";
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct Sidecar {
+    version: u32,
+    tags: Vec<String>,
+    rating: Option<u8>,
+    note: Option<String>,
+}
+
+impl Sidecar {
+    fn is_empty(&self) -> bool {
+        self.tags.is_empty() && self.rating.is_none() && self.note.is_none()
+    }
+}
+
+pub fn enabled() -> bool {
+    settings::get().sidecars
+}
+
+// Other formats keep their extension so `hero.fbx` and `hero.blend` don't
+// share a sidecar
+pub fn path_for(file: &Path) -> PathBuf {
+    if formats::of_path(file, false) == Some(formats::BLEND) {
+        return file.with_extension(SIDECAR_EXTENSION);
+    }
+    let mut name = file.file_name().unwrap_or_default().to_owned();
+    name.push(".");
+    name.push(SIDECAR_EXTENSION);
+    file.with_file_name(name)
+}
+
+fn current(path_id: &str) -> Result<Sidecar, AppError> {
+    Ok(Sidecar {
+        version: FORMAT_VERSION,
+        tags: tags::of(path_id)?,
+        rating: ratings::get(path_id)?,
+        note: notes::get(path_id)?,
+    })
+}
+
+// Writes the file's current user data next to it; no data, no sidecar
+fn write(path_id: &str) -> Result<(), AppError> {
+    let blend = paths::from_id(path_id);
+    if !blend.is_file() {
+        return Ok(());
+    }
+    let side = path_for(&blend);
+    let data = current(path_id)?;
+    if data.is_empty() {
+        return match fs::remove_file(&side) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(AppError::io(&e, &side)),
+            _ => Ok(()),
+        };
+    }
+    let text = serde_json::to_string_pretty(&data).unwrap_or_default();
+    fs::write(&side, text).map_err(|e| AppError::io(&e, &side))
+}
+
+// Call after user data of `path_ids` changed; failures are only logged
+pub fn sync(path_ids: &[String]) {
+    if !enabled() {
+        return;
+    }
+    for p in path_ids {
+        if let Err(e) = write(p) {
+            tracing::warn!("writing sidecar failed: {}", e);
+        }
+    }
+}
+
+// Writes sidecars for every file that has user data (mode switched on)
+pub fn export_all() -> Result<usize, AppError> {
+    let path_ids: Vec<String> = db::with(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT path_id FROM file_tags UNION SELECT path_id FROM ratings
+             UNION SELECT path_id FROM notes",
+        )?;
+        let rows = stmt.query_map([], |r| r.get(0))?;
+        rows.collect()
+    })?;
+    sync(&path_ids);
+    Ok(path_ids.len())
+}
+
+// Merges a blend's sidecar into the index: tags are added, rating and
+// note only taken when the index has none
+pub fn import(blend: &Path) -> Result<(), AppError> {
+    let side = path_for(blend);
+    let text = match fs::read_to_string(&side) {
+        Ok(t) => t,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => return Err(AppError::io(&e, &side)),
+    };
+    let Ok(data) = serde_json::from_str::<Sidecar>(&text) else {
+        tracing::warn!(path = %side.display(), "ignoring unreadable sidecar");
+        return Ok(());
+    };
+    let path_id = paths::to_id(blend);
+    let ids = [path_id.clone()];
+    for name in data.tags.iter().filter(|n| !n.trim().is_empty()) {
+        tags::assign(&ids, name)?;
+    }
+    if data.rating.is_some() && ratings::get(&path_id)?.is_none() {
+        ratings::set(&path_id, data.rating)?;
+    }
+    if data.note.is_some() && notes::get(&path_id)?.is_none() {
+        notes::set(&path_id, data.note.as_deref())?;
+    }
+    Ok(())
+}
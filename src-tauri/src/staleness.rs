@@ -0,0 +1,117 @@
+use crate::{archive, formats, paths, settings, FlatFile, ScanResult};
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+
+// -----------------------------
+// Staleness scores
+// -----------------------------
+// A 0–100 score per file for finding cleanup candidates: old, with a pile
+// of backups next to it, linked by no other blend of the scan, and a
+// likely duplicate (same name and size elsewhere). Each part has a weight
+// in settings; a weight of 0 leaves that part out.
+
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+
+// Backups at which that part of the score is full
+const FULL_BACKUPS: usize = 5;
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Debug)]
+#[serde(default)]
+pub struct Weights {
+    pub age: f64,
+    pub full_age_days: u32, // age at which the age part is full
+    pub backups: f64,
+    pub unlinked: f64,
+    pub duplicate: f64,
+}
+
+impl Default for Weights {
+    fn default() -> Self {
+        Self {
+            age: 0.5,
+            full_age_days: 730,
+            backups: 0.15,
+            unlinked: 0.15,
+            duplicate: 0.2,
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct Staleness {
+    pub score: u8,
+    pub age_days: Option<i64>,
+    pub backups: usize,
+    pub linked: bool, // another blend of the scan links data from it
+    pub duplicate: bool,
+}
+
+// `//`-relative library paths are relative to the linking blend's folder
+pub(crate) fn resolve(blend: &Path, saved: &str) -> PathBuf {
+    match saved.strip_prefix("//") {
+        Some(rel) => {
+            let dir = blend.parent().unwrap_or(Path::new(""));
+            paths::normalize(&dir.join(rel.replace('\\', "/")))
+        }
+        None => paths::normalize(Path::new(saved)),
+    }
+}
+
+fn duplicate_key(f: &FlatFile) -> (String, u64) {
+    (f.name.to_lowercase(), f.size_bytes)
+}
+
+// Scores every file of `result` with the weights from settings
+pub fn apply(result: &mut ScanResult) {
+    let w = settings::get().staleness;
+    let linked: HashSet<String> = result
+        .files
+        .iter()
+        .flat_map(|f| {
+            let blend = paths::from_id(&f.path_id);
+            f.libraries
+                .iter()
+                .map(move |l| paths::to_id(&resolve(&blend, l)))
+        })
+        .collect();
+    let mut copies: HashMap<(String, u64), usize> = HashMap::new();
+    for f in result.files.iter().filter(|f| f.size_bytes > 0) {
+        *copies.entry(duplicate_key(f)).or_default() += 1;
+    }
+
+    let now = Local::now().timestamp_millis();
+    let total = w.age + w.backups + w.unlinked + w.duplicate;
+    for f in &mut result.files {
+        let path = paths::from_id(&f.path_id);
+        let age_days = f.modified_ms.map(|m| ((now - m) / DAY_MS).max(0));
+        // Archive entries and placeholders have no backups on disk to count
+        let backups = if archive::split(&path).is_some() || f.cloud_only {
+            0
+        } else {
+            formats::backups_of(&path).len()
+        };
+        let is_linked = linked.contains(&f.path_id);
+        let duplicate = f.size_bytes > 0 && copies.get(&duplicate_key(f)).is_some_and(|n| *n > 1);
+
+        let age_part =
+            age_days.map_or(0.0, |d| (d as f64 / w.full_age_days.max(1) as f64).min(1.0));
+        let sum = w.age * age_part
+            + w.backups * (backups as f64 / FULL_BACKUPS as f64).min(1.0)
+            + if is_linked { 0.0 } else { w.unlinked }
+            + if duplicate { w.duplicate } else { 0.0 };
+        let score = if total > 0.0 {
+            (sum / total * 100.0).round().clamp(0.0, 100.0) as u8
+        } else {
+            0
+        };
+        f.staleness = Some(Staleness {
+            score,
+            age_days,
+            backups,
+            linked: is_linked,
+            duplicate,
+        });
+    }
+}
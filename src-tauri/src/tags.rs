@@ -0,0 +1,224 @@
+use crate::db;
+use crate::error::{AppError, ErrorKind};
+use crate::FlatFile;
+use rusqlite::{params, OptionalExtension};
+use serde::Serialize;
+use std::collections::HashMap;
+
+// -----------------------------
+// User tags ("approved", "wip", "to-delete", …)
+// -----------------------------
+// Tag names are unique ignoring case; assignments are keyed by path id so
+// they survive rescans.
+
+#[derive(Serialize, Clone)]
+pub struct Tag {
+    pub id: i64,
+    pub name: String,
+    pub color: Option<String>,
+    pub file_count: u64,
+}
+
+fn clean_name(name: &str) -> Result<String, AppError> {
+    let name = name.trim();
+    if name.is_empty() {
+        return Err(
+            AppError::new(ErrorKind::InvalidInput, "Tag name can't be empty")
+                .with_code("tag_name_empty"),
+        );
+    }
+    Ok(name.to_string())
+}
+
+fn duplicate(name: &str) -> AppError {
+    AppError::new(
+        ErrorKind::InvalidInput,
+        format!("A tag named \"{}\" already exists", name),
+    )
+    .with_code("tag_exists")
+    .with_param("name", name)
+}
+
+fn is_unique_violation(e: &rusqlite::Error) -> bool {
+    matches!(
+        e.sqlite_error_code(),
+        Some(rusqlite::ErrorCode::ConstraintViolation)
+    )
+}
+
+pub fn list() -> Result<Vec<Tag>, AppError> {
+    db::with(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT t.id, t.name, t.color, COUNT(ft.path_id)
+             FROM tags t LEFT JOIN file_tags ft ON ft.tag_id = t.id
+             GROUP BY t.id ORDER BY t.name COLLATE NOCASE",
+        )?;
+        let rows = stmt.query_map([], |r| {
+            Ok(Tag {
+                id: r.get(0)?,
+                name: r.get(1)?,
+                color: r.get(2)?,
+                file_count: r.get(3)?,
+            })
+        })?;
+        rows.collect()
+    })
+}
+
+fn get(id: i64) -> Result<Tag, AppError> {
+    list()?.into_iter().find(|t| t.id == id).ok_or_else(|| {
+        AppError::new(ErrorKind::NotFound, format!("Tag {} not found", id))
+            .with_code("tag_not_found")
+            .with_param("id", id)
+    })
+}
+
+pub fn create(name: &str, color: Option<String>) -> Result<Tag, AppError> {
+    let name = clean_name(name)?;
+    let id = db::with(|conn| {
+        match conn.execute(
+            "INSERT INTO tags (name, color) VALUES (?1, ?2)",
+            params![name, color],
+        ) {
+            Err(e) if is_unique_violation(&e) => Ok(None),
+            other => other.map(|_| Some(conn.last_insert_rowid())),
+        }
+    })?
+    .ok_or_else(|| duplicate(&name))?;
+    get(id)
+}
+
+pub fn rename(id: i64, name: &str) -> Result<Tag, AppError> {
+    let name = clean_name(name)?;
+    db::with(|conn| {
+        match conn.execute("UPDATE tags SET name = ?1 WHERE id = ?2", params![name, id]) {
+            Err(e) if is_unique_violation(&e) => Ok(None),
+            other => other.map(Some),
+        }
+    })?
+    .ok_or_else(|| duplicate(&name))?;
+    get(id)
+}
+
+pub fn set_color(id: i64, color: Option<String>) -> Result<Tag, AppError> {
+    db::with(|conn| {
+        conn.execute(
+            "UPDATE tags SET color = ?1 WHERE id = ?2",
+            params![color, id],
+        )
+    })?;
+    get(id)
+}
+
+// Assignments go with the tag
+pub fn delete(id: i64) -> Result<(), AppError> {
+    db::with(|conn| conn.execute("DELETE FROM tags WHERE id = ?1", [id])).map(|_| ())
+}
+
+// Tags `path_ids` by name, creating the tag if needed
+pub fn assign(path_ids: &[String], name: &str) -> Result<Tag, AppError> {
+    let name = clean_name(name)?;
+    let id = db::with(|conn| {
+        let tx = conn.transaction()?;
+        let existing: Option<i64> = tx
+            .query_row("SELECT id FROM tags WHERE name = ?1", [&name], |r| r.get(0))
+            .optional()?;
+        let id = match existing {
+            Some(id) => id,
+            None => {
+                tx.execute("INSERT INTO tags (name) VALUES (?1)", [&name])?;
+                tx.last_insert_rowid()
+            }
+        };
+        {
+            let mut stmt =
+                tx.prepare("INSERT OR IGNORE INTO file_tags (path_id, tag_id) VALUES (?1, ?2)")?;
+            for p in path_ids {
+                stmt.execute(params![p, id])?;
+            }
+        }
+        tx.commit()?;
+        Ok(id)
+    })?;
+    get(id)
+}
+
+pub fn attach(path_ids: &[String], id: i64) -> Result<(), AppError> {
+    db::with(|conn| {
+        let tx = conn.transaction()?;
+        {
+            let mut stmt =
+                tx.prepare("INSERT OR IGNORE INTO file_tags (path_id, tag_id) VALUES (?1, ?2)")?;
+            for p in path_ids {
+                stmt.execute(params![p, id])?;
+            }
+        }
+        tx.commit()
+    })
+}
+
+pub fn unassign(path_ids: &[String], id: i64) -> Result<(), AppError> {
+    db::with(|conn| {
+        let tx = conn.transaction()?;
+        {
+            let mut stmt =
+                tx.prepare("DELETE FROM file_tags WHERE path_id = ?1 AND tag_id = ?2")?;
+            for p in path_ids {
+                stmt.execute(params![p, id])?;
+            }
+        }
+        tx.commit()
+    })
+}
+
+// Tag names on one file
+pub fn of(path_id: &str) -> Result<Vec<String>, AppError> {
+    db::with(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT t.name FROM file_tags ft JOIN tags t ON t.id = ft.tag_id
+             WHERE ft.path_id = ?1 ORDER BY t.name COLLATE NOCASE",
+        )?;
+        let rows = stmt.query_map([path_id], |r| r.get(0))?;
+        rows.collect()
+    })
+}
+
+// Path ids carrying a tag
+pub fn files(id: i64) -> Result<Vec<String>, AppError> {
+    db::with(|conn| {
+        let mut stmt = conn.prepare("SELECT path_id FROM file_tags WHERE tag_id = ?1")?;
+        let rows = stmt.query_map([id], |r| r.get(0))?;
+        rows.collect()
+    })
+}
+
+// path id -> tag names, for every tagged file
+fn assignments() -> Result<HashMap<String, Vec<String>>, AppError> {
+    db::with(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT ft.path_id, t.name FROM file_tags ft JOIN tags t ON t.id = ft.tag_id
+             ORDER BY t.name COLLATE NOCASE",
+        )?;
+        let mut map: HashMap<String, Vec<String>> = HashMap::new();
+        let rows = stmt.query_map([], |r| Ok((r.get::<_, String>(0)?, r.get(1)?)))?;
+        for row in rows {
+            let (path_id, name) = row?;
+            map.entry(path_id).or_default().push(name);
+        }
+        Ok(map)
+    })
+}
+
+// Fills `tags` on result files from the current assignments
+pub fn annotate(files: &mut [FlatFile]) {
+    let map = match assignments() {
+        Ok(map) => map,
+        Err(e) => {
+            tracing::warn!("loading tags failed: {}", e);
+            return;
+        }
+    };
+    for f in files {
+        f.tags = map.get(&f.path_id).cloned().unwrap_or_default();
+    }
+}
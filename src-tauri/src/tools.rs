@@ -0,0 +1,220 @@
+use crate::error::{AppError, ErrorKind};
+use crate::jobs::JobCtx;
+use crate::{archive, paths};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::ffi::OsString;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+use std::time::Duration;
+
+// -----------------------------
+// External tools
+// -----------------------------
+// User-defined context menu actions ("Open in fSpy", "Submit to farm").
+// The program is started directly, never through a shell, once per file
+// and in the file's folder. Each argument is filled on its own, so a path
+// with spaces or quotes stays one argument:
+//
+//   {path}    the file
+//   {folder}  its folder
+//   {name}    file name
+//   {stem}    file name without extension
+//
+// Archive entries are extracted first (see archive.rs). Output is kept
+// for the job result.
+
+// Output kept per stream; the end is kept, that's where errors show
+const OUTPUT_LIMIT: usize = 64 * 1024;
+const POLL: Duration = Duration::from_millis(100);
+
+#[derive(Serialize, Deserialize, Clone, Default, Debug, PartialEq)]
+#[serde(default)]
+pub struct ExternalTool {
+    pub name: String,         // menu entry
+    pub program: String,      // executable path, or a name on PATH
+    pub args: Vec<String>,    // one argument each, tokens filled per file
+    pub formats: Vec<String>, // offered for these formats only; empty = all
+}
+
+#[derive(Serialize, Clone)]
+pub struct ToolRun {
+    pub path: String,
+    pub path_id: String,
+    pub exit_code: Option<i32>, // None when killed by a signal or not started
+    pub stdout: String,
+    pub stderr: String,
+    pub error: Option<AppError>, // the program could not be started
+}
+
+#[derive(Serialize, Clone)]
+pub struct ToolReport {
+    pub tool: String,
+    pub runs: Vec<ToolRun>,
+}
+
+// Rejects tools that could not be offered or run
+pub fn check(tools: &[ExternalTool]) -> Result<(), AppError> {
+    let mut names = HashSet::new();
+    for tool in tools {
+        let name = tool.name.trim();
+        if name.is_empty() {
+            return Err(AppError::new(
+                ErrorKind::InvalidInput,
+                "A tool needs a name",
+            ));
+        }
+        if tool.program.trim().is_empty() {
+            return Err(AppError::new(
+                ErrorKind::InvalidInput,
+                format!("Tool \"{}\" needs a program", name),
+            ));
+        }
+        if !names.insert(name.to_lowercase()) {
+            return Err(AppError::new(
+                ErrorKind::InvalidInput,
+                format!("There is more than one tool named \"{}\"", name),
+            ));
+        }
+    }
+    Ok(())
+}
+
+// One pass, so braces inside a substituted value are left alone. Paths go
+// in as OS strings, so names that aren't valid Unicode reach the tool as is.
+fn fill(arg: &str, file: &Path) -> OsString {
+    let mut out = OsString::new();
+    let mut rest = arg;
+    while let Some(start) = rest.find('{') {
+        let Some(len) = rest[start..].find('}') else {
+            break;
+        };
+        out.push(&rest[..start]);
+        match &rest[start + 1..start + len] {
+            "path" => out.push(file.as_os_str()),
+            "folder" => out.push(file.parent().map(Path::as_os_str).unwrap_or_default()),
+            "name" => out.push(file.file_name().unwrap_or_default()),
+            "stem" => out.push(file.file_stem().unwrap_or_default()),
+            // Unknown tokens stay as typed
+            other => out.push(format!("{{{}}}", other)),
+        }
+        rest = &rest[start + len + 1..];
+    }
+    out.push(rest);
+    out
+}
+
+// Reads a pipe to its end, keeping the last OUTPUT_LIMIT bytes
+fn capture(mut pipe: impl Read + Send + 'static) -> thread::JoinHandle<String> {
+    thread::spawn(move || {
+        let mut kept = Vec::new();
+        let mut buf = [0u8; 8192];
+        while let Ok(n) = pipe.read(&mut buf) {
+            if n == 0 {
+                break;
+            }
+            kept.extend_from_slice(&buf[..n]);
+            if kept.len() > 2 * OUTPUT_LIMIT {
+                kept.drain(..kept.len() - OUTPUT_LIMIT);
+            }
+        }
+        if kept.len() > OUTPUT_LIMIT {
+            kept.drain(..kept.len() - OUTPUT_LIMIT);
+        }
+        String::from_utf8_lossy(&kept).to_string()
+    })
+}
+
+fn command(tool: &ExternalTool, file: &Path) -> Command {
+    let mut cmd = Command::new(tool.program.trim());
+    cmd.args(tool.args.iter().map(|a| fill(a, file)))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(folder) = file.parent().filter(|p| p.is_dir()) {
+        cmd.current_dir(folder);
+    }
+    #[cfg(windows)]
+    {
+        use std::os::windows::process::CommandExt;
+        const CREATE_NO_WINDOW: u32 = 0x0800_0000;
+        cmd.creation_flags(CREATE_NO_WINDOW);
+    }
+    cmd
+}
+
+// Cancelling the job kills the running program
+fn run_one(ctx: &JobCtx, tool: &ExternalTool, file: &Path) -> Result<ToolRun, AppError> {
+    let mut run = ToolRun {
+        path: file.to_string_lossy().to_string(),
+        path_id: paths::to_id(file),
+        exit_code: None,
+        stdout: String::new(),
+        stderr: String::new(),
+        error: None,
+    };
+    let target = if archive::split(file).is_some() {
+        match archive::extract(file) {
+            Ok(p) => p,
+            Err(e) => {
+                run.error = Some(e);
+                return Ok(run);
+            }
+        }
+    } else {
+        file.to_path_buf()
+    };
+    let mut child = match command(tool, &target).spawn() {
+        Ok(child) => child,
+        Err(e) => {
+            run.error = Some(AppError::io(&e, Path::new(tool.program.trim())));
+            return Ok(run);
+        }
+    };
+    let stdout = child.stdout.take().map(capture);
+    let stderr = child.stderr.take().map(capture);
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break Some(status),
+            Ok(None) => {}
+            Err(e) => {
+                run.error = Some(AppError::io(&e, Path::new(tool.program.trim())));
+                break None;
+            }
+        }
+        if let Err(e) = ctx.check_cancelled() {
+            let _ = child.kill();
+            let _ = child.wait();
+            return Err(e);
+        }
+        thread::sleep(POLL);
+    };
+    run.exit_code = status.and_then(|s| s.code());
+    run.stdout = stdout.and_then(|h| h.join().ok()).unwrap_or_default();
+    run.stderr = stderr.and_then(|h| h.join().ok()).unwrap_or_default();
+    Ok(run)
+}
+
+pub fn run(ctx: &JobCtx, tool: &ExternalTool, files: &[PathBuf]) -> Result<ToolReport, AppError> {
+    ctx.set_totals(files.len() as u64, 0);
+    let mut runs = Vec::with_capacity(files.len());
+    for file in files {
+        ctx.check_cancelled()?;
+        ctx.set_current(file);
+        let run = run_one(ctx, tool, file)?;
+        tracing::info!(
+            tool = %tool.name,
+            path = %file.display(),
+            exit_code = ?run.exit_code,
+            "external tool finished"
+        );
+        runs.push(run);
+        ctx.item_done();
+    }
+    Ok(ToolReport {
+        tool: tool.name.clone(),
+        runs,
+    })
+}
@@ -0,0 +1,271 @@
+use crate::error::{AppError, ErrorKind};
+use crate::paths;
+use crate::volume;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// -----------------------------
+// OS trash / recycle bin
+// -----------------------------
+// Optional scan source so accidentally deleted blends can be found and put
+// back. Each platform records the original location differently:
+// freedesktop `.trashinfo` files, `$I` records next to `$R` files in the
+// Windows recycle bin, and nothing readable on macOS.
+
+#[derive(Serialize, Deserialize, Clone, Default)]
+pub struct TrashInfo {
+    pub original_path: Option<String>,
+    pub original_path_id: Option<String>,
+    pub deleted_at: Option<String>,
+}
+
+// Where a top-level trashed item came from
+struct Origin {
+    original: PathBuf,
+    deleted_at: Option<String>,
+    record: Option<PathBuf>, // metadata file to remove on restore
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn home() -> Option<PathBuf> {
+    std::env::var_os("HOME").map(PathBuf::from)
+}
+
+// Trash directories relevant to a scan of `root`: the user's own trash
+// plus the one on root's volume
+pub fn locations(root: &Path) -> Vec<PathBuf> {
+    let mut out: Vec<PathBuf> = Vec::new();
+    for loc in platform_locations(&volume::volume_of(root)) {
+        if loc.is_dir() && !out.contains(&loc) {
+            out.push(loc);
+        }
+    }
+    out
+}
+
+#[cfg(target_os = "linux")]
+fn platform_locations(volume: &Path) -> Vec<PathBuf> {
+    // SAFETY: getuid has no preconditions
+    let uid = unsafe { libc::getuid() };
+    let data = std::env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| home().map(|h| h.join(".local/share")));
+    data.map(|d| d.join("Trash/files"))
+        .into_iter()
+        .chain([
+            volume.join(".Trash").join(uid.to_string()).join("files"),
+            volume.join(format!(".Trash-{}", uid)).join("files"),
+        ])
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+fn platform_locations(volume: &Path) -> Vec<PathBuf> {
+    // SAFETY: getuid has no preconditions
+    let uid = unsafe { libc::getuid() };
+    home()
+        .map(|h| h.join(".Trash"))
+        .into_iter()
+        .chain([volume.join(".Trashes").join(uid.to_string())])
+        .collect()
+}
+
+// Every user's bin lives under `$Recycle.Bin\<SID>`; other users' folders
+// just fail to list
+#[cfg(windows)]
+fn platform_locations(volume: &Path) -> Vec<PathBuf> {
+    let system = std::env::var_os("SystemDrive").map(|d| PathBuf::from(d).join("\\"));
+    [Some(volume.to_path_buf()), system]
+        .into_iter()
+        .flatten()
+        .flat_map(|v| fs::read_dir(v.join("$Recycle.Bin")).into_iter().flatten())
+        .flatten()
+        .map(|e| e.path())
+        .collect()
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn platform_locations(_volume: &Path) -> Vec<PathBuf> {
+    Vec::new()
+}
+
+// Top-level trashed item holding `path`
+fn top_item(loc: &Path, path: &Path) -> Option<PathBuf> {
+    let first = path.strip_prefix(loc).ok()?.components().next()?;
+    Some(loc.join(first))
+}
+
+// Splits a trashed path into (location, top-level trashed item)
+fn split(path: &Path) -> Option<(PathBuf, PathBuf)> {
+    locations(path)
+        .into_iter()
+        .find_map(|loc| Some((loc.clone(), top_item(&loc, path)?)))
+}
+
+// `path` was found under trash location `loc`; files inside a trashed
+// folder come back relative to the folder's origin
+pub fn info(loc: &Path, path: &Path) -> TrashInfo {
+    let Some(top) = top_item(loc, path) else {
+        return TrashInfo::default();
+    };
+    let Some(origin) = origin(loc, &top) else {
+        return TrashInfo::default();
+    };
+    let original = paths::normalize(&match path.strip_prefix(&top) {
+        Ok(rest) if !rest.as_os_str().is_empty() => origin.original.join(rest),
+        _ => origin.original,
+    });
+    TrashInfo {
+        original_path: Some(original.to_string_lossy().to_string()),
+        original_path_id: Some(paths::to_id(&original)),
+        deleted_at: origin.deleted_at,
+    }
+}
+
+// Moves a trashed file back to where it was deleted from
+pub fn restore(path: &Path) -> Result<PathBuf, AppError> {
+    let not_trashed =
+        || AppError::new(ErrorKind::InvalidInput, "File is not in the trash").with_path(path);
+    let (loc, top) = split(path).ok_or_else(not_trashed)?;
+    let origin = origin(&loc, &top).ok_or_else(|| {
+        AppError::new(
+            ErrorKind::InvalidInput,
+            "The original location is unknown; restore it from the system trash",
+        )
+        .with_path(path)
+    })?;
+    let rest = path.strip_prefix(&top).map_err(|_| not_trashed())?;
+    let dest = if rest.as_os_str().is_empty() {
+        origin.original.clone()
+    } else {
+        origin.original.join(rest)
+    };
+
+    if dest.exists() {
+        return Err(AppError::new(
+            ErrorKind::InvalidInput,
+            "A file already exists at the original location",
+        )
+        .with_path(&dest));
+    }
+    if let Some(parent) = dest.parent() {
+        fs::create_dir_all(parent).map_err(|e| AppError::io(&e, parent))?;
+    }
+    fs::rename(path, &dest).map_err(|e| AppError::io(&e, path))?;
+    // Only a fully restored item loses its trash record
+    if path == top {
+        if let Some(record) = origin.record {
+            let _ = fs::remove_file(record);
+        }
+    }
+    Ok(dest)
+}
+
+// `<trash>/info/<name>.trashinfo`:
+//   [Trash Info]
+//   Path=/home/me/scenes/shot%2001.blend
+//   DeletionDate=2024-03-01T10:22:15
+#[cfg(target_os = "linux")]
+fn origin(loc: &Path, top: &Path) -> Option<Origin> {
+    use std::os::unix::ffi::OsStringExt;
+
+    let mut record_name = top.file_name()?.to_owned();
+    record_name.push(".trashinfo");
+    let record = loc.parent()?.join("info").join(record_name);
+    let text = fs::read_to_string(&record).ok()?;
+
+    let field = |key: &str| {
+        text.lines()
+            .find_map(|l| l.strip_prefix(key)?.strip_prefix('='))
+            .map(str::trim)
+    };
+    let raw = PathBuf::from(std::ffi::OsString::from_vec(percent_decode(field("Path")?)));
+    // Per-volume trashes store paths relative to the volume's top directory
+    let original = if raw.is_absolute() {
+        raw
+    } else {
+        volume::volume_of(loc).join(raw)
+    };
+    let deleted_at = field("DeletionDate").map(|d| {
+        chrono::NaiveDateTime::parse_from_str(d, "%Y-%m-%dT%H:%M:%S")
+            .ok()
+            .and_then(|t| t.and_local_timezone(chrono::Local).single())
+            .map(|t| t.to_rfc3339())
+            .unwrap_or_else(|| d.to_string())
+    });
+    Some(Origin {
+        original,
+        deleted_at,
+        record: Some(record),
+    })
+}
+
+#[cfg(target_os = "linux")]
+fn percent_decode(s: &str) -> Vec<u8> {
+    let bytes = s.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(b)) => {
+                out.push(b);
+                i += 3;
+            }
+            (c, _) => {
+                out.push(c);
+                i += 1;
+            }
+        }
+    }
+    out
+}
+
+// `$R123ABC.blend` is described by `$I123ABC.blend`:
+//   i64 version (1 or 2), i64 size, i64 FILETIME of deletion, then the
+//   original path as UTF-16 (v1: fixed 260 chars; v2: u32 length + chars)
+#[cfg(windows)]
+fn origin(_loc: &Path, top: &Path) -> Option<Origin> {
+    use std::os::windows::ffi::OsStringExt;
+
+    let name = top.file_name()?.to_str()?;
+    let record = top.with_file_name(format!("$I{}", name.strip_prefix("$R")?));
+    let data = fs::read(&record).ok()?;
+    let i64_at = |at: usize| Some(i64::from_le_bytes(data.get(at..at + 8)?.try_into().ok()?));
+
+    let version = i64_at(0)?;
+    let filetime = i64_at(16)?;
+    let (start, chars) = match version {
+        1 => (24, 260),
+        _ => {
+            let len = u32::from_le_bytes(data.get(24..28)?.try_into().ok()?) as usize;
+            (28, len)
+        }
+    };
+    let wide: Vec<u16> = data
+        .get(start..start + chars * 2)?
+        .chunks_exact(2)
+        .map(|c| u16::from_le_bytes([c[0], c[1]]))
+        .take_while(|&c| c != 0)
+        .collect();
+
+    // FILETIME counts 100 ns ticks since 1601-01-01
+    let unix_secs = filetime / 10_000_000 - 11_644_473_600;
+    let deleted_at = chrono::DateTime::from_timestamp(unix_secs, 0)
+        .map(|t| t.with_timezone(&chrono::Local).to_rfc3339());
+    Some(Origin {
+        original: PathBuf::from(std::ffi::OsString::from_wide(&wide)),
+        deleted_at,
+        record: Some(record),
+    })
+}
+
+// Finder keeps "Put Back" locations in a private .DS_Store record
+#[cfg(not(any(target_os = "linux", windows)))]
+fn origin(_loc: &Path, _top: &Path) -> Option<Origin> {
+    None
+}
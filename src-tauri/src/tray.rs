@@ -0,0 +1,166 @@
+use crate::deeplink::{self, AppRequest};
+use crate::error::AppError;
+use crate::{paths, settings};
+use crate::{scans_map, MAIN_WINDOW};
+use std::collections::HashMap;
+use std::sync::{atomic::Ordering, Mutex, OnceLock};
+use tauri::menu::{Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::TrayIconBuilder;
+use tauri::Manager;
+
+// -----------------------------
+// System tray
+// -----------------------------
+const TRAY_ID: &str = "main";
+const MAX_RECENT_FILES: usize = 10;
+
+static RECENT_FILES: OnceLock<Mutex<Vec<String>>> = OnceLock::new();
+
+fn recent_files() -> &'static Mutex<Vec<String>> {
+    RECENT_FILES.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+// Closing the main window hides it to the tray instead of exiting; off
+// unless turned on in settings
+pub fn close_to_tray() -> bool {
+    settings::get().close_to_tray
+}
+
+pub fn set_close_to_tray(enabled: bool) -> Result<(), AppError> {
+    settings::update(|s| s.close_to_tray = enabled)?;
+    Ok(())
+}
+
+// Records a file opened through the app and refreshes the tray's recent list
+pub fn note_recent_file(app: &tauri::AppHandle, path: &str) {
+    {
+        let mut recent = recent_files().lock().unwrap();
+        recent.retain(|p| p != path);
+        recent.insert(0, path.to_string());
+        recent.truncate(MAX_RECENT_FILES);
+    }
+    if let (Some(tray), Ok(menu)) = (app.tray_by_id(TRAY_ID), build_menu(app)) {
+        let _ = tray.set_menu(Some(menu));
+    }
+}
+
+fn build_menu<M: Manager<tauri::Wry>>(manager: &M) -> tauri::Result<Menu<tauri::Wry>> {
+    let recent = recent_files().lock().unwrap().clone();
+    let recent_items = recent
+        .iter()
+        .enumerate()
+        .map(|(i, path)| {
+            let label = paths::from_id(path).to_string_lossy().to_string();
+            MenuItem::with_id(manager, format!("recent:{}", i), label, true, None::<&str>)
+        })
+        .collect::<tauri::Result<Vec<_>>>()?;
+    let recent_refs: Vec<&dyn tauri::menu::IsMenuItem<tauri::Wry>> = recent_items
+        .iter()
+        .map(|i| i as &dyn tauri::menu::IsMenuItem<tauri::Wry>)
+        .collect();
+
+    let show = MenuItem::with_id(manager, "show", "Show Window", true, None::<&str>)?;
+    let rescan = MenuItem::with_id(manager, "rescan", "Rescan Folders", true, None::<&str>)?;
+    let last_scan = MenuItem::with_id(manager, "last_scan", "Open Last Scan", true, None::<&str>)?;
+    let recent_menu = Submenu::with_id_and_items(
+        manager,
+        "recent",
+        "Recent Files",
+        !recent_refs.is_empty(),
+        &recent_refs,
+    )?;
+    let quit = MenuItem::with_id(manager, "quit", "Quit", true, None::<&str>)?;
+
+    Menu::with_items(
+        manager,
+        &[
+            &show,
+            &PredefinedMenuItem::separator(manager)?,
+            &rescan,
+            &last_scan,
+            &recent_menu,
+            &PredefinedMenuItem::separator(manager)?,
+            &quit,
+        ],
+    )
+}
+
+pub fn init(app: &tauri::App) -> tauri::Result<()> {
+    let menu = build_menu(app)?;
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .tooltip("Blender File Finder")
+        .menu(&menu)
+        .on_menu_event(on_menu_event);
+    if let Some(icon) = app.default_window_icon() {
+        builder = builder.icon(icon.clone());
+    }
+    builder.build(app)?;
+    Ok(())
+}
+
+fn show_main_window(app: &tauri::AppHandle) {
+    if let Some(window) = app.get_webview_window(MAIN_WINDOW) {
+        let _ = window.unminimize();
+        let _ = window.show();
+        let _ = window.set_focus();
+    }
+}
+
+fn on_menu_event(app: &tauri::AppHandle, event: MenuEvent) {
+    match event.id.as_ref() {
+        "show" => show_main_window(app),
+        "rescan" => rescan_known_roots(app),
+        "last_scan" => {
+            show_main_window(app);
+            if let Some(scan_id) = latest_finished_scan() {
+                deeplink::dispatch(app, AppRequest::ShowScan { scan_id });
+            }
+        }
+        "quit" => app.exit(0),
+        id => {
+            if let Some(idx) = id
+                .strip_prefix("recent:")
+                .and_then(|i| i.parse::<usize>().ok())
+            {
+                let path = recent_files().lock().unwrap().get(idx).cloned();
+                if let Some(path) = path {
+                    let _ = tauri_plugin_opener::open_path(paths::from_id(&path), None::<&str>);
+                }
+            }
+        }
+    }
+}
+
+fn latest_finished_scan() -> Option<u64> {
+    let map = scans_map().lock().unwrap();
+    map.iter()
+        .filter(|(_, st)| st.done.load(Ordering::Relaxed))
+        .map(|(id, _)| *id)
+        .max()
+}
+
+// Asks each window to rescan the folder it last scanned, without raising it,
+// so scans keep running while the app sits in the tray
+fn rescan_known_roots(app: &tauri::AppHandle) {
+    let mut latest: HashMap<String, (u64, Vec<String>)> = HashMap::new();
+    {
+        let map = scans_map().lock().unwrap();
+        for (id, st) in map.iter() {
+            let owner = st.owner.clone().unwrap_or_else(|| MAIN_WINDOW.to_string());
+            let entry = latest.entry(owner).or_insert((*id, st.roots.clone()));
+            if *id > entry.0 {
+                *entry = (*id, st.roots.clone());
+            }
+        }
+    }
+    for (label, (_, mut roots)) in latest {
+        let request = if roots.len() == 1 {
+            AppRequest::Scan {
+                root: roots.remove(0),
+            }
+        } else {
+            AppRequest::ScanAll { roots }
+        };
+        deeplink::send_to(app, &label, request);
+    }
+}
@@ -0,0 +1,326 @@
+use crate::error::AppError;
+use crate::jobs::JobCtx;
+use crate::{formats, handles, paths, FlatFile};
+use flate2::read::GzDecoder;
+use serde::Serialize;
+use std::io::{self, BufReader, Read, Seek, SeekFrom};
+use std::path::Path;
+
+// -----------------------------
+// Damaged file triage
+// -----------------------------
+// Walks the block headers of every blend in a scan and reports the ones a
+// crash, full disk or interrupted copy left broken. A blend is written
+// front to back and ends with its struct layouts (DNA1) and an ENDB
+// marker; a file cut off after DNA1 usually still opens in Blender.
+
+const BLEND_MAGIC: &[u8] = b"BLENDER";
+const GZIP_MAGIC: &[u8] = &[0x1f, 0x8b];
+const ZSTD_MAGIC: &[u8] = &[0x28, 0xb5, 0x2f, 0xfd];
+
+#[derive(Serialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Damage {
+    Empty,
+    BadMagic,      // no BLENDER header; overwritten or not a blend
+    Decompression, // the gzip/zstd stream is broken
+    Truncated,     // ends inside a block
+    MissingEndb,   // ends between blocks without the ENDB marker
+    BadBlock,      // a block header is garbage
+}
+
+#[derive(Serialize, Clone)]
+pub struct TriageEntry {
+    pub path: String,
+    pub path_id: String,
+    pub size_bytes: u64,
+    pub modified: Option<String>,
+    pub damage: Damage,
+    pub detail: String,
+    pub recoverable: bool, // the struct layouts were read; Blender may open it
+    pub backup: Option<String>, // newest .blendN next to it, as a path id
+}
+
+#[derive(Serialize, Clone)]
+pub struct TriageReport {
+    pub scan_id: u64,
+    pub checked: usize,
+    pub entries: Vec<TriageEntry>,
+    pub errors: Vec<AppError>, // files that could not be opened
+}
+
+pub(crate) struct Finding {
+    pub damage: Damage,
+    pub detail: String,
+    pub recoverable: bool,
+}
+
+impl Finding {
+    fn new(damage: Damage, detail: impl Into<String>) -> Self {
+        Self {
+            damage,
+            detail: detail.into(),
+            recoverable: false,
+        }
+    }
+
+    fn after_dna(mut self, saw_dna: bool) -> Self {
+        self.recoverable = saw_dna;
+        self
+    }
+}
+
+// Plain files are seeked through with their length known; compressed ones
+// are read through
+enum Source<'a> {
+    File(BufReader<&'a std::fs::File>, u64),
+    Stream(Box<dyn Read + 'a>),
+}
+
+// How a read or skip ended
+enum Step {
+    Done,
+    Short(usize), // end of data after this many bytes
+    Broken(io::Error),
+}
+
+impl Source<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> Step {
+        let r: &mut dyn Read = match self {
+            Source::File(f, _) => f,
+            Source::Stream(s) => s,
+        };
+        let mut got = 0;
+        while got < buf.len() {
+            match r.read(&mut buf[got..]) {
+                Ok(0) => return Step::Short(got),
+                Ok(n) => got += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Step::Broken(e),
+            }
+        }
+        Step::Done
+    }
+
+    fn skip(&mut self, pos: u64, n: u64) -> Step {
+        match self {
+            Source::File(_, len) if pos.saturating_add(n) > *len => Step::Short(0),
+            Source::File(f, _) => match f.seek(SeekFrom::Start(pos + n)) {
+                Ok(_) => Step::Done,
+                Err(e) => Step::Broken(e),
+            },
+            Source::Stream(s) => match io::copy(&mut s.take(n), &mut io::sink()) {
+                Ok(copied) if copied == n => Step::Done,
+                Ok(_) => Step::Short(0),
+                Err(e) => Step::Broken(e),
+            },
+        }
+    }
+}
+
+// Block codes are short uppercase tags padded with zeros (`OB\0\0`, `DATA`)
+fn plausible_code(code: &[u8]) -> bool {
+    code[0].is_ascii_alphanumeric() && code.iter().all(|&b| b == 0 || b.is_ascii_alphanumeric())
+}
+
+// The damage found in `path`, or None for a sound file
+pub(crate) fn classify(path: &Path) -> Result<Option<Finding>, AppError> {
+    let file = handles::open(path)?;
+    let len = file.metadata().map_err(|e| AppError::io(&e, path))?.len();
+    if len == 0 {
+        return Ok(Some(Finding::new(Damage::Empty, "File is empty")));
+    }
+
+    let mut magic = [0u8; 4];
+    let got = (&*file)
+        .read(&mut magic)
+        .map_err(|e| AppError::io(&e, path))?;
+    (&*file).rewind().map_err(|e| AppError::io(&e, path))?;
+    let (mut src, compressed) = if magic[..got].starts_with(GZIP_MAGIC) {
+        let s = Source::Stream(Box::new(GzDecoder::new(BufReader::new(&*file))));
+        (s, true)
+    } else if magic[..got].starts_with(ZSTD_MAGIC) {
+        let d = zstd::stream::read::Decoder::new(&*file).map_err(|e| AppError::io(&e, path))?;
+        (Source::Stream(Box::new(d)), true)
+    } else {
+        (Source::File(BufReader::new(&*file), len), false)
+    };
+    Ok(walk(&mut src, compressed))
+}
+
+fn walk(src: &mut Source, compressed: bool) -> Option<Finding> {
+    let broken = |e: io::Error| {
+        let damage = if compressed {
+            Damage::Decompression
+        } else {
+            Damage::Truncated
+        };
+        Some(Finding::new(damage, format!("Read failed: {}", e)))
+    };
+
+    // `BLENDER-v402` (pointer size, endianness, version), or since 5.0
+    // `BLENDER17-01v0500` with wider block headers
+    let mut head = [0u8; 12];
+    match src.read(&mut head) {
+        Step::Done => {}
+        Step::Short(_) if compressed => {
+            return Some(Finding::new(
+                Damage::Decompression,
+                "Compressed data ends early",
+            ))
+        }
+        Step::Short(_) => return Some(Finding::new(Damage::BadMagic, "Too short for a header")),
+        Step::Broken(e) => return broken(e),
+    }
+    if !head.starts_with(BLEND_MAGIC) {
+        let detail = if head.iter().all(|&b| b == 0) {
+            "Starts with zeros; the save never finished"
+        } else {
+            "No BLENDER header"
+        };
+        return Some(Finding::new(Damage::BadMagic, detail));
+    }
+    let header_len = crate::header_len(&head);
+    let large = header_len.is_some();
+    let mut little = head[8] == b'v';
+    let mut pos = 12u64;
+    if let Some(len) = header_len {
+        let mut rest = vec![0u8; len - 12];
+        if !matches!(src.read(&mut rest), Step::Done) {
+            return Some(Finding::new(
+                Damage::Truncated,
+                "Ends inside the file header",
+            ));
+        }
+        // Block headers of later file formats are not known yet
+        if &head[10..12] != b"01" {
+            return None;
+        }
+        little = rest[0] == b'v';
+        pos = len as u64;
+    }
+    let ptr_size = if head[7] == b'_' { 4 } else { 8 };
+    // code, len, old, sdna, nr before 5.0; code, sdna, old, len, nr after
+    let (head_len, len_at, len_size) = if large {
+        (32, 16, 8)
+    } else {
+        (16 + ptr_size, 4, 4)
+    };
+
+    let mut buf = vec![0u8; head_len];
+    let mut saw_dna = false;
+    loop {
+        let at = pos;
+        match src.read(&mut buf) {
+            Step::Done => {}
+            Step::Short(0) => {
+                return Some(
+                    Finding::new(
+                        Damage::MissingEndb,
+                        format!("Ends at byte {} without ENDB", at),
+                    )
+                    .after_dna(saw_dna),
+                );
+            }
+            Step::Short(_) => {
+                return Some(
+                    Finding::new(
+                        Damage::Truncated,
+                        format!("Ends inside a block header at byte {}", at),
+                    )
+                    .after_dna(saw_dna),
+                );
+            }
+            Step::Broken(e) => return broken(e),
+        }
+        pos += head_len as u64;
+        let code = &buf[..4];
+        if code == b"ENDB" {
+            return None;
+        }
+        if code.iter().all(|&b| b == 0) {
+            return Some(
+                Finding::new(Damage::Truncated, format!("Zero-filled from byte {}", at))
+                    .after_dna(saw_dna),
+            );
+        }
+        if !plausible_code(code) {
+            return Some(
+                Finding::new(
+                    Damage::BadBlock,
+                    format!("Unreadable block header at byte {}", at),
+                )
+                .after_dna(saw_dna),
+            );
+        }
+        if code == b"DNA1" {
+            saw_dna = true;
+        }
+        let v = crate::sdna::View { data: &buf, little };
+        let size = v.uint_at(len_at, len_size).unwrap_or(0);
+        match src.skip(pos, size) {
+            Step::Done => pos += size,
+            Step::Short(_) => {
+                let code = String::from_utf8_lossy(code)
+                    .trim_end_matches('\0')
+                    .to_string();
+                return Some(
+                    Finding::new(
+                        Damage::Truncated,
+                        format!("{} block at byte {} runs past the end", code, at),
+                    )
+                    .after_dna(saw_dna),
+                );
+            }
+            Step::Broken(e) => return broken(e),
+        }
+    }
+}
+
+fn entry(f: &FlatFile, finding: Finding) -> TriageEntry {
+    let path = paths::from_id(&f.path_id);
+    TriageEntry {
+        path: f.path.clone(),
+        path_id: f.path_id.clone(),
+        size_bytes: f.size_bytes,
+        modified: f.modified.clone(),
+        damage: finding.damage,
+        detail: finding.detail,
+        recoverable: finding.recoverable,
+        backup: formats::backups_of(&path).first().map(|p| paths::to_id(p)),
+    }
+}
+
+// Blends of the scan that are on disk; archive entries and online-only
+// placeholders are left out
+pub fn triage_files(
+    ctx: &JobCtx,
+    scan_id: u64,
+    files: &[FlatFile],
+) -> Result<TriageReport, AppError> {
+    let files: Vec<&FlatFile> = files
+        .iter()
+        .filter(|f| f.format == formats::BLEND && f.archive.is_none() && !f.cloud_only)
+        .collect();
+    ctx.set_totals(files.len() as u64, files.iter().map(|f| f.size_bytes).sum());
+    let mut report = TriageReport {
+        scan_id,
+        checked: 0,
+        entries: Vec::new(),
+        errors: Vec::new(),
+    };
+    for f in files {
+        ctx.check_cancelled()?;
+        let path = paths::from_id(&f.path_id);
+        ctx.set_current(&path);
+        match classify(&path) {
+            Ok(Some(finding)) => report.entries.push(entry(f, finding)),
+            Ok(None) => {}
+            Err(e) => report.errors.push(e),
+        }
+        report.checked += 1;
+        ctx.add_bytes(f.size_bytes);
+        ctx.item_done();
+    }
+    Ok(report)
+}
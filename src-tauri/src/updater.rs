@@ -0,0 +1,100 @@
+use crate::error::{AppError, ErrorKind};
+use crate::jobs::JobCtx;
+use crate::settings;
+use serde::Serialize;
+use std::thread;
+use tauri::{Emitter, Manager};
+use tauri_plugin_updater::{Update, UpdaterExt};
+
+// -----------------------------
+// Update checks
+// -----------------------------
+// Releases are checked at startup (unless turned off in settings) and on
+// request; a newer one is announced to every window. Installing downloads
+// the signed bundle and restarts the app. Until `plugins.updater.pubkey`
+// in tauri.conf.json holds the release signing key (see README.md), no
+// download could pass verification, so updates are off.
+
+pub const EVENT_UPDATE_AVAILABLE: &str = "update://available";
+
+#[derive(Serialize, Clone)]
+pub struct UpdateInfo {
+    pub version: String,
+    pub current_version: String,
+    pub notes: Option<String>,
+    pub date: Option<String>,
+}
+
+fn update_error(e: impl std::fmt::Display) -> AppError {
+    AppError::new(ErrorKind::Update, e.to_string())
+}
+
+fn configured(app: &tauri::AppHandle) -> bool {
+    app.config()
+        .plugins
+        .0
+        .get("updater")
+        .and_then(|u| u.get("pubkey"))
+        .and_then(|k| k.as_str())
+        .is_some_and(|k| !k.trim().is_empty())
+}
+
+// The updater is async; callers are already on a background thread
+fn find(app: &tauri::AppHandle) -> Result<Option<Update>, AppError> {
+    if !configured(app) {
+        return Err(
+            AppError::new(ErrorKind::Update, "Updates are not set up in this build")
+                .with_code("updates_unavailable"),
+        );
+    }
+    let updater = app.updater().map_err(update_error)?;
+    tauri::async_runtime::block_on(updater.check()).map_err(update_error)
+}
+
+// Looks for a newer release and announces it
+pub fn check(app: &tauri::AppHandle) -> Result<Option<UpdateInfo>, AppError> {
+    let info = find(app)?.map(|u| UpdateInfo {
+        version: u.version.clone(),
+        current_version: u.current_version.clone(),
+        notes: u.body.clone(),
+        date: u.date.map(|d| d.to_string()),
+    });
+    if let Some(info) = &info {
+        tracing::info!(version = %info.version, "update available");
+        let _ = app.emit(EVENT_UPDATE_AVAILABLE, info.clone());
+    }
+    Ok(info)
+}
+
+// Startup check; failures (offline, no release feed) are only logged
+pub fn check_in_background(app: &tauri::AppHandle) {
+    if !settings::get().check_updates || !configured(app) {
+        return;
+    }
+    let app = app.clone();
+    thread::spawn(move || {
+        if let Err(e) = check(&app) {
+            tracing::warn!("update check failed: {}", e);
+        }
+    });
+}
+
+// Downloads and installs the newest release, then restarts into it
+pub fn install(app: &tauri::AppHandle, ctx: &JobCtx) -> Result<(), AppError> {
+    let update = find(app)?
+        .ok_or_else(|| AppError::new(ErrorKind::Update, "Already on the latest version"))?;
+    let mut sized = false;
+    tauri::async_runtime::block_on(update.download_and_install(
+        |chunk, total| {
+            if !sized {
+                sized = true;
+                ctx.set_totals(1, total.unwrap_or(0));
+            }
+            ctx.add_bytes(chunk as u64);
+        },
+        || ctx.item_done(),
+    ))
+    .map_err(update_error)?;
+    tracing::info!(version = %update.version, "update installed, restarting");
+    app.restart()
+}
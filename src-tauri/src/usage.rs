@@ -0,0 +1,173 @@
+use crate::db;
+use crate::error::AppError;
+use crate::settings;
+use crate::{paths, FlatFile};
+use chrono::{DateTime, Local};
+use rusqlite::params;
+use serde::Serialize;
+use std::collections::HashSet;
+
+// -----------------------------
+// Usage statistics (local only)
+// -----------------------------
+// Counts, per path id, how often a file was opened, picked from a search
+// and tagged through the app. Nothing leaves index.db; the `usage_stats`
+// setting turns recording off and `clear` forgets everything recorded.
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Event {
+    Open,
+    Search, // picked from search results or quick find
+    Tag,
+}
+
+impl Event {
+    fn as_str(self) -> &'static str {
+        match self {
+            Event::Open => "open",
+            Event::Search => "search",
+            Event::Tag => "tag",
+        }
+    }
+}
+
+#[derive(Serialize, Clone)]
+pub struct UsageEntry {
+    pub path: String,
+    pub path_id: String,
+    pub name: String,
+    pub count: u64,
+    pub last_at: String,
+}
+
+#[derive(Serialize, Clone, Default)]
+pub struct UsageStats {
+    pub since: Option<String>, // the oldest recorded event
+    pub opens: u64,
+    pub searches: u64,
+    pub tags: u64,
+    pub most_opened: Vec<UsageEntry>,
+    pub most_searched: Vec<UsageEntry>,
+    pub most_tagged: Vec<UsageEntry>,
+    // Files of the scan with no event at all, oldest first (empty without a
+    // scan)
+    pub never_touched: Vec<UsageEntry>,
+    pub never_touched_count: usize,
+}
+
+// Failures are logged, never surfaced: stats must not break opening a file
+pub fn record(path_ids: &[String], event: Event) {
+    if !settings::get().usage_stats || path_ids.is_empty() {
+        return;
+    }
+    let now = Local::now().to_rfc3339();
+    let res = db::with(|conn| {
+        let tx = conn.transaction()?;
+        {
+            let mut stmt = tx.prepare(
+                "INSERT INTO usage (path_id, event, count, first_at, last_at)
+                 VALUES (?1, ?2, 1, ?3, ?3)
+                 ON CONFLICT(path_id, event)
+                 DO UPDATE SET count = count + 1, last_at = excluded.last_at",
+            )?;
+            for id in path_ids {
+                stmt.execute(params![id, event.as_str(), now])?;
+            }
+        }
+        tx.commit()
+    });
+    if let Err(e) = res {
+        tracing::warn!("recording usage failed: {}", e);
+    }
+}
+
+pub fn clear() -> Result<(), AppError> {
+    db::with(|conn| conn.execute("DELETE FROM usage", []).map(|_| ()))
+}
+
+fn entry(path_id: String, count: u64, last_at: String) -> UsageEntry {
+    let path = paths::from_id(&path_id);
+    UsageEntry {
+        name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        path: path.to_string_lossy().to_string(),
+        path_id,
+        count,
+        last_at,
+    }
+}
+
+// Compared as instants; the offset in the text follows DST
+fn earlier(a: &str, b: &str) -> bool {
+    match (
+        DateTime::parse_from_rfc3339(a),
+        DateTime::parse_from_rfc3339(b),
+    ) {
+        (Ok(a), Ok(b)) => a < b,
+        _ => a < b,
+    }
+}
+
+// `files` narrows the lists to one scan's files; `limit` caps each list
+pub fn stats(files: Option<&[FlatFile]>, limit: usize) -> Result<UsageStats, AppError> {
+    let rows: Vec<(String, String, u64, String, String)> = db::with(|conn| {
+        let mut stmt = conn.prepare(
+            "SELECT path_id, event, count, first_at, last_at FROM usage
+             ORDER BY count DESC, last_at DESC",
+        )?;
+        let rows = stmt.query_map([], |r| {
+            Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?))
+        })?;
+        rows.collect()
+    })?;
+    let in_scan: Option<HashSet<&str>> =
+        files.map(|files| files.iter().map(|f| f.path_id.as_str()).collect());
+
+    let mut stats = UsageStats::default();
+    let mut touched = HashSet::new();
+    for (path_id, event, count, first_at, last_at) in rows {
+        if stats.since.as_deref().is_none_or(|s| earlier(&first_at, s)) {
+            stats.since = Some(first_at);
+        }
+        let (total, list) = match event.as_str() {
+            "open" => (&mut stats.opens, &mut stats.most_opened),
+            "search" => (&mut stats.searches, &mut stats.most_searched),
+            "tag" => (&mut stats.tags, &mut stats.most_tagged),
+            _ => continue,
+        };
+        *total += count;
+        if in_scan
+            .as_ref()
+            .is_some_and(|s| !s.contains(path_id.as_str()))
+        {
+            continue;
+        }
+        touched.insert(path_id.clone());
+        if list.len() < limit {
+            list.push(entry(path_id, count, last_at));
+        }
+    }
+
+    if let Some(files) = files {
+        let mut never: Vec<&FlatFile> = files
+            .iter()
+            .filter(|f| f.trash.is_none() && !touched.contains(&f.path_id))
+            .collect();
+        never.sort_by_key(|f| f.modified_ms.unwrap_or(i64::MAX));
+        stats.never_touched_count = never.len();
+        stats.never_touched = never
+            .into_iter()
+            .take(limit)
+            .map(|f| UsageEntry {
+                path: f.path.clone(),
+                path_id: f.path_id.clone(),
+                name: f.name.clone(),
+                count: 0,
+                last_at: f.modified.clone().unwrap_or_default(),
+            })
+            .collect();
+    }
+    Ok(stats)
+}
@@ -0,0 +1,138 @@
+use crate::error::{AppError, ErrorKind};
+use std::path::{Component, Path, PathBuf};
+
+// -----------------------------
+// Volumes (drive letters, mount points)
+// -----------------------------
+// Used to tell "this file is gone" apart from "the whole drive is gone"
+// when a removable disk or share disconnects mid-scan or mid-copy.
+
+// (mount point, filesystem type)
+#[cfg(target_os = "linux")]
+fn mounts() -> Vec<(PathBuf, String)> {
+    let Ok(text) = std::fs::read_to_string("/proc/mounts") else {
+        return Vec::new();
+    };
+    text.lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let mount = fields.nth(1)?.replace("\\040", " ");
+            let fs = fields.next()?.to_string();
+            Some((PathBuf::from(mount), fs))
+        })
+        .collect()
+}
+
+// `//user@nas/share on /Volumes/share (smbfs, nodev, nosuid, mounted by me)`
+#[cfg(target_os = "macos")]
+fn mounts() -> Vec<(PathBuf, String)> {
+    let Ok(out) = std::process::Command::new("/sbin/mount").output() else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&out.stdout)
+        .lines()
+        .filter_map(|line| {
+            let (_, rest) = line.split_once(" on ")?;
+            let (mount, opts) = rest.rsplit_once(" (")?;
+            let fs = opts.split(',').next()?.trim().to_string();
+            Some((PathBuf::from(mount), fs))
+        })
+        .collect()
+}
+
+// Windows has no mount table; drive letters and UNC shares are the volumes
+#[cfg(not(any(target_os = "linux", target_os = "macos")))]
+fn mounts() -> Vec<(PathBuf, String)> {
+    Vec::new()
+}
+
+// Mount table entry holding `path` (longest mount point wins)
+pub fn mount_entry(path: &Path) -> Option<(PathBuf, String)> {
+    mounts()
+        .into_iter()
+        .filter(|(mount, _)| path.starts_with(mount))
+        .max_by_key(|(mount, _)| mount.as_os_str().len())
+}
+
+// `E:\`, `\\server\share\`, or the mount point on Unix
+pub fn volume_of(path: &Path) -> PathBuf {
+    if let Some((mount, _)) = mount_entry(path) {
+        return mount;
+    }
+    path.components()
+        .take_while(|c| matches!(c, Component::Prefix(_) | Component::RootDir))
+        .collect()
+}
+
+pub fn is_present(volume: &Path) -> bool {
+    if !volume.exists() {
+        return false;
+    }
+    // An unmounted mount point usually leaves its empty directory behind
+    let mounted = mounts();
+    mounted.is_empty()
+        || volume.parent().is_none()
+        || mounted.iter().any(|(mount, _)| mount == volume)
+}
+
+// Call after an I/O error: a clearer error if the volume itself went away
+pub fn check(volume: &Path) -> Result<(), AppError> {
+    if is_present(volume) {
+        Ok(())
+    } else {
+        Err(offline_error(volume))
+    }
+}
+
+// Bytes available to this user on the volume holding `path`; a destination
+// that doesn't exist yet is measured at its nearest existing ancestor
+pub fn free_space(path: &Path) -> Option<u64> {
+    let existing = path.ancestors().find(|p| p.exists())?;
+    available_bytes(existing)
+}
+
+#[cfg(unix)]
+fn available_bytes(path: &Path) -> Option<u64> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = std::ffi::CString::new(path.as_os_str().as_bytes()).ok()?;
+    // SAFETY: c_path is NUL-terminated and `stat` is a valid out pointer
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    if unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) } != 0 {
+        return None;
+    }
+    #[allow(clippy::unnecessary_cast)] // field widths differ per platform
+    Some(stat.f_bavail as u64 * stat.f_frsize as u64)
+}
+
+#[cfg(windows)]
+fn available_bytes(path: &Path) -> Option<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+    let mut available = 0u64;
+    // SAFETY: `wide` is NUL-terminated; unused outputs may be null
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(
+            wide.as_ptr(),
+            &mut available,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    (ok != 0).then_some(available)
+}
+
+#[cfg(not(any(unix, windows)))]
+fn available_bytes(_path: &Path) -> Option<u64> {
+    None
+}
+
+pub fn offline_error(volume: &Path) -> AppError {
+    AppError::new(
+        ErrorKind::VolumeOffline,
+        "The drive or share was disconnected",
+    )
+    .with_path(volume)
+}
@@ -0,0 +1,112 @@
+use crate::error::{AppError, ErrorKind};
+use crate::{exclude, formats, paths, settings};
+use notify::{RecommendedWatcher, RecursiveMode};
+use notify_debouncer_mini::{new_debouncer, DebounceEventResult, Debouncer};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+use walkdir::WalkDir;
+
+// -----------------------------
+// Watching scanned roots
+// -----------------------------
+// Keeps the results of a root current while files under it are saved,
+// added or deleted, without rescanning. Events are debounced, since Blender
+// saves through a temporary `name.blend@` renamed over the file, and then
+// handed to `crate::apply_watched` as settled paths: re-read when they
+// exist, dropped from the results (with everything below) when not.
+// Folders excluded from scans are ignored here too.
+
+const DEBOUNCE: Duration = Duration::from_secs(2);
+
+// Root path id -> its watcher; dropping one stops it
+static WATCHERS: OnceLock<Mutex<HashMap<String, Debouncer<RecommendedWatcher>>>> = OnceLock::new();
+
+fn watchers() -> &'static Mutex<HashMap<String, Debouncer<RecommendedWatcher>>> {
+    WATCHERS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+fn watch_error(root: &Path, e: notify::Error) -> AppError {
+    AppError::new(ErrorKind::Io, format!("Can't watch the folder: {}", e))
+        .with_code("watch_failed")
+        .with_path(root)
+}
+
+fn excluded(root: &Path, exclusions: &exclude::Exclusions, path: &Path) -> bool {
+    path.ancestors()
+        .take_while(|dir| dir.starts_with(root) && *dir != root)
+        .any(|dir| exclusions.matches(root, dir))
+}
+
+fn indexed(path: &Path) -> bool {
+    formats::of_path(path, true).is_some() || formats::backup_number(path).is_some()
+}
+
+// Indexed files among the event paths. A folder moved in brings its files
+// without events of their own, so it is walked; gone paths are kept as
+// they are, being files or whole folders.
+fn settle(root: &Path, paths: impl Iterator<Item = PathBuf>) -> Vec<PathBuf> {
+    let exclusions = exclude::Exclusions::new(&settings::get().exclusions);
+    let mut settled = BTreeSet::new();
+    for path in paths {
+        // Its own attribute changes, not a folder moved in
+        if path == root {
+            continue;
+        }
+        if path.is_dir() {
+            settled.extend(
+                WalkDir::new(&path)
+                    .into_iter()
+                    .filter_entry(|e| {
+                        !e.file_type().is_dir() || !excluded(root, &exclusions, e.path())
+                    })
+                    .flatten()
+                    .filter(|e| e.file_type().is_file() && indexed(e.path()))
+                    .map(|e| e.into_path()),
+            );
+        } else if (!path.exists() || indexed(&path))
+            && !excluded(root, &exclusions, path.parent().unwrap_or(root))
+        {
+            settled.insert(path);
+        }
+    }
+    settled.into_iter().collect()
+}
+
+pub fn is_watching(root_id: &str) -> bool {
+    watchers().lock().unwrap().contains_key(root_id)
+}
+
+// Watching an already watched root is a no-op
+pub fn start(app: &tauri::AppHandle, root: &Path) -> Result<(), AppError> {
+    let root_id = paths::to_id(root);
+    let mut map = watchers().lock().unwrap();
+    if map.contains_key(&root_id) {
+        return Ok(());
+    }
+    let (app, dir, id) = (app.clone(), root.to_path_buf(), root_id.clone());
+    let mut debouncer = new_debouncer(DEBOUNCE, move |res: DebounceEventResult| match res {
+        Ok(events) => {
+            let changed = settle(&dir, events.into_iter().map(|e| e.path));
+            if !changed.is_empty() {
+                crate::apply_watched(&app, &id, changed);
+            }
+        }
+        Err(e) => tracing::warn!(root = %dir.display(), "watch failed: {}", e),
+    })
+    .map_err(|e| watch_error(root, e))?;
+    debouncer
+        .watcher()
+        .watch(root, RecursiveMode::Recursive)
+        .map_err(|e| watch_error(root, e))?;
+    map.insert(root_id, debouncer);
+    tracing::info!(root = %root.display(), "watching");
+    Ok(())
+}
+
+pub fn stop(root_id: &str) {
+    if watchers().lock().unwrap().remove(root_id).is_some() {
+        tracing::info!(root = %paths::from_id(root_id).display(), "stopped watching");
+    }
+}
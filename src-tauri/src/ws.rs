@@ -0,0 +1,98 @@
+use crate::http_api;
+use serde::Serialize;
+use std::sync::mpsc::{channel, Sender};
+use std::sync::{Mutex, OnceLock};
+use std::thread;
+use tiny_http::{Header, Request, Response, StatusCode};
+use tungstenite::{handshake::derive_accept_key, protocol::Role, Message, WebSocket};
+
+// -----------------------------
+// WebSocket event stream (served by the HTTP API on /api/events)
+// -----------------------------
+// Each message is `{"event": "scan://progress", "payload": {…}}`.
+// Browsers let any page open a socket to localhost, so browser clients
+// (those sending Origin) must be the app or a page served by the API.
+
+#[derive(Serialize)]
+struct WsEvent<'a, T: Serialize> {
+    event: &'a str,
+    payload: &'a T,
+}
+
+static CLIENTS: OnceLock<Mutex<Vec<Sender<String>>>> = OnceLock::new();
+
+fn clients() -> &'static Mutex<Vec<Sender<String>>> {
+    CLIENTS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+// Sends an event to every connected client; cheap when nobody listens
+pub fn broadcast<T: Serialize>(event: &str, payload: &T) {
+    let mut list = clients().lock().unwrap();
+    if list.is_empty() {
+        return;
+    }
+    let Ok(text) = serde_json::to_string(&WsEvent { event, payload }) else {
+        return;
+    };
+    list.retain(|tx| tx.send(text.clone()).is_ok());
+}
+
+pub fn is_upgrade(request: &Request) -> bool {
+    request
+        .headers()
+        .iter()
+        .any(|h| h.field.equiv("Upgrade") && h.value.as_str().eq_ignore_ascii_case("websocket"))
+}
+
+fn origin_allowed(request: &Request, port: u16) -> bool {
+    let Some(origin) = http_api::header(request, "Origin") else {
+        return true; // not a browser
+    };
+    let origin = origin.to_ascii_lowercase();
+    [
+        "tauri://localhost",
+        "http://tauri.localhost",
+        "https://tauri.localhost",
+    ]
+    .contains(&origin.as_str())
+        || origin == format!("http://127.0.0.1:{}", port)
+        || origin == format!("http://localhost:{}", port)
+}
+
+// Completes the handshake and streams broadcasts until the client goes away
+pub fn accept(request: Request, port: u16) {
+    if !http_api::host_allowed(&request, port) || !origin_allowed(&request, port) {
+        tracing::warn!(
+            origin = http_api::header(&request, "Origin").unwrap_or(""),
+            "websocket refused"
+        );
+        let _ = request.respond(Response::empty(403));
+        return;
+    }
+    let key = http_api::header(&request, "Sec-WebSocket-Key").map(str::to_string);
+    let Some(key) = key else {
+        let _ = request.respond(Response::empty(400));
+        return;
+    };
+
+    let response = Response::empty(StatusCode(101))
+        .with_header(Header::from_bytes("Upgrade", "websocket").unwrap())
+        .with_header(
+            Header::from_bytes("Sec-WebSocket-Accept", derive_accept_key(key.as_bytes())).unwrap(),
+        );
+    let stream = request.upgrade("websocket", response);
+
+    let (tx, rx) = channel::<String>();
+    clients().lock().unwrap().push(tx);
+
+    thread::spawn(move || {
+        let mut socket = WebSocket::from_raw_socket(stream, Role::Server, None);
+        // Write-only stream: a failed send means the client disconnected
+        for text in rx {
+            if socket.send(Message::text(text)).is_err() {
+                break;
+            }
+        }
+        let _ = socket.close(None);
+    });
+}
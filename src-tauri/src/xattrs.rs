@@ -0,0 +1,233 @@
+use crate::error::AppError;
+use crate::{db, paths, ratings, settings, tags};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::Path;
+
+// -----------------------------
+// Extended-attribute metadata (xattrs, NTFS alternate data streams)
+// -----------------------------
+// Optional copy of a file's tags and rating kept on the file itself: no
+// sidecar clutter, and it follows the file through moves on the same
+// volume. As with sidecars the index stays authoritative; on scan, the
+// attribute only fills in what it lacks.
+
+const FORMAT_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize, Default)]
+#[serde(default)]
+struct Stored {
+    version: u32,
+    tags: Vec<String>,
+    rating: Option<u8>,
+}
+
+pub fn supported() -> bool {
+    cfg!(any(target_os = "linux", target_os = "macos", windows))
+}
+
+pub fn enabled() -> bool {
+    supported() && settings::get().xattrs
+}
+
+fn write(path_id: &str) -> Result<(), AppError> {
+    let file = paths::from_id(path_id);
+    if !file.is_file() {
+        return Ok(());
+    }
+    let data = Stored {
+        version: FORMAT_VERSION,
+        tags: tags::of(path_id)?,
+        rating: ratings::get(path_id)?,
+    };
+    let res = if data.tags.is_empty() && data.rating.is_none() {
+        remove_attr(&file)
+    } else {
+        write_attr(&file, &serde_json::to_vec(&data).unwrap_or_default())
+    };
+    res.map_err(|e| AppError::io(&e, &file))
+}
+
+// Call after tags or ratings of `path_ids` changed; failures (e.g. a file
+// system without xattrs) are only logged
+pub fn sync(path_ids: &[String]) {
+    if !enabled() {
+        return;
+    }
+    for p in path_ids {
+        if let Err(e) = write(p) {
+            tracing::warn!("writing extended attribute failed: {}", e);
+        }
+    }
+}
+
+// Stores attributes on every file that has tags or a rating (mode switched on)
+pub fn export_all() -> Result<usize, AppError> {
+    let path_ids: Vec<String> = db::with(|conn| {
+        let mut stmt =
+            conn.prepare("SELECT path_id FROM file_tags UNION SELECT path_id FROM ratings")?;
+        let rows = stmt.query_map([], |r| r.get(0))?;
+        rows.collect()
+    })?;
+    sync(&path_ids);
+    Ok(path_ids.len())
+}
+
+// Merges a file's attribute into the index: tags are added, the rating
+// only taken when the index has none
+pub fn import(file: &Path) -> Result<(), AppError> {
+    let Some(bytes) = read_attr(file).map_err(|e| AppError::io(&e, file))? else {
+        return Ok(());
+    };
+    let Ok(data) = serde_json::from_slice::<Stored>(&bytes) else {
+        tracing::warn!(path = %file.display(), "ignoring unreadable metadata attribute");
+        return Ok(());
+    };
+    let path_id = paths::to_id(file);
+    let ids = [path_id.clone()];
+    for name in data.tags.iter().filter(|n| !n.trim().is_empty()) {
+        tags::assign(&ids, name)?;
+    }
+    if data.rating.is_some() && ratings::get(&path_id)?.is_none() {
+        ratings::set(&path_id, data.rating)?;
+    }
+    Ok(())
+}
+
+// Linux only allows unprivileged attributes in the `user.` namespace
+#[cfg(target_os = "linux")]
+const ATTR_NAME: &str = "user.blendfinder.meta";
+#[cfg(target_os = "macos")]
+const ATTR_NAME: &str = "com.blendfinder.meta";
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn c_strings(path: &Path) -> io::Result<(std::ffi::CString, std::ffi::CString)> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let invalid = |_| io::Error::from(io::ErrorKind::InvalidInput);
+    Ok((
+        std::ffi::CString::new(path.as_os_str().as_bytes()).map_err(invalid)?,
+        std::ffi::CString::new(ATTR_NAME).map_err(invalid)?,
+    ))
+}
+
+#[cfg(target_os = "linux")]
+const NO_ATTR: i32 = libc::ENODATA;
+#[cfg(target_os = "macos")]
+const NO_ATTR: i32 = libc::ENOATTR;
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn read_attr(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    let (c_path, c_name) = c_strings(path)?;
+    // SAFETY: both strings are NUL-terminated; a null buffer asks for the size
+    // and `buf` is exactly `len` bytes long
+    let get = |buf: *mut libc::c_void, len: usize| unsafe {
+        #[cfg(target_os = "linux")]
+        {
+            libc::getxattr(c_path.as_ptr(), c_name.as_ptr(), buf, len)
+        }
+        #[cfg(target_os = "macos")]
+        {
+            libc::getxattr(c_path.as_ptr(), c_name.as_ptr(), buf, len, 0, 0)
+        }
+    };
+    let size = get(std::ptr::null_mut(), 0);
+    if size < 0 {
+        let err = io::Error::last_os_error();
+        return match err.raw_os_error() {
+            Some(NO_ATTR) => Ok(None),
+            _ => Err(err),
+        };
+    }
+    let mut buf = vec![0u8; size as usize];
+    let read = get(buf.as_mut_ptr().cast(), buf.len());
+    if read < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    buf.truncate(read as usize);
+    Ok(Some(buf))
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn write_attr(path: &Path, value: &[u8]) -> io::Result<()> {
+    let (c_path, c_name) = c_strings(path)?;
+    let ptr = value.as_ptr().cast();
+    // SAFETY: both strings are NUL-terminated and `value` outlives the call
+    #[cfg(target_os = "linux")]
+    let rc = unsafe { libc::setxattr(c_path.as_ptr(), c_name.as_ptr(), ptr, value.len(), 0) };
+    #[cfg(target_os = "macos")]
+    let rc = unsafe { libc::setxattr(c_path.as_ptr(), c_name.as_ptr(), ptr, value.len(), 0, 0) };
+    if rc != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[cfg(any(target_os = "linux", target_os = "macos"))]
+fn remove_attr(path: &Path) -> io::Result<()> {
+    let (c_path, c_name) = c_strings(path)?;
+    // SAFETY: both strings are NUL-terminated
+    #[cfg(target_os = "linux")]
+    let rc = unsafe { libc::removexattr(c_path.as_ptr(), c_name.as_ptr()) };
+    #[cfg(target_os = "macos")]
+    let rc = unsafe { libc::removexattr(c_path.as_ptr(), c_name.as_ptr(), 0) };
+    if rc != 0 {
+        let err = io::Error::last_os_error();
+        if err.raw_os_error() != Some(NO_ATTR) {
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+// `scene.blend:blendfinder.meta`; only NTFS (and ReFS) have streams
+#[cfg(windows)]
+fn stream_path(path: &Path) -> std::path::PathBuf {
+    let mut s = path.as_os_str().to_owned();
+    s.push(":blendfinder.meta");
+    s.into()
+}
+
+#[cfg(windows)]
+fn read_attr(path: &Path) -> io::Result<Option<Vec<u8>>> {
+    match std::fs::read(stream_path(path)) {
+        Ok(bytes) => Ok(Some(bytes)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+// Writing a stream bumps the file's modified time; it is put back so the
+// blend doesn't look edited
+#[cfg(windows)]
+fn write_attr(path: &Path, value: &[u8]) -> io::Result<()> {
+    let modified = std::fs::metadata(path)?.modified()?;
+    std::fs::write(stream_path(path), value)?;
+    std::fs::File::options()
+        .write(true)
+        .open(path)?
+        .set_modified(modified)
+}
+
+#[cfg(windows)]
+fn remove_attr(path: &Path) -> io::Result<()> {
+    match std::fs::remove_file(stream_path(path)) {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn read_attr(_path: &Path) -> io::Result<Option<Vec<u8>>> {
+    Ok(None)
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn write_attr(_path: &Path, _value: &[u8]) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+fn remove_attr(_path: &Path) -> io::Result<()> {
+    Ok(())
+}
@@ -8,9 +8,46 @@ const $ = (id) => document.getElementById(id);
 let state = {
   tree: null,
   files: [],
-  selectedPath: null,
+  skipped: [],
+  selectedPath: null, // path_id of the selected file
+  selectedAsset: null, // { path_id, code, name } of an asset inside it
+  assetPreviews: new Map(), // path_id + code + name -> preview, or null
+  catalogs: [], // remote catalogs to attach to (see catalog.rs)
+  remote: null, // name of the attached catalog shown instead of the results
+  remoteFiles: [],
+  catalogJob: null,
+  catalogThumbs: new Map(), // path_id -> base64 PNG, or null
+  multi: new Set(), // path_ids picked with Ctrl/Cmd-click for bulk edits
+  shown: [], // results currently listed, for Ctrl+A
   expanded: new Set(),
+  children: new Map(), // dir path -> child nodes fetched on expand (lazy tree)
   scanId: null,
+  scanPaused: false, // the running scan waits for resume_scan
+  foundNext: 0, // files of the running scan received so far (poll cursor)
+  foundAt: new Map(), // path_id -> index in files, while a scan streams them
+  resultScanId: null, // scan whose result is shown
+  parseDepth: "deep", // of the shown result; shallower files are read on demand
+  enriched: new Set(), // path_ids read on demand since the result was applied
+  elevationJobs: new Map(), // job id -> folder path id
+  update: null, // newer release announced by the backend
+  updateJob: null, // running update check or install
+  triageJob: null, // running damaged file check
+  verifyJob: null, // running checksum verification
+  renameJob: null, // running batch rename
+  toolJobs: new Map(), // job id -> external tool name
+  scriptJob: null, // running script
+  scriptApply: false, // the running script applies its actions
+  scriptFilter: null, // path ids a filter script picked; null shows all
+  searchPicked: "", // query + path id last counted as a search pick
+  scanStartedAt: 0,
+  pendingSelect: null,
+  palette: [], // color labels ({ name, color })
+  projectRoots: [], // path ids of folders marked as projects
+  tools: [], // external tools ({ name, program, args, formats })
+  collections: [], // { id, name, file_count }
+  collection: null, // id of the collection shown instead of the scan
+  creatingSmart: false, // collection dialog is creating a smart collection
+  collectionFiles: [],
   polling: null,
   lastFolder: null,
 };
@@ -26,10 +63,80 @@ const infoEl = $("info");
 const infoContent = $("infoContent");
 const thumbContainer = $("thumbContainer");
 const searchEl = $("search");
+const sortBy = $("sortBy");
+const treeOrder = $("treeOrder");
+const collectionSel = $("collectionSel");
+const btnCollectionEdit = $("btnCollectionEdit");
+const collectionDialog = $("collectionDialog");
+const collectionName = $("collectionName");
+const collectionQuery = $("collectionQuery");
+const collectionQueryWrap = $("collectionQueryWrap");
+const btnSaveSearch = $("btnSaveSearch");
 
 const btnPick = $("btnPick");
+const btnAddFolder = $("btnAddFolder");
 const btnOpen = $("btnOpen");
 const btnReveal = $("btnReveal");
+const btnRefreshFile = $("btnRefreshFile");
+const btnRestoreBackup = $("btnRestoreBackup");
+const btnHealth = $("btnHealth");
+const btnFavorite = $("btnFavorite");
+const btnNewWindow = $("btnNewWindow");
+const btnCopyLogs = $("btnCopyLogs");
+const btnTriage = $("btnTriage");
+const btnVerify = $("btnVerify");
+const btnProjects = $("btnProjects");
+const btnStats = $("btnStats");
+const btnCatalogs = $("btnCatalogs");
+const optUsage = $("optUsage");
+const reportDialog = $("reportDialog");
+const btnUpdate = $("btnUpdate");
+const optUpdates = $("optUpdates");
+const optCloseToTray = $("optCloseToTray");
+const optTrash = $("optTrash");
+const optGentle = $("optGentle");
+const parseDepth = $("parseDepth");
+const optCompact = $("optCompact");
+const optFormats = $("optFormats");
+const optBackups = $("optBackups");
+const optRelative = $("optRelative");
+const optCountsOnly = $("optCountsOnly");
+const optArchives = $("optArchives");
+const optWatch = $("optWatch");
+const optSidecars = $("optSidecars");
+const optXattrs = $("optXattrs");
+const optExportUtc = $("optExportUtc");
+const btnExclusions = $("btnExclusions");
+const exclusionsDialog = $("exclusionsDialog");
+const quickDialog = $("quickDialog");
+const quickInput = $("quickInput");
+const quickList = $("quickList");
+const quickShortcut = $("quickShortcut");
+const exclusionsText = $("exclusionsText");
+const patternsDialog = $("patternsDialog");
+const patternsInclude = $("patternsInclude");
+const patternsExclude = $("patternsExclude");
+const btnRules = $("btnRules");
+const rulesDialog = $("rulesDialog");
+const rulesText = $("rulesText");
+const renameDialog = $("renameDialog");
+const btnTools = $("btnTools");
+const btnFields = $("btnFields");
+const btnScripts = $("btnScripts");
+const btnScriptFilter = $("btnScriptFilter");
+const scriptsDialog = $("scriptsDialog");
+const scriptSel = $("scriptSel");
+const scriptText = $("scriptText");
+const fieldsDialog = $("fieldsDialog");
+const fieldsText = $("fieldsText");
+const toolsDialog = $("toolsDialog");
+const catalogsDialog = $("catalogsDialog");
+const catalogsText = $("catalogsText");
+const toolsText = $("toolsText");
+
+// Secondary windows are opened by `open_window` with their folder in the URL
+const urlParams = new URLSearchParams(window.location.search);
+const isSecondaryWindow = urlParams.has("secondary");
 
 const themeDark = $("themeDark");
 const themeLight = $("themeLight");
@@ -38,8 +145,14 @@ const progressWrap = $("progressWrap");
 const progressBar = $("progressBar");
 const progressText = $("progressText");
 const currentPathEl = $("currentPath");
+const btnPauseScan = $("btnPauseScan");
 
 // ------------------ Utils ------------------
+// Blends show their version; other formats (not parsed) their format
+const isBlend = (format) => !format || format === "blend";
+const versionText = (format, version) =>
+  isBlend(format) ? (version ? `v${version}` : "v?") : format.toUpperCase();
+
 const escapeHtml = (s) =>
   String(s ?? "")
   .replaceAll("&", "&amp;")
@@ -71,7 +184,94 @@ function setCounts(scanned, found, elapsedMs) {
 function setActionButtons() {
   const enabled = !!state.selectedPath;
   btnOpen && (btnOpen.disabled = !enabled);
+  if (btnOpen) {
+    const archived = state.files.some((f) => f.path_id === state.selectedPath && f.archive);
+    btnOpen.textContent = archived ? "Extract & Open" : "Open File";
+  }
   btnReveal && (btnReveal.disabled = !enabled);
+  btnRefreshFile && (btnRefreshFile.disabled = !enabled);
+  if (btnRestoreBackup) {
+    const f = state.files.find((f) => f.path_id === state.selectedPath);
+    btnRestoreBackup.disabled = !f || !!f.archive || f.format !== "blend";
+  }
+  if (btnHealth) {
+    const f = state.files.find((f) => f.path_id === state.selectedPath);
+    btnHealth.disabled = !enabled || !!f?.archive || (f != null && f.format !== "blend");
+  }
+  if (btnFavorite) {
+    btnFavorite.disabled = !enabled;
+    const fav = state.files.some((f) => f.path_id === state.selectedPath && f.favorite);
+    btnFavorite.textContent = fav ? "★ Favorite" : "☆ Favorite";
+  }
+}
+
+// Commands reject with structured errors ({ kind, path, os_code, message })
+// Messages by error code; `{name}` is filled from the error's params (and
+// path). Codes without an entry fall back to the backend's English text, so
+// a translation only needs to replace this table.
+const ERROR_TEXT = {
+  permission_denied: "Permission denied",
+  volume_offline: "The drive is not available",
+  too_many_open_files: "Too many files are open",
+  not_a_blend: "Not a .blend file",
+  scan_not_found: "Scan {scan_id} not found",
+  scan_not_finished: "Scan {scan_id} has not finished",
+  job_not_found: "Job {job_id} not found",
+  cancelled: "Job was cancelled",
+  timeout: "No response after {seconds} s",
+  share_stuck: "The network share stopped responding",
+  insufficient_space: "Not enough free space: {needed} needed, {available} available",
+  no_common_folder: "The files have no folder in common; copy them instead",
+  copy_onto_source: "The destination is the file itself",
+  folder_missing: "Folder does not exist",
+  roots_empty: "No folders to scan",
+  updates_unavailable: "Updates are not set up in this build",
+  catalog_key_required: "Publishing a catalog needs a key",
+  glob_invalid: "Pattern \"{pattern}\" is not valid",
+  header_unreadable: "Unable to read header",
+  backup_picked: "Pick the file, not one of its backups",
+  backup_missing: "No backup next to the file",
+  rating_range: "Ratings go from 1 to {max}",
+  tag_name_empty: "Tag name can't be empty",
+  tag_exists: "A tag named \"{name}\" already exists",
+  tag_not_found: "Tag {id} not found",
+  collection_name_empty: "Collection name can't be empty",
+  collection_exists: "A collection named \"{name}\" already exists",
+  collection_not_found: "Collection {id} not found",
+  label_unknown: "\"{name}\" is not in the label palette",
+  query_invalid: "Query: {detail}",
+  rule_invalid: "Rule \"{pattern}\" is not valid",
+  script_invalid: "Script is not valid: {detail}",
+  seven_zip_missing: "7-Zip is needed to read .7z and .rar archives",
+  shortcut_invalid: "Invalid shortcut '{shortcut}'",
+  shortcut: "Could not register '{shortcut}'",
+  elevation_cancelled: "Elevation was cancelled",
+  health_archive: "Extract the file from its archive to check it",
+  compare_archive: "Extract the file from its archive to compare it",
+  chunk_mismatch: "A copied chunk at {offset} did not verify; run the copy again to resume",
+  catalog_not_found: "No catalog named \"{name}\"",
+  scan_finished: "Scan {scan_id} has already finished",
+  watch_failed: "Can't watch the folder for changes",
+};
+
+const BYTE_PARAMS = new Set(["needed", "available", "offset"]);
+
+function errText(e) {
+  if (!e || typeof e !== "object" || !("message" in e)) return String(e);
+  const params = { ...(e.params || {}), path: e.path || "" };
+  const template = ERROR_TEXT[e.code];
+  // A template naming a param the error didn't send isn't usable
+  const usable =
+    template &&
+    [...template.matchAll(/\{(\w+)\}/g)].every(([, k]) => k in params);
+  const text = usable
+    ? template.replace(/\{(\w+)\}/g, (_, k) =>
+        BYTE_PARAMS.has(k) ? bytesToHuman(Number(params[k])) : params[k]
+      )
+    : e.message;
+  return e.path && !(usable && template.includes("{path}"))
+    ? `${text} (${e.path})`
+    : text;
 }
 
 function showError(msg) {
@@ -99,7 +299,7 @@ async function openSelected() {
       path: state.selectedPath
     });
   } catch (e) {
-    showError(`Open failed: ${e}`);
+    showError(`Open failed: ${errText(e)}`);
   }
 }
 
@@ -110,7 +310,7 @@ async function revealSelected() {
       path: state.selectedPath
     });
   } catch (e) {
-    showError(`Reveal failed: ${e}`);
+    showError(`Reveal failed: ${errText(e)}`);
   }
 }
 
@@ -121,8 +321,12 @@ function makeRow({
   meta,
   indent = 0,
   active = false,
+  color = null,
+  change = null,
   onClick,
+  onIconClick,
   onDblClick,
+  onContextMenu,
 }) {
   const row = document.createElement("div");
   row.className = "nodeRow" + (active ? " active" : "");
@@ -131,10 +335,28 @@ function makeRow({
   const ic = document.createElement("div");
   ic.className = "icon";
   ic.innerHTML = icon;
+  if (onIconClick) {
+    ic.addEventListener("click", (e) => {
+      e.stopPropagation();
+      onIconClick();
+    });
+  }
 
   const la = document.createElement("div");
   la.className = "label";
   la.textContent = label ?? "";
+  if (color) {
+    const dot = document.createElement("span");
+    dot.className = "labelDot";
+    dot.style.background = color;
+    la.prepend(dot);
+  }
+  if (change) {
+    const badge = document.createElement("span");
+    badge.className = `changeBadge ${change}`;
+    badge.textContent = change === "new" ? "new" : "changed";
+    la.appendChild(badge);
+  }
 
   const me = document.createElement("div");
   me.className = "meta";
@@ -146,27 +368,405 @@ function makeRow({
 
   row.addEventListener("click", (e) => {
     e.stopPropagation();
-    onClick?.();
+    onClick?.(e);
   });
   row.addEventListener("dblclick", (e) => {
     e.stopPropagation();
     onDblClick?.();
   });
+  if (onContextMenu) {
+    row.addEventListener("contextmenu", (e) => {
+      e.preventDefault();
+      onContextMenu(e);
+    });
+  }
 
   return row;
 }
 
+// ------------------ Color labels ------------------
+const labelColor = (name) => state.palette.find((l) => l.name === name)?.color || null;
+
+async function loadPalette() {
+  try {
+    state.palette = await invoke("get_label_palette");
+  } catch (e) {
+    console.warn("get_label_palette failed", e);
+  }
+}
+
+async function setLabel(pathId, label) {
+  try {
+    await invoke("set_label", {
+      paths: [pathId],
+      label
+    });
+    refreshResult();
+  } catch (e) {
+    showError(`Setting label failed: ${errText(e)}`);
+  }
+}
+
+// Right-click menu for files and folders
+// Folders also get a project toggle, files the external tools for their format
+function showLabelMenu(e, pathId, folder = false) {
+  let menu = $("labelMenu");
+  if (!menu) {
+    menu = document.createElement("div");
+    menu.id = "labelMenu";
+    menu.className = "labelMenu";
+    document.body.appendChild(menu);
+    document.addEventListener("click", () => (menu.style.display = "none"));
+  }
+  menu.innerHTML = state.palette
+    .map((l) => `<button class="linkBtn" data-menu-label="${escapeHtml(l.name)}">
+      <span class="labelDot" style="background:${escapeHtml(l.color)}"></span>${escapeHtml(l.name)}</button>`)
+    .join("") + `<button class="linkBtn" data-menu-label="">No label</button>` +
+    (folder ? `<button class="linkBtn" data-menu-project>${state.projectRoots.includes(pathId) ? "Unmark project" : "Mark as project"}</button>` : "") +
+    (folder ? "" : toolsFor(pathId)
+      .map((t) => `<button class="linkBtn" data-menu-tool="${escapeHtml(t.name)}">${escapeHtml(t.name)}</button>`)
+      .join(""));
+  menu.onclick = (ev) => {
+    const btn = ev.target.closest("[data-menu-label]");
+    if (btn) setLabel(pathId, btn.dataset.menuLabel || null);
+    if (ev.target.closest("[data-menu-project]")) toggleProjectRoot(pathId);
+    const tool = ev.target.closest("[data-menu-tool]");
+    if (tool) runTool(tool.dataset.menuTool, pathId);
+  };
+  menu.style.left = `${e.clientX}px`;
+  menu.style.top = `${e.clientY}px`;
+  menu.style.display = "flex";
+}
+
+function labelHtml(pathId) {
+  const own = state.files.find((x) => x.path_id === pathId)?.label || null;
+  return state.palette
+    .map((l) => `<button class="linkBtn labelPick${l.name === own ? " active" : ""}" data-label="${l.name === own ? "" : escapeHtml(l.name)}" title="${escapeHtml(l.name)}">
+      <span class="labelDot" style="background:${escapeHtml(l.color)}"></span></button>`)
+    .join("");
+}
+
+// Clicking the current rating clears it
+function starsHtml(pathId) {
+  const rating = state.files.find((x) => x.path_id === pathId)?.rating || 0;
+  const stars = [1, 2, 3, 4, 5]
+    .map((n) => `<button class="linkBtn" data-rate="${n === rating ? 0 : n}" title="${n} star${n > 1 ? "s" : ""}">${n <= rating ? "★" : "☆"}</button>`)
+    .join("");
+  return `<span class="stars">${stars}</span>`;
+}
+
+// Tags live on the flat file entries (refreshed from the index DB)
+function tagsHtml(pathId) {
+  const f = state.files.find((x) => x.path_id === pathId);
+  const chips = (f?.tags || [])
+    .map((t) => `<span class="badge tagChip" style="margin-left:0;">${escapeHtml(t)}
+      <button class="linkBtn" data-untag="${escapeHtml(t)}" title="Remove tag">×</button></span>`)
+    .join(" ");
+  return `${chips}<input class="tagInput" data-tag-path="${escapeHtml(pathId)}" placeholder="+ tag" list="tagNames"/>`;
+}
+
+function collectionsHtml(pathId) {
+  const f = state.files.find((x) => x.path_id === pathId) ||
+    state.collectionFiles.find((x) => x.path_id === pathId);
+  const chips = (f?.collections || [])
+    .map((c) => `<span class="badge tagChip" style="margin-left:0;">${escapeHtml(c)}
+      <button class="linkBtn" data-uncollect="${escapeHtml(c)}" title="Remove from collection">×</button></span>`)
+    .join(" ");
+  return `${chips}<input class="tagInput" data-collect-path="${escapeHtml(pathId)}" placeholder="+ collection" list="collectionNames"/>`;
+}
+
+function noteHtml(pathId) {
+  const f = state.files.find((x) => x.path_id === pathId) ||
+    state.collectionFiles.find((x) => x.path_id === pathId);
+  return `<textarea class="noteInput" data-note-path="${escapeHtml(pathId)}" rows="2" placeholder="Add a note…">${escapeHtml(f?.note || "")}</textarea>`;
+}
+
+// ------------------ Bulk edits ------------------
+function renderBulkInfo() {
+  if (!infoContent || !thumbContainer) return;
+  thumbContainer.style.display = "none";
+  thumbContainer.innerHTML = "";
+  const labels = state.palette
+    .map((l) => `<button class="linkBtn labelPick" data-bulk-label="${escapeHtml(l.name)}" title="${escapeHtml(l.name)}">
+      <span class="labelDot" style="background:${escapeHtml(l.color)}"></span></button>`)
+    .join("");
+  const stars = [1, 2, 3, 4, 5]
+    .map((n) => `<button class="linkBtn" data-bulk-rate="${n}" title="${n} star${n > 1 ? "s" : ""}">${"★".repeat(n)}</button>`)
+    .join(" ");
+  infoContent.innerHTML = `
+    <div class="kv">
+      <div class="k">Selected</div>
+      <div class="v">${state.multi.size.toLocaleString()} files
+        <button class="linkBtn" data-bulk-clear>Clear selection</button></div>
+
+      <div class="k">Details</div>
+      <div class="v" id="bulkDetails">…</div>
+
+      <div class="k">Label</div>
+      <div class="v">${labels}<button class="linkBtn" data-bulk-label="">None</button></div>
+
+      <div class="k">Rating</div>
+      <div class="v"><span class="stars">${stars}</span> <button class="linkBtn" data-bulk-rate="0">Clear</button></div>
+
+      <div class="k">Add Tag</div>
+      <div class="v"><input class="tagInput" data-bulk-tag="add" placeholder="+ tag" list="tagNames"/></div>
+
+      <div class="k">Remove Tag</div>
+      <div class="v"><input class="tagInput" data-bulk-tag="remove" placeholder="− tag" list="tagNames"/></div>
+
+      <div class="k">Protection</div>
+      <div class="v"><button class="linkBtn" data-lock="1">Make read-only</button> <button class="linkBtn" data-lock="0">Make writable</button></div>
+
+      <div class="k">Names</div>
+      <div class="v"><button class="linkBtn" data-rename>Rename…</button></div>
+      ${state.multi.size === 2 ? `
+      <div class="k">Differences</div>
+      <div class="v"><button class="linkBtn" data-bulk-compare>Compare…</button></div>` : ""}
+    </div>
+  `;
+  setActionButtons();
+  loadBulkDetails();
+}
+
+// One call for the whole selection; ignored if the selection changed meanwhile
+async function loadBulkDetails() {
+  const paths = [...state.multi];
+  const key = paths.join("\n");
+  try {
+    const items = await invoke("get_files_meta", { paths });
+    const el = $("bulkDetails");
+    if (!el || [...state.multi].join("\n") !== key) return;
+    const metas = items.filter((i) => i.meta).map((i) => i.meta);
+    const total = metas.reduce((n, m) => n + (m.size_bytes || 0), 0);
+    const versions = new Map();
+    for (const m of metas) {
+      const v = versionText(m.format, m.blender?.version);
+      versions.set(v, (versions.get(v) || 0) + 1);
+    }
+    const missing = items.length - metas.length;
+    el.textContent = [
+      bytesToHuman(total),
+      [...versions].map(([v, n]) => `${v} ×${n}`).join(", "),
+      missing ? `${missing} missing` : "",
+    ].filter(Boolean).join(" • ");
+  } catch (e) {
+    const el = $("bulkDetails");
+    if (el) el.textContent = `unavailable: ${errText(e)}`;
+  }
+}
+
+async function bulkEdit(edit) {
+  try {
+    const results = await invoke("bulk_edit", {
+      paths: [...state.multi],
+      edit
+    });
+    const failed = results.filter((r) => !r.ok);
+    if (failed.length) {
+      showError(`${failed.length} of ${results.length} files failed: ${errText(failed[0].error)}`);
+    }
+    await refreshResult();
+    loadTagNames();
+  } catch (e) {
+    showError(`Bulk edit failed: ${errText(e)}`);
+  }
+}
+
+infoContent?.addEventListener("click", (e) => {
+  const btn = e.target.closest("[data-bulk-label], [data-bulk-rate], [data-bulk-clear], [data-bulk-compare]");
+  if (!btn) return;
+  if (btn.dataset.bulkCompare != null) compareSelected();
+  else if (btn.dataset.bulkLabel != null) bulkEdit({
+    label: btn.dataset.bulkLabel
+  });
+  else if (btn.dataset.bulkRate != null) bulkEdit({
+    rating: Number(btn.dataset.bulkRate)
+  });
+  else selectPath(state.selectedPath);
+});
+
+infoContent?.addEventListener("keydown", (e) => {
+  const input = e.target.closest("[data-bulk-tag]");
+  if (!input || e.key !== "Enter" || !input.value.trim()) return;
+  const names = [input.value.trim()];
+  bulkEdit(input.dataset.bulkTag === "add" ? {
+    add_tags: names
+  } : {
+    remove_tags: names
+  });
+});
+
+// Ctrl/Cmd+A selects every listed result (outside text fields)
+document.addEventListener("keydown", (e) => {
+  if (!(e.ctrlKey || e.metaKey) || e.key.toLowerCase() !== "a") return;
+  if (e.target.closest("input, textarea, select") || !state.shown.length) return;
+  e.preventDefault();
+  state.multi = new Set(state.shown.map((f) => f.path_id));
+  if (!state.multi.has(state.selectedPath)) state.selectedPath = state.shown[0].path_id;
+  renderTree();
+  renderResults();
+  renderSelection();
+});
+
+// Protected folders can be rescanned with admin rights unless denied
+function skippedActions(s) {
+  if (s.reason !== "permission_denied" || s.decision === "deny") return "";
+  const pending = [...state.elevationJobs.values()].includes(s.path);
+  if (pending) return `<span class="skippedPending">scanning…</span>`;
+  const p = escapeHtml(s.path);
+  return `<span class="skippedActions">
+    <button class="linkBtn" data-elevate="once" data-path="${p}">Grant access</button>
+    <button class="linkBtn" data-elevate="always" data-path="${p}">Always</button>
+    <button class="linkBtn" data-elevate="never" data-path="${p}">Ignore</button>
+  </span>`;
+}
+
+// Paths the last scan could not read (permission denied, vanished, …)
+function skippedHtml() {
+  if (!state.skipped.length) return "";
+  const items = state.skipped
+    .slice(0, 50)
+    .map((s) => `<li title="${escapeHtml(s.reason)}">${escapeHtml(s.path)}${skippedActions(s)}</li>`)
+    .join("");
+  const more = state.skipped.length > 50 ? `<li>… ${state.skipped.length - 50} more</li>` : "";
+  return `<div class="skipped">
+    <div class="skippedTitle">${state.skipped.length.toLocaleString()} path(s) not indexed</div>
+    <ul>${items}${more}</ul>
+  </div>`;
+}
+
+// Draws Base64 RGBA (thumbnails, asset previews) into the thumbnail pane
+function drawThumb(data, width, height) {
+  thumbContainer.style.display = "flex";
+  thumbContainer.innerHTML = "";
+  thumbContainer.appendChild(thumbCanvas(data, width, height));
+}
+
+function thumbCanvas(data, width, height) {
+  // Decode base64 to bytes
+  const binaryString = atob(data);
+  const bytes = new Uint8ClampedArray(binaryString.length);
+  for (let i = 0; i < binaryString.length; i++) {
+    bytes[i] = binaryString.charCodeAt(i);
+  }
+
+  const thumbW = width || 128;
+  const thumbH = height || 128;
+
+  // 1. Create offscreen canvas for source data
+  const offscreen = document.createElement("canvas");
+  offscreen.width = thumbW;
+  offscreen.height = thumbH;
+  const offCtx = offscreen.getContext("2d");
+  const imageData = offCtx.createImageData(thumbW, thumbH);
+  imageData.data.set(bytes);
+  offCtx.putImageData(imageData, 0, 0);
+
+  // 2. Setup main canvas with High DPI support
+  const canvas = document.createElement("canvas");
+  const dpr = window.devicePixelRatio || 1;
+
+  // Use native resolution for display size
+  const displayW = thumbW;
+  const displayH = thumbH;
+
+  canvas.width = displayW * dpr;
+  canvas.height = displayH * dpr;
+  canvas.style.width = `${displayW}px`;
+  canvas.style.height = `${displayH}px`;
+
+  const ctx = canvas.getContext("2d");
+  ctx.imageSmoothingEnabled = false;
+  ctx.webkitImageSmoothingEnabled = false;
+  ctx.mozImageSmoothingEnabled = false;
+  ctx.msImageSmoothingEnabled = false;
+
+  // Draw offscreen to main
+  ctx.drawImage(offscreen, 0, 0, canvas.width, canvas.height);
+  return canvas;
+}
+
+// Asset previews are read from the file on demand and kept
+async function showAssetPreview(node, asset) {
+  const key = `${node.path_id}\n${asset.code}${asset.name}`;
+  if (!state.assetPreviews.has(key)) {
+    thumbContainer.style.display = "none";
+    thumbContainer.innerHTML = "";
+    try {
+      state.assetPreviews.set(key, await invoke("get_asset_preview", {
+        path: node.path_id,
+        code: asset.code,
+        name: asset.name
+      }));
+    } catch (e) {
+      console.warn(`Asset preview failed: ${errText(e)}`);
+      state.assetPreviews.set(key, null);
+    }
+  }
+  const prv = state.assetPreviews.get(key);
+  const still = state.selectedAsset?.path_id === node.path_id &&
+    state.selectedAsset.code === asset.code && state.selectedAsset.name === asset.name;
+  if (prv && still) drawThumb(prv.data, prv.width, prv.height);
+}
+
+// Published catalogs send thumbnails apart from the file list
+async function showCatalogThumb(node) {
+  thumbContainer.style.display = "none";
+  thumbContainer.innerHTML = "";
+  if (!state.catalogThumbs.has(node.path_id)) {
+    try {
+      state.catalogThumbs.set(node.path_id, await invoke("get_catalog_thumbnail", {
+        name: state.remote,
+        path: node.path_id
+      }));
+    } catch (e) {
+      console.warn(`Catalog thumbnail failed: ${errText(e)}`);
+      return;
+    }
+  }
+  const png = state.catalogThumbs.get(node.path_id);
+  if (!png || state.selectedPath !== node.path_id) return;
+  thumbContainer.style.display = "flex";
+  thumbContainer.innerHTML = `<img src="data:image/png;base64,${png}" alt="">`;
+}
+
+// Cloth "Flag" • baked • on disk: //cache/
+function simText(s) {
+  const where = s.external ? `on disk${s.path ? `: ${s.path}` : ""}` : "in the blend";
+  return `${s.kind}${s.name ? ` "${s.name}"` : ""} • ${s.baked ? "baked" : "not baked"} • ${where}`;
+}
+
+// 12 strips (4 movie, 3 sound) • 6 media files
+function vseText(v) {
+  const kinds = [[v.movies, "movie"], [v.sounds, "sound"], [v.images, "image"]]
+    .filter(([n]) => n)
+    .map(([n, k]) => `${n} ${k}`);
+  const plural = (n, w) => `${n.toLocaleString()} ${w}${n === 1 ? "" : "s"}`;
+  return `${plural(v.strips, "strip")}${kinds.length ? ` (${kinds.join(", ")})` : ""} • ${plural(v.media, "media file")}`;
+}
+
+// "200 (150 Material, 50 Node Group)"
+function assetSummary(assets) {
+  const byType = new Map();
+  for (const a of assets) byType.set(a.id_type, (byType.get(a.id_type) || 0) + 1);
+  const parts = [...byType].sort((a, b) => b[1] - a[1]).map(([t, n]) => `${n} ${t}`);
+  return `${assets.length.toLocaleString()} (${parts.join(", ")})`;
+}
+
 function renderInfo(node) {
   if (!infoContent || !thumbContainer) return;
 
   if (!node) {
-    infoContent.innerHTML = `<div class="hint">Select a .blend file from the tree or search results to view details.</div>`;
+    infoContent.innerHTML = `<div class="hint">Select a .blend file from the tree or search results to view details.</div>` +
+      skippedHtml();
     thumbContainer.innerHTML = "";
     thumbContainer.style.display = "none";
     setActionButtons();
     return;
   }
 
+  const flat = state.files.find((f) => f.path_id === node.path_id);
+  const relPath = flat?.rel_path;
   const b = node.meta?.blender;
   const blenderText = b?.version ?
     `${b.version} (raw ${b.raw ?? "???"}, ${b.pointer_size ?? "?"}-bit, ${b.endianness ?? "?"} endian)` :
@@ -174,53 +774,18 @@ function renderInfo(node) {
     `Unknown (${b.error})` :
     "Unknown";
 
+  const asset = state.selectedAsset?.path_id === node.path_id ?
+    (b?.assets || []).find((a) => a.code === state.selectedAsset.code && a.name === state.selectedAsset.name) :
+    null;
+
   // Thumbnail rendering
-  if (b?.thumbnail) {
+  if (asset?.has_preview) {
+    showAssetPreview(node, asset);
+  } else if (state.remote != null && state.remoteFiles.some((f) => f.path_id === node.path_id)) {
+    showCatalogThumb(node);
+  } else if (b?.thumbnail && !asset) {
     console.log(`Thumbnail found for ${node.name}: ${b.thumb_width}x${b.thumb_height}`);
-    thumbContainer.style.display = "flex";
-    thumbContainer.innerHTML = "";
-    // Decode base64 to bytes
-    const binaryString = atob(b.thumbnail);
-    const bytes = new Uint8ClampedArray(binaryString.length);
-    for (let i = 0; i < binaryString.length; i++) {
-      bytes[i] = binaryString.charCodeAt(i);
-    }
-
-    const thumbW = b.thumb_width || 128;
-    const thumbH = b.thumb_height || 128;
-
-    // 1. Create offscreen canvas for source data
-    const offscreen = document.createElement("canvas");
-    offscreen.width = thumbW;
-    offscreen.height = thumbH;
-    const offCtx = offscreen.getContext("2d");
-    const imageData = offCtx.createImageData(thumbW, thumbH);
-    imageData.data.set(bytes);
-    offCtx.putImageData(imageData, 0, 0);
-
-    // 2. Setup main canvas with High DPI support
-    const canvas = document.createElement("canvas");
-    const dpr = window.devicePixelRatio || 1;
-
-    // Use native resolution for display size
-    const displayW = thumbW;
-    const displayH = thumbH;
-
-    canvas.width = displayW * dpr;
-    canvas.height = displayH * dpr;
-    canvas.style.width = `${displayW}px`;
-    canvas.style.height = `${displayH}px`;
-
-    const ctx = canvas.getContext("2d");
-    ctx.imageSmoothingEnabled = false;
-    ctx.webkitImageSmoothingEnabled = false;
-    ctx.mozImageSmoothingEnabled = false;
-    ctx.msImageSmoothingEnabled = false;
-
-    // Draw offscreen to main
-    ctx.drawImage(offscreen, 0, 0, canvas.width, canvas.height);
-
-    thumbContainer.appendChild(canvas);
+    drawThumb(b.thumbnail, b.thumb_width, b.thumb_height);
   } else {
     thumbContainer.style.display = "none";
     thumbContainer.innerHTML = "";
@@ -228,12 +793,33 @@ function renderInfo(node) {
 
   infoContent.innerHTML = `
     <div class="kv">
+      ${asset ? `
+      <div class="k">Asset</div>
+      <div class="v">${escapeHtml(asset.name)}</div>
+
+      <div class="k">Asset Type</div>
+      <div class="v"><span class="badge" style="margin-left:0;">${escapeHtml(asset.id_type)}</span></div>
+
+      <div class="k">Catalog</div>
+      <div class="v">${escapeHtml(asset.catalog || "—")}</div>
+      ` : ""}
       <div class="k">File Name</div>
       <div class="v">${escapeHtml(node.name || "")}</div>
       
       <div class="k">Full Path</div>
       <div class="v">${escapeHtml(node.path || "")}</div>
-      
+      ${relPath ? `
+      <div class="k">Relative Path</div>
+      <div class="v">${escapeHtml(relPath)}</div>` : ""}
+      ${node.meta?.aliases?.length ? `
+      <div class="k">Also At</div>
+      <div class="v">${node.meta.aliases.map((a) => `<div>${escapeHtml(a)}</div>`).join("")}</div>` : ""}
+      ${node.meta?.archive ? `
+      <div class="k">In Archive</div>
+      <div class="v">${escapeHtml(node.meta.archive)}</div>` : ""}
+      ${b?.assets?.length ? `
+      <div class="k">Assets</div>
+      <div class="v">${escapeHtml(assetSummary(b.assets))}</div>` : ""}
       <div class="k">Directory</div>
       <div class="v">${escapeHtml(node.meta?.folder || "")}</div>
       
@@ -243,13 +829,72 @@ function renderInfo(node) {
         <span class="badge">${Number(node.meta?.size_bytes || 0).toLocaleString()} bytes</span>
       </div>
       
+      ${isBlend(node.meta?.format) ? `
       <div class="k">Blender</div>
-      <div class="v">${escapeHtml(blenderText)}</div>
+      <div class="v">${escapeHtml(blenderText)}</div>` : `
+      <div class="k">Format</div>
+      <div class="v"><span class="badge" style="margin-left:0;">${escapeHtml(node.meta.format.toUpperCase())}</span> not parsed</div>`}
+      <div class="k">Label</div>
+      <div class="v">${labelHtml(node.path_id)}</div>
+
+      <div class="k">Rating</div>
+      <div class="v">${starsHtml(node.path_id)}</div>
+
+      <div class="k">Tags</div>
+      <div class="v">${tagsHtml(node.path_id)}</div>
+
+      <div class="k">Collections</div>
+      <div class="v">${collectionsHtml(node.path_id)}</div>
+
+      <div class="k">Notes</div>
+      <div class="v">${noteHtml(node.path_id)}</div>
+      ${node.meta?.trash ? `
+      <div class="k">Trash</div>
+      <div class="v">
+        <span class="badge" style="margin-left:0;">In trash</span>
+        ${node.meta.trash.deleted_at ? `deleted ${escapeHtml(node.meta.trash.deleted_at)}` : ""}
+        ${node.meta.trash.original_path ? `<div>from ${escapeHtml(node.meta.trash.original_path)}</div>
+        <button class="linkBtn" data-restore="${escapeHtml(node.path_id)}">Restore</button>` : ""}
+      </div>` : ""}
+      ${node.meta?.cloud_only ? `
+      <div class="k">Storage</div>
+      <div class="v"><span class="badge" style="margin-left:0;">Cloud-only</span> not downloaded</div>` : ""}
+      ${node.meta && !node.meta.archive && !node.meta.trash ? `
+      <div class="k">Protection</div>
+      <div class="v">${node.meta.read_only ?
+        `<span class="badge" style="margin-left:0;">Read-only</span> <button class="linkBtn" data-lock="0">Make writable</button>` :
+        `Writable <button class="linkBtn" data-lock="1" title="Blender can't save over a read-only file">Make read-only</button>`}</div>
+      <div class="k">Name</div>
+      <div class="v"><button class="linkBtn" data-rename>Rename…</button></div>` : ""}
       
       <div class="k">Engine</div>
       <div class="v">
-        ${b?.render_engine ? `<span class="badge" style="background:var(--accent2);color:#fff;margin-left:0;margin-right:8px;text-transform:uppercase;">${b.render_engine}</span>` : "—"}
+        ${b?.render_engine ? `<span class="badge" style="background:var(--accent2);color:#fff;margin-left:0;margin-right:8px;text-transform:uppercase;">${escapeHtml(b.render_engine)}</span>${b.render_engine_id && b.render_engine_id !== b.render_engine ? escapeHtml(b.render_engine_id) : ""}` : "—"}
       </div>
+      ${b?.compositing != null ? `
+      <div class="k">Compositing</div>
+      <div class="v">${b.compositing ? `<span class="badge" style="margin-left:0;">Comp pass</span> node tree beyond the default` : "None"}</div>` : ""}
+      ${b?.autopack != null ? `
+      <div class="k">Auto-pack</div>
+      <div class="v">${b.autopack ? `<span class="badge" style="margin-left:0;">On</span> external files are packed on save` : "Off"}</div>` : ""}
+      ${b?.simulations?.length ? `
+      <div class="k">Simulations</div>
+      <div class="v">${b.simulations.map((s) => `<div>${escapeHtml(simText(s))}</div>`).join("")}</div>` : ""}
+      ${b?.sequencer ? `
+      <div class="k">Sequencer</div>
+      <div class="v">${escapeHtml(vseText(b.sequencer))}</div>` : ""}
+      ${b?.environments?.length ? `
+      <div class="k">Environment</div>
+      <div class="v">${b.environments.map((e) => `<div>${escapeHtml(e.world)}: ${escapeHtml(e.path)}${e.packed ? ` <span class="badge">packed</span>` : ""}</div>`).join("")}</div>` : ""}
+      ${b?.libraries?.length ? `
+      <div class="k">Links</div>
+      <div class="v">${b.libraries.map((l) => `<div>${escapeHtml(l)}</div>`).join("")}</div>` : ""}
+      ${Object.entries(flat?.fields || {}).map(([k, v]) => `
+      <div class="k">${escapeHtml(k)}</div>
+      <div class="v">${escapeHtml(v)}</div>`).join("")}
+      ${flat?.staleness ? `
+      <div class="k">Staleness</div>
+      <div class="v">${escapeHtml(staleText(flat.staleness))}</div>` : ""}
 
       <div class="k">Created</div>
       <div class="v">${escapeHtml(node.meta?.created || "—")}</div>
@@ -261,10 +906,29 @@ function renderInfo(node) {
   setActionButtons();
 }
 
+// Results arrive with the top tree level only; deeper ones are fetched as
+// folders are expanded
+const childrenOf = (node) => node.children || state.children.get(node.path);
+
+async function loadChildren(dirPath) {
+  const kids = await invoke("get_tree_children", {
+    scanId: state.resultScanId,
+    dirPath
+  });
+  state.children.set(dirPath, kids);
+}
+
+// After a refresh, expanded folders are fetched again (labels may have
+// changed); the root too when it came without children (light tree)
+async function reloadExpanded() {
+  const paths = [...state.expanded].filter((p) => p !== state.tree?.path || !state.tree.children);
+  await Promise.all(paths.map((p) => loadChildren(p).catch(() => state.expanded.delete(p))));
+}
+
 function findNodeByPath(node, targetPath) {
   if (!node) return null;
-  if (node.node_type === "file" && node.path === targetPath) return node;
-  const kids = node.children || [];
+  if (node.node_type === "file" && node.path_id === targetPath) return node;
+  const kids = childrenOf(node) || [];
   for (const k of kids) {
     const hit = findNodeByPath(k, targetPath);
     if (hit) return hit;
@@ -272,20 +936,139 @@ function findNodeByPath(node, targetPath) {
   return null;
 }
 
-function selectPath(filePath) {
-  state.selectedPath = filePath;
-  const node = findNodeByPath(state.tree, filePath);
+// Collection members may come from another scan (or none); their info is
+// built from the flat entry
+function nodeFromFlat(f) {
+  if (!f) return null;
+  return {
+    node_type: "file",
+    name: f.name,
+    path: f.path,
+    path_id: f.path_id,
+    meta: {
+      folder: f.folder,
+      size_bytes: f.size_bytes,
+      created: f.created,
+      modified: f.modified,
+      created_ms: f.created_ms,
+      modified_ms: f.modified_ms,
+      cloud_only: f.cloud_only,
+      read_only: f.read_only,
+      trash: f.trash,
+      format: f.format,
+      aliases: f.aliases,
+      archive: f.archive,
+      blender: {
+        version: f.blender_version,
+        thumbnail: f.thumbnail,
+        thumb_width: f.thumb_width,
+        thumb_height: f.thumb_height,
+        render_engine: f.render_engine,
+        render_engine_id: f.render_engine_id,
+        assets: f.assets,
+        compositing: f.compositing,
+        autopack: f.autopack,
+        simulations: f.simulations,
+        sequencer: f.sequencer,
+        environments: f.environments,
+        libraries: f.libraries,
+      },
+    },
+  };
+}
+
+// "72 — 3 years old, 4 backups, not linked, duplicate name and size"
+function staleText(s) {
+  const why = [
+    s.age_days != null && (s.age_days >= 365 ? `${Math.floor(s.age_days / 365)} years old` : `${s.age_days} days old`),
+    s.backups && `${s.backups} backup${s.backups === 1 ? "" : "s"}`,
+    s.linked ? "linked by another blend" : "not linked",
+    s.duplicate && "duplicate name and size",
+  ].filter(Boolean);
+  return `${s.score} — ${why.join(", ")}`;
+}
+
+function findSelected(pathId) {
+  return findNodeByPath(state.tree, pathId) ||
+    nodeFromFlat(state.files.find((f) => f.path_id === pathId)) ||
+    nodeFromFlat(state.collectionFiles.find((f) => f.path_id === pathId)) ||
+    nodeFromFlat(state.remoteFiles.find((f) => f.path_id === pathId));
+}
+
+// `additive` (Ctrl/Cmd-click) toggles the file in the bulk selection
+function selectPath(filePath, additive = false) {
+  state.selectedAsset = null;
+  if (additive) {
+    if (state.selectedPath) state.multi.add(state.selectedPath);
+    if (state.multi.has(filePath)) state.multi.delete(filePath);
+    else state.multi.add(filePath);
+    state.selectedPath = state.multi.has(filePath) ? filePath : [...state.multi].pop() || null;
+  } else {
+    state.multi.clear();
+    state.selectedPath = filePath;
+  }
+  renderTree();
+  renderResults();
+  renderSelection();
+}
+
+function selectAsset(pathId, asset) {
+  state.multi.clear();
+  state.selectedPath = pathId;
+  state.selectedAsset = { path_id: pathId, code: asset.code, name: asset.name };
   renderTree();
   renderResults();
-  renderInfo(node);
+  renderSelection();
 }
 
-function toggleFolder(path) {
-  if (state.expanded.has(path)) state.expanded.delete(path);
-  else state.expanded.add(path);
+// Asset inside a listed blend, one level below its file
+function assetRow(pathId, asset, indent) {
+  const sel = state.selectedAsset;
+  return makeRow({
+    icon: "◆",
+    label: asset.name,
+    meta: asset.catalog ? `${asset.id_type} • ${asset.catalog}` : asset.id_type,
+    indent,
+    active: sel?.path_id === pathId && sel.code === asset.code && sel.name === asset.name,
+    onClick: () => selectAsset(pathId, asset),
+    onDblClick: () => {
+      selectAsset(pathId, asset);
+      openSelected();
+    },
+  });
+}
+
+function renderSelection() {
+  if (state.multi.size > 1) renderBulkInfo();
+  else renderInfo(state.selectedPath ? findSelected(state.selectedPath) : null);
+}
+
+async function toggleFolder(node) {
+  const path = node.path;
+  if (state.expanded.has(path)) {
+    state.expanded.delete(path);
+  } else {
+    state.expanded.add(path);
+    if (!childrenOf(node)) {
+      try {
+        await loadChildren(path);
+      } catch (e) {
+        state.expanded.delete(path);
+        showError(`Loading folder failed: ${errText(e)}`);
+      }
+    }
+  }
   renderTree();
 }
 
+// "new" / "modified" since the previous scan of the folder; on folders,
+// anything inside
+function changeOf(x) {
+  if (x?.is_new) return "new";
+  if (x?.is_modified_since_last_scan) return "modified";
+  return null;
+}
+
 function renderTreeNode(node, indent = 0) {
   const rows = [];
 
@@ -297,14 +1080,20 @@ function renderTreeNode(node, indent = 0) {
       makeRow({
         icon: caret,
         label: node.name,
-        meta: "folder",
+        meta: (node.blend_count != null ?
+          `${node.blend_count.toLocaleString()} • ${bytesToHuman(node.total_size)}` :
+          "folder") +
+          (node.error ? (node.error.partial ? " • ⚠ incomplete" : " • ⚠ unreadable") : ""),
         indent,
-        onClick: () => toggleFolder(node.path),
+        color: labelColor(node.label),
+        change: changeOf(node),
+        onClick: () => toggleFolder(node),
+        onContextMenu: (e) => showLabelMenu(e, node.path_id, true),
       })
     );
 
     if (isExpanded) {
-      const kids = node.children || [];
+      const kids = childrenOf(node) || [];
       for (const child of kids) {
         rows.push(...renderTreeNode(child, indent + 12));
       }
@@ -312,24 +1101,41 @@ function renderTreeNode(node, indent = 0) {
     return rows;
   }
 
-  // file
-  const ver = node.meta?.blender?.version || "";
-  const isActive = state.selectedPath === node.path;
+  // file; one with listed backups or assets expands to them from its icon
+  const isActive = state.selectedPath === node.path_id && !state.selectedAsset;
+  const backups = node.children || [];
+  const assets = node.meta?.blender?.assets || [];
+  const expandable = backups.length > 0 || assets.length > 0;
+  const isExpanded = expandable && state.expanded.has(node.path);
 
   rows.push(
     makeRow({
-      icon: "🧊",
+      icon: expandable ? (isExpanded ? "▾" : "▸") : isBlend(node.meta?.format) ? "🧊" : "📦",
       label: node.name,
-      meta: ver ? `v${ver}` : "v?",
+      meta: versionText(node.meta?.format, node.meta?.blender?.version) +
+        (backups.length ? ` • ${backups.length} backup${backups.length > 1 ? "s" : ""}` : "") +
+        (assets.length ? ` • ${assets.length} asset${assets.length > 1 ? "s" : ""}` : ""),
       indent,
       active: isActive,
-      onClick: () => selectPath(node.path),
+      color: labelColor(node.label),
+      change: changeOf(node),
+      onClick: () => selectPath(node.path_id),
+      onIconClick: expandable ? () => {
+        if (isExpanded) state.expanded.delete(node.path);
+        else state.expanded.add(node.path);
+        renderTree();
+      } : null,
+      onContextMenu: (e) => showLabelMenu(e, node.path_id),
       onDblClick: () => {
-        selectPath(node.path);
+        selectPath(node.path_id);
         openSelected();
       },
     })
   );
+  if (isExpanded) {
+    for (const b of backups) rows.push(...renderTreeNode(b, indent + 12));
+    for (const a of assets) rows.push(assetRow(node.path_id, a, indent + 12));
+  }
 
   return rows;
 }
@@ -356,49 +1162,136 @@ function renderResults() {
   const q = (searchEl?.value || "").trim().toLowerCase();
   resultsEl.innerHTML = "";
 
-  if (!state.files.length) {
+  const files = state.remote != null ? state.remoteFiles :
+    state.collection != null ? state.collectionFiles : state.files;
+  if (!files.length) {
+    state.shown = [];
     resultsCount.textContent = "—";
-    resultsEl.innerHTML = `<div class="hint">No results yet. Select a folder to scan.</div>`;
+    resultsEl.innerHTML = state.collection != null ?
+      `<div class="hint">This collection is empty. Add files from the info pane.</div>` :
+      `<div class="hint">No results yet. Select a folder to scan.</div>`;
     return;
   }
 
-  let list = state.files;
+  // `tag:name`, `label:color`, `format:fbx`, `hdri:part`, `is:fav`, `is:new`,
+  // `is:modified`, `is:comp`, `is:autopack`, `is:sim`, `is:baked`, `is:vse`,
+  // `is:readonly`, `rating:N` (N stars or more) and `stale:N` (score N or
+  // more) filter, as does `name:value` for a custom field; the rest matches
+  // name or path
+  const terms = q.split(/\s+/).filter(Boolean);
+  const fieldNames = new Set(files.flatMap((f) => Object.keys(f.fields || {})));
+  const fieldTerms = terms
+    .map((t) => t.split(/:(.*)/s))
+    .filter(([k, v]) => v && fieldNames.has(k));
+  const isFilter = (t) =>
+    (t.includes(":") && fieldNames.has(t.split(":")[0])) ||
+    t.startsWith("tag:") || t.startsWith("label:") || t.startsWith("rating:") || t.startsWith("stale:") ||
+    t.startsWith("format:") || t.startsWith("hdri:") || t === "is:fav" || t === "is:new" || t === "is:modified" || t === "is:comp" ||
+    t === "is:autopack" || t === "is:sim" || t === "is:baked" || t === "is:vse" || t === "is:readonly";
+  const labelTerm = terms.find((t) => t.startsWith("label:"))?.slice(6);
+  const formatTerm = terms.find((t) => t.startsWith("format:"))?.slice(7);
+  const hdriTerm = terms.find((t) => t.startsWith("hdri:"))?.slice(5);
+  const tagTerms = terms.filter((t) => t.startsWith("tag:")).map((t) => t.slice(4));
+  const favOnly = terms.includes("is:fav");
+  const newOnly = terms.includes("is:new");
+  const modifiedOnly = terms.includes("is:modified");
+  const compOnly = terms.includes("is:comp");
+  const autopackOnly = terms.includes("is:autopack");
+  const simOnly = terms.includes("is:sim");
+  const bakedOnly = terms.includes("is:baked");
+  const vseOnly = terms.includes("is:vse");
+  const readOnly = terms.includes("is:readonly");
+  const minRating = Number(terms.find((t) => t.startsWith("rating:"))?.slice(7)) || 0;
+  const minStale = Number(terms.find((t) => t.startsWith("stale:"))?.slice(6)) || 0;
+  const text = terms.filter((t) => !isFilter(t)).join(" ");
+  let list = files;
   if (q) {
-    list = state.files.filter(
+    list = files.filter(
       (f) =>
-      (f.name || "").toLowerCase().includes(q) ||
-      (f.path || "").toLowerCase().includes(q)
+      (!favOnly || f.favorite) &&
+      (!newOnly || f.is_new) &&
+      (!modifiedOnly || f.is_modified_since_last_scan) &&
+      (!compOnly || f.compositing) &&
+      (!autopackOnly || f.autopack) &&
+      (!simOnly || f.simulations?.length) &&
+      (!bakedOnly || f.simulations?.some((s) => s.baked)) &&
+      (!vseOnly || f.sequencer) &&
+      (!readOnly || f.read_only) &&
+      (f.rating || 0) >= minRating &&
+      (f.staleness?.score ?? 0) >= minStale &&
+      (!labelTerm || (f.label || "").toLowerCase() === labelTerm) &&
+      (!formatTerm || (f.format || "blend") === formatTerm) &&
+      (!hdriTerm || (f.environments || []).some((e) => e.path.toLowerCase().includes(hdriTerm))) &&
+      tagTerms.every((t) => (f.tags || []).some((x) => x.toLowerCase() === t)) &&
+      fieldTerms.every(([k, v]) => (f.fields?.[k] || "").toLowerCase().includes(v)) &&
+      (!text ||
+        (f.name || "").toLowerCase().includes(text) ||
+        (f.path || "").toLowerCase().includes(text) ||
+        (f.note || "").toLowerCase().includes(text) ||
+        (f.assets || []).some((a) => a.name.toLowerCase().includes(text)))
     );
   }
-
-  resultsCount.textContent = `${list.length.toLocaleString()}`;
+  if (state.scriptFilter) list = list.filter((f) => state.scriptFilter.has(f.path_id));
+  const sorters = {
+    name: (a, b) => (a.name || "").localeCompare(b.name || ""),
+    rating: (a, b) => (b.rating || 0) - (a.rating || 0),
+    // Groups by label in palette order, unlabeled last
+    label: (a, b) => labelRank(a.label) - labelRank(b.label),
+    size: (a, b) => (b.size_bytes || 0) - (a.size_bytes || 0),
+    modified: (a, b) => (b.modified_ms ?? 0) - (a.modified_ms ?? 0),
+    stale: (a, b) => (b.staleness?.score ?? 0) - (a.staleness?.score ?? 0),
+  };
+  const sorter = sorters[sortBy?.value];
+  if (sorter) list = [...list].sort(sorter);
+  // Favorites are pinned to the top
+  list = [...list.filter((f) => f.favorite), ...list.filter((f) => !f.favorite)];
+
+  resultsCount.textContent = state.multi.size > 1 ?
+    `${state.multi.size.toLocaleString()} of ${list.length.toLocaleString()} selected` :
+    `${list.length.toLocaleString()}`;
+  state.shown = list;
 
   const max = Math.min(list.length, 2000);
   for (let i = 0; i < max; i++) {
     const f = list[i];
-    const isActive = state.selectedPath === f.path;
-
-    resultsEl.appendChild(
-      makeRow({
-        icon: `<img src="assets/blender_icon.png" style="width:18px;height:18px;vertical-align:text-bottom">`,
-        label: f.name,
-        meta: `${bytesToHuman(f.size_bytes)} • ${
-          f.blender_version ? "v" + f.blender_version : "v?"
-        }`,
-        active: isActive,
-        indent: 0,
-        onClick: () => selectPath(f.path),
-        onDblClick: () => {
-          selectPath(f.path);
-          openSelected();
-        },
-      })
-    );
+    const isActive = (state.selectedPath === f.path_id && !state.selectedAsset) || state.multi.has(f.path_id);
+
+    const row = makeRow({
+      icon: isBlend(f.format) ?
+        `<img src="assets/blender_icon.png" style="width:18px;height:18px;vertical-align:text-bottom">` :
+        "📦",
+      label: f.favorite ? `★ ${f.name}` : f.name,
+      meta: `${bytesToHuman(f.size_bytes)} • ${versionText(f.format, f.blender_version)}${f.rating ? " • " + "★".repeat(f.rating) : ""}${f.cloud_only ? " • ☁" : ""}${f.read_only ? " • 🔒" : ""}${f.trash ? " • 🗑" : ""}${f.archive ? " • 🗜 archived" : ""}${f.assets?.length ? ` • ${f.assets.length} assets` : ""}${f.sequencer ? " • 🎞" : ""}${f.missing ? " • missing" : ""}${
+        f.tags?.length ? " • " + f.tags.join(", ") : ""
+      }`,
+      active: isActive,
+      indent: 0,
+      color: labelColor(f.label),
+      change: changeOf(f),
+      onClick: (e) => {
+        if (q) noteSearchPick(f.path_id, q);
+        selectPath(f.path_id, e.ctrlKey || e.metaKey);
+      },
+      onContextMenu: (e) => showLabelMenu(e, f.path_id),
+      onDblClick: () => {
+        selectPath(f.path_id);
+        openSelected();
+      },
+    });
+    row.dataset.path = f.path_id;
+    resultsEl.appendChild(row);
 
     const sub = document.createElement("div");
     sub.className = "subPath";
     sub.textContent = f.path;
     resultsEl.appendChild(sub);
+
+    // Assets whose name matches are listed under their library
+    if (text) {
+      for (const a of (f.assets || []).filter((a) => a.name.toLowerCase().includes(text))) {
+        resultsEl.appendChild(assetRow(f.path_id, a, 12));
+      }
+    }
   }
 
   if (list.length > max) {
@@ -407,6 +1300,7 @@ function renderResults() {
     note.textContent = `Showing first ${max.toLocaleString()} results. Refine search to see more.`;
     resultsEl.appendChild(note);
   }
+  requestShown();
 }
 
 // ------------------ Scanning + Progress ------------------
@@ -420,126 +1314,2103 @@ function setProgressIndeterminate(on) {
   progressBar.classList.toggle("indeterminate", !!on);
 }
 
-async function startScan(folder) {
+// `indexed` reads only files changed since the last scan of the folder
+// `folder` is a path id, or a list of them for one multi-root scan
+async function startScan(folder, {
+  indexed = false
+} = {}) {
+  const roots = Array.isArray(folder) ? folder : [folder];
   state.lastFolder = folder;
-  localStorage.setItem("lastFolder", folder);
+  if (!isSecondaryWindow) localStorage.setItem("lastFolder", roots.join("\n"));
 
-  folderPill && (folderPill.textContent = folder);
+  folderPill && (folderPill.textContent = roots.join(" + "));
   showProgress(true);
   setProgressIndeterminate(true);
 
   if (currentPathEl) currentPathEl.textContent = "";
+  setScanPaused(false);
+  if (btnPauseScan) btnPauseScan.disabled = true;
 
   // reset UI state
   state.selectedPath = null;
+  state.multi.clear();
   state.tree = null;
   state.files = [];
   state.expanded = new Set();
+  state.children = new Map();
   renderTree();
   renderResults();
   renderInfo(null);
   setActionButtons();
 
   const startedAt = performance.now();
+  state.scanStartedAt = startedAt;
+  state.scanId = null;
+  state.foundNext = 0;
 
   try {
-    const scanId = await invoke("start_scan", {
-      folderPath: folder
-    });
+    const options = {
+      include_trash: !!optTrash?.checked,
+      profile: optGentle?.checked ? "gentle" : "default",
+      parse_depth: parseDepth?.value || null,
+      compact_tree: !!optCompact?.checked,
+      tree_order: treeOrder?.value || "name",
+      other_formats: !!optFormats?.checked,
+      backups: !!optBackups?.checked,
+      relative_paths: !!optRelative?.checked,
+      counts_only_tree: !!optCountsOnly?.checked,
+      archives: !!optArchives?.checked,
+      include: scanPatterns("scanInclude"),
+      exclude: scanPatterns("scanExclude")
+    };
+    const scanId = roots.length > 1 ?
+      await invoke("start_multi_scan", {
+        roots,
+        options: { ...options, use_index: indexed }
+      }) :
+      await invoke(indexed ? "rescan_indexed" : "start_scan", {
+        folderPath: roots[0],
+        options
+      });
     state.scanId = scanId;
+    if (btnPauseScan) btnPauseScan.disabled = false;
 
-    // poll
+    // Events drive the UI; polling stays as a slower safety net (or the
+    // only channel when the event API is unavailable).
     if (state.polling) clearInterval(state.polling);
     state.polling = setInterval(async () => {
       try {
         const p = await invoke("poll_scan", {
-          scanId: state.scanId
+          scanId: state.scanId,
+          lazyTree: true,
+          foundFrom: state.foundNext
         });
-
-        setCounts(
-          p.scanned_entries,
-          p.found_blends,
-          performance.now() - startedAt
-        );
-        if (progressText)
-          progressText.textContent = `${p.found_blends.toLocaleString()} .blend files found`;
-
-        if (currentPathEl && p.current_path)
-          currentPathEl.textContent = p.current_path;
-
-        if (p.status === "done") {
-          clearInterval(state.polling);
-          state.polling = null;
-
-          setProgressIndeterminate(false);
-          showProgress(false);
-
-          // Apply results
-          state.tree = p.result.tree;
-          state.files = p.result.files;
-
-          // expand root by default
-          state.expanded.add(state.tree.path);
-
-          renderTree();
-          renderResults();
-          renderInfo(null);
-          setActionButtons();
-        }
-
-        if (p.status === "error") {
-          clearInterval(state.polling);
-          state.polling = null;
-
-          setProgressIndeterminate(false);
-          showProgress(false);
-          showError(p.error || "Scan failed");
-        }
+        handleScanUpdate(p);
       } catch (err) {
-        clearInterval(state.polling);
-        state.polling = null;
+        stopPolling();
         setProgressIndeterminate(false);
         showProgress(false);
-        showError(`Polling failed: ${err}`);
+        showError(`Polling failed: ${errText(err)}`);
       }
-    }, 200);
+    }, TAURI?.event?.listen ? 1000 : 200);
   } catch (e) {
     setProgressIndeterminate(false);
     showProgress(false);
-    showError(`Start scan failed: ${e}`);
+    showError(`Start scan failed: ${errText(e)}`);
   }
 }
 
-// ------------------ UI wiring ------------------
-btnPick?.addEventListener("click", async () => {
+function stopPolling() {
+  if (state.polling) clearInterval(state.polling);
+  state.polling = null;
+}
+
+const PHASE_LABELS = {
+  walking: "Walking folders",
+  parsing_headers: "Reading headers",
+  extracting_thumbnails: "Extracting thumbnails",
+  building_tree: "Building tree",
+};
+
+function phaseText(p) {
+  const found = `${p.found_blends.toLocaleString()} .blend files found`;
+  const ph = (p.phases || []).find((x) => x.name === p.phase);
+  if (!ph || ph.name === "walking") return found;
+  const total = ph.total != null ? ` / ${ph.total.toLocaleString()}` : "";
+  return `${PHASE_LABELS[ph.name] || ph.name}: ${ph.completed.toLocaleString()}${total} • ${found}`;
+}
+
+function setScanPaused(paused) {
+  state.scanPaused = paused;
+  if (btnPauseScan) btnPauseScan.textContent = paused ? "Resume" : "Pause";
+}
+
+// Workers stop between entries, so a share can be freed for a while
+btnPauseScan?.addEventListener("click", async () => {
+  if (state.scanId == null) return;
   try {
-    const folder = await invoke("pick_folder");
-    if (typeof folder === "string" && folder.length) startScan(folder);
+    await invoke(state.scanPaused ? "resume_scan" : "pause_scan", {
+      scanId: state.scanId
+    });
   } catch (e) {
-    showError(`Folder picker failed: ${e}`);
+    showError(`${state.scanPaused ? "Resuming" : "Pausing"} the scan failed: ${errText(e)}`);
   }
 });
 
-btnOpen?.addEventListener("click", openSelected);
-btnReveal?.addEventListener("click", revealSelected);
+function handleScanUpdate(p) {
+  if (!p || p.scan_id !== state.scanId) return;
 
-searchEl?.addEventListener("input", renderResults);
+  setCounts(
+    p.scanned_entries,
+    p.found_blends,
+    performance.now() - state.scanStartedAt
+  );
+  if (progressText) progressText.textContent = p.paused ? `Paused • ${phaseText(p)}` : phaseText(p);
+  setScanPaused(!!p.paused);
+
+  if (currentPathEl && p.current_path)
+    currentPathEl.textContent = p.current_path;
+
+  // Files stream in with the polls until the result replaces them: once
+  // found, then again with header data, which replaces the first entry. A
+  // batch from an overlapping poll that was already applied is dropped.
+  if (p.found?.length && p.status === "scanning" && p.found_next - p.found.length === state.foundNext) {
+    if (state.foundNext === 0) state.foundAt = new Map();
+    for (const f of p.found) {
+      const at = state.foundAt.get(f.path_id);
+      if (at != null) {
+        state.files[at] = f;
+      } else {
+        state.foundAt.set(f.path_id, state.files.length);
+        state.files.push(f);
+      }
+    }
+    state.foundNext = p.found_next;
+    renderResults();
+  }
 
-themeDark?.addEventListener("click", () => applyTheme("dark"));
-themeLight?.addEventListener("click", () => applyTheme("light"));
+  if (p.status === "done" && p.result) {
+    stopPolling();
+    state.scanId = null;
 
-// ------------------ Boot ------------------
-applyTheme(localStorage.getItem("theme") || "dark");
-renderTree();
-renderResults();
-renderInfo(null);
-setActionButtons();
-setCounts(null, null, null);
+    setProgressIndeterminate(false);
+    showProgress(false);
+
+    // Apply results
+    state.resultScanId = p.scan_id;
+    if (btnTriage) btnTriage.disabled = state.triageJob != null;
+    if (btnVerify) btnVerify.disabled = state.verifyJob != null;
+    if (btnProjects) btnProjects.disabled = false;
+    if (btnScripts) btnScripts.disabled = state.scriptJob != null;
+    setScriptFilter(null);
+    applyResult(p.result);
+    if (optWatch?.checked) setWatching(true);
+
+    // expand root by default
+    state.expanded.add(state.tree.path);
+
+    renderTree();
+    reloadExpanded().then(renderTree);
+    renderResults();
+    renderInfo(null);
+    setActionButtons();
+
+    // Folders the user always allows are rescanned without asking again
+    state.skipped
+      .filter((s) => s.reason === "permission_denied" && s.decision === "allow")
+      .forEach((s) => requestElevation(s.path, false));
+
+    if (state.pendingSelect) {
+      const target = state.pendingSelect;
+      state.pendingSelect = null;
+      if (state.files.some((f) => f.path_id === target)) selectPath(target);
+    }
+  }
+
+  if (p.status === "error") {
+    stopPolling();
+    state.scanId = null;
+
+    setProgressIndeterminate(false);
+    showProgress(false);
+    showError(p.error ? errText(p.error) : "Scan failed");
+  }
+}
+
+function applyResult(result) {
+  state.tree = result.tree;
+  state.children = new Map();
+  state.files = result.files;
+  state.skipped = result.skipped || [];
+  state.parseDepth = result.parse_depth || "deep";
+  state.enriched = new Set();
+}
+
+// ------------------ On-demand parsing ------------------
+// Results of shallow scans: the rows in view are read first
+let shownTimer = null;
+function requestShown() {
+  if (state.parseDepth === "deep" || state.resultScanId == null || state.collection != null ||
+    state.remote != null) return;
+  clearTimeout(shownTimer);
+  shownTimer = setTimeout(() => {
+    const box = resultsEl.getBoundingClientRect();
+    const byId = new Map(state.files.map((f) => [f.path_id, f]));
+    const paths = [];
+    for (const row of resultsEl.querySelectorAll(".nodeRow[data-path]")) {
+      const r = row.getBoundingClientRect();
+      if (r.bottom < box.top || r.top > box.bottom) continue;
+      const f = byId.get(row.dataset.path);
+      if (f && isBlend(f.format) && !f.cloud_only && !state.enriched.has(f.path_id)) paths.push(f.path_id);
+    }
+    if (!paths.length) return;
+    invoke("enrich_files", {
+      scanId: state.resultScanId,
+      paths,
+      visible: true
+    }).catch((e) => console.warn("enrich_files failed", e));
+  }, 150);
+}
+
+let enrichedTimer = null;
+function handleEnriched({ scan_id, file }) {
+  if (scan_id !== state.resultScanId) return;
+  state.enriched.add(file.path_id);
+  const i = state.files.findIndex((f) => f.path_id === file.path_id);
+  if (i < 0) return;
+  state.files[i] = file;
+  // The info pane reads the tree node, so the selected file is fetched anew
+  if (state.selectedPath === file.path_id) refreshResult();
+  clearTimeout(enrichedTimer);
+  enrichedTimer = setTimeout(renderResults, 250);
+}
+
+// ------------------ Watching ------------------
+async function setWatching(on) {
+  if (state.resultScanId == null) return;
+  try {
+    await invoke(on ? "watch_scan" : "unwatch_scan", {
+      scanId: state.resultScanId
+    });
+  } catch (e) {
+    showError(`Watching failed: ${errText(e)}`);
+  }
+}
+
+// Saves come in bursts; the result is re-read once they settle
+let watchedTimer = null;
+function handleWatched({ scan_id }) {
+  if (scan_id !== state.resultScanId) return;
+  clearTimeout(watchedTimer);
+  watchedTimer = setTimeout(refreshResult, 500);
+}
+
+// ------------------ Protected folders ------------------
+async function requestElevation(path, remember) {
+  if (state.resultScanId == null) return;
+  try {
+    const jobId = await invoke("request_elevated_access", {
+      scanId: state.resultScanId,
+      path,
+      remember
+    });
+    state.elevationJobs.set(jobId, path);
+    if (!state.selectedPath) renderInfo(null);
+  } catch (e) {
+    showError(`Elevated access failed: ${errText(e)}`);
+  }
+}
+
+// Re-reads the shown result (merged files, user data) keeping the selection
+async function refreshResult() {
+  if (state.resultScanId == null) return;
+  try {
+    const p = await invoke("poll_scan", {
+      scanId: state.resultScanId,
+      lazyTree: true
+    });
+    if (!p?.result) return;
+    applyResult(p.result);
+    await reloadExpanded();
+    renderTree();
+    if (state.collection != null) await loadCollectionFiles();
+    renderResults();
+    renderSelection();
+  } catch (e) {
+    showError(`Refreshing results failed: ${errText(e)}`);
+  }
+}
+
+async function handleJobDone(job) {
+  if (job && job.job_id === state.updateJob) return handleUpdateJobDone(job);
+  if (job && job.job_id === state.triageJob) return handleTriageJobDone(job);
+  if (job && job.job_id === state.verifyJob) return handleVerifyJobDone(job);
+  if (job && job.job_id === state.renameJob) return handleRenameJobDone(job);
+  if (job && state.toolJobs.has(job.job_id)) return handleToolJobDone(job);
+  if (job && job.job_id === state.scriptJob) return handleScriptJobDone(job);
+  if (job && job.job_id === state.catalogJob) return handleCatalogJobDone(job);
+  if (!job || !state.elevationJobs.has(job.job_id)) return;
+  state.elevationJobs.delete(job.job_id);
+  if (job.status === "error") {
+    showError(job.error ? errText(job.error) : "Elevated scan failed");
+    return;
+  }
+  // The job merged its files into the scan result
+  refreshResult();
+}
+
+infoContent?.addEventListener("click", async (e) => {
+  const btn = e.target.closest("[data-elevate]");
+  if (!btn) return;
+  const path = btn.dataset.path;
+  if (btn.dataset.elevate === "never") {
+    try {
+      await invoke("set_elevation_decision", {
+        path,
+        decision: "deny"
+      });
+      const s = state.skipped.find((x) => x.path === path);
+      if (s) s.decision = "deny";
+      renderInfo(null);
+    } catch (err) {
+      showError(`Saving decision failed: ${errText(err)}`);
+    }
+    return;
+  }
+  requestElevation(path, btn.dataset.elevate === "always");
+});
+
+// ------------------ Tags ------------------
+async function loadTagNames() {
+  try {
+    const tags = await invoke("list_tags");
+    let list = $("tagNames");
+    if (!list) {
+      list = document.createElement("datalist");
+      list.id = "tagNames";
+      document.body.appendChild(list);
+    }
+    list.innerHTML = tags.map((t) => `<option value="${escapeHtml(t.name)}">`).join("");
+  } catch (e) {
+    console.warn("list_tags failed", e);
+  }
+}
+
+infoContent?.addEventListener("keydown", async (e) => {
+  const input = e.target.closest("[data-tag-path]");
+  if (!input || e.key !== "Enter" || !input.value.trim()) return;
+  try {
+    await invoke("tag_files", {
+      paths: [input.dataset.tagPath],
+      name: input.value.trim()
+    });
+    await refreshResult();
+    loadTagNames();
+  } catch (err) {
+    showError(`Tagging failed: ${errText(err)}`);
+  }
+});
+
+infoContent?.addEventListener("click", async (e) => {
+  const btn = e.target.closest("[data-untag]");
+  if (!btn || !state.selectedPath) return;
+  try {
+    const tags = await invoke("list_tags");
+    const tag = tags.find((t) => t.name === btn.dataset.untag);
+    if (!tag) return;
+    await invoke("untag_files", {
+      paths: [state.selectedPath],
+      tagId: tag.id
+    });
+    refreshResult();
+  } catch (err) {
+    showError(`Removing tag failed: ${errText(err)}`);
+  }
+});
+
+// ------------------ Collections ------------------
+async function loadCollections() {
+  try {
+    state.collections = await invoke("list_collections");
+  } catch (e) {
+    console.warn("list_collections failed", e);
+    return;
+  }
+  let list = $("collectionNames");
+  if (!list) {
+    list = document.createElement("datalist");
+    list.id = "collectionNames";
+    document.body.appendChild(list);
+  }
+  // Smart collections can't take files by hand
+  list.innerHTML = state.collections
+    .filter((c) => c.query == null)
+    .map((c) => `<option value="${escapeHtml(c.name)}">`)
+    .join("");
+  if (state.collection != null && !state.collections.some((c) => c.id === state.collection)) {
+    state.collection = null;
+    state.collectionFiles = [];
+  }
+  if (collectionSel) {
+    collectionSel.innerHTML = `<option value="">All files</option>` +
+      state.collections
+      .map((c) => `<option value="${c.id}">${escapeHtml(c.name)} ${c.query != null ? "⚡" : `(${c.file_count})`}</option>`)
+      .join("") +
+      state.catalogs
+      .map((c) => `<option value="remote:${escapeHtml(c.name)}">🌐 ${escapeHtml(c.name)}</option>`)
+      .join("");
+    collectionSel.value = state.remote != null ? `remote:${state.remote}` : state.collection ?? "";
+  }
+  if (btnCollectionEdit) btnCollectionEdit.style.display = state.collection != null ? "" : "none";
+}
+
+// Members not found in any scan are listed by path only
+async function loadCollectionFiles() {
+  try {
+    const entries = await invoke("get_collection_files", {
+      collectionId: state.collection
+    });
+    state.collectionFiles = entries.map((e) => e.file || {
+      name: e.name,
+      path: e.path,
+      path_id: e.path_id,
+      missing: !e.exists,
+      collections: [],
+    });
+  } catch (e) {
+    showError(`Loading collection failed: ${errText(e)}`);
+  }
+}
+
+async function collectionChanged() {
+  await loadCollections();
+  await refreshResult();
+  if (state.resultScanId == null && state.collection != null) {
+    await loadCollectionFiles();
+    renderResults();
+  }
+}
+
+collectionSel?.addEventListener("change", async () => {
+  const remote = collectionSel.value.startsWith("remote:") ? collectionSel.value.slice(7) : null;
+  state.remote = null;
+  state.remoteFiles = [];
+  state.catalogThumbs.clear();
+  if (remote != null) {
+    state.collection = null;
+    if (btnCollectionEdit) btnCollectionEdit.style.display = "none";
+    attachCatalog(remote);
+    return;
+  }
+  state.collection = collectionSel.value ? Number(collectionSel.value) : null;
+  state.collectionFiles = [];
+  if (state.collection != null) await loadCollectionFiles();
+  if (btnCollectionEdit) btnCollectionEdit.style.display = state.collection != null ? "" : "none";
+  renderResults();
+});
+
+infoContent?.addEventListener("keydown", async (e) => {
+  const input = e.target.closest("[data-collect-path]");
+  if (!input || e.key !== "Enter" || !input.value.trim()) return;
+  const name = input.value.trim();
+  try {
+    let c = state.collections.find((x) => x.name.toLowerCase() === name.toLowerCase());
+    if (!c) c = await invoke("create_collection", {
+      name
+    });
+    await invoke("add_to_collection", {
+      collectionId: c.id,
+      paths: [input.dataset.collectPath]
+    });
+    await collectionChanged();
+  } catch (err) {
+    showError(`Adding to collection failed: ${errText(err)}`);
+  }
+});
+
+infoContent?.addEventListener("click", async (e) => {
+  const btn = e.target.closest("[data-uncollect]");
+  if (!btn || !state.selectedPath) return;
+  const c = state.collections.find((x) => x.name === btn.dataset.uncollect);
+  if (!c) return;
+  try {
+    await invoke("remove_from_collection", {
+      collectionId: c.id,
+      paths: [state.selectedPath]
+    });
+    await collectionChanged();
+  } catch (err) {
+    showError(`Removing from collection failed: ${errText(err)}`);
+  }
+});
+
+function openCollectionDialog(name, query, creating) {
+  state.creatingSmart = creating;
+  collectionName.value = name;
+  collectionQuery.value = query ?? "";
+  collectionQueryWrap.style.display = query != null ? "" : "none";
+  $("collectionError").textContent = "";
+  $("btnCollectionDelete").style.display = creating ? "none" : "";
+  collectionDialog.showModal();
+}
+
+btnCollectionEdit?.addEventListener("click", () => {
+  const c = state.collections.find((x) => x.id === state.collection);
+  if (c) openCollectionDialog(c.name, c.query, false);
+});
+
+btnSaveSearch?.addEventListener("click", () =>
+  openCollectionDialog("", (searchEl?.value || "").trim(), true)
+);
+
+$("btnCollectionSave")?.addEventListener("click", async () => {
+  try {
+    if (state.creatingSmart) {
+      const c = await invoke("create_collection", {
+        name: collectionName.value,
+        query: collectionQuery.value
+      });
+      state.collection = c.id;
+    } else {
+      const c = state.collections.find((x) => x.id === state.collection);
+      await invoke("rename_collection", {
+        collectionId: state.collection,
+        name: collectionName.value
+      });
+      if (c?.query != null && c.query !== collectionQuery.value) {
+        await invoke("set_collection_query", {
+          collectionId: state.collection,
+          query: collectionQuery.value
+        });
+      }
+    }
+    collectionDialog.close();
+    await collectionChanged();
+  } catch (err) {
+    // Stays open so a bad query can be fixed
+    $("collectionError").textContent = errText(err);
+  }
+});
+
+// The files themselves are untouched
+$("btnCollectionDelete")?.addEventListener("click", async () => {
+  try {
+    await invoke("delete_collection", {
+      collectionId: state.collection
+    });
+    collectionDialog.close();
+    await collectionChanged();
+  } catch (err) {
+    collectionDialog.close();
+    showError(`Deleting collection failed: ${errText(err)}`);
+  }
+});
+$("btnCollectionCancel")?.addEventListener("click", () => collectionDialog.close());
+
+// ------------------ Quick open ------------------
+// Ctrl/Cmd+P: file names from every folder scanned so far; picking one
+// selects it, scanning its folder first when it isn't in the open result
+const quick = {
+  hits: [],
+  active: 0,
+  seq: 0
+};
+
+function renderQuickList() {
+  quickList.innerHTML = "";
+  quick.hits.forEach((h, i) => {
+    quickList.appendChild(
+      makeRow({
+        icon: /\.blend\d*$/i.test(h.name) ? "🧊" : "📦",
+        label: h.name,
+        meta: h.folder,
+        active: i === quick.active,
+        onClick: () => jumpTo(h),
+      })
+    );
+  });
+  quickList.children[quick.active]?.scrollIntoView({
+    block: "nearest"
+  });
+}
+
+async function runQuickFind() {
+  const seq = ++quick.seq;
+  let hits = [];
+  try {
+    hits = await invoke("quick_find", {
+      query: quickInput.value,
+      limit: 20
+    });
+  } catch (e) {
+    console.warn("quick_find failed", e);
+  }
+  // A slower, older query must not replace a newer one
+  if (seq !== quick.seq) return;
+  quick.hits = hits;
+  quick.active = 0;
+  renderQuickList();
+}
+
+function jumpTo(hit) {
+  noteSearchPick(hit.path_id, quickInput.value.trim().toLowerCase());
+  quickDialog.close();
+  handleAppRequest({
+    action: "open",
+    path: hit.path_id
+  });
+}
+
+function openQuickFind() {
+  if (!quickDialog) return;
+  quickInput.value = "";
+  quick.hits = [];
+  renderQuickList();
+  if (!quickDialog.open) quickDialog.showModal();
+  quickInput.focus();
+  invoke("get_quick_find_shortcut")
+    .then((s) => (quickShortcut.value = s || ""))
+    .catch((e) => console.warn("get_quick_find_shortcut failed", e));
+}
+
+// The global shortcut (see hotkey.rs) arrives as an app request
+document.addEventListener("keydown", (e) => {
+  if (!(e.ctrlKey || e.metaKey) || e.key.toLowerCase() !== "p") return;
+  e.preventDefault();
+  openQuickFind();
+});
+
+quickShortcut?.addEventListener("keydown", async (e) => {
+  if (e.key !== "Enter") return;
+  e.preventDefault();
+  try {
+    const saved = await invoke("set_quick_find_shortcut", {
+      shortcut: quickShortcut.value.trim() || null
+    });
+    quickShortcut.value = saved || "";
+    quickInput.focus();
+  } catch (err) {
+    quickDialog.close();
+    showError(`Changing the shortcut failed: ${errText(err)}`);
+  }
+});
+
+quickInput?.addEventListener("input", runQuickFind);
+quickInput?.addEventListener("keydown", (e) => {
+  if (e.key === "ArrowDown" || e.key === "ArrowUp") {
+    e.preventDefault();
+    const n = quick.hits.length;
+    if (!n) return;
+    quick.active = (quick.active + (e.key === "ArrowDown" ? 1 : n - 1)) % n;
+    renderQuickList();
+  } else if (e.key === "Enter" && quick.hits[quick.active]) {
+    e.preventDefault();
+    jumpTo(quick.hits[quick.active]);
+  }
+});
+
+// ------------------ Notes ------------------
+infoContent?.addEventListener("change", async (e) => {
+  const input = e.target.closest("[data-note-path]");
+  if (!input) return;
+  try {
+    await invoke("set_note", {
+      path: input.dataset.notePath,
+      note: input.value
+    });
+    refreshResult();
+  } catch (err) {
+    showError(`Saving note failed: ${errText(err)}`);
+  }
+});
+
+function labelRank(name) {
+  const i = state.palette.findIndex((l) => l.name === name);
+  return i < 0 ? state.palette.length : i;
+}
+
+infoContent?.addEventListener("click", (e) => {
+  const btn = e.target.closest("[data-label]");
+  if (btn && state.selectedPath) setLabel(state.selectedPath, btn.dataset.label || null);
+});
+
+infoContent?.addEventListener("click", async (e) => {
+  const btn = e.target.closest("[data-rate]");
+  if (!btn || !state.selectedPath) return;
+  try {
+    await invoke("set_rating", {
+      path: state.selectedPath,
+      rating: Number(btn.dataset.rate)
+    });
+    refreshResult();
+  } catch (err) {
+    showError(`Rating failed: ${errText(err)}`);
+  }
+});
+
+// ------------------ Trash ------------------
+infoContent?.addEventListener("click", async (e) => {
+  const btn = e.target.closest("[data-restore]");
+  if (!btn) return;
+  try {
+    const restored = await invoke("restore_from_trash", {
+      path: btn.dataset.restore
+    });
+    // Rescan so the file shows up at its original location
+    if (state.lastFolder) {
+      state.pendingSelect = restored;
+      startScan(state.lastFolder, {
+        indexed: true
+      });
+    }
+  } catch (err) {
+    showError(`Restore failed: ${errText(err)}`);
+  }
+});
+
+// Locks or unlocks the multi-selection, or the selected file
+infoContent?.addEventListener("click", async (e) => {
+  const btn = e.target.closest("[data-lock]");
+  if (!btn) return;
+  const paths = state.multi.size > 1 ? [...state.multi] : [state.selectedPath];
+  try {
+    const results = await invoke("set_read_only", {
+      paths,
+      readOnly: btn.dataset.lock === "1"
+    });
+    const failed = results.filter((r) => !r.ok);
+    if (failed.length) {
+      showError(`${failed.length} of ${results.length} files failed: ${errText(failed[0].error)}`);
+    }
+    await refreshResult();
+  } catch (err) {
+    showError(`Changing read-only failed: ${errText(err)}`);
+  }
+});
+
+function listenScanEvents() {
+  const listen = TAURI?.event?.listen;
+  if (!listen) return;
+  listen("scan://progress", (e) => handleScanUpdate(e.payload));
+  listen("scan://done", (e) => handleScanUpdate(e.payload));
+  listen("scan://file-enriched", (e) => handleEnriched(e.payload));
+  for (const kind of ["added", "removed", "modified"])
+    listen(`scan://file-${kind}`, (e) => handleWatched(e.payload));
+  listen("app://request", (e) => handleAppRequest(e.payload));
+  listen("job://done", (e) => handleJobDone(e.payload));
+  listen("update://available", (e) => {
+    state.update = e.payload;
+    setUpdateButton();
+  });
+}
+
+// ------------------ External requests (blendfinder:// links) ------------------
+function parentFolder(path) {
+  const i = Math.max(path.lastIndexOf("/"), path.lastIndexOf("\\"));
+  return i > 0 ? path.slice(0, i) : path;
+}
+
+async function showScan(scanId) {
+  try {
+    const p = await invoke("poll_scan", {
+      scanId,
+      lazyTree: true
+    });
+    state.scanId = scanId;
+    if (p.result) folderPill && (folderPill.textContent = p.result.roots.join(" + "));
+    handleScanUpdate(p);
+  } catch (e) {
+    showError(`Could not open scan: ${errText(e)}`);
+  }
+}
+
+function handleAppRequest(req) {
+  if (!req) return;
+  if (req.action === "scan") {
+    startScan(req.root);
+  } else if (req.action === "scan_all") {
+    startScan(req.roots);
+  } else if (req.action === "search") {
+    if (searchEl) searchEl.value = req.q;
+    renderResults();
+  } else if (req.action === "show_scan") {
+    showScan(req.scan_id);
+  } else if (req.action === "quick_find") {
+    openQuickFind();
+  } else if (req.action === "open") {
+    if (state.files.some((f) => f.path_id === req.path)) {
+      selectPath(req.path);
+    } else {
+      startScan(parentFolder(req.path));
+      state.pendingSelect = req.path;
+    }
+  }
+}
+
+// ------------------ UI wiring ------------------
+btnPick?.addEventListener("click", async () => {
+  try {
+    const folder = await invoke("pick_folder");
+    if (typeof folder === "string" && folder.length) startScan(folder);
+  } catch (e) {
+    showError(`Folder picker failed: ${errText(e)}`);
+  }
+});
+
+// Scans the picked folder together with the ones shown, as one result
+btnAddFolder?.addEventListener("click", async () => {
+  try {
+    const folder = await invoke("pick_folder");
+    if (typeof folder !== "string" || !folder.length) return;
+    const roots = state.lastFolder == null ? [] : [state.lastFolder].flat();
+    startScan(roots.includes(folder) ? roots : [...roots, folder]);
+  } catch (e) {
+    showError(`Folder picker failed: ${errText(e)}`);
+  }
+});
+
+btnNewWindow?.addEventListener("click", async () => {
+  try {
+    await invoke("open_window", {
+      folderPath: null
+    });
+  } catch (e) {
+    showError(`New window failed: ${errText(e)}`);
+  }
+});
+
+btnCopyLogs?.addEventListener("click", async () => {
+  try {
+    const logs = await invoke("get_recent_logs", {
+      level: null,
+      limit: 500
+    });
+    const text = logs
+      .map((l) => `${l.timestamp} ${l.level.padEnd(5)} ${l.target}: ${l.message}`)
+      .join("\n");
+    await navigator.clipboard.writeText(text);
+    btnCopyLogs.textContent = "Copied";
+    setTimeout(() => (btnCopyLogs.textContent = "Copy Logs"), 1500);
+  } catch (e) {
+    showError(`Copy logs failed: ${errText(e)}`);
+  }
+});
+
+// ------------------ Projects ------------------
+// Files group under the nearest marked folder, or one holding a
+// .blender_project, .git or .svn; rows pick the newest file of a project
+async function loadProjectRoots() {
+  try {
+    state.projectRoots = await invoke("get_project_roots");
+  } catch (e) {
+    console.warn("get_project_roots failed", e);
+  }
+}
+
+async function toggleProjectRoot(pathId) {
+  const roots = state.projectRoots.includes(pathId) ?
+    state.projectRoots.filter((r) => r !== pathId) :
+    [...state.projectRoots, pathId];
+  try {
+    state.projectRoots = await invoke("set_project_roots", {
+      roots
+    });
+  } catch (e) {
+    showError(`Marking project failed: ${errText(e)}`);
+  }
+}
+
+function projectMeta(p) {
+  const versions = Object.entries(p.versions)
+    .map(([v, n]) => `${v} ×${n}`)
+    .join(", ");
+  const missing = p.missing_links.length + p.missing_images.length;
+  return [
+    `${p.file_count.toLocaleString()} files`,
+    bytesToHuman(p.total_bytes),
+    versions,
+    missing ? `⚠ ${missing} missing (${p.missing_links.length} links, ${p.missing_images.length} images)` : "nothing missing",
+    `last saved ${p.last_modified || "—"}`,
+  ].join(" · ");
+}
+
+btnProjects?.addEventListener("click", async () => {
+  if (state.resultScanId == null) return;
+  try {
+    const projects = await invoke("get_projects", {
+      scanId: state.resultScanId
+    });
+    const missing = projects.filter((p) => p.missing_links.length + p.missing_images.length > 0).length;
+    showReport(
+      "Projects",
+      projects.length ?
+      `${projects.length} project${projects.length === 1 ? "" : "s"}, ${missing} with missing files. Right-click a folder to mark it as a project.` :
+      "No projects found. Right-click a folder to mark it as a project.",
+      projects.map((p) => ({
+        icon: p.assigned ? "📌" : "📁",
+        label: `${p.name} — ${p.root}`,
+        meta: projectMeta(p),
+        path_id: p.last_file,
+      }))
+    );
+  } catch (e) {
+    showError(`Loading projects failed: ${errText(e)}`);
+  }
+});
+
+// ------------------ Shared catalogs ------------------
+async function loadCatalogs() {
+  try {
+    state.catalogs = await invoke("get_catalogs");
+  } catch (e) {
+    console.warn("get_catalogs failed", e);
+  }
+}
+
+// The whole file list comes over once; search then filters it here
+async function attachCatalog(name) {
+  try {
+    state.catalogJob = await invoke("attach_catalog", {
+      name
+    });
+    resultsCount.textContent = "Loading…";
+    resultsEl.innerHTML = `<div class="hint">Loading catalog "${escapeHtml(name)}"…</div>`;
+  } catch (e) {
+    showError(`Attaching catalog failed: ${errText(e)}`);
+    collectionSel.value = "";
+    renderResults();
+  }
+}
+
+function handleCatalogJobDone(job) {
+  state.catalogJob = null;
+  if (job.status !== "done" || !job.result) {
+    if (job.status === "error")
+      showError(`Loading catalog failed: ${job.error ? errText(job.error) : "unknown error"}`);
+    collectionSel.value = "";
+    renderResults();
+    return;
+  }
+  const snap = job.result;
+  // Another source was picked while it loaded
+  if (collectionSel.value !== `remote:${snap.name}`) return;
+  state.remote = snap.name;
+  state.remoteFiles = snap.files;
+  renderResults();
+  showError(`Attached "${snap.name}" from ${snap.info.host}: ${snap.files.length.toLocaleString()} files in ${snap.info.roots.join(", ") || "no folders"}`);
+}
+
+function catalogStatusText(s) {
+  if (!s.running) return "Not published.";
+  if (!s.shared) return `The HTTP API runs on port ${s.port}, for this computer only.`;
+  return `Published on port ${s.port}. Others attach with http://<this computer's name or IP>:${s.port}`;
+}
+
+btnCatalogs?.addEventListener("click", async () => {
+  await loadCatalogs();
+  catalogsText.value = JSON.stringify(state.catalogs, null, 2);
+  $("catalogPort").value = localStorage.getItem("catalogPort") || "";
+  $("catalogKey").value = localStorage.getItem("catalogKey") || "";
+  try {
+    $("catalogStatus").textContent = catalogStatusText(await invoke("http_api_status"));
+  } catch (e) {
+    $("catalogStatus").textContent = errText(e);
+  }
+  catalogsDialog.showModal();
+});
+
+$("btnCatalogPublish")?.addEventListener("click", async () => {
+  const port = Number($("catalogPort").value) || null;
+  const key = $("catalogKey").value.trim() || null;
+  localStorage.setItem("catalogPort", port ?? "");
+  localStorage.setItem("catalogKey", key ?? "");
+  try {
+    $("catalogStatus").textContent = catalogStatusText(await invoke("publish_catalog", {
+      port,
+      key
+    }));
+  } catch (e) {
+    $("catalogStatus").textContent = `Publishing failed: ${errText(e)}`;
+  }
+});
+
+$("btnCatalogStop")?.addEventListener("click", async () => {
+  try {
+    $("catalogStatus").textContent = catalogStatusText(await invoke("stop_http_api"));
+  } catch (e) {
+    $("catalogStatus").textContent = errText(e);
+  }
+});
+
+$("btnCatalogsSave")?.addEventListener("click", async () => {
+  let catalogs;
+  try {
+    catalogs = JSON.parse(catalogsText.value.trim() || "[]");
+  } catch (e) {
+    window.alert(`Catalogs are not valid JSON: ${e.message}`);
+    return;
+  }
+  try {
+    state.catalogs = await invoke("set_catalogs", {
+      catalogs
+    });
+    catalogsDialog.close();
+    loadCollections();
+  } catch (e) {
+    window.alert(errText(e));
+  }
+});
+$("btnCatalogsCancel")?.addEventListener("click", () => catalogsDialog.close());
+
+// ------------------ Usage stats ------------------
+// Recorded in index.db only (see usage.rs); clicking the same hit again for
+// the same query counts once
+function noteSearchPick(pathId, query) {
+  const key = `${query}\n${pathId}`;
+  if (state.searchPicked === key) return;
+  state.searchPicked = key;
+  invoke("record_search_pick", {
+    path: pathId
+  }).catch((e) => console.warn("record_search_pick failed", e));
+}
+
+function usageRows(title, entries, meta) {
+  if (!entries.length) return [];
+  return [{
+      icon: "📊",
+      label: title,
+      meta: ""
+    },
+    ...entries.map((u) => ({
+      icon: "🧊",
+      label: u.name,
+      meta: meta(u),
+      path_id: u.path_id,
+    })),
+  ];
+}
+
+btnStats?.addEventListener("click", async () => {
+  try {
+    const s = await invoke("get_usage_stats", {
+      scanId: state.resultScanId,
+      limit: 15
+    });
+    const times = (n) => `${n} time${n === 1 ? "" : "s"}`;
+    const last = (u) => `${times(u.count)} • last ${u.last_at.slice(0, 10)}`;
+    const summary = [
+      `${s.opens.toLocaleString()} opens, ${s.searches.toLocaleString()} search picks, ${s.tags.toLocaleString()} taggings`,
+      s.since ? `since ${s.since.slice(0, 10)}` : "nothing recorded yet",
+      state.resultScanId != null ?
+      `${s.never_touched_count.toLocaleString()} files in these results never touched` :
+      "scan a folder to list files never touched",
+    ].join(" · ");
+    showReport("Usage stats", summary, [
+      ...usageRows("Most opened", s.most_opened, last),
+      ...usageRows("Most picked from searches", s.most_searched, last),
+      ...usageRows("Most tagged", s.most_tagged, last),
+      ...usageRows("Never touched (oldest first)", s.never_touched, (u) => `saved ${u.last_at.slice(0, 10) || "—"}`),
+    ]);
+  } catch (e) {
+    showError(`Loading usage stats failed: ${errText(e)}`);
+  }
+});
+
+optUsage?.addEventListener("change", async () => {
+  try {
+    await invoke("set_usage_tracking", {
+      enabled: optUsage.checked
+    });
+  } catch (e) {
+    optUsage.checked = !optUsage.checked;
+    showError(`Changing usage stats failed: ${errText(e)}`);
+    return;
+  }
+  if (optUsage.checked || !window.confirm("Also forget the usage recorded so far?")) return;
+  try {
+    await invoke("clear_usage_stats");
+  } catch (e) {
+    showError(`Clearing usage stats failed: ${errText(e)}`);
+  }
+});
+
+// ------------------ Damaged files ------------------
+const DAMAGE_TEXT = {
+  empty: "Empty",
+  bad_magic: "Bad header",
+  decompression: "Decompression error",
+  truncated: "Truncated",
+  missing_endb: "Missing end marker",
+  bad_block: "Corrupt block",
+};
+
+btnTriage?.addEventListener("click", async () => {
+  if (state.resultScanId == null) return;
+  try {
+    state.triageJob = await invoke("start_triage_job", {
+      scanId: state.resultScanId
+    });
+    btnTriage.disabled = true;
+    btnTriage.textContent = "Checking…";
+  } catch (e) {
+    showError(`Checking files failed: ${errText(e)}`);
+  }
+});
+
+function handleTriageJobDone(job) {
+  state.triageJob = null;
+  btnTriage.disabled = state.resultScanId == null;
+  btnTriage.textContent = "Check Files";
+  if (job.status === "error") {
+    showError(`Checking files failed: ${job.error ? errText(job.error) : "unknown error"}`);
+    return;
+  }
+  const report = job.result;
+  if (!report) return;
+  const skipped = report.errors.length ? `, ${report.errors.length} unreadable` : "";
+  showReport(
+    "Possibly corrupted files",
+    `${report.entries.length} of ${report.checked} files look damaged${skipped}.`,
+    report.entries.map((e) => {
+      const fate = e.recoverable ? "may still open" : e.backup ? "restore the .blend1" : "not recoverable";
+      return {
+        icon: e.recoverable ? "⚠️" : "❌",
+        label: e.path,
+        meta: `${DAMAGE_TEXT[e.damage] || e.damage} · ${e.detail} · ${fate}`,
+        path_id: e.path_id,
+      };
+    })
+  );
+}
+
+// Report of a file check: rows with a path_id pick their file in the results
+function showReport(title, summary, rows) {
+  $("reportTitle").textContent = title;
+  $("reportSummary").textContent = summary;
+  const list = $("reportList");
+  list.innerHTML = "";
+  for (const r of rows) {
+    list.appendChild(
+      makeRow({
+        icon: r.icon,
+        label: r.label,
+        meta: r.meta,
+        onClick: r.path_id ? () => {
+          reportDialog.close();
+          handleAppRequest({
+            action: "open",
+            path: r.path_id
+          });
+        } : null,
+      })
+    );
+  }
+  reportDialog.showModal();
+}
+
+$("btnReportClose")?.addEventListener("click", () => reportDialog.close());
+
+// ------------------ Checksums ------------------
+// The first run stores checksums; later runs report files whose bytes
+// changed while their size and mtime did not
+btnVerify?.addEventListener("click", async () => {
+  if (state.resultScanId == null) return;
+  try {
+    state.verifyJob = await invoke("verify_integrity", {
+      scope: {
+        kind: "scan",
+        scan_id: state.resultScanId
+      }
+    });
+    btnVerify.disabled = true;
+    btnVerify.textContent = "Verifying…";
+  } catch (e) {
+    showError(`Verifying failed: ${errText(e)}`);
+  }
+});
+
+function handleVerifyJobDone(job) {
+  state.verifyJob = null;
+  btnVerify.disabled = state.resultScanId == null;
+  btnVerify.textContent = "Verify";
+  if (job.status === "error") {
+    showError(`Verifying failed: ${job.error ? errText(job.error) : "unknown error"}`);
+    return;
+  }
+  const report = job.result;
+  if (!report) return;
+  const extra = [
+    report.recorded && `${report.recorded} checksummed for the first time or since saving`,
+    report.skipped.length && `${report.skipped.length} online-only skipped`,
+    report.errors.length && `${report.errors.length} unreadable`,
+  ].filter(Boolean);
+  showReport(
+    "Checksum verification",
+    `${report.verified} of ${report.checked} files match, ${report.mismatches.length} changed on disk` +
+      (extra.length ? ` (${extra.join(", ")})` : "") + ".",
+    report.mismatches.map((m) => ({
+      icon: "❌",
+      label: m.path,
+      meta: `Content changed since ${m.hashed_at} without a save`,
+      path_id: m.path_id,
+    }))
+  );
+}
+
+// ------------------ Batch rename ------------------
+// Selected files in the order listed, so the counter follows the list
+function renamePaths() {
+  const picked = state.multi.size > 1 ? state.multi : new Set([state.selectedPath]);
+  const listed = state.shown.map((f) => f.path_id).filter((p) => picked.has(p));
+  return listed.length === picked.size ? listed : [...picked];
+}
+
+function renamePattern() {
+  return {
+    template: $("renameTemplate").value || "{name}",
+    find: $("renameFind").value || null,
+    replace: $("renameReplace").value,
+    start: Number($("renameStart").value) || 0,
+    step: 1,
+    padding: Number($("renamePadding").value) || 0
+  };
+}
+
+let renameTimer = null;
+async function previewRename() {
+  const paths = renamePaths();
+  const runBtn = $("btnRenameRun");
+  try {
+    const items = await invoke("preview_rename", {
+      paths,
+      pattern: renamePattern()
+    });
+    const ready = items.filter((i) => !i.problem && !i.unchanged).length;
+    const blocked = items.filter((i) => i.problem).length;
+    $("renameSummary").textContent = `${ready} of ${items.length} files will be renamed` +
+      (blocked ? `; ${blocked} can't be` : "") + ".";
+    const list = $("renameList");
+    list.innerHTML = "";
+    for (const i of items) {
+      list.appendChild(
+        makeRow({
+          icon: i.problem ? "⚠" : i.unchanged ? "=" : "→",
+          label: i.name,
+          meta: i.problem || i.from.split(/[\\/]/).pop(),
+        })
+      );
+    }
+    runBtn.disabled = ready === 0;
+  } catch (e) {
+    $("renameSummary").textContent = errText(e);
+    $("renameList").innerHTML = "";
+    runBtn.disabled = true;
+  }
+}
+
+infoContent?.addEventListener("click", (e) => {
+  if (!e.target.closest("[data-rename]") || state.renameJob != null) return;
+  $("renameTemplate").value = "{name}";
+  $("renameFind").value = "";
+  $("renameReplace").value = "";
+  renameDialog.showModal();
+  previewRename();
+});
+
+for (const id of ["renameTemplate", "renameFind", "renameReplace", "renameStart", "renamePadding"]) {
+  $(id)?.addEventListener("input", () => {
+    clearTimeout(renameTimer);
+    renameTimer = setTimeout(previewRename, 200);
+  });
+}
+
+$("btnRenameCancel")?.addEventListener("click", () => renameDialog.close());
+$("btnRenameRun")?.addEventListener("click", async () => {
+  try {
+    state.renameJob = await invoke("start_rename_job", {
+      paths: renamePaths(),
+      pattern: renamePattern()
+    });
+    renameDialog.close();
+  } catch (e) {
+    $("renameSummary").textContent = errText(e);
+  }
+});
+
+async function handleRenameJobDone(job) {
+  state.renameJob = null;
+  if (job.status === "error") {
+    showError(`Renaming failed: ${job.error ? errText(job.error) : "unknown error"}`);
+    return;
+  }
+  const report = job.result;
+  if (!report) return;
+  // The selection follows renamed files
+  const moved = new Map(report.renamed.map((r) => [r.from_id, r.to_id]));
+  state.multi = new Set([...state.multi].map((p) => moved.get(p) || p));
+  if (moved.has(state.selectedPath)) state.selectedPath = moved.get(state.selectedPath);
+  await refreshResult();
+  const blocked = report.skipped.filter((i) => i.problem);
+  if (!blocked.length && !report.errors.length) return;
+  showReport(
+    "Batch rename",
+    `${report.renamed.length} renamed, ${blocked.length} skipped, ${report.errors.length} failed.`,
+    [
+      ...blocked.map((i) => ({
+        icon: "⚠",
+        label: i.from,
+        meta: i.problem,
+        path_id: i.from_id,
+      })),
+      ...report.errors.map((e) => ({
+        icon: "❌",
+        label: e.path || "",
+        meta: errText(e),
+        path_id: e.path,
+      })),
+    ]
+  );
+}
+
+// ------------------ Updates ------------------
+function setUpdateButton() {
+  if (!btnUpdate) return;
+  btnUpdate.disabled = state.updateJob != null;
+  if (state.update) {
+    btnUpdate.textContent = state.updateJob != null ? "Updating…" : `Update to ${state.update.version}`;
+    btnUpdate.classList.add("primary");
+    btnUpdate.title = state.update.notes || "Install the new version and restart";
+  } else {
+    btnUpdate.textContent = state.updateJob != null ? "Checking…" : "Check for Updates";
+  }
+}
+
+// Checks when nothing is known yet, installs (and restarts) otherwise
+btnUpdate?.addEventListener("click", async () => {
+  try {
+    state.updateJob = await invoke(state.update ? "install_update" : "check_for_updates");
+  } catch (e) {
+    showError(`Update failed: ${errText(e)}`);
+  }
+  setUpdateButton();
+});
+
+function handleUpdateJobDone(job) {
+  state.updateJob = null;
+  if (job.status === "error") {
+    showError(`Update failed: ${job.error ? errText(job.error) : "unknown error"}`);
+  } else if (job.kind === "update_check" && job.result) {
+    state.update = job.result;
+  } else if (job.kind === "update_check") {
+    btnUpdate.textContent = "Up to Date";
+    setTimeout(setUpdateButton, 1500);
+    return;
+  }
+  setUpdateButton();
+}
+
+optUpdates?.addEventListener("change", async () => {
+  try {
+    await invoke("set_update_checks", {
+      enabled: optUpdates.checked
+    });
+  } catch (e) {
+    optUpdates.checked = !optUpdates.checked;
+    showError(`Changing update checks failed: ${errText(e)}`);
+  }
+});
+
+optCloseToTray?.addEventListener("change", async () => {
+  try {
+    await invoke("set_close_to_tray", {
+      enabled: optCloseToTray.checked
+    });
+  } catch (e) {
+    optCloseToTray.checked = !optCloseToTray.checked;
+    showError(`Changing close to tray failed: ${errText(e)}`);
+  }
+});
+
+// ------------------ Exclusions ------------------
+btnExclusions?.addEventListener("click", async () => {
+  try {
+    const list = await invoke("get_exclusions");
+    exclusionsText.value = list.join("\n");
+    exclusionsDialog.showModal();
+  } catch (e) {
+    showError(`Loading exclusions failed: ${errText(e)}`);
+  }
+});
+
+async function saveExclusions(exclusions) {
+  try {
+    const list = await invoke("set_exclusions", {
+      exclusions
+    });
+    exclusionsText.value = list.join("\n");
+    exclusionsDialog.close();
+  } catch (e) {
+    exclusionsDialog.close();
+    showError(`Saving exclusions failed: ${errText(e)}`);
+  }
+}
+
+$("btnExclusionsSave")?.addEventListener("click", () =>
+  saveExclusions(exclusionsText.value.split("\n").map((l) => l.trim()).filter(Boolean))
+);
+$("btnExclusionsReset")?.addEventListener("click", () => saveExclusions(null));
+$("btnExclusionsCancel")?.addEventListener("click", () => exclusionsDialog.close());
+
+// ------------------ Scan patterns ------------------
+function scanPatterns(key) {
+  return (localStorage.getItem(key) || "").split("\n").filter(Boolean);
+}
+
+$("btnPatterns")?.addEventListener("click", () => {
+  patternsInclude.value = scanPatterns("scanInclude").join("\n");
+  patternsExclude.value = scanPatterns("scanExclude").join("\n");
+  patternsDialog.showModal();
+});
+$("btnPatternsSave")?.addEventListener("click", () => {
+  for (const [key, text] of [["scanInclude", patternsInclude], ["scanExclude", patternsExclude]]) {
+    localStorage.setItem(key, text.value.split("\n").map((l) => l.trim()).filter(Boolean).join("\n"));
+  }
+  patternsDialog.close();
+});
+$("btnPatternsCancel")?.addEventListener("click", () => patternsDialog.close());
+
+// ------------------ Auto-tagging rules ------------------
+btnRules?.addEventListener("click", async () => {
+  try {
+    const rules = await invoke("get_tag_rules");
+    rulesText.value = JSON.stringify(rules, null, 2);
+    rulesDialog.showModal();
+  } catch (e) {
+    showError(`Loading rules failed: ${errText(e)}`);
+  }
+});
+
+// Invalid rules keep the dialog open (and its text) so they can be fixed
+async function saveRules(apply) {
+  let tagRules;
+  try {
+    tagRules = JSON.parse(rulesText.value.trim() || "[]");
+  } catch (e) {
+    window.alert(`Rules are not valid JSON: ${e.message}`);
+    return;
+  }
+  try {
+    await invoke("set_tag_rules", {
+      tagRules
+    });
+  } catch (e) {
+    window.alert(errText(e));
+    return;
+  }
+  rulesDialog.close();
+  try {
+    if (apply && state.resultScanId != null) {
+      await invoke("apply_tag_rules", {
+        scanId: state.resultScanId
+      });
+      await refreshResult();
+    }
+  } catch (e) {
+    showError(`Applying rules failed: ${errText(e)}`);
+  }
+}
+
+$("btnRulesSave")?.addEventListener("click", () => saveRules(false));
+$("btnRulesApply")?.addEventListener("click", () => saveRules(true));
+$("btnRulesCancel")?.addEventListener("click", () => rulesDialog.close());
+
+// ------------------ Custom fields ------------------
+btnFields?.addEventListener("click", async () => {
+  try {
+    const extractors = await invoke("get_extractors");
+    fieldsText.value = JSON.stringify(extractors, null, 2);
+    fieldsDialog.showModal();
+  } catch (e) {
+    showError(`Loading fields failed: ${errText(e)}`);
+  }
+});
+
+// Invalid extractors keep the dialog open (and its text) so they can be fixed
+async function saveFields(apply) {
+  let extractors;
+  try {
+    extractors = JSON.parse(fieldsText.value.trim() || "[]");
+  } catch (e) {
+    window.alert(`Fields are not valid JSON: ${e.message}`);
+    return;
+  }
+  try {
+    await invoke("set_extractors", {
+      extractors
+    });
+  } catch (e) {
+    window.alert(errText(e));
+    return;
+  }
+  fieldsDialog.close();
+  try {
+    if (apply && state.resultScanId != null) {
+      await invoke("apply_extractors", {
+        scanId: state.resultScanId
+      });
+      await refreshResult();
+    }
+  } catch (e) {
+    showError(`Reading fields failed: ${errText(e)}`);
+  }
+}
+
+$("btnFieldsSave")?.addEventListener("click", () => saveFields(false));
+$("btnFieldsApply")?.addEventListener("click", () => saveFields(true));
+$("btnFieldsCancel")?.addEventListener("click", () => fieldsDialog.close());
+
+// ------------------ Scripts ------------------
+let savedScripts = [];
+
+function setScriptFilter(ids, name) {
+  state.scriptFilter = ids ? new Set(ids) : null;
+  if (btnScriptFilter) {
+    btnScriptFilter.style.display = ids ? "" : "none";
+    btnScriptFilter.textContent = ids ? `⚡ ${name || "Script"} ×` : "";
+  }
+}
+
+function fillScriptSel(selected) {
+  scriptSel.innerHTML = `<option value="">New script</option>` +
+    savedScripts.map((s, i) => `<option value="${i}">${escapeHtml(s.name)}</option>`).join("");
+  const i = savedScripts.findIndex((s) => s.name === selected);
+  scriptSel.value = i >= 0 ? String(i) : "";
+}
+
+btnScripts?.addEventListener("click", async () => {
+  try {
+    savedScripts = await invoke("get_scripts");
+  } catch (e) {
+    console.warn("get_scripts failed", e);
+  }
+  fillScriptSel($("scriptName").value);
+  $("scriptError").textContent = "";
+  scriptsDialog.showModal();
+});
+
+scriptSel?.addEventListener("change", () => {
+  const s = savedScripts[Number(scriptSel.value)];
+  $("scriptName").value = s?.name || "";
+  scriptText.value = s?.source || "";
+});
+
+async function saveScripts(list) {
+  try {
+    savedScripts = await invoke("set_scripts", {
+      scripts: list
+    });
+    $("scriptError").textContent = "";
+    return true;
+  } catch (e) {
+    $("scriptError").textContent = errText(e);
+    return false;
+  }
+}
+
+$("btnScriptSave")?.addEventListener("click", async () => {
+  const name = $("scriptName").value.trim();
+  if (!name) {
+    $("scriptError").textContent = "Give the script a name to save it.";
+    return;
+  }
+  const list = savedScripts.filter((s) => s.name !== name);
+  list.push({
+    name,
+    source: scriptText.value
+  });
+  if (await saveScripts(list)) fillScriptSel(name);
+});
+
+$("btnScriptDelete")?.addEventListener("click", async () => {
+  const name = $("scriptName").value.trim();
+  if (!savedScripts.some((s) => s.name === name)) return;
+  if (await saveScripts(savedScripts.filter((s) => s.name !== name))) {
+    $("scriptName").value = "";
+    scriptText.value = "";
+    fillScriptSel(null);
+  }
+});
+
+async function runScript(apply) {
+  if (state.resultScanId == null || state.scriptJob != null) return;
+  try {
+    state.scriptJob = await invoke("run_script", {
+      scanId: state.resultScanId,
+      source: scriptText.value,
+      apply
+    });
+    state.scriptApply = apply;
+    btnScripts.disabled = true;
+    scriptsDialog.close();
+  } catch (e) {
+    // A script that doesn't compile keeps the dialog open to be fixed
+    $("scriptError").textContent = errText(e);
+  }
+}
+
+async function handleScriptJobDone(job) {
+  state.scriptJob = null;
+  btnScripts.disabled = state.resultScanId == null;
+  if (job.status === "error") {
+    showError(`Script failed: ${job.error ? errText(job.error) : "unknown error"}`);
+    return;
+  }
+  const report = job.result;
+  if (!report) return;
+  if (state.scriptApply) {
+    await refreshResult();
+  } else {
+    setScriptFilter(report.matched, $("scriptName").value.trim());
+    renderResults();
+  }
+  if (!report.error_count && !report.log.length && (state.scriptApply || !report.actions)) return;
+  const actions = report.applied ? `${report.actions} actions applied` :
+    `${report.actions} actions (not applied in a filter run)`;
+  showReport(
+    "Script",
+    `${report.matched.length} files picked, ${actions}, ${report.error_count} errors.`,
+    [
+      ...report.errors.map((e) => ({
+        icon: "❌",
+        label: e.path_id,
+        meta: e.message,
+        path_id: e.path_id,
+      })),
+      ...report.log.map((line) => ({
+        icon: "💬",
+        label: line,
+        meta: "",
+        path_id: null,
+      })),
+    ]
+  );
+}
+
+$("btnScriptFilterRun")?.addEventListener("click", () => runScript(false));
+$("btnScriptApply")?.addEventListener("click", () => runScript(true));
+$("btnScriptCancel")?.addEventListener("click", () => scriptsDialog.close());
+btnScriptFilter?.addEventListener("click", () => {
+  setScriptFilter(null);
+  renderResults();
+});
+
+// ------------------ External tools ------------------
+async function loadTools() {
+  try {
+    state.tools = await invoke("get_tools");
+  } catch (e) {
+    console.warn("get_tools failed", e);
+  }
+}
+
+function toolsFor(pathId) {
+  const format = state.files.find((f) => f.path_id === pathId)?.format;
+  return state.tools.filter((t) => !t.formats?.length || t.formats.includes(format));
+}
+
+// A right-clicked file inside the selection runs the tool on all of it
+async function runTool(name, pathId) {
+  const paths = state.multi.size > 1 && state.multi.has(pathId) ? [...state.multi] : [pathId];
+  try {
+    const jobId = await invoke("run_tool", {
+      name,
+      paths
+    });
+    state.toolJobs.set(jobId, name);
+  } catch (e) {
+    showError(`${name} failed: ${errText(e)}`);
+  }
+}
+
+// The last line of output says the most about a run
+function lastLine(text) {
+  return (text || "").trim().split(/\r?\n/).pop() || "";
+}
+
+function handleToolJobDone(job) {
+  const name = state.toolJobs.get(job.job_id);
+  state.toolJobs.delete(job.job_id);
+  if (job.status === "error") {
+    showError(`${name} failed: ${job.error ? errText(job.error) : "unknown error"}`);
+    return;
+  }
+  const report = job.result;
+  if (!report) return;
+  const failed = report.runs.filter((r) => r.error || r.exit_code !== 0);
+  showReport(
+    report.tool,
+    `${report.runs.length - failed.length} of ${report.runs.length} runs succeeded.`,
+    report.runs.map((r) => ({
+      icon: r.error || r.exit_code !== 0 ? "❌" : "✅",
+      label: r.path,
+      meta: r.error ?
+        errText(r.error) :
+        [`exit ${r.exit_code ?? "?"}`, lastLine(r.stderr) || lastLine(r.stdout)].filter(Boolean).join(" · "),
+      path_id: r.path_id,
+    }))
+  );
+}
+
+btnTools?.addEventListener("click", async () => {
+  await loadTools();
+  toolsText.value = JSON.stringify(state.tools, null, 2);
+  toolsDialog.showModal();
+});
+
+$("btnToolsSave")?.addEventListener("click", async () => {
+  let tools;
+  try {
+    tools = JSON.parse(toolsText.value.trim() || "[]");
+  } catch (e) {
+    window.alert(`Tools are not valid JSON: ${e.message}`);
+    return;
+  }
+  try {
+    state.tools = await invoke("set_tools", {
+      tools
+    });
+    toolsDialog.close();
+  } catch (e) {
+    window.alert(errText(e));
+  }
+});
+$("btnToolsCancel")?.addEventListener("click", () => toolsDialog.close());
+
+optTrash?.addEventListener("change", () => {
+  localStorage.setItem("includeTrash", optTrash.checked ? "1" : "");
+});
+
+// Persisted by the backend; turning it on writes sidecars for existing data
+optSidecars?.addEventListener("change", async () => {
+  try {
+    await invoke("set_sidecar_mode", {
+      enabled: optSidecars.checked
+    });
+  } catch (e) {
+    optSidecars.checked = !optSidecars.checked;
+    showError(`Changing sidecar mode failed: ${errText(e)}`);
+  }
+});
+
+optXattrs?.addEventListener("change", async () => {
+  try {
+    await invoke("set_xattr_mode", {
+      enabled: optXattrs.checked
+    });
+  } catch (e) {
+    optXattrs.checked = !optXattrs.checked;
+    showError(`Changing attribute mode failed: ${errText(e)}`);
+  }
+});
+
+optExportUtc?.addEventListener("change", async () => {
+  try {
+    await invoke("set_export_utc", {
+      enabled: optExportUtc.checked
+    });
+  } catch (e) {
+    optExportUtc.checked = !optExportUtc.checked;
+    showError(`Changing export time zone failed: ${errText(e)}`);
+  }
+});
+
+// Re-sorts the shown result in place; new scans use it too
+treeOrder?.addEventListener("change", async () => {
+  localStorage.setItem("treeOrder", treeOrder.value);
+  if (state.resultScanId == null) return;
+  try {
+    await invoke("resort_tree", {
+      scanId: state.resultScanId,
+      order: treeOrder.value
+    });
+    refreshResult();
+  } catch (e) {
+    showError(`Sorting tree failed: ${errText(e)}`);
+  }
+});
+
+optFormats?.addEventListener("change", () => {
+  localStorage.setItem("otherFormats", optFormats.checked ? "1" : "");
+});
+
+optBackups?.addEventListener("change", () => {
+  localStorage.setItem("scanBackups", optBackups.checked ? "1" : "");
+});
+
+optCountsOnly?.addEventListener("change", () => {
+  localStorage.setItem("countsOnlyTree", optCountsOnly.checked ? "1" : "");
+});
+
+optArchives?.addEventListener("change", () => {
+  localStorage.setItem("scanArchives", optArchives.checked ? "1" : "");
+});
+
+optWatch?.addEventListener("change", () => {
+  localStorage.setItem("watchScans", optWatch.checked ? "1" : "");
+  setWatching(optWatch.checked);
+});
+
+optRelative?.addEventListener("change", () => {
+  localStorage.setItem("relativePaths", optRelative.checked ? "1" : "");
+});
+
+optCompact?.addEventListener("change", () => {
+  localStorage.setItem("compactTree", optCompact.checked ? "1" : "");
+});
+
+parseDepth?.addEventListener("change", () => {
+  localStorage.setItem("parseDepth", parseDepth.value);
+});
+
+optGentle?.addEventListener("change", () => {
+  localStorage.setItem("gentleScan", optGentle.checked ? "1" : "");
+});
+
+btnFavorite?.addEventListener("click", async () => {
+  if (!state.selectedPath) return;
+  try {
+    await invoke("toggle_favorite", {
+      path: state.selectedPath
+    });
+    await refreshResult();
+    setActionButtons();
+  } catch (e) {
+    showError(`Favorite failed: ${errText(e)}`);
+  }
+});
+
+btnRefreshFile?.addEventListener("click", async () => {
+  if (!state.selectedPath) return;
+  try {
+    await invoke("refresh_file", {
+      path: state.selectedPath
+    });
+    await refreshResult();
+  } catch (e) {
+    showError(`Refreshing file failed: ${errText(e)}`);
+  }
+});
+
+// Shows both files before swapping; the current file becomes the .blend1
+btnRestoreBackup?.addEventListener("click", async () => {
+  if (!state.selectedPath) return;
+  try {
+    const swap = await invoke("get_backup_restore", {
+      path: state.selectedPath
+    });
+    const side = (s) => `${s.path}\n  Blender ${s.version || "?"}, modified ${s.modified || "—"}, ${bytesToHuman(s.size_bytes)}`;
+    const ok = window.confirm(
+      `Replace\n${side(swap.current)}\n\nwith its backup\n${side(swap.backup)}?\n\nThe current file is kept as the backup.`
+    );
+    if (!ok) return;
+    await invoke("restore_backup", {
+      path: state.selectedPath
+    });
+    await invoke("refresh_file", {
+      path: state.selectedPath
+    });
+    await refreshResult();
+  } catch (e) {
+    showError(`Restoring backup failed: ${errText(e)}`);
+  }
+});
+
+// ------------------ Health check ------------------
+const SEVERITY_ICON = {
+  ok: "✅",
+  info: "ℹ️",
+  warning: "⚠️",
+  error: "⛔"
+};
+
+const CHECK_TITLE = {
+  parse: "File structure",
+  compression: "Compression",
+  dependencies: "Dependencies",
+  absolute_paths: "Absolute paths",
+  autorun: "Auto-run scripts",
+  lock: "Lock",
+};
+
+btnHealth?.addEventListener("click", async () => {
+  if (!state.selectedPath) return;
+  try {
+    const r = await invoke("check_file", {
+      path: state.selectedPath
+    });
+    const problems = r.checks.filter((c) => c.severity === "warning" || c.severity === "error").length;
+    showReport(
+      `Health: ${r.path.split(/[\\/]/).pop()}`,
+      problems ? `${problems} problem${problems === 1 ? "" : "s"} found` : "No problems found",
+      r.checks.flatMap((c) => [{
+          icon: SEVERITY_ICON[c.severity],
+          label: CHECK_TITLE[c.id] || c.id,
+          meta: c.message
+        },
+        ...c.items.map((item) => ({
+          icon: "",
+          label: item,
+          meta: ""
+        })),
+      ])
+    );
+  } catch (e) {
+    showError(`Health check failed: ${errText(e)}`);
+  }
+});
+
+// ------------------ Compare ------------------
+const COMPARE_FIELD = {
+  version: "Blender version",
+  size: "Size",
+  modified: "Modified",
+  compression: "Compression",
+  render_engine: "Render engine",
+};
+
+function compareValue(field, v) {
+  if (v == null) return "—";
+  if (field === "size") return bytesToHuman(Number(v));
+  if (field === "modified") return new Date(v).toLocaleString();
+  return v;
+}
+
+// Both thumbnails side by side in the (otherwise empty) bulk thumbnail pane
+function showCompareThumbs(r) {
+  thumbContainer.innerHTML = "";
+  const sides = [r.a, r.b].filter((s) => s.thumbnail);
+  thumbContainer.style.display = sides.length ? "flex" : "none";
+  for (const s of sides) {
+    const canvas = thumbCanvas(s.thumbnail, s.thumb_width, s.thumb_height);
+    canvas.title = s.name;
+    thumbContainer.appendChild(canvas);
+  }
+}
+
+async function compareSelected() {
+  const [a, b] = [...state.multi];
+  try {
+    const r = await invoke("compare_files", {
+      pathA: a,
+      pathB: b
+    });
+    if (state.multi.size === 2 && state.multi.has(a) && state.multi.has(b)) showCompareThumbs(r);
+    const rows = [
+      ...r.fields.map((f) => ({
+        icon: "≠",
+        label: COMPARE_FIELD[f.field] || f.field,
+        meta: `${compareValue(f.field, f.a)} → ${compareValue(f.field, f.b)}`
+      })),
+      ...r.counts.map((c) => ({
+        icon: "#",
+        label: c.id_type,
+        meta: `${c.a} → ${c.b}`
+      })),
+      ...r.only_a.map((d) => ({
+        icon: "−",
+        label: d,
+        meta: `only in ${r.a.name}`
+      })),
+      ...r.only_b.map((d) => ({
+        icon: "+",
+        label: d,
+        meta: `only in ${r.b.name}`
+      })),
+    ];
+    if (!r.same_thumbnail) rows.push({
+      icon: "🖼",
+      label: "Thumbnail",
+      meta: r.a.thumbnail && r.b.thumbnail ? "differs" : `only in ${(r.a.thumbnail ? r.a : r.b).name}`
+    });
+    const diffs = rows.length;
+    for (const s of [r.a, r.b]) {
+      if (s.note) rows.push({
+        icon: "ℹ️",
+        label: s.name,
+        meta: s.note,
+        path_id: s.path_id
+      });
+    }
+    showReport(
+      `${r.a.name} → ${r.b.name}`,
+      diffs ? `${diffs} difference${diffs === 1 ? "" : "s"}` : "No differences found",
+      rows
+    );
+  } catch (e) {
+    showError(`Comparing failed: ${errText(e)}`);
+  }
+}
+
+btnOpen?.addEventListener("click", openSelected);
+btnReveal?.addEventListener("click", revealSelected);
+
+searchEl?.addEventListener("input", renderResults);
+resultsEl?.addEventListener("scroll", requestShown);
+sortBy?.addEventListener("change", renderResults);
+
+themeDark?.addEventListener("click", () => applyTheme("dark"));
+themeLight?.addEventListener("click", () => applyTheme("light"));
+
+// ------------------ Boot ------------------
+applyTheme(localStorage.getItem("theme") || "dark");
+if (optTrash) optTrash.checked = !!localStorage.getItem("includeTrash");
+if (optGentle) optGentle.checked = !!localStorage.getItem("gentleScan");
+if (parseDepth) parseDepth.value = localStorage.getItem("parseDepth") || "";
+if (optCompact) optCompact.checked = !!localStorage.getItem("compactTree");
+if (optFormats) optFormats.checked = !!localStorage.getItem("otherFormats");
+if (optBackups) optBackups.checked = !!localStorage.getItem("scanBackups");
+if (optRelative) optRelative.checked = !!localStorage.getItem("relativePaths");
+if (optCountsOnly) optCountsOnly.checked = !!localStorage.getItem("countsOnlyTree");
+if (optArchives) optArchives.checked = !!localStorage.getItem("scanArchives");
+if (optWatch) optWatch.checked = !!localStorage.getItem("watchScans");
+if (treeOrder) treeOrder.value = localStorage.getItem("treeOrder") || "name";
+listenScanEvents();
+invoke("get_sidecar_mode")
+  .then((on) => optSidecars && (optSidecars.checked = on))
+  .catch((e) => console.warn("get_sidecar_mode failed", e));
+invoke("get_xattr_mode")
+  .then((on) => optXattrs && (optXattrs.checked = on))
+  .catch((e) => console.warn("get_xattr_mode failed", e));
+invoke("get_update_checks")
+  .then((on) => optUpdates && (optUpdates.checked = on))
+  .catch((e) => console.warn("get_update_checks failed", e));
+invoke("get_close_to_tray")
+  .then((on) => optCloseToTray && (optCloseToTray.checked = on))
+  .catch((e) => console.warn("get_close_to_tray failed", e));
+invoke("get_export_utc")
+  .then((on) => optExportUtc && (optExportUtc.checked = on))
+  .catch((e) => console.warn("get_export_utc failed", e));
+invoke("get_usage_tracking")
+  .then((on) => optUsage && (optUsage.checked = on))
+  .catch((e) => console.warn("get_usage_tracking failed", e));
+loadTagNames();
+loadCollections();
+loadPalette().then(() => renderResults());
+loadProjectRoots();
+loadTools();
+loadCatalogs().then(() => loadCollections());
+renderTree();
+renderResults();
+renderInfo(null);
+setActionButtons();
+setCounts(null, null, null);
+
+async function boot() {
+  // Links that launched the app take precedence over the remembered folder
+  if (!isSecondaryWindow) {
+    try {
+      const pending = await invoke("take_pending_requests");
+      if (pending?.length) {
+        pending.forEach(handleAppRequest);
+        return;
+      }
+    } catch (e) {
+      console.warn("take_pending_requests failed", e);
+    }
+  }
+
+  // Remember last folder on startup (secondary windows use their URL folder)
+  const last = isSecondaryWindow ?
+    urlParams.get("folder") :
+    localStorage.getItem("lastFolder");
+  if (last && typeof last === "string" && last.length) {
+    const roots = last.split("\n");
+    folderPill && (folderPill.textContent = roots.join(" + "));
+    // Auto-scan shortly after load (lets UI paint first)
+    setTimeout(() => startScan(roots.length > 1 ? roots : last, {
+      indexed: true
+    }), 150);
+  }
+}
 
-// Remember last folder on startup
-const last = localStorage.getItem("lastFolder");
-if (last && typeof last === "string" && last.length) {
-  folderPill && (folderPill.textContent = last);
-  // Auto-scan shortly after load (lets UI paint first)
-  setTimeout(() => startScan(last), 150);
-}
\ No newline at end of file
+boot();